@@ -0,0 +1,97 @@
+//! Benchmark of the natural sorting of pages, comparing the precomputed keys of the "sort" module
+//!  to the plain comparison function they replace
+//! The crate only builds a binary, so the modules are included directly from its sources
+//! Run with: cargo bench --bench natural_sort
+
+#![forbid(unsafe_code)]
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// Named after the crate's module, which the included sources import from
+#[allow(dead_code)]
+#[path = "modules.rs"]
+mod lib;
+
+use lib::{deter, sort};
+
+/// Number of chapters in the generated tree
+const CHAPTERS: usize = 400;
+
+/// Number of pages in each chapter
+const PAGES_PER_CHAPTER: usize = 250;
+
+/// Number of times each sort is run, the fastest run being kept
+const RUNS: usize = 5;
+
+/// Generate the paths of a comic's pages, in a deterministic but shuffled order
+fn generate_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::with_capacity(CHAPTERS * PAGES_PER_CHAPTER);
+
+    for chapter in 1..=CHAPTERS {
+        for page in 1..=PAGES_PER_CHAPTER {
+            paths.push(PathBuf::from(format!(
+                "Series/Chapter {}/Page {}.jpg",
+                chapter, page
+            )));
+        }
+    }
+
+    // Fisher-Yates shuffle with a linear congruential generator, so every run sorts the same input
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+
+    for i in (1..paths.len()).rev() {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+
+        paths.swap(i, (state >> 33) as usize % (i + 1));
+    }
+
+    paths
+}
+
+/// Run a sort on a fresh copy of the paths several times, returning the fastest run and the sorted paths
+fn bench(paths: &[PathBuf], sort: impl Fn(&mut Vec<PathBuf>)) -> (Duration, Vec<PathBuf>) {
+    let mut times = vec![];
+    let mut sorted = vec![];
+
+    for _ in 0..RUNS {
+        let mut copy = paths.to_vec();
+
+        let started = Instant::now();
+        sort(&mut copy);
+        times.push(started.elapsed());
+
+        sorted = copy;
+    }
+
+    (times.into_iter().min().unwrap(), sorted)
+}
+
+fn main() {
+    let paths = generate_paths();
+
+    println!("Sorting {} paths (best of {} runs)...", paths.len(), RUNS);
+
+    let (cmp_time, by_cmp) = bench(&paths, |paths| paths.sort_by(deter::natural_paths_cmp));
+    println!(
+        "deter::natural_paths_cmp     {:>10.3} ms",
+        cmp_time.as_secs_f64() * 1000.0
+    );
+
+    let (keys_time, by_keys) = bench(&paths, |paths| sort::natural_sort_paths(paths, false));
+    println!(
+        "sort::natural_sort_paths     {:>10.3} ms",
+        keys_time.as_secs_f64() * 1000.0
+    );
+
+    let (roman_time, _) = bench(&paths, |paths| sort::natural_sort_paths(paths, true));
+    println!(
+        "  with roman numerals        {:>10.3} ms",
+        roman_time.as_secs_f64() * 1000.0
+    );
+
+    // Both orders must match, as paths with distinct names never compare as equal
+    assert_eq!(by_cmp, by_keys, "Natural sorting orders differ");
+}
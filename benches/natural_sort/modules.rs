@@ -0,0 +1,7 @@
+//! Modules of the crate used by the benchmark, included from its sources
+
+#[path = "../../src/lib/deter.rs"]
+pub mod deter;
+
+#[path = "../../src/lib/sort.rs"]
+pub mod sort;
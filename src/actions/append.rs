@@ -0,0 +1,277 @@
+use crate::cli::error::EncodingError;
+use crate::cli::opts::{EncodeAppend, EncodingOptions};
+use crate::lib::build_vol::{
+    load_ignore_file, load_signing_key, open_page_store, read_volume_numbering,
+    rewriting_zip_writer, zip_options, BuildMethod, EntryNames, StagingFile, VolumeZip,
+    STAGING_EXT,
+};
+use crate::lib::chapters::{collect_chapters, ChapterFilter};
+use crate::lib::deter;
+use crate::lib::hooks;
+use crate::lib::manifest::{self, EmbeddedManifest};
+use crate::lib::prompt;
+use crate::lib::report::Report;
+use crate::lib::sign;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::time::Instant;
+use zip::ZipArchive;
+
+/// Append chapters to an existing volume
+/// Entries of the existing chapters are copied as they are, without being decompressed
+pub fn append(opts: &EncodeAppend, enc_opts: &EncodingOptions) -> Result<Report, EncodingError> {
+    let mut report = Report::new();
+
+    if enc_opts.also_extract.is_some() {
+        warn!("Warning: '--also-extract' is ignored when appending chapters to a volume.");
+    }
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(EncodingError::FailedToGetCWD)?;
+
+    let input_dir = cwd.join(&enc_opts.input);
+
+    if !input_dir.is_dir() {
+        return Err(EncodingError::ChaptersDirectoryNotFound);
+    }
+
+    let source = cwd.join(&opts.volume);
+
+    if !source.is_file() {
+        return Err(EncodingError::ExistingVolumeNotFound(source));
+    }
+
+    // Write to another file if an output is provided, otherwise update the volume
+    let target = match &enc_opts.output {
+        Some(output) => cwd.join(output),
+        None => source.clone(),
+    };
+
+    if target != source
+        && target.exists()
+        && !enc_opts.overwrite
+        && !prompt::confirm_overwrite(&target)
+    {
+        return Err(EncodingError::OutputVolumeFileAlreadyExists(1, target));
+    }
+
+    // Get timestamp to measure the scanning phase
+    let scan_started = Instant::now();
+
+    trace!("Reading existing volume's numbering...");
+
+    let numbering = read_volume_numbering(&source)?;
+
+    debug!(
+        "Existing volume {} contains {} chapter(s) and {} page(s).",
+        numbering.volume, numbering.last_chapter, numbering.pages
+    );
+
+    // Load the patterns of chapters and pages to exclude
+    let ignore_file = load_ignore_file(&input_dir)?;
+
+    // List the chapters to append
+    let chapters = collect_chapters(
+        &input_dir,
+        None,
+        &ChapterFilter::new(
+            opts.dirs_prefix.as_deref(),
+            opts.prefix_ignore_case,
+            opts.prefix_transliterate,
+            opts.dirs_regex.clone(),
+        ),
+        &ignore_file,
+        enc_opts.simple_sorting,
+        enc_opts.roman_numerals,
+    )?;
+
+    report.phase("scan", scan_started.elapsed());
+    report.count("chapters to append", chapters.len());
+
+    if chapters.is_empty() {
+        warn!("No chapter found. Nothing to do.");
+        return Ok(report);
+    }
+
+    info!(
+        "Going to append {} chapter(s) to volume '{}', starting at chapter {}.",
+        chapters.len(),
+        source.to_string_lossy(),
+        numbering.last_chapter + 1
+    );
+
+    // Load the signing key before building anything
+    let signing_key = load_signing_key(enc_opts)?;
+
+    // Run the pre-build hook once everything is ready
+    if let Some(pre_hook) = &enc_opts.pre_hook {
+        hooks::run_hook(pre_hook, &target)
+            .map_err(|err| EncodingError::PreHookFailed(err.to_string()))?;
+    }
+
+    // Get timestamp to measure the building phase
+    let build_started = Instant::now();
+
+    let source_file = File::open(&source)
+        .map_err(|err| EncodingError::FailedToOpenExistingVolume(source.clone(), err))?;
+
+    let mut source_zip = ZipArchive::new(source_file)
+        .map_err(|err| EncodingError::InvalidExistingVolume(source.clone(), err))?;
+
+    // Records of the pages embedded in the volume must be updated with the new chapters
+    let mut records = manifest::read_embedded_records(&mut source_zip, &source);
+
+    let write_manifest = records.has_manifest || enc_opts.bit_exact;
+    let write_checksums = records.has_checksums || enc_opts.checksums;
+
+    // Write the updated volume to a staging file, so the volume is left untouched if something goes wrong
+    let staging_path = deter::add_extension(&target, STAGING_EXT);
+
+    let staging_file = File::create(&staging_path).map_err(|err| {
+        EncodingError::FailedToCreateVolumeFile(numbering.volume, staging_path.clone(), err)
+    })?;
+
+    // Ensure the staging file is removed if anything goes wrong
+    let mut staging_guard = StagingFile {
+        path: staging_path.clone(),
+        finalized: false,
+    };
+
+    // Make room for the new chapters' numbers
+    let last_chapter = numbering.last_chapter + chapters.len();
+
+    let mut volume_zip = VolumeZip {
+        zip_writer: rewriting_zip_writer(&source_zip, staging_file),
+        zip_options: zip_options(enc_opts),
+        method: &BuildMethod::Append(opts),
+        enc_opts,
+        volume: numbering.volume,
+        vol_num_len: numbering.vol_num_len,
+        chapter_num_len: std::cmp::max(numbering.chapter_num_len, last_chapter.to_string().len()),
+        volume_display_name: format!(
+            "'{}'",
+            target.file_name().unwrap_or_default().to_string_lossy()
+        ),
+        display_name_individual: None,
+        pics_counter: 0,
+        ignore_file: &ignore_file,
+        extract_dir: None,
+        comic_info: None,
+        entry_names: EntryNames::default(),
+        pages: None,
+        embedded_manifest: if write_manifest || write_checksums {
+            Some(EmbeddedManifest {
+                // Existing pages are only copied, so the volume stays bit-exact if the new pages are
+                bit_exact: records.bit_exact && enc_opts.bit_exact,
+                pages: vec![],
+                sources: records.sources.clone(),
+            })
+        } else {
+            None
+        },
+        contact_sheet: None,
+        page_store: open_page_store(enc_opts)?,
+        pending_page: None,
+        // Only the appended pages are compared with each other, the existing ones being left untouched
+        page_digests: if enc_opts.dedupe_pages {
+            Some(HashMap::new())
+        } else {
+            None
+        },
+        duplicate_pages: 0,
+        page_hashes: if enc_opts.detect_near_duplicates.is_some() {
+            Some(vec![])
+        } else {
+            None
+        },
+        near_duplicates: vec![],
+    };
+
+    // New entries must not collide with the existing ones
+    for entry_name in &numbering.entry_names {
+        volume_zip.entry_names.reserve(entry_name);
+    }
+
+    // Number of pages already in the volume
+    let mut existing_pages = 0;
+
+    for i in 0..source_zip.len() {
+        if volume_zip.copy_existing_entry(&mut source_zip, i, &source, &mut records.pages)? {
+            existing_pages += 1;
+        }
+    }
+
+    for (i, (chapter_path, chapter_name)) in chapters.iter().enumerate() {
+        volume_zip.add_chapter(numbering.last_chapter + i + 1, chapter_path, chapter_name)?;
+    }
+
+    // Write the metadata file again with the new number of pages
+    if let Some(xml) = &records.comic_info {
+        volume_zip.add_existing_comic_info(xml, existing_pages + volume_zip.pics_counter)?;
+    }
+
+    // Write the records of the pages again, with the new ones
+    if let Some(embedded_manifest) = volume_zip.embedded_manifest.take() {
+        if write_manifest {
+            volume_zip.add_embedded_manifest(&embedded_manifest)?;
+        }
+
+        if write_checksums {
+            volume_zip.add_checksums(&embedded_manifest)?;
+        }
+    }
+
+    trace!("Closing ZIP archive...");
+
+    volume_zip
+        .zip_writer
+        .finish()
+        .map_err(|err| EncodingError::FailedToCloseZipArchive(numbering.volume, err))?;
+
+    // Replace the target with the updated volume
+    fs::rename(&staging_path, &target)
+        .map_err(|err| EncodingError::FailedToRenameCompleteArchive(numbering.volume, err))?;
+
+    staging_guard.finalized = true;
+
+    // Sign the updated volume file
+    if let Some(keypair) = &signing_key {
+        trace!("Signing volume...");
+
+        sign::sign_file(keypair, &target).map_err(|err| {
+            EncodingError::FailedToSignVolume(numbering.volume, target.clone(), err)
+        })?;
+    }
+
+    info!(
+        "Successfully appended {} chapter(s) containing {} pages to '{}', which now contains {} pages.",
+        chapters.len(),
+        volume_zip.pics_counter,
+        target.to_string_lossy(),
+        existing_pages + volume_zip.pics_counter
+    );
+
+    // Run the post-build hook now the volume is complete
+    if let Some(post_hook) = &enc_opts.post_hook {
+        hooks::run_hook(post_hook, &target)
+            .map_err(|err| EncodingError::PostHookFailed(numbering.volume, err.to_string()))?;
+    }
+
+    report.phase("build", build_started.elapsed());
+    report.count("pages added", volume_zip.pics_counter);
+    report.count("renamed entries", volume_zip.entry_names.renamed);
+
+    if enc_opts.dedupe_pages {
+        report.count("duplicate pages dropped", volume_zip.duplicate_pages);
+    }
+
+    if enc_opts.detect_near_duplicates.is_some() {
+        report.count("near-duplicate pages", volume_zip.near_duplicates.len());
+        report.near_duplicates = volume_zip.near_duplicates;
+    }
+
+    report.outputs.push(target);
+
+    Ok(report)
+}
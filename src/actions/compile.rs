@@ -1,24 +1,59 @@
 use crate::cli::error::EncodingError;
 use crate::cli::opts::{CompilationMethod, CompilationOptions, EncodingOptions};
 use crate::lib::build_vol::*;
-use crate::lib::deter;
+use crate::lib::chapters::{collect_chapters, ChapterFilter};
+use crate::lib::comic_info::ComicMetadata;
+use crate::lib::contact_sheet;
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::hooks;
+use crate::lib::ignore_file::IgnoreFile;
+use crate::lib::index::{Index, IndexVolume, INDEX_FILE_NAME};
+use crate::lib::manifest::{self, Manifest, ManifestVolume};
+use crate::lib::opds::{self, OpdsEntry, VolumeInfo, OPDS_CATALOG_FILE_NAME, OPDS_COVERS_DIR_NAME};
+use crate::lib::plan_editor::{self, PlanChapter};
+use crate::lib::planner::{self, BalanceMode, Strategy};
+use crate::lib::provenance::Sources;
+use crate::lib::reading_list::{self, ReadingListEntry};
+use crate::lib::report::{Report, VolumeStats};
+use crate::lib::safety;
+use crate::lib::series_json::{self, SeriesInfo, SERIES_JSON_FILE_NAME};
+use crate::lib::sort;
+use crate::lib::source_meta::{self, SourceMetadata};
+use crate::logger;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 /// Compile directories to volumes
 pub fn compile(
     opts: &CompilationOptions,
     enc_opts: &EncodingOptions,
-) -> Result<Vec<PathBuf>, EncodingError> {
-    // Get the number of chapters to put in each volume
-    let chap_per_vol = match &opts.method {
-        CompilationMethod::Ranges(opts) => opts.chapters_per_volume,
-        CompilationMethod::Each(_) => 1,
+) -> Result<Report, EncodingError> {
+    let mut report = Report::new();
+
+    // Determine how to group chapters into volumes
+    let strategy = match &opts.method {
+        CompilationMethod::Ranges(opts) => match (
+            opts.chapters_per_volume,
+            opts.target_pages_per_volume,
+            opts.volumes,
+        ) {
+            (_, _, Some(volumes)) => {
+                Strategy::Balance(opts.balance.unwrap_or(BalanceMode::Chapters), volumes)
+            }
+            (_, Some(target_pages), None) => Strategy::TargetPages(target_pages),
+            (Some(chap_per_vol), None, None) => Strategy::ChaptersPerVolume(chap_per_vol.into()),
+            (None, None, None) => return Err(EncodingError::MissingChaptersPerVolume),
+        },
+        CompilationMethod::Each(_) => Strategy::ChaptersPerVolume(1),
     };
 
-    if chap_per_vol == 0 {
-        return Err(EncodingError::AtLeast1ChapterPerVolume);
+    match strategy {
+        Strategy::ChaptersPerVolume(0) => return Err(EncodingError::AtLeast1ChapterPerVolume),
+        Strategy::TargetPages(0) => return Err(EncodingError::AtLeast1PagePerVolume),
+        Strategy::Balance(_, 0) => return Err(EncodingError::AtLeast1Volume),
+        _ => {}
     }
 
     if let Some(start_chapter) = opts.start_chapter {
@@ -69,59 +104,58 @@ pub fn compile(
         None => input_dir.clone(),
     };
 
-    // List of chapter directories
-    let mut chapter_dirs: Vec<(PathBuf, String)> = vec![];
-
-    trace!("Reading chapter directories...");
-
-    // Iterate over all items in the input directory
-    for entry in fs::read_dir(input_dir).map_err(EncodingError::FailedToReadChaptersDirectory)? {
-        let entry = entry.map_err(EncodingError::FailedToReadChaptersDirectory)?;
-        let path = entry.path();
-
-        // Ignore files
-        if path.is_dir() {
-            let entry_name = entry
-                .file_name()
-                .into_string()
-                .map_err(|_| EncodingError::ItemHasInvalidUTF8Name(entry.file_name()))?;
-
-            // Ignore directories not starting by the provided prefix
-            if opts
-                .dirs_prefix
-                .as_ref()
-                .map(|prefix| entry_name.starts_with(prefix))
-                .unwrap_or(true)
-            {
-                chapter_dirs.push((path, entry_name));
-            }
-        }
+    // Volumes must not be written to a directory typed by mistake
+    if safety::is_dangerous_dir(&output) {
+        return Err(EncodingError::DangerousOutputDirectory(output));
     }
 
-    trace!("Sorting chapter directories by name...");
-
-    if enc_opts.simple_sorting {
-        chapter_dirs.sort_by(|a, b| a.0.cmp(&b.0));
-    } else {
-        chapter_dirs.sort_by(|a, b| deter::natural_paths_cmp(&a.0, &b.0));
-    }
+    // Get timestamp to measure the scanning phase
+    let scan_started = Instant::now();
 
-    // Disable mutability for this variable
-    let chapter_dirs = chapter_dirs;
+    // Load the patterns of chapters and pages to exclude
+    let ignore_file = load_ignore_file(&input_dir)?;
 
-    // Current volume
-    let mut volume = 1;
+    // Load the metadata of the series, if any
+    let series_metadata = SourceMetadata::load(&input_dir)
+        .map_err(|err| EncodingError::InvalidMetadataFile(err.to_string()))?;
 
-    // List of chapter directories of the current volume
-    let mut volume_chapters = vec![];
+    // Load the origin of the chapters, if any
+    let sources = Sources::load(&input_dir)
+        .map_err(|err| EncodingError::InvalidSourcesFile(err.to_string()))?;
 
-    // First chapter of current volume
-    let mut volume_start_chapter = 1;
-
-    // Number of volumes to make, before considering start and end chapter
-    // It is used to determine the number of digits volumes should be displayed with
-    let untrimmed_volumes = deter::ceil_div(chapter_dirs.len(), chap_per_vol.into());
-    let vol_num_len = untrimmed_volumes.to_string().len();
+    // Name of the series, used for the naming presets and the reading list
+    let series_name = enc_opts
+        .series
+        .clone()
+        .or_else(|| series_metadata.as_ref().and_then(|meta| meta.title.clone()))
+        .unwrap_or_else(|| {
+            input_dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+
+    // List the chapters to compile
+    let chapter_dirs = collect_chapters(
+        &input_dir,
+        Some(output.as_path()),
+        &ChapterFilter::new(
+            opts.dirs_prefix.as_deref(),
+            opts.prefix_ignore_case,
+            opts.prefix_transliterate,
+            opts.dirs_regex.clone(),
+        ),
+        &ignore_file,
+        enc_opts.simple_sorting,
+        enc_opts.roman_numerals,
+    )?;
+
+    report.phase("scan", scan_started.elapsed());
+    report.count("chapters found", chapter_dirs.len());
+
+    // Get timestamp to measure the planning phase
+    let plan_started = Instant::now();
 
     // Determine the number of digits for chapters
     let chapter_num_len = chapter_dirs.len().to_string().len();
@@ -135,14 +169,49 @@ pub fn compile(
 
     if end_chapter == 0 {
         warn!("No chapter found. Nothing to do.");
-        return Ok(vec![]);
+        return Ok(report);
     }
 
     // Determine the real number of chapters to encode
     let chapter_len = end_chapter - start_chapter;
 
+    // Keep track of the chapters left out by the start and end chapters
+    report.skipped_chapters = chapter_dirs
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i < start_chapter || *i >= start_chapter + chapter_len)
+        .map(|(_, (_, chapter_name))| chapter_name.clone())
+        .collect();
+
+    // Determine the number of chapters each volume will contain
+    let chapter_paths = chapter_dirs
+        .iter()
+        .skip(start_chapter)
+        .take(chapter_len)
+        .map(|(path, _)| path.as_path())
+        .collect::<Vec<_>>();
+
+    let volumes_sizes = planner::plan(
+        strategy,
+        &chapter_paths,
+        &|path| is_page(enc_opts, path),
+        &ignore_file,
+    )
+    .map_err(|(path, err)| EncodingError::FailedToCountChapterPages(path, err.to_string()))?;
+
     // Determine the real number of volumes to create
-    let volumes = deter::ceil_div(chapter_len, chap_per_vol.into());
+    let volumes = volumes_sizes.len();
+
+    // Number of volumes to make, before considering start and end chapter
+    // It is used to determine the number of digits volumes should be displayed with
+    let untrimmed_volumes = match strategy {
+        Strategy::ChaptersPerVolume(chap_per_vol) => {
+            deter::ceil_div(chapter_dirs.len(), chap_per_vol)
+        }
+        _ => volumes,
+    };
+
+    let vol_num_len = untrimmed_volumes.to_string().len();
 
     info!(
         "Going to treat chapter{} {} to {} ({} out of {}, {} to ignore) into {} volume{}.",
@@ -164,58 +233,631 @@ pub fn compile(
         CompilationMethod::Each(sub_opts) => BuildMethod::Each(sub_opts, opts),
     };
 
-    // The list of all created volume files
-    let mut output_files = vec![];
+    // Volumes to build, as tuples containing: (volume number, first chapter's number, chapters)
+    let mut planned_volumes = vec![];
+
+    // First chapter of current volume
+    let mut volume_start_chapter = 1;
 
     // Iterate over chapters
-    for (chapter, (path, chapter_name)) in chapter_dirs
+    let mut chapters = chapter_dirs
         .into_iter()
         .skip(start_chapter)
         .take(chapter_len)
-        .enumerate()
-    {
-        // Add this chapter to the current volume
-        volume_chapters.push((chapter + 1, path, chapter_name));
-
-        // If this volume contains enough chapters, build it
-        if volume_chapters.len() == chap_per_vol.into() {
-            output_files.push(build_volume(&BuildVolumeArgs {
-                method: &build_method,
-                enc_opts,
-                output: &output,
-                volume,
-                volumes,
-                vol_num_len,
-                chapter_num_len,
-                start_chapter: volume_start_chapter,
-                chapters: &volume_chapters,
-            })?);
-            volume_start_chapter += volume_chapters.len();
-            volume_chapters = vec![];
-            volume += 1;
+        .enumerate();
+
+    for (i, size) in volumes_sizes.iter().enumerate() {
+        // Take the chapters of this volume
+        let volume_chapters: Vec<_> = chapters
+            .by_ref()
+            .take(*size)
+            .map(|(chapter, (path, chapter_name))| (chapter + 1, path, chapter_name))
+            .collect();
+
+        planned_volumes.push((i + 1, volume_start_chapter, volume_chapters));
+        volume_start_chapter += size;
+    }
+
+    // Let the user edit the plan if asked to
+    let (planned_volumes, volumes, vol_num_len, chapter_len) = if opts.tui {
+        let allow_grouping = matches!(opts.method, CompilationMethod::Ranges(_));
+
+        let planned_volumes = match edit_plan(
+            planned_volumes,
+            allow_grouping,
+            enc_opts,
+            &ignore_file,
+            &mut report,
+        )? {
+            Some(planned_volumes) => planned_volumes,
+            None => {
+                warn!("Plan edition was aborted. Nothing to do.");
+                return Ok(report);
+            }
+        };
+
+        let volumes = planned_volumes.len();
+
+        let chapter_len = planned_volumes
+            .iter()
+            .map(|(_, _, chapters)| chapters.len())
+            .sum::<usize>();
+
+        info!(
+            "Going to treat {} chapter{} into {} volume{}, as planned.",
+            chapter_len,
+            if chapter_len > 1 { "s" } else { "" },
+            volumes,
+            if volumes > 1 { "s" } else { "" }
+        );
+
+        (
+            planned_volumes,
+            volumes,
+            std::cmp::max(vol_num_len, volumes.to_string().len()),
+            chapter_len,
+        )
+    } else {
+        (planned_volumes, volumes, vol_num_len, chapter_len)
+    };
+
+    report.phase("plan", plan_started.elapsed());
+    report.count("chapters to encode", chapter_len);
+
+    // Only display the plan if asked to
+    if opts.dry_run {
+        preview_plan(
+            opts,
+            enc_opts,
+            &planned_volumes,
+            vol_num_len,
+            &ignore_file,
+            &mut report,
+        )?;
+        return Ok(report);
+    }
+
+    // Put the volumes in a directory named after their series if the naming preset requires it
+    let output = match opts.naming_preset {
+        Some(preset) if preset.uses_series_dir() => {
+            let series_dir = output.join(source_meta::sanitize_file_name(&series_name));
+
+            if !series_dir.is_dir() {
+                debug!(
+                    "Creating series directory '{}'...",
+                    series_dir.to_string_lossy()
+                );
+
+                fs::create_dir_all(&series_dir)
+                    .map_err(EncodingError::FailedToCreateOutputDirectory)?;
+            }
+
+            series_dir
         }
+
+        _ => output,
+    };
+
+    // Load the signing key before building anything
+    let signing_key = load_signing_key(enc_opts)?;
+
+    // Remove the staging files previous compilations failed to remove
+    if let Err(err) = Manifest::update(&output, |manifest| manifest.clean_stranded(&output)) {
+        warn!("Warning: failed to update manifest: {}", err);
     }
 
-    // If there are remaining chapters, build a last volume with them
-    if volume_chapters.is_empty() {
-        output_files.push(build_volume(&BuildVolumeArgs {
+    // Run the pre-build hook once everything is ready
+    if let Some(pre_hook) = &enc_opts.pre_hook {
+        hooks::run_hook(pre_hook, &output)
+            .map_err(|err| EncodingError::PreHookFailed(err.to_string()))?;
+    }
+
+    // Get timestamp to measure the building phase
+    let build_started = Instant::now();
+
+    // The list of all created volumes
+    let mut output_files = vec![];
+
+    for (i, (volume, volume_start_chapter, volume_chapters)) in planned_volumes.iter().enumerate() {
+        logger::set_progress(
+            i,
+            planned_volumes.len(),
+            &format!("Building volume {}...", volume),
+        );
+
+        // Get timestamp to measure the volume's building time
+        let volume_started = Instant::now();
+
+        let built = build_volume(&BuildVolumeArgs {
             method: &build_method,
             enc_opts,
             output: &output,
-            volume,
+            volume: *volume,
             volumes,
             vol_num_len,
             chapter_num_len,
-            start_chapter: volume_start_chapter,
-            chapters: &volume_chapters,
-        })?);
+            start_chapter: *volume_start_chapter,
+            chapters: volume_chapters,
+            signing_key: signing_key.as_ref(),
+            ignore_file: &ignore_file,
+            series_metadata: series_metadata.as_ref(),
+            sources: sources.as_ref(),
+            previous_manifest: None,
+        })?;
+
+        // Register the volume in the manifest, unless it has been skipped
+        if built.pages.is_some() {
+            register_volume(&output, &built);
+        }
+
+        report.volumes.push(VolumeStats {
+            volume: *volume,
+            path: built.path.clone(),
+            chapters: volume_chapters.len(),
+            pages: built.pages,
+            elapsed_ms: volume_started.elapsed().as_millis() as u64,
+            skipped: built.pages.is_none(),
+        });
+
+        output_files.push(built);
     }
 
+    logger::clear_progress();
+
     info!(
         "Successfully built {} volume{}.",
         output_files.len(),
         if output_files.len() > 1 { "s" } else { "" }
     );
 
-    Ok(output_files)
+    report.phase("build", build_started.elapsed());
+    report.count("volumes", output_files.len());
+    report.count(
+        "pages",
+        output_files.iter().filter_map(|built| built.pages).sum(),
+    );
+    report.count(
+        "renamed entries",
+        output_files.iter().map(|built| built.renamed_entries).sum(),
+    );
+
+    if enc_opts.dedupe_pages {
+        report.count(
+            "duplicate pages dropped",
+            output_files.iter().map(|built| built.duplicate_pages).sum(),
+        );
+    }
+
+    if enc_opts.detect_near_duplicates.is_some() {
+        report.near_duplicates = output_files
+            .iter()
+            .flat_map(|built| built.near_duplicates.iter().cloned())
+            .collect();
+
+        report.count("near-duplicate pages", report.near_duplicates.len());
+    }
+
+    // Summarize the volumes for other programs
+    let index = if opts.index {
+        Some(Index {
+            volumes: planned_volumes
+                .iter()
+                .zip(output_files.iter())
+                .map(
+                    |((volume, volume_start_chapter, volume_chapters), built)| IndexVolume {
+                        volume: *volume,
+                        path: built.path.clone(),
+                        first_chapter: *volume_start_chapter,
+                        last_chapter: volume_start_chapter + volume_chapters.len() - 1,
+                        chapters: volume_chapters
+                            .iter()
+                            .map(|(_, _, chapter_name)| chapter_name.clone())
+                            .collect(),
+                        pages: built.pages,
+                        size: fs::metadata(&built.path).map(|meta| meta.len()).ok(),
+                        skipped: built.pages.is_none(),
+                    },
+                )
+                .collect(),
+        })
+    } else {
+        None
+    };
+
+    report.outputs = output_files.into_iter().map(|built| built.path).collect();
+
+    if let Some(index) = index {
+        let index_path = output.join(INDEX_FILE_NAME);
+
+        debug!(
+            "Writing summary of volumes to '{}'...",
+            index_path.to_string_lossy()
+        );
+
+        fs::write(&index_path, index.to_json())
+            .map_err(|err| EncodingError::FailedToWriteIndex(index_path.clone(), err))?;
+
+        report.outputs.push(index_path);
+    }
+
+    // Write the manifest of the series
+    if opts.series_json {
+        let series_json_path = output.join(SERIES_JSON_FILE_NAME);
+
+        debug!(
+            "Writing series manifest to '{}'...",
+            series_json_path.to_string_lossy()
+        );
+
+        let series_json = series_json::render_series_json(&SeriesInfo {
+            name: &series_name,
+            publisher: opts.publisher.as_deref(),
+            year: enc_opts.year,
+            volumes,
+            status: opts.series_status,
+        });
+
+        fs::write(&series_json_path, series_json)
+            .map_err(|err| EncodingError::FailedToWriteSeriesJson(series_json_path.clone(), err))?;
+
+        report.outputs.push(series_json_path);
+    }
+
+    // Write the reading list of the built volumes
+    if let Some(reading_list_path) = &opts.reading_list {
+        let reading_list_path = cwd.join(reading_list_path);
+
+        let entries = planned_volumes
+            .iter()
+            .zip(report.outputs.iter())
+            .map(|((volume, _, _), path)| ReadingListEntry {
+                series: series_name.clone(),
+                number: ComicMetadata::from_options(enc_opts, *volume)
+                    .number
+                    .unwrap_or_else(|| volume.to_string()),
+                year: enc_opts.year,
+                file_name: path
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        debug!(
+            "Writing reading list to '{}'...",
+            reading_list_path.to_string_lossy()
+        );
+
+        fs::write(
+            &reading_list_path,
+            reading_list::render_cbl(&series_name, &entries),
+        )
+        .map_err(|err| EncodingError::FailedToWriteReadingList(reading_list_path.clone(), err))?;
+
+        report.outputs.push(reading_list_path);
+    }
+
+    // Write the OPDS catalog of the output directory
+    if opts.opds {
+        write_opds_catalog(&output, &series_name, enc_opts, &mut report)?;
+    }
+
+    Ok(report)
+}
+
+/// Write an OPDS catalog listing the volumes registered in the manifest of the output directory, including the ones built previously
+/// The cover of each volume is extracted in a directory next to the catalog
+fn write_opds_catalog(
+    output: &Path,
+    series_name: &str,
+    enc_opts: &EncodingOptions,
+    report: &mut Report,
+) -> Result<(), EncodingError> {
+    let manifest = Manifest::load(output).map_err(|err| {
+        EncodingError::FailedToLoadManifest(output.to_path_buf(), err.to_string())
+    })?;
+
+    let mut volumes = manifest
+        .volumes
+        .into_iter()
+        .filter(|volume| output.join(&volume.file_name).is_file())
+        .collect::<Vec<_>>();
+
+    sort::natural_sort_by_path(&mut volumes, |volume| Path::new(&volume.file_name), false);
+
+    let covers_dir = output.join(OPDS_COVERS_DIR_NAME);
+
+    fs::create_dir_all(&covers_dir)
+        .map_err(|err| EncodingError::FailedToWriteOpdsCover(covers_dir.clone(), err))?;
+
+    let mut entries = vec![];
+
+    for volume in volumes {
+        let path = output.join(&volume.file_name);
+
+        trace!("Adding volume '{}' to OPDS catalog...", volume.file_name);
+
+        let (size, updated) =
+            match fs::metadata(&path).and_then(|meta| Ok((meta.len(), meta.modified()?))) {
+                Ok(meta) => meta,
+                Err(err) => {
+                    warn!(
+                        "Warning: failed to read volume '{}', skipping it in the OPDS catalog: {}",
+                        path.to_string_lossy(),
+                        err
+                    );
+                    continue;
+                }
+            };
+
+        let format = deter::sniff_format(&path).ok().flatten();
+
+        // Metadata and covers are only read from ZIP volumes
+        let (metadata, cover) = if format == Some(ComicFormat::Zip) {
+            match opds::read_volume(&path, enc_opts.accept_extended_image_formats) {
+                Ok(read) => read,
+                Err(err) => {
+                    warn!(
+                        "Warning: failed to read metadata and cover of volume '{}': {}",
+                        path.to_string_lossy(),
+                        err
+                    );
+                    (VolumeInfo::default(), None)
+                }
+            }
+        } else {
+            (VolumeInfo::default(), None)
+        };
+
+        let cover = match cover {
+            Some((ext, content)) => {
+                let cover_name = format!(
+                    "{}/{}.{}",
+                    OPDS_COVERS_DIR_NAME,
+                    Path::new(&volume.file_name)
+                        .file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy(),
+                    ext
+                );
+
+                let cover_path = output.join(&cover_name);
+
+                fs::write(&cover_path, content).map_err(|err| {
+                    EncodingError::FailedToWriteOpdsCover(cover_path.clone(), err)
+                })?;
+
+                Some(cover_name)
+            }
+
+            None => None,
+        };
+
+        entries.push(OpdsEntry {
+            id: format!("urn:sha256:{}", volume.sha256),
+            file_name: volume.file_name,
+            format,
+            size,
+            updated,
+            metadata,
+            cover,
+        });
+    }
+
+    let catalog_path = output.join(OPDS_CATALOG_FILE_NAME);
+
+    debug!(
+        "Writing OPDS catalog of {} volume(s) to '{}'...",
+        entries.len(),
+        catalog_path.to_string_lossy()
+    );
+
+    let catalog = opds::render_catalog(
+        &format!("urn:sha256:{}", manifest::sha256(series_name.as_bytes())),
+        series_name,
+        &entries,
+    );
+
+    fs::write(&catalog_path, catalog)
+        .map_err(|err| EncodingError::FailedToWriteOpdsCatalog(catalog_path.clone(), err))?;
+
+    report.count("catalog entries", entries.len());
+    report.outputs.push(catalog_path);
+
+    Ok(())
+}
+
+/// Register a built volume in the manifest of the output directory
+/// Failing to do so is not fatal as the volume has been built nonetheless
+fn register_volume(output: &Path, built: &BuiltVolume) {
+    trace!("Registering volume in manifest...");
+
+    let sha256 = match manifest::file_sha256(&built.path) {
+        Ok(sha256) => sha256,
+        Err(err) => {
+            warn!("Warning: failed to compute digest of volume file: {}", err);
+            return;
+        }
+    };
+
+    let volume = ManifestVolume {
+        base_name: built.base_name.clone(),
+        file_name: built
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        sha256,
+    };
+
+    if let Err(err) = Manifest::update(output, |manifest| manifest.register_volume(volume)) {
+        warn!("Warning: failed to update manifest: {}", err);
+    }
+}
+
+/// Let the user edit the planned volumes interactively
+/// Chapters are numbered again following their new order, and the unselected ones are reported as skipped
+/// Returns `None` if the user aborted
+fn edit_plan(
+    planned_volumes: Vec<(usize, usize, Vec<(usize, PathBuf, String)>)>,
+    allow_grouping: bool,
+    enc_opts: &EncodingOptions,
+    ignore_file: &IgnoreFile,
+    report: &mut Report,
+) -> Result<Option<Vec<(usize, usize, Vec<(usize, PathBuf, String)>)>>, EncodingError> {
+    trace!("Counting pages of each chapter...");
+
+    let mut chapters = vec![];
+
+    for (_, _, volume_chapters) in planned_volumes {
+        for (i, (_, path, name)) in volume_chapters.into_iter().enumerate() {
+            let pages = planner::count_chapter_pages(
+                &path,
+                &|path| is_page(enc_opts, path),
+                ignore_file,
+            )
+            .map_err(|err| {
+                EncodingError::FailedToCountChapterPages(path.clone(), err.to_string())
+            })?;
+
+            chapters.push(PlanChapter {
+                path,
+                name,
+                pages,
+                selected: true,
+                starts_volume: i == 0,
+            });
+        }
+    }
+
+    let edited_volumes = match plan_editor::edit_plan(chapters.clone(), allow_grouping)
+        .map_err(|err| EncodingError::FailedToEditPlan(err.to_string()))?
+    {
+        Some(edited_volumes) => edited_volumes,
+        None => return Ok(None),
+    };
+
+    // Keep track of the chapters the user left out
+    report.skipped_chapters.extend(
+        chapters
+            .into_iter()
+            .filter(|chapter| {
+                !edited_volumes
+                    .iter()
+                    .flatten()
+                    .any(|selected| selected.path == chapter.path)
+            })
+            .map(|chapter| chapter.name),
+    );
+
+    let mut planned_volumes = vec![];
+
+    // First chapter of current volume
+    let mut volume_start_chapter = 1;
+
+    for (i, volume_chapters) in edited_volumes.into_iter().enumerate() {
+        let volume_chapters = volume_chapters
+            .into_iter()
+            .enumerate()
+            .map(|(j, chapter)| (volume_start_chapter + j, chapter.path, chapter.name))
+            .collect::<Vec<_>>();
+
+        let size = volume_chapters.len();
+
+        planned_volumes.push((i + 1, volume_start_chapter, volume_chapters));
+        volume_start_chapter += size;
+    }
+
+    Ok(Some(planned_volumes))
+}
+
+/// Display the volumes that would be built, and render a contact sheet for each of them if asked to
+fn preview_plan(
+    opts: &CompilationOptions,
+    enc_opts: &EncodingOptions,
+    planned_volumes: &[(usize, usize, Vec<(usize, PathBuf, String)>)],
+    vol_num_len: usize,
+    ignore_file: &IgnoreFile,
+    report: &mut Report,
+) -> Result<(), EncodingError> {
+    // Create the thumbnails directory if needed
+    let thumbnails_dir = match &opts.plan_thumbnails {
+        Some(dir) => {
+            let dir = env::current_dir()
+                .map_err(EncodingError::FailedToGetCWD)?
+                .join(dir);
+
+            fs::create_dir_all(&dir).map_err(|err| {
+                EncodingError::FailedToCreatePlanThumbnailsDirectory(dir.clone(), err)
+            })?;
+
+            Some(dir)
+        }
+
+        None => None,
+    };
+
+    // Get timestamp to measure the preview phase
+    let preview_started = Instant::now();
+
+    for (volume, volume_start_chapter, volume_chapters) in planned_volumes {
+        info!(
+            "Volume {:0vol_num_len$} would contain chapter(s) {} to {}:",
+            volume,
+            volume_start_chapter,
+            volume_start_chapter + volume_chapters.len() - 1,
+            vol_num_len = vol_num_len
+        );
+
+        for (chapter, _, chapter_name) in volume_chapters {
+            info!("    Chapter {}: '{}'", chapter, chapter_name);
+        }
+
+        let thumbnails_dir = match &thumbnails_dir {
+            Some(dir) => dir,
+            None => continue,
+        };
+
+        // Get the first page of each chapter, ignoring the chapters they can't be read from
+        let mut first_pages = vec![];
+
+        for (chapter, chapter_path, _) in volume_chapters {
+            match contact_sheet::read_first_page(
+                chapter_path,
+                enc_opts.accept_extended_image_formats,
+                enc_opts.simple_sorting,
+                enc_opts.roman_numerals,
+                ignore_file,
+            ) {
+                Ok(Some(page)) => first_pages.push(page),
+                Ok(None) => warn!("Chapter {} has no page to preview.", chapter),
+                Err(err) => warn!("Failed to preview chapter {}: {}", chapter, err),
+            }
+        }
+
+        let sheet_path = thumbnails_dir.join(format!(
+            "Volume-{:0vol_num_len$}.png",
+            volume,
+            vol_num_len = vol_num_len
+        ));
+
+        trace!(
+            "Writing contact sheet of volume {} to '{}'...",
+            volume,
+            sheet_path.to_string_lossy()
+        );
+
+        contact_sheet::render_contact_sheet(&first_pages)
+            .save(&sheet_path)
+            .map_err(|err| EncodingError::FailedToSavePlanThumbnail(sheet_path.clone(), err))?;
+
+        report.outputs.push(sheet_path);
+    }
+
+    report.phase("preview", preview_started.elapsed());
+    report.count("volumes", planned_volumes.len());
+
+    Ok(())
 }
@@ -0,0 +1,137 @@
+use crate::cli::error::EncodingError;
+use crate::cli::opts::{Convert, EncodeSingle, EncodingOptions};
+use crate::lib::build_vol::{
+    build_volume, load_signing_key, BuildMethod, BuildVolumeArgs, SIDECAR_EXT,
+};
+use crate::lib::deter;
+use crate::lib::ignore_file::IgnoreFile;
+use crate::lib::manifest;
+use crate::lib::report::Report;
+use std::env;
+use std::time::Instant;
+
+/// Convert a comic book to another format
+/// Pages are streamed from the input to the output, without going through a temporary directory
+pub fn convert(opts: &Convert) -> Result<Report, EncodingError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(EncodingError::FailedToGetCWD)?;
+
+    let input = cwd.join(&opts.input);
+
+    if !input.is_file() {
+        return Err(EncodingError::ConversionInputNotFound(input));
+    }
+
+    if !opts.to.is_supported_for_encoding() {
+        return Err(EncodingError::UnsupportedConversionOutput(opts.to));
+    }
+
+    // Get timestamp to measure the scanning phase
+    let scan_started = Instant::now();
+
+    // Detect the input's format from its content (EPUB documents are ZIP archives as well)
+    let input_format = deter::sniff_format(&input)
+        .map_err(|err| EncodingError::FailedToReadConversionInput(input.clone(), err))?;
+
+    match input_format {
+        Some(format) if format.is_supported_for_chapters() => {}
+        format => return Err(EncodingError::UnsupportedConversionInput(input, format)),
+    }
+
+    // Recover the chapters and metadata of the input from its sidecar file or embedded manifest, if any
+    let record = manifest::load_volume_record(&input);
+
+    report.phase("scan", scan_started.elapsed());
+
+    let input_name = input
+        .file_stem()
+        .ok_or(EncodingError::SingleInputDirectorHasNoName)?
+        .to_string_lossy()
+        .to_string();
+
+    // Get the path of the output file, derived from the input's name if the output is a directory
+    let output = match &opts.output {
+        Some(output) if !output.is_dir() => cwd.join(output),
+        output => deter::add_extension(
+            match output {
+                Some(output) => cwd.join(output).join(&input_name),
+                None => input.with_extension(""),
+            },
+            opts.to.comic_ext(),
+        ),
+    };
+
+    if output == input && !opts.overwrite {
+        return Err(EncodingError::OutputVolumeFileAlreadyExists(1, output));
+    }
+
+    info!(
+        "Converting '{}' ({}) to '{}' ({})...",
+        input.to_string_lossy(),
+        input_format.unwrap(),
+        output.to_string_lossy(),
+        opts.to
+    );
+
+    let metadata = record
+        .as_ref()
+        .map(|record| record.metadata.clone())
+        .unwrap_or_default();
+
+    // Options to encode the converted volume with, keeping the metadata and sidecar file of the input
+    let enc_opts = EncodingOptions {
+        input: input.clone(),
+        output: Some(output.clone()),
+        overwrite: opts.overwrite,
+        accept_extended_image_formats: opts.accept_extended_image_formats,
+        simple_sorting: opts.simple_sorting,
+        format: Some(opts.to),
+        compress_losslessly: opts.compress_losslessly,
+        sign_key: opts.sign_key.clone(),
+        title: metadata.title,
+        series: metadata.series,
+        writer: metadata.writer,
+        volume_number: metadata.number.and_then(|number| number.parse().ok()),
+        year: metadata.year,
+        reading_direction: metadata.reading_direction,
+        anilist_id: metadata.anilist_id,
+        mangadex_id: metadata.mangadex_id,
+        comicvine_id: metadata.comicvine_id,
+        sidecar_json: input.with_extension(SIDECAR_EXT).is_file(),
+        ..Default::default()
+    };
+
+    let signing_key = load_signing_key(&enc_opts)?;
+
+    // Get timestamp to measure the building phase
+    let build_started = Instant::now();
+
+    // The input is encoded as the single chapter of a single volume
+    let built = build_volume(&BuildVolumeArgs {
+        method: &BuildMethod::Single(&EncodeSingle {
+            no_auto_extension: false,
+        }),
+        enc_opts: &enc_opts,
+        output: &output,
+        volume: 1,
+        volumes: 1,
+        vol_num_len: 1,
+        chapter_num_len: 1,
+        start_chapter: 1,
+        chapters: &vec![(1, input, input_name)],
+        signing_key: signing_key.as_ref(),
+        ignore_file: &IgnoreFile::default(),
+        series_metadata: None,
+        sources: None,
+        previous_manifest: record.as_ref().map(|record| &record.manifest),
+    })?;
+
+    report.phase("build", build_started.elapsed());
+    report.count("pages", built.pages.unwrap_or(0));
+    report.count("renamed entries", built.renamed_entries);
+    report.outputs.push(built.path);
+
+    Ok(report)
+}
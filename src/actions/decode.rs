@@ -1,17 +1,28 @@
 use crate::cli::error::DecodingError;
 use crate::cli::opts::Decode;
-use crate::lib::deter;
-use pdf::file::File as PDFFile;
-use pdf::object::XObject;
+use crate::lib::comic_info::{
+    self, EmbeddedMetadata, COMIC_INFO_FILE_NAME, EMBEDDED_METADATA_FILE_NAME,
+};
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::entry_names;
+use crate::lib::pdf_images::{self, PdfImagesErr};
+use crate::lib::report::{self, Report};
+use crate::lib::safety;
+use crate::lib::sevenz;
+use crate::lib::sort;
+use crate::lib::source_meta;
+use std::collections::HashMap;
 use std::env;
 use std::fs::{self, File};
-use std::io;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 use zip::ZipArchive;
 
 /// Perform a decoding using the provided configuration object
-pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
+pub fn decode(dec: &Decode) -> Result<Report, DecodingError> {
+    let mut report = Report::new();
+
     // Get absolute path to the input for path manipulation
     let input = env::current_dir()
         .map_err(DecodingError::FailedToGetCWD)?
@@ -48,23 +59,60 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
         }
     };
 
-    // Get the input file's extension to determine its format
+    // Pages must not be extracted to a directory typed by mistake
+    if safety::is_dangerous_dir(&output) {
+        return Err(DecodingError::DangerousOutputDirectory(output));
+    }
+
+    // Get the input file's extension to determine its claimed format
     let ext = input
         .extension()
-        .ok_or_else(|| DecodingError::UnsupportedFormat(String::new()))?;
+        .map(|ext| {
+            ext.to_str()
+                .ok_or_else(|| DecodingError::InputFileHasInvalidUTF8FileExtension(
+                    input.file_name().unwrap().to_os_string(),
+                ))
+        })
+        .transpose()?;
+
+    let claimed = ext.and_then(ComicFormat::from_ext);
+
+    // Determine the input file's actual format from its content
+    let detected = deter::sniff_format(&input).map_err(DecodingError::FailedToReadInputFile)?;
+
+    let format = match (dec.force_format, claimed, detected) {
+        (Some(forced), _, _) => {
+            debug!("Using forced format: {}", forced);
+            forced
+        }
 
-    let ext = ext
-        .to_str()
-        .ok_or_else(|| DecodingError::InputFileHasInvalidUTF8FileExtension(
-            input.file_name().unwrap().to_os_string(),
-        ))?;
+        (None, Some(claimed), Some(detected)) if claimed != detected => {
+            return Err(DecodingError::FormatMismatch { claimed, detected })
+        }
+
+        (None, _, Some(detected)) => detected,
+
+        (None, Some(claimed), None) => {
+            warn!(
+                "Failed to detect input format from its content, relying on its extension ({})",
+                claimed
+            );
+            claimed
+        }
+
+        (None, None, None) => {
+            return Err(DecodingError::UnsupportedFormat(
+                ext.unwrap_or_default().to_owned(),
+            ))
+        }
+    };
 
     // Get timestamp to measure decoding time
     let extraction_started = Instant::now();
 
     // Decode
-    let result = match ext.to_lowercase().as_str() {
-        "zip" | "cbz" => {
+    let result = match format {
+        ComicFormat::Zip => {
             debug!("Matched input format: ZIP / CBZ");
             trace!("Opening input file...");
 
@@ -76,16 +124,20 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
 
             let zip_files = zip.len();
 
-            /// Represent a page that has been extracted from the comic archive
-            struct ExtractedFile {
+            /// Represent a page of the comic archive
+            struct ArchivePage {
+                index: usize,
                 path_in_zip: PathBuf,
-                extracted_path: PathBuf,
                 extension: Option<String>,
             }
 
-            // List of extracted pages
-            let mut pages: Vec<ExtractedFile> = vec![];
+            // List of pages to extract
+            let mut pages: Vec<ArchivePage> = vec![];
+
+            // Path to the extracted metadata file and its content, if any
+            let mut extracted_comic_info = None;
 
+            // List the pages first, so they can be extracted directly to their final name once sorted
             for i in 0..zip.len() {
                 trace!("Retrieving ZIP file with ID {}...", i);
 
@@ -94,7 +146,36 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
 
                 // Ignore folders
                 if file.is_file() {
-                    let file_name = file.sanitized_name();
+                    // Decode the name with the provided encoding if it isn't flagged as UTF-8
+                    let file_name = entry_names::sanitize_entry_name(&entry_names::decode_entry_name(
+                        &file,
+                        dec.entry_encoding,
+                        dec.fallback_encoding,
+                    ));
+
+                    // Keep the metadata file as it is, so it is embedded again if the pages are encoded back
+                    if comic_info::is_comic_info(&file_name) {
+                        let outpath = output.join(COMIC_INFO_FILE_NAME);
+
+                        debug!("Extracting metadata file {} out of {}...", i + 1, zip_files);
+
+                        let mut content = vec![];
+
+                        file.read_to_end(&mut content).map_err(|err| {
+                            DecodingError::FailedToExtractZipFile {
+                                path_in_zip: file_name.clone(),
+                                extract_to: outpath.clone(),
+                                err,
+                            }
+                        })?;
+
+                        fs::write(&outpath, &content).map_err(|err| {
+                            DecodingError::FailedToCreateOutputFile(err, outpath.clone())
+                        })?;
+
+                        extracted_comic_info = Some((outpath, content));
+                        continue;
+                    }
 
                     // Ensure the file is an image if only images have to be extracted
                     if dec.extract_images_only
@@ -115,28 +196,10 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
                         })
                         .transpose()?;
 
-                    let outpath = output.join(Path::new(&format!("___tmp_pic_{}", pages.len())));
-
-                    // Create output file
-                    trace!("File is a page. Creating an output file for it...");
-                    let mut outfile = File::create(&outpath).map_err(|err| {
-                        DecodingError::FailedToCreateOutputFile(err, outpath.clone())
-                    })?;
-
-                    // Extract the page
-                    debug!("Extracting file {} out of {}...", i + 1, zip_files);
-                    io::copy(&mut file, &mut outfile).map_err(|err| {
-                        DecodingError::FailedToExtractZipFile {
-                            path_in_zip: file_name.clone(),
-                            extract_to: outpath.clone(),
-                            err,
-                        }
-                    })?;
-
-                    pages.push(ExtractedFile {
+                    pages.push(ArchivePage {
+                        index: i,
                         extension: ext.map(|ext| ext.to_owned()),
                         path_in_zip: file_name,
-                        extracted_path: outpath,
                     });
                 }
             }
@@ -146,7 +209,11 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
             if dec.simple_sorting {
                 pages.sort_by(|a, b| a.path_in_zip.cmp(&b.path_in_zip));
             } else {
-                pages.sort_by(|a, b| deter::natural_paths_cmp(&a.path_in_zip, &b.path_in_zip));
+                sort::natural_sort_by_path(
+                    &mut pages,
+                    |page| &page.path_in_zip,
+                    dec.roman_numerals,
+                );
             }
 
             let total_pages = pages.len();
@@ -156,10 +223,8 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
             // Get the number of characters the last page takes to display
             let page_num_len = pages.len().to_string().len();
 
-            debug!("Renaming pictures...");
-
             for (i, page) in pages.into_iter().enumerate() {
-                let target = output.join(&match page.extension {
+                let outpath = output.join(&match page.extension {
                     None => format!("{:0page_num_len$}", i + 1, page_num_len = page_num_len),
                     Some(ref ext) => format!(
                         "{:0page_num_len$}.{}",
@@ -169,63 +234,283 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
                     ),
                 });
 
-                trace!("Renaming picture {}/{}...", i + 1, total_pages);
+                let mut file = zip.by_index(page.index).map_err(DecodingError::ZipError)?;
+
+                // Create output file
+                trace!("Creating an output file for page {}/{}...", i + 1, total_pages);
+                let mut outfile = File::create(&outpath).map_err(|err| {
+                    DecodingError::FailedToCreateOutputFile(err, outpath.clone())
+                })?;
 
-                fs::rename(&page.extracted_path, &target).map_err(|err| {
-                    DecodingError::FailedToRenameTemporaryFile {
-                        from: page.extracted_path,
-                        to: target.to_owned(),
+                // Extract the page
+                debug!("Extracting page {} out of {}...", i + 1, total_pages);
+                io::copy(&mut file, &mut outfile).map_err(|err| {
+                    DecodingError::FailedToExtractZipFile {
+                        path_in_zip: page.path_in_zip.clone(),
+                        extract_to: outpath.clone(),
                         err,
                     }
                 })?;
 
-                extracted.push(target);
+                extracted.push(outpath);
             }
 
+            report.phase("extract", extraction_started.elapsed());
+
+            // Regroup the archive's comment and metadata file, so they can be reused by other tools
+            write_embedded_metadata(&mut report, &output, zip.comment(), extracted_comic_info)?;
+
             Ok(extracted)
         }
 
-        "pdf" => {
-            debug!("Matched input format: PDF");
-            trace!("Opening input file...");
+        ComicFormat::SevenZip => {
+            debug!("Matched input format: 7-Zip / CB7");
+
+            // Files of solid archives cannot be read one by one without decompressing the previous ones again,
+            //  so they are always extracted in a single pass, in their order in the archive
+            if sevenz::is_solid(&input)
+                .map_err(|err| DecodingError::InvalidSevenZipArchive(err.to_string()))?
+            {
+                warn!("Warning: input is a solid 7-Zip archive, all of its files will be decompressed (including the ones which are not extracted) in a single pass");
+            }
+
+            trace!("Listing files of the 7-Zip archive...");
+
+            let files = sevenz::list_files(&input)
+                .map_err(|err| DecodingError::InvalidSevenZipArchive(err.to_string()))?;
+
+            let total_files = files.len();
+
+            // List the pages first, so they can be extracted directly to their final name once sorted
+            // Pages are identified by their position in the archive, as several files may have the same name
+            let mut pages = files
+                .iter()
+                .enumerate()
+                .map(|(index, name)| (entry_names::sanitize_entry_name(name), index))
+                .filter(|(path, _)| {
+                    !comic_info::is_comic_info(path)
+                        && (!dec.extract_images_only
+                            || deter::has_image_ext(path, dec.accept_extended_image_formats))
+                })
+                .collect::<Vec<_>>();
+
+            trace!("Sorting pages...");
+
+            if dec.simple_sorting {
+                pages.sort_by(|a, b| a.0.cmp(&b.0));
+            } else {
+                sort::natural_sort_by_path(
+                    &mut pages,
+                    |(path, _)| path.as_path(),
+                    dec.roman_numerals,
+                );
+            }
+
+            let total_pages = pages.len();
+
+            // Get the number of characters the last page takes to display
+            let page_num_len = pages.len().to_string().len();
+
+            let extracted = pages
+                .iter()
+                .enumerate()
+                .map(|(i, (path, _))| {
+                    output.join(&match path.extension() {
+                        None => format!("{:0page_num_len$}", i + 1, page_num_len = page_num_len),
+                        Some(ext) => format!(
+                            "{:0page_num_len$}.{}",
+                            i + 1,
+                            ext.to_string_lossy(),
+                            page_num_len = page_num_len
+                        ),
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            // Path each page is extracted to, by its position in the archive
+            let outpaths = pages
+                .iter()
+                .map(|(_, index)| *index)
+                .zip(extracted.iter())
+                .collect::<HashMap<_, _>>();
+
+            // Files are compressed together, so they are extracted in their order in the archive
+            let mut extracted_comic_info = None;
+            let mut extracted_pages = 0;
+            let mut read_files = 0;
+            let mut failure = None;
+
+            sevenz::for_each_file(&input, |name, content| {
+                let index = read_files;
+                read_files += 1;
+
+                if failure.is_some() {
+                    return;
+                }
+
+                // Keep the metadata file as it is, so it is embedded again if the pages are encoded back
+                if comic_info::is_comic_info(&entry_names::sanitize_entry_name(name)) {
+                    let outpath = output.join(COMIC_INFO_FILE_NAME);
+
+                    debug!("Extracting metadata file...");
+
+                    let mut bytes = vec![];
 
-            let pdf = PDFFile::open(input).map_err(DecodingError::FailedToOpenPdfFile)?;
+                    failure = content
+                        .read_to_end(&mut bytes)
+                        .and_then(|_| fs::write(&outpath, &bytes))
+                        .map_err(|err| DecodingError::FailedToExtractSevenZipFile {
+                            path_in_archive: name.to_owned(),
+                            extract_to: outpath.clone(),
+                            err,
+                        })
+                        .err();
+
+                    extracted_comic_info = Some((outpath, bytes));
+                    return;
+                }
+
+                let outpath = match outpaths.get(&index) {
+                    Some(outpath) => *outpath,
+                    None => {
+                        trace!("Ignoring file '{}' based on extension", name);
+                        return;
+                    }
+                };
+
+                extracted_pages += 1;
+
+                // Extract the page
+                debug!(
+                    "Extracting page {} out of {}...",
+                    extracted_pages, total_pages
+                );
+
+                failure = File::create(outpath)
+                    .map_err(|err| DecodingError::FailedToCreateOutputFile(err, outpath.clone()))
+                    .and_then(|mut outfile| {
+                        io::copy(content, &mut outfile).map_err(|err| {
+                            DecodingError::FailedToExtractSevenZipFile {
+                                path_in_archive: name.to_owned(),
+                                extract_to: outpath.clone(),
+                                err,
+                            }
+                        })
+                    })
+                    .err();
+            })
+            .map_err(|err| DecodingError::InvalidSevenZipArchive(err.to_string()))?;
+
+            if let Some(err) = failure {
+                return Err(err);
+            }
+
+            // Files are matched by their position, so they must be read in the order they were listed
+            if read_files != total_files || extracted_pages != total_pages {
+                return Err(DecodingError::InvalidSevenZipArchive(format!(
+                    "{} files were listed ({} pages) but {} were read ({} pages)",
+                    total_files, total_pages, read_files, extracted_pages
+                )));
+            }
 
-            let mut images = vec![];
+            report.phase("extract", extraction_started.elapsed());
+
+            // 7-Zip archives don't have a comment
+            write_embedded_metadata(&mut report, &output, &[], extracted_comic_info)?;
+
+            Ok(extracted)
+        }
+
+        ComicFormat::Pdf => {
+            debug!("Matched input format: PDF");
+            trace!("Opening input file...");
 
             debug!("Looking for images in the provided PDF...");
 
-            // List all images in the PDF
-            for (i, page) in pdf.pages().enumerate() {
-                trace!("Counting images from page {}...", i);
+            let images = pdf_images::extract_jpeg_images_with_pages(&input, dec.skip_bad_pdf_pages)
+                .map_err(|err| match err {
+                    PdfImagesErr::FailedToOpen(err) => DecodingError::FailedToOpenPdfFile(err),
+                    PdfImagesErr::FailedToGetPage(page, err) => {
+                        DecodingError::FailedToGetPdfPage(page, err)
+                    }
+                    PdfImagesErr::FailedToGetPageResources(page, err) => {
+                        DecodingError::FailedToGetPdfPageResources(page, err)
+                    }
+                })?;
 
-                match page.map_err(|err| DecodingError::FailedToGetPdfPage(i + 1, err)) {
-                    Err(err) if dec.skip_bad_pdf_pages => warn!("{}", err),
-                    Err(err) => return Err(err),
-                    Ok(page) => match page
-                        .resources(&pdf)
-                        .map_err(|err| DecodingError::FailedToGetPdfPageResources(i + 1, err))
+            // Put the images in one directory per bookmark, named after it
+            let bookmarks = if dec.ignore_pdf_bookmarks {
+                vec![]
+            } else {
+                pdf_images::read_bookmarks(&input).unwrap_or_else(|err| {
+                    warn!("Warning: failed to read the PDF's bookmarks, all pages will be extracted in a single directory: {}", err);
+                    vec![]
+                })
+            };
+
+            let chapter_num_len = bookmarks.len().to_string().len();
+
+            let chapter_dirs = bookmarks
+                .iter()
+                .enumerate()
+                .map(|(i, bookmark)| {
+                    match bookmark
+                        .title
+                        .as_deref()
+                        .map(source_meta::sanitize_file_name)
+                        .filter(|title| !title.is_empty())
                     {
-                        Err(err) if dec.skip_bad_pdf_pages => warn!("{}", err),
-                        Err(err) => return Err(err),
-                        Ok(resources) => {
-                            images.extend(resources.xobjects.iter().filter_map(|(_, o)| match o {
-                                XObject::Image(im) => Some(im.clone()),
-                                _ => None,
-                            }));
-                        }
-                    },
-                }
+                        Some(title) => output.join(format!(
+                            "{:0chapter_num_len$} - {}",
+                            i + 1,
+                            title,
+                            chapter_num_len = chapter_num_len
+                        )),
+                        None => output.join(format!(
+                            "{:0chapter_num_len$}",
+                            i + 1,
+                            chapter_num_len = chapter_num_len
+                        )),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            if !chapter_dirs.is_empty() {
+                debug!(
+                    "Splitting the PDF into {} chapters using its bookmarks...",
+                    chapter_dirs.len()
+                );
+                report.count("chapters", chapter_dirs.len());
             }
 
+            report.phase("scan", extraction_started.elapsed());
+
+            // Get timestamp to measure the extraction phase
+            let images_extraction_started = Instant::now();
+
             info!("Extracting {} images from PDF...", images.len());
 
             let mut extracted = vec![];
             let page_num_len = images.len().to_string().len();
 
             // Extract all images from the PDF
-            for (i, image) in images.iter().enumerate() {
-                let outpath = output.join(Path::new(&format!(
+            for (i, (page, image)) in images.iter().enumerate() {
+                // Pages located before the first bookmark are put in the first chapter
+                let dir = match bookmarks
+                    .iter()
+                    .rposition(|bookmark| bookmark.page <= *page)
+                {
+                    Some(chapter) => &chapter_dirs[chapter],
+                    None => chapter_dirs.first().unwrap_or(&output),
+                };
+
+                if !dir.exists() {
+                    fs::create_dir(dir).map_err(|err| {
+                        DecodingError::FailedToCreateChapterDirectory(dir.clone(), err)
+                    })?;
+                }
+
+                let outpath = dir.join(Path::new(&format!(
                     "{:0page_num_len$}.jpg",
                     i + 1,
                     page_num_len = page_num_len
@@ -233,34 +518,93 @@ pub fn decode(dec: &Decode) -> Result<Vec<PathBuf>, DecodingError> {
 
                 debug!("Extracting page {}/{}...", i + 1, images.len());
 
-                fs::write(&outpath, image.as_jpeg().unwrap()).map_err(|err| {
+                fs::write(&outpath, image).map_err(|err| {
                     DecodingError::FailedToExtractPdfImage(i + 1, outpath.clone(), err)
                 })?;
 
                 extracted.push(outpath);
             }
 
+            // Keep the document's informations, so they are embedded if the pages are encoded back
+            match pdf_images::read_info(&input) {
+                Ok(info) => {
+                    if let Some(xml) = info.to_comic_info() {
+                        let outpath = output.join(COMIC_INFO_FILE_NAME);
+
+                        debug!("Writing document informations to '{}'...", outpath.to_string_lossy());
+
+                        fs::write(&outpath, xml).map_err(|err| {
+                            DecodingError::FailedToCreateOutputFile(err, outpath.clone())
+                        })?;
+
+                        report.outputs.push(outpath);
+                    }
+                }
+
+                Err(err) => warn!("Warning: failed to read the PDF's document informations: {}", err),
+            }
+
+            report.phase("extract", images_extraction_started.elapsed());
+
             Ok(extracted)
         }
 
-        _ => {
-            if deter::is_supported_for_decoding(ext) {
-                warn!("Internal error: format '{}' cannot be handled but is marked as supported nonetheless", ext);
+        ComicFormat::Rar | ComicFormat::Tar => {
+            if format.is_supported_for_decoding() {
+                warn!("Internal error: format '{}' cannot be handled but is marked as supported nonetheless", format);
             }
 
-            Err(DecodingError::UnsupportedFormat(ext.to_owned()))
+            Err(DecodingError::UnsupportedFormat(format.to_string()))
         }
     };
 
-    if let Ok(pages) = &result {
-        let elapsed = extraction_started.elapsed();
-        info!(
-            "Successfully extracted {} pages in {}.{:03} s!",
-            pages.len(),
-            elapsed.as_secs(),
-            elapsed.subsec_millis()
-        );
+    let pages = result?;
+
+    info!(
+        "Successfully extracted {} pages in {}!",
+        pages.len(),
+        report::format_duration(extraction_started.elapsed())
+    );
+
+    report.count("pages", pages.len());
+    report.outputs.extend(pages);
+
+    Ok(report)
+}
+
+/// Regroup the comment and the metadata file of an archive, so they can be reused by other tools
+/// `extracted_comic_info` is the path the metadata file was extracted to and its content, if any
+fn write_embedded_metadata(
+    report: &mut Report,
+    output: &Path,
+    comment: &[u8],
+    extracted_comic_info: Option<(PathBuf, Vec<u8>)>,
+) -> Result<(), DecodingError> {
+    let metadata = EmbeddedMetadata::from_parts(
+        comment,
+        extracted_comic_info
+            .as_ref()
+            .map(|(_, content)| String::from_utf8_lossy(content).into_owned()),
+    );
+
+    if let Some((path, _)) = extracted_comic_info {
+        report.outputs.push(path);
     }
 
-    result
+    if let Some(metadata) = &metadata {
+        let outpath = output.join(EMBEDDED_METADATA_FILE_NAME);
+
+        debug!("Writing embedded metadata to '{}'...", outpath.to_string_lossy());
+
+        serde_json::to_string_pretty(metadata)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+            .and_then(|json| fs::write(&outpath, json))
+            .map_err(|err| DecodingError::FailedToWriteMetadataFile(outpath.clone(), err))?;
+
+        report.outputs.push(outpath);
+    }
+
+    report.metadata = metadata;
+
+    Ok(())
 }
@@ -0,0 +1,198 @@
+use crate::cli::error::DoctorError;
+use crate::cli::opts::Doctor;
+use crate::lib::deter::ComicFormat;
+use crate::lib::history::RunHistory;
+use crate::lib::jpegtran;
+use crate::lib::report::Report;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Minimum number of files that can be opened at once for large compilations to work reliably
+const MIN_OPEN_FILES_LIMIT: u64 = 1024;
+
+/// Minimum free space (in MB) of the temporary and output directories, as whole volumes are written to them while building
+const MIN_FREE_SPACE_MB: u64 = 1024;
+
+/// Check the environment comic-enc runs in, displaying a finding for each check
+pub fn doctor(opts: &Doctor) -> Result<Report, DoctorError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(DoctorError::FailedToGetCWD)?;
+
+    // Number of checks that found a problem
+    let mut problems = 0;
+
+    let mut check = |result: Result<String, String>| match result {
+        Ok(finding) => info!("OK: {}", finding),
+        Err(finding) => {
+            warn!("Warning: {}", finding);
+            problems += 1;
+        }
+    };
+
+    // Check the directories comic-enc writes to
+    check(check_writable("Temporary directory", &env::temp_dir()));
+    check(check_free_space("Temporary directory", &env::temp_dir()));
+
+    let output = match &opts.output {
+        Some(output) => cwd.join(output),
+        None => cwd.clone(),
+    };
+
+    check(check_writable("Output directory", &output));
+    check(check_free_space("Output directory", &output));
+
+    check(match RunHistory::path() {
+        Some(path) => match path.parent() {
+            Some(dir) if dir.is_dir() => check_writable("Configuration directory", dir),
+            _ => Ok(format!(
+                "Configuration directory '{}' doesn't exist yet, it will be created when recording the history",
+                path.parent().unwrap_or(&path).to_string_lossy()
+            )),
+        },
+        None => Err("Configuration directory could not be determined (set 'XDG_CONFIG_HOME' or 'HOME'), so runs cannot be recorded in the history".to_owned()),
+    });
+
+    // Check the number of files that can be opened at once
+    if let Some(limit) = open_files_limit() {
+        check(if limit >= MIN_OPEN_FILES_LIMIT {
+            Ok(format!("Up to {} files can be opened at once", limit))
+        } else {
+            Err(format!(
+                "Only {} files can be opened at once, which may not be enough to compile many chapters, raise it with 'ulimit -n {}'",
+                limit, MIN_OPEN_FILES_LIMIT
+            ))
+        });
+    }
+
+    // Check the terminal can display colors
+    check(match env::var("TERM") {
+        Ok(term) if term == "dumb" => Err("Terminal doesn't support colors ('TERM' is 'dumb'), messages will contain escape codes".to_owned()),
+        Ok(term) => Ok(format!("Terminal type is '{}'", term)),
+        Err(_) if cfg!(windows) => Ok("Terminal type is not set, which is expected on Windows".to_owned()),
+        Err(_) => Err("Terminal type is not set ('TERM'), messages may contain escape codes".to_owned()),
+    });
+
+    // Display if the program used by '--optimize-jpeg' is available, which is only required by this option
+    info!(
+        "JPEG optimization: {}",
+        if jpegtran::is_available() {
+            format!("'{}' is available", jpegtran::JPEGTRAN_PROGRAM)
+        } else {
+            format!(
+                "'{}' was not found (install libjpeg-turbo or mozjpeg to use '--optimize-jpeg')",
+                jpegtran::JPEGTRAN_PROGRAM
+            )
+        }
+    );
+
+    // Display the supported formats, as some of them require to convert the volumes first
+    for format in &[
+        ComicFormat::Zip,
+        ComicFormat::Pdf,
+        ComicFormat::Tar,
+        ComicFormat::Rar,
+        ComicFormat::SevenZip,
+    ] {
+        info!(
+            "Format {}: {} for decoding, {} for encoding",
+            format,
+            if format.is_supported_for_decoding() {
+                "supported"
+            } else {
+                "not supported"
+            },
+            if format.is_supported_for_encoding() {
+                "supported"
+            } else {
+                "not supported"
+            }
+        );
+    }
+
+    if problems == 0 {
+        info!("No problem was found.");
+    } else {
+        warn!("Warning: {} problem(s) were found.", problems);
+    }
+
+    report.count("problems", problems);
+
+    Ok(report)
+}
+
+/// Check a directory exists and files can be written to it
+fn check_writable(name: &str, dir: &Path) -> Result<String, String> {
+    if !dir.is_dir() {
+        return Err(format!(
+            "{} '{}' doesn't exist",
+            name,
+            dir.to_string_lossy()
+        ));
+    }
+
+    let probe = dir.join(format!(".comic-enc-doctor-{}", std::process::id()));
+
+    match fs::write(&probe, b"") {
+        Ok(()) => {
+            // Failing to remove the probe doesn't prevent writing to the directory
+            let _ = fs::remove_file(&probe);
+            Ok(format!("{} '{}' is writable", name, dir.to_string_lossy()))
+        }
+        Err(err) => Err(format!(
+            "{} '{}' is not writable: {}",
+            name,
+            dir.to_string_lossy(),
+            err
+        )),
+    }
+}
+
+/// Check a directory's filesystem has enough free space to build volumes in it
+fn check_free_space(name: &str, dir: &Path) -> Result<String, String> {
+    // A missing directory is already reported when checking it's writable
+    if !dir.is_dir() {
+        return Ok(format!(
+            "{} '{}' doesn't exist, its free space cannot be checked",
+            name,
+            dir.to_string_lossy()
+        ));
+    }
+
+    match fs2::available_space(dir) {
+        Ok(space) if space / 1024 / 1024 >= MIN_FREE_SPACE_MB => Ok(format!(
+            "{} '{}' has {} MB of free space",
+            name,
+            dir.to_string_lossy(),
+            space / 1024 / 1024
+        )),
+        Ok(space) => Err(format!(
+            "{} '{}' only has {} MB of free space, large volumes may fail to be built (at least {} MB are recommended)",
+            name,
+            dir.to_string_lossy(),
+            space / 1024 / 1024,
+            MIN_FREE_SPACE_MB
+        )),
+        Err(err) => Err(format!(
+            "Free space of {} '{}' could not be determined: {}",
+            name.to_lowercase(),
+            dir.to_string_lossy(),
+            err
+        )),
+    }
+}
+
+/// Get the number of files the process can open at once, if it can be determined (only on Linux)
+fn open_files_limit() -> Option<u64> {
+    let limits = fs::read_to_string("/proc/self/limits").ok()?;
+
+    limits
+        .lines()
+        .find(|line| line.starts_with("Max open files"))?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()
+}
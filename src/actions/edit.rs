@@ -0,0 +1,285 @@
+use crate::cli::error::EncodingError;
+use crate::cli::opts::{Edit, EncodingOptions};
+use crate::lib::build_vol::{
+    load_ignore_file, load_signing_key, read_volume_numbering, rewriting_zip_writer, zip_options,
+    BuildMethod, EntryNames, StagingFile, VolumeZip, STAGING_EXT,
+};
+use crate::lib::deter;
+use crate::lib::ignore_file::IgnoreFile;
+use crate::lib::manifest::{self, EmbeddedManifest};
+use crate::lib::prompt;
+use crate::lib::report::Report;
+use crate::lib::sign;
+use std::env;
+use std::fs::{self, File};
+use std::time::Instant;
+use zip::ZipArchive;
+
+/// Replace or remove a chapter inside an existing volume
+/// Entries of the other chapters are copied as they are, without being decompressed
+pub fn edit(opts: &Edit) -> Result<Report, EncodingError> {
+    let mut report = Report::new();
+
+    // Determine the chapter to edit
+    let (edited_chapter, replacement) = match (opts.replace_chapter, opts.remove_chapter) {
+        (Some(chapter), _) => (chapter, opts.chapter.as_ref()),
+        (None, Some(chapter)) => (chapter, None),
+        (None, None) => return Err(EncodingError::NothingToEdit),
+    };
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(EncodingError::FailedToGetCWD)?;
+
+    let source = cwd.join(&opts.volume);
+
+    if !source.is_file() {
+        return Err(EncodingError::ExistingVolumeNotFound(source));
+    }
+
+    let replacement = replacement.map(|path| cwd.join(path));
+
+    if let Some(replacement) = &replacement {
+        if !replacement.exists() {
+            return Err(EncodingError::ChaptersDirectoryNotFound);
+        }
+    }
+
+    // Write to another file if an output is provided, otherwise update the volume
+    let target = match &opts.output {
+        Some(output) => cwd.join(output),
+        None => source.clone(),
+    };
+
+    if target != source && target.exists() && !opts.overwrite && !prompt::confirm_overwrite(&target)
+    {
+        return Err(EncodingError::OutputVolumeFileAlreadyExists(1, target));
+    }
+
+    // Options to encode the replacement chapter with
+    let enc_opts = EncodingOptions {
+        input: replacement.clone().unwrap_or_else(|| source.clone()),
+        output: Some(target.clone()),
+        overwrite: opts.overwrite,
+        accept_extended_image_formats: opts.accept_extended_image_formats,
+        simple_sorting: opts.simple_sorting,
+        compress_losslessly: opts.compress_losslessly,
+        sign_key: opts.sign_key.clone(),
+        ..Default::default()
+    };
+
+    // Get timestamp to measure the scanning phase
+    let scan_started = Instant::now();
+
+    trace!("Reading existing volume's numbering...");
+
+    let numbering = read_volume_numbering(&source)?;
+
+    // Find the directory of the chapter to edit
+    let edited_dir = numbering
+        .chapters
+        .iter()
+        .find(|(chapter, _)| *chapter == edited_chapter)
+        .map(|(_, dir)| format!("{}/", dir))
+        .ok_or_else(|| EncodingError::ChapterNotFoundInVolume(edited_chapter, source.clone()))?;
+
+    report.phase("scan", scan_started.elapsed());
+
+    match &replacement {
+        Some(replacement) => info!(
+            "Going to replace chapter {} of volume '{}' by '{}'.",
+            edited_chapter,
+            source.to_string_lossy(),
+            replacement.to_string_lossy()
+        ),
+
+        None => info!(
+            "Going to remove chapter {} from volume '{}'.",
+            edited_chapter,
+            source.to_string_lossy()
+        ),
+    }
+
+    // Load the patterns of pages to exclude from the replacement chapter
+    let ignore_file = match &replacement {
+        Some(replacement) if replacement.is_dir() => load_ignore_file(replacement)?,
+        _ => IgnoreFile::default(),
+    };
+
+    // Load the signing key before building anything
+    let signing_key = load_signing_key(&enc_opts)?;
+
+    // Get timestamp to measure the building phase
+    let build_started = Instant::now();
+
+    let source_file = File::open(&source)
+        .map_err(|err| EncodingError::FailedToOpenExistingVolume(source.clone(), err))?;
+
+    let mut source_zip = ZipArchive::new(source_file)
+        .map_err(|err| EncodingError::InvalidExistingVolume(source.clone(), err))?;
+
+    // Records of the pages embedded in the volume must be updated with the edited chapter
+    let mut records = manifest::read_embedded_records(&mut source_zip, &source);
+
+    // Write the edited volume to a staging file, so the volume is left untouched if something goes wrong
+    let staging_path = deter::add_extension(&target, STAGING_EXT);
+
+    let staging_file = File::create(&staging_path).map_err(|err| {
+        EncodingError::FailedToCreateVolumeFile(numbering.volume, staging_path.clone(), err)
+    })?;
+
+    // Ensure the staging file is removed if anything goes wrong
+    let mut staging_guard = StagingFile {
+        path: staging_path.clone(),
+        finalized: false,
+    };
+
+    let mut volume_zip = VolumeZip {
+        zip_writer: rewriting_zip_writer(&source_zip, staging_file),
+        zip_options: zip_options(&enc_opts),
+        method: &BuildMethod::Edit(opts),
+        enc_opts: &enc_opts,
+        volume: numbering.volume,
+        vol_num_len: numbering.vol_num_len,
+        chapter_num_len: numbering.chapter_num_len,
+        volume_display_name: format!(
+            "'{}'",
+            target.file_name().unwrap_or_default().to_string_lossy()
+        ),
+        display_name_individual: None,
+        pics_counter: 0,
+        ignore_file: &ignore_file,
+        extract_dir: None,
+        comic_info: None,
+        entry_names: EntryNames::default(),
+        pages: None,
+        embedded_manifest: if records.has_manifest || records.has_checksums {
+            Some(EmbeddedManifest {
+                bit_exact: records.bit_exact,
+                pages: vec![],
+                sources: records.sources.clone(),
+            })
+        } else {
+            None
+        },
+        contact_sheet: None,
+        page_store: None,
+        pending_page: None,
+        page_digests: None,
+        duplicate_pages: 0,
+        page_hashes: None,
+        near_duplicates: vec![],
+    };
+
+    // The replacement chapter must not collide with the entries of the other chapters
+    for entry_name in &numbering.entry_names {
+        if !entry_name.starts_with(&edited_dir) {
+            volume_zip.entry_names.reserve(entry_name);
+        }
+    }
+
+    // Number of pages of the edited chapter
+    let mut removed_pages = 0;
+
+    // Number of pages of the other chapters
+    let mut kept_pages = 0;
+
+    // Was the replacement chapter already written?
+    let mut replaced = false;
+
+    for i in 0..source_zip.len() {
+        let entry = source_zip.by_index(i).map_err(|err| {
+            EncodingError::FailedToCopyExistingVolumeEntry {
+                volume_path: source.clone(),
+                entry_name: format!("#{}", i),
+                err,
+            }
+        })?;
+
+        let entry_name = entry.name().to_owned();
+        let is_file = entry.is_file();
+
+        drop(entry);
+
+        // Copy entries of the other chapters as they are
+        if !entry_name.starts_with(&edited_dir) {
+            if volume_zip.copy_existing_entry(&mut source_zip, i, &source, &mut records.pages)? {
+                kept_pages += 1;
+            }
+
+            continue;
+        }
+
+        if is_file {
+            removed_pages += 1;
+        }
+
+        // Write the replacement chapter where the edited one was
+        if let Some(replacement) = &replacement {
+            if !replaced {
+                let chapter_name = replacement
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+
+                volume_zip.add_chapter(edited_chapter, replacement, &chapter_name)?;
+                replaced = true;
+            }
+        }
+    }
+
+    // Write the metadata file again with the new number of pages
+    if let Some(xml) = &records.comic_info {
+        volume_zip.add_existing_comic_info(xml, kept_pages + volume_zip.pics_counter)?;
+    }
+
+    // Write the records of the pages again, without the removed ones
+    if let Some(embedded_manifest) = volume_zip.embedded_manifest.take() {
+        if records.has_manifest {
+            volume_zip.add_embedded_manifest(&embedded_manifest)?;
+        }
+
+        if records.has_checksums {
+            volume_zip.add_checksums(&embedded_manifest)?;
+        }
+    }
+
+    trace!("Closing ZIP archive...");
+
+    volume_zip
+        .zip_writer
+        .finish()
+        .map_err(|err| EncodingError::FailedToCloseZipArchive(numbering.volume, err))?;
+
+    // Replace the target with the edited volume
+    fs::rename(&staging_path, &target)
+        .map_err(|err| EncodingError::FailedToRenameCompleteArchive(numbering.volume, err))?;
+
+    staging_guard.finalized = true;
+
+    // Sign the edited volume file
+    if let Some(keypair) = &signing_key {
+        trace!("Signing volume...");
+
+        sign::sign_file(keypair, &target).map_err(|err| {
+            EncodingError::FailedToSignVolume(numbering.volume, target.clone(), err)
+        })?;
+    }
+
+    info!(
+        "Successfully edited chapter {} of '{}' ({} pages removed, {} pages added), which now contains {} pages.",
+        edited_chapter,
+        target.to_string_lossy(),
+        removed_pages,
+        volume_zip.pics_counter,
+        kept_pages + volume_zip.pics_counter
+    );
+
+    report.phase("build", build_started.elapsed());
+    report.count("pages removed", removed_pages);
+    report.count("pages added", volume_zip.pics_counter);
+    report.count("renamed entries", volume_zip.entry_names.renamed);
+    report.outputs.push(target);
+
+    Ok(report)
+}
@@ -1,22 +1,34 @@
 use crate::cli::opts::{EncodeSingle, EncodingOptions};
-use crate::lib::build_vol::{build_volume, BuildMethod};
+use crate::lib::build_vol::{
+    build_volume, load_ignore_file, load_signing_key, BuildMethod, DEFAULT_VOLUME_EXT,
+};
+use crate::lib::deter;
+use crate::lib::hooks;
+use crate::lib::report::Report;
 use crate::{cli::error::EncodingError, lib::build_vol::BuildVolumeArgs};
-use std::path::PathBuf;
+use std::time::Instant;
 
 /// Compile a single directory to a single volume file
 pub fn encode_one(
     opts: &EncodeSingle,
     enc_opts: &EncodingOptions,
-) -> Result<PathBuf, EncodingError> {
+) -> Result<Report, EncodingError> {
+    let mut report = Report::new();
+
     let input = enc_opts.input.clone();
 
+    // Get the path of the output file, derived from the input directory's name if the output is a directory
     let output = match &enc_opts.output {
-        Some(output) => output.clone(),
-        None => {
+        Some(output) if !output.is_dir() => output.clone(),
+        output => {
             let filename = input
                 .file_name()
                 .ok_or(EncodingError::SingleInputDirectorHasNoName)?;
-            input.join(filename).with_extension("cbz")
+
+            deter::add_extension(
+                output.as_ref().unwrap_or(&input).join(filename),
+                DEFAULT_VOLUME_EXT,
+            )
         }
     };
 
@@ -27,14 +39,28 @@ pub fn encode_one(
     }
 
     if output.is_dir() {
-        return Err(EncodingError::OutputVolumeFileAlreadyExists(1, input));
+        return Err(EncodingError::OutputVolumeFileAlreadyExists(1, output));
     }
 
     let out_filename = output
         .file_name()
         .ok_or(EncodingError::SingleOutputFileHasNoName)?;
 
-    build_volume(&BuildVolumeArgs {
+    let signing_key = load_signing_key(enc_opts)?;
+
+    // Load the patterns of pages to exclude
+    let ignore_file = load_ignore_file(&input)?;
+
+    // Run the pre-build hook once everything is ready
+    if let Some(pre_hook) = &enc_opts.pre_hook {
+        hooks::run_hook(pre_hook, &output)
+            .map_err(|err| EncodingError::PreHookFailed(err.to_string()))?;
+    }
+
+    // Get timestamp to measure the building phase
+    let build_started = Instant::now();
+
+    let built = build_volume(&BuildVolumeArgs {
         method: &BuildMethod::Single(opts),
         enc_opts,
         output: &output,
@@ -44,5 +70,27 @@ pub fn encode_one(
         chapter_num_len: 1,
         start_chapter: 1,
         chapters: &vec![(1, input, out_filename.to_string_lossy().to_string())],
-    })
+        signing_key: signing_key.as_ref(),
+        ignore_file: &ignore_file,
+        series_metadata: None,
+        sources: None,
+        previous_manifest: None,
+    })?;
+
+    report.phase("build", build_started.elapsed());
+    report.count("pages", built.pages.unwrap_or(0));
+    report.count("renamed entries", built.renamed_entries);
+
+    if enc_opts.dedupe_pages {
+        report.count("duplicate pages dropped", built.duplicate_pages);
+    }
+
+    if enc_opts.detect_near_duplicates.is_some() {
+        report.count("near-duplicate pages", built.near_duplicates.len());
+        report.near_duplicates = built.near_duplicates;
+    }
+
+    report.outputs.push(built.path);
+
+    Ok(report)
 }
@@ -0,0 +1,194 @@
+use crate::cli::error::ExtractCoverError;
+use crate::cli::opts::ExtractCover;
+use crate::lib::build_vol::STAGING_EXT;
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::pdf_images;
+use crate::lib::prompt;
+use crate::lib::report::Report;
+use crate::lib::sort;
+use crate::lib::transform;
+use image::ImageFormat;
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use zip::ZipArchive;
+
+/// Name of the cover files written next to the volumes
+pub const COVER_FILE_NAME: &str = "cover.jpg";
+
+/// Extract the cover of volumes, without decoding their other pages
+pub fn extract_cover(opts: &ExtractCover) -> Result<Report, ExtractCoverError> {
+    let mut report = Report::new();
+
+    if opts.page == Some(0) {
+        return Err(ExtractCoverError::InvalidPageNumber);
+    }
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(ExtractCoverError::FailedToGetCWD)?;
+
+    // Get timestamp to measure the extraction phase
+    let extract_started = Instant::now();
+
+    for input in &opts.input {
+        let input = cwd.join(input);
+
+        if !input.is_file() {
+            return Err(ExtractCoverError::VolumeNotFound(input));
+        }
+
+        let output = input
+            .parent()
+            .ok_or_else(|| ExtractCoverError::VolumeNotFound(input.clone()))?
+            .join(COVER_FILE_NAME);
+
+        if output.exists() && !opts.overwrite && !prompt::confirm_overwrite(&output) {
+            return Err(ExtractCoverError::OutputFileAlreadyExists(output));
+        }
+
+        debug!("Extracting cover of '{}'...", input.to_string_lossy());
+
+        let (cover_name, bytes) =
+            read_cover(&input, opts.page, opts.accept_extended_image_formats)?;
+
+        trace!(
+            "Writing page '{}' to '{}'...",
+            cover_name,
+            output.to_string_lossy()
+        );
+
+        // Write the cover to a staging file, so an existing one is left untouched if something goes wrong
+        let staging_path = deter::add_extension(&output, STAGING_EXT);
+
+        // JPEG pages are written as they are, other ones are converted
+        if image::guess_format(&bytes).ok() == Some(ImageFormat::Jpeg) {
+            fs::write(&staging_path, &bytes)
+                .map_err(|err| ExtractCoverError::FailedToWriteCover(staging_path.clone(), err))?;
+        } else {
+            trace!("Converting page '{}' to JPEG...", cover_name);
+
+            transform::load_picture(&bytes)
+                .map_err(|err| ExtractCoverError::FailedToDecodePage {
+                    volume: input.clone(),
+                    page_name: cover_name.clone(),
+                    err,
+                })?
+                .to_rgb8()
+                .save_with_format(&staging_path, ImageFormat::Jpeg)
+                .map_err(|err| ExtractCoverError::FailedToEncodeCover(staging_path.clone(), err))?;
+        }
+
+        fs::rename(&staging_path, &output)
+            .map_err(|err| ExtractCoverError::FailedToRenameCover(output.clone(), err))?;
+
+        info!(
+            "Successfully extracted cover of '{}' to '{}'.",
+            input.to_string_lossy(),
+            output.to_string_lossy()
+        );
+
+        report.outputs.push(output);
+    }
+
+    report.phase("extract", extract_started.elapsed());
+    report.count("covers", report.outputs.len());
+
+    Ok(report)
+}
+
+/// Read the cover page of a volume, or the provided page (starting at 1)
+/// Returns the name of the page along with its content
+pub(super) fn read_cover(
+    input: &Path,
+    page: Option<usize>,
+    accept_extended_image_formats: bool,
+) -> Result<(String, Vec<u8>), ExtractCoverError> {
+    let format = deter::sniff_format(input)
+        .map_err(|err| ExtractCoverError::FailedToReadVolume(input.to_path_buf(), err))?;
+
+    match format {
+        Some(ComicFormat::Zip) => {
+            let file = File::open(input)
+                .map_err(|err| ExtractCoverError::FailedToReadVolume(input.to_path_buf(), err))?;
+
+            let mut archive = ZipArchive::new(file)
+                .map_err(|err| ExtractCoverError::InvalidZipArchive(input.to_path_buf(), err))?;
+
+            // Only list the entries, without decompressing them
+            let mut pages: Vec<PathBuf> = archive
+                .file_names()
+                .filter(|name| !name.ends_with('/'))
+                .map(PathBuf::from)
+                .filter(|path| deter::has_image_ext(path, accept_extended_image_formats))
+                .collect();
+
+            sort::natural_sort_paths(&mut pages, false);
+
+            let index = match page {
+                Some(page) => page - 1,
+
+                // Prefer a page explicitly named as the cover, then the first one
+                None => pages
+                    .iter()
+                    .position(|page| is_cover_name(page))
+                    .unwrap_or(0),
+            };
+
+            let cover = match pages.get(index) {
+                Some(cover) => cover.to_string_lossy().to_string(),
+                None => return Err(page_not_found(input, page, pages.len())),
+            };
+
+            let mut entry = archive
+                .by_name(&cover)
+                .map_err(|err| ExtractCoverError::InvalidZipArchive(input.to_path_buf(), err))?;
+
+            let mut bytes = vec![];
+
+            entry
+                .read_to_end(&mut bytes)
+                .map_err(|err| ExtractCoverError::FailedToReadVolume(input.to_path_buf(), err))?;
+
+            Ok((cover, bytes))
+        }
+
+        Some(ComicFormat::Pdf) => {
+            let images = pdf_images::extract_jpeg_images(input, true)
+                .map_err(|_| ExtractCoverError::FailedToReadPdf(input.to_path_buf()))?;
+
+            let index = page.map(|page| page - 1).unwrap_or(0);
+            let pages = images.len();
+
+            match images.into_iter().nth(index) {
+                Some(bytes) => Ok((format!("{}.jpg", index + 1), bytes)),
+                None => Err(page_not_found(input, page, pages)),
+            }
+        }
+
+        format => Err(ExtractCoverError::UnsupportedFormat(
+            input.to_path_buf(),
+            format,
+        )),
+    }
+}
+
+/// Check if a page's name indicates it is the volume's cover (e.g. "cover.jpg" or "000_Cover.png")
+fn is_cover_name(path: &Path) -> bool {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase().contains("cover"))
+        .unwrap_or(false)
+}
+
+/// Get the error to return when the requested cover page does not exist
+fn page_not_found(input: &Path, page: Option<usize>, pages: usize) -> ExtractCoverError {
+    match page {
+        Some(page) => ExtractCoverError::PageNotFound {
+            volume: input.to_path_buf(),
+            page,
+            pages,
+        },
+        None => ExtractCoverError::NoPageInVolume(input.to_path_buf()),
+    }
+}
@@ -0,0 +1,74 @@
+use crate::cli::error::HistoryError;
+use crate::cli::opts::{History, HistoryCommand};
+use crate::lib::history::{HistoryEntry, RunHistory};
+use crate::lib::report::{self, Report};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Display the recent runs, or the details of one of them
+pub fn history(opts: &History) -> Result<Report, HistoryError> {
+    let report = Report::new();
+
+    let history =
+        RunHistory::load().map_err(|err| HistoryError::FailedToLoadHistory(err.to_string()))?;
+
+    match &opts.command {
+        None => {
+            if history.runs.is_empty() {
+                info!("No run was recorded yet.");
+                return Ok(report);
+            }
+
+            let nth_len = history.runs.len().to_string().len();
+
+            // Most recent runs first, so their position is the one to provide to 'history show'
+            for (i, entry) in history.runs.iter().rev().enumerate() {
+                info!(
+                    "{:>nth_len$}. {} [{}] {} ({})",
+                    i + 1,
+                    format_started_at(entry),
+                    if entry.error.is_some() {
+                        "FAILED"
+                    } else {
+                        "OK"
+                    },
+                    entry.args.join(" "),
+                    report::format_millis(entry.duration_ms),
+                    nth_len = nth_len
+                );
+            }
+        }
+
+        Some(HistoryCommand::Show(show)) => {
+            let entry = history
+                .get(show.nth)
+                .ok_or(HistoryError::RunNotFound(show.nth, history.runs.len()))?;
+
+            info!("Started at: {}", format_started_at(entry));
+            info!("Command: comic-enc {}", entry.args.join(" "));
+            info!("Working directory: {}", entry.cwd.to_string_lossy());
+            info!("Duration: {}", report::format_millis(entry.duration_ms));
+
+            match &entry.error {
+                Some(err) => info!("Result: failed ({})", err),
+                None => info!("Result: succeeded"),
+            }
+
+            if entry.outputs.is_empty() {
+                info!("Outputs: none");
+            } else {
+                info!("Outputs:");
+
+                for output in &entry.outputs {
+                    info!("    {}", output.to_string_lossy());
+                }
+            }
+        }
+    }
+
+    Ok(report)
+}
+
+/// Format the time a run started at for display
+fn format_started_at(entry: &HistoryEntry) -> String {
+    report::format_timestamp(UNIX_EPOCH + Duration::from_secs(entry.started_at))
+}
@@ -0,0 +1,256 @@
+use super::compile;
+use crate::cli::error::InitError;
+use crate::cli::opts::{Action, EncodingMethod, Init, Opts};
+use crate::lib::hooks;
+use crate::lib::prompt;
+use crate::lib::report::Report;
+use clap::Clap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default name of the script the generated command is written to
+const DEFAULT_SCRIPT_NAME: &str = if cfg!(windows) {
+    "comic-enc.cmd"
+} else {
+    "comic-enc.sh"
+};
+
+/// Default number of chapters to put in each volume
+const DEFAULT_CHAPTERS_PER_VOLUME: &str = "10";
+
+/// Largest dimension of the pages for phones and tablets, most of them being unable to display larger pictures
+const PHONE_READER_LIMIT: &str = "4096";
+
+/// Largest dimension of the pages for e-ink readers, whose screens are much smaller than most scans
+const E_READER_LIMIT: &str = "2048";
+
+/// Ask the user what they want to do, write the matching command to a script and run a dry-run of it if asked to
+pub fn init(opts: &Init) -> Result<Report, InitError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(InitError::FailedToGetCWD)?;
+
+    info!("This wizard generates the command for what you want to do, and writes it to a script you can run again later.");
+
+    let compile_each = choose(
+        "What do you want to do?",
+        &[
+            "Compile chapters into volumes",
+            "Convert a library of comics (PDFs or archives) into individual volumes",
+        ],
+    )? == 1;
+
+    let input = loop {
+        let input = ask(
+            if compile_each {
+                "Directory containing the comics to convert:"
+            } else {
+                "Directory containing the chapters:"
+            },
+            None,
+        )?;
+
+        if cwd.join(&input).is_dir() {
+            break input;
+        }
+
+        println!("'{}' is not a directory.", input);
+    };
+
+    let output = ask("Directory to put the volumes in:", Some("./build"))?;
+
+    let chapters_per_volume = if compile_each {
+        None
+    } else {
+        Some(loop {
+            let chapters_per_volume = ask(
+                "Number of chapters per volume:",
+                Some(DEFAULT_CHAPTERS_PER_VOLUME),
+            )?;
+
+            match chapters_per_volume.parse::<u16>() {
+                Ok(number) if number > 0 => break chapters_per_volume,
+                _ => println!("Please provide a number of chapters (at least 1)."),
+            }
+        })
+    };
+
+    let device = choose(
+        "Which device will the volumes be read on?",
+        &["Computer or large tablet", "Phone", "E-ink reader"],
+    )?;
+
+    let mut args = vec!["encode".to_owned(), input, "-o".to_owned(), output];
+
+    // Small screens need pages they can display, and double-page spreads are hard to read on them
+    if device > 0 {
+        let reader_limit = if device == 1 {
+            PHONE_READER_LIMIT
+        } else {
+            E_READER_LIMIT
+        };
+
+        args.extend(vec![
+            "--reader-limit".to_owned(),
+            reader_limit.to_owned(),
+            "--downscale-oversized".to_owned(),
+        ]);
+
+        match choose(
+            "Split the double-page spreads into two pages?",
+            &[
+                "No",
+                "Yes, read from left to right",
+                "Yes, read from right to left (manga)",
+            ],
+        )? {
+            0 => {}
+            1 => args.push("--split-spreads=ltr".to_owned()),
+
+            // Readers have to know the pages are read from right to left as well
+            _ => args.extend(vec![
+                "--split-spreads=rtl".to_owned(),
+                "--reading-direction".to_owned(),
+                "rtl".to_owned(),
+            ]),
+        }
+    }
+
+    // E-ink screens only display shades of gray, so colors only make the volumes larger
+    if device == 2 {
+        args.push("--grayscale".to_owned());
+    }
+
+    args.push("compile".to_owned());
+
+    match chapters_per_volume {
+        Some(chapters_per_volume) => args.extend(vec!["ranges".to_owned(), chapters_per_volume]),
+        None => args.push("each".to_owned()),
+    }
+
+    args.push("--create-output-dir".to_owned());
+
+    let command = format!(
+        "comic-enc {}",
+        args.iter()
+            .map(|arg| quote_arg(arg))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+
+    info!("The command is:");
+    info!("{}", command);
+
+    // Write the command to a script
+    let script_path = cwd.join(
+        opts.output
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SCRIPT_NAME)),
+    );
+
+    if !script_path.exists() || prompt::confirm_overwrite(&script_path) {
+        write_script(&script_path, &command)?;
+        info!(
+            "Written the command to '{}'.",
+            script_path.to_string_lossy()
+        );
+        report.outputs.push(script_path);
+    } else {
+        warn!("Warning: the command was not written to a script.");
+    }
+
+    if !prompt::confirm("Preview the volumes now with a dry run?") {
+        return Ok(report);
+    }
+
+    args.push("--dry-run".to_owned());
+
+    let dry_run_opts =
+        Opts::try_parse_from(std::iter::once("comic-enc".to_owned()).chain(args.into_iter()))
+            .map_err(|err| InitError::InvalidCommand(err.to_string()))?;
+
+    let mut dry_run_report = match &dry_run_opts.action {
+        Action::Encode(encode) => match &encode.method {
+            EncodingMethod::Compile(compile_opts) => compile(compile_opts, &encode.options)
+                .map_err(|err| InitError::DryRunFailed(err.to_string()))?,
+
+            _ => unreachable!(),
+        },
+
+        _ => unreachable!(),
+    };
+
+    // The script is still an output of the action
+    dry_run_report.outputs.splice(0..0, report.outputs);
+
+    Ok(dry_run_report)
+}
+
+/// Ask a question, from which an answer is required
+fn ask(question: &str, default: Option<&str>) -> Result<String, InitError> {
+    loop {
+        let answer = prompt::ask(question, default).ok_or(InitError::NoAnswer)?;
+
+        if !answer.is_empty() {
+            return Ok(answer);
+        }
+    }
+}
+
+/// Ask the user to choose between the provided answers, returning the position of the chosen one
+fn choose(question: &str, answers: &[&str]) -> Result<usize, InitError> {
+    println!("{}", question);
+
+    for (i, answer) in answers.iter().enumerate() {
+        println!("  {}. {}", i + 1, answer);
+    }
+
+    loop {
+        let answer = ask("Your choice:", Some("1"))?;
+
+        match answer.parse::<usize>() {
+            Ok(choice) if choice >= 1 && choice <= answers.len() => return Ok(choice - 1),
+            _ => println!("Please type a number between 1 and {}.", answers.len()),
+        }
+    }
+}
+
+/// Quote an argument for the platform's shell if it needs to be
+fn quote_arg(arg: &str) -> String {
+    if arg
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_./=:".contains(c))
+    {
+        arg.to_owned()
+    } else {
+        hooks::quote(arg)
+    }
+}
+
+/// Write a script running the provided command
+fn write_script(path: &Path, command: &str) -> Result<(), InitError> {
+    let content = if cfg!(windows) {
+        format!(
+            "@echo off\r\nrem Generated by 'comic-enc init'\r\n{}\r\n",
+            command
+        )
+    } else {
+        format!("#!/bin/sh\n# Generated by 'comic-enc init'\n{}\n", command)
+    };
+
+    fs::write(path, content)
+        .map_err(|err| InitError::FailedToWriteScript(path.to_path_buf(), err))?;
+
+    // Make the script executable
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+            .map_err(|err| InitError::FailedToWriteScript(path.to_path_buf(), err))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+use crate::cli::error::MergeError;
+use crate::cli::opts::Merge;
+use crate::lib::build_vol::{StagingFile, STAGING_EXT};
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::prompt;
+use crate::lib::report::Report;
+use crate::lib::sort;
+use std::env;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::Instant;
+use zip::write::{FileOptions, ZipWriter};
+use zip::ZipArchive;
+
+/// Merge existing volumes into a single one
+/// Pages are copied as they are, without being decompressed
+pub fn merge(opts: &Merge) -> Result<Report, MergeError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(MergeError::FailedToGetCWD)?;
+
+    let output = cwd.join(&opts.output);
+
+    if output.exists() && !opts.overwrite && !prompt::confirm_overwrite(&output) {
+        return Err(MergeError::OutputFileAlreadyExists(output));
+    }
+
+    let mut inputs: Vec<PathBuf> = opts.input.iter().map(|input| cwd.join(input)).collect();
+
+    if opts.natural_sort {
+        sort::natural_sort_paths(&mut inputs, false);
+    }
+
+    // Get timestamp to measure the scanning phase
+    let scan_started = Instant::now();
+
+    // Open all volumes and list their pages before writing anything
+    // Each source is a tuple containing: (path to the volume, its archive, indexes of its pages)
+    let mut sources = vec![];
+
+    for input in inputs {
+        if !input.is_file() {
+            return Err(MergeError::VolumeNotFound(input));
+        }
+
+        match deter::sniff_format(&input)
+            .map_err(|err| MergeError::FailedToReadVolume(input.clone(), err))?
+        {
+            Some(ComicFormat::Zip) => {}
+            format => return Err(MergeError::UnsupportedFormat(input, format)),
+        }
+
+        let file =
+            File::open(&input).map_err(|err| MergeError::FailedToReadVolume(input.clone(), err))?;
+
+        let mut archive = ZipArchive::new(file)
+            .map_err(|err| MergeError::InvalidZipArchive(input.clone(), err))?;
+
+        let mut pages = vec![];
+
+        for i in 0..archive.len() {
+            let entry = archive
+                .by_index(i)
+                .map_err(|err| MergeError::InvalidZipArchive(input.clone(), err))?;
+
+            let entry_path = entry.mangled_name();
+
+            if entry.is_file()
+                && deter::has_image_ext(&entry_path, opts.accept_extended_image_formats)
+            {
+                pages.push((entry_path, i));
+            }
+        }
+
+        // Keep the pages' order of each volume
+        sort::natural_sort_by_path(&mut pages, |(path, _)| path, false);
+
+        debug!(
+            "Found {} page(s) in volume '{}'.",
+            pages.len(),
+            input.to_string_lossy()
+        );
+
+        let pages = pages.into_iter().map(|(_, i)| i).collect::<Vec<_>>();
+
+        sources.push((input, archive, pages));
+    }
+
+    report.phase("scan", scan_started.elapsed());
+    report.count("volumes", sources.len());
+
+    info!(
+        "Going to merge {} volume(s) into '{}'.",
+        sources.len(),
+        output.to_string_lossy()
+    );
+
+    // Get timestamp to measure the building phase
+    let build_started = Instant::now();
+
+    // Write the merged volume to a staging file, so an existing output is left untouched if something goes wrong
+    let staging_path = deter::add_extension(&output, STAGING_EXT);
+
+    let staging_file = File::create(&staging_path)
+        .map_err(|err| MergeError::FailedToCreateOutputFile(staging_path.clone(), err))?;
+
+    // Ensure the staging file is removed if anything goes wrong
+    let mut staging_guard = StagingFile {
+        path: staging_path.clone(),
+        finalized: false,
+    };
+
+    let mut zip_writer = ZipWriter::new(staging_file);
+
+    // Compute the length of displayable page numbers, across all volumes if pages are not kept in separate directories
+    let total_pages: usize = sources.iter().map(|(_, _, pages)| pages.len()).sum();
+
+    // Number of pages written so far
+    let mut pics_counter = 0;
+
+    for (input, archive, pages) in sources.iter_mut() {
+        // Name of the directory containing this volume's pages, if any
+        let source_dir = if opts.keep_source_dirs {
+            let dir_name = input
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+
+            trace!("Adding directory '{}' to ZIP archive...", dir_name);
+
+            zip_writer
+                .add_directory(dir_name.clone(), FileOptions::default())
+                .map_err(|err| MergeError::FailedToCreateDirectory {
+                    dir_name: dir_name.clone(),
+                    err,
+                })?;
+
+            Some(dir_name)
+        } else {
+            None
+        };
+
+        let pic_num_len = if opts.keep_source_dirs {
+            pages.len().to_string().len()
+        } else {
+            total_pages.to_string().len()
+        };
+
+        for (page_nb, index) in pages.iter().enumerate() {
+            let entry = archive
+                .by_index(*index)
+                .map_err(|err| MergeError::InvalidZipArchive(input.clone(), err))?;
+
+            let file_ext = entry
+                .mangled_name()
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            // Pages are renumbered, either inside each volume's directory or across the whole volume
+            let name_in_zip = match &source_dir {
+                Some(dir_name) => format!(
+                    "{}/{}_Pic_{:0pic_num_len$}.{}",
+                    dir_name,
+                    dir_name,
+                    page_nb,
+                    file_ext,
+                    pic_num_len = pic_num_len
+                ),
+
+                None => format!(
+                    "Pic_{:0pic_num_len$}.{}",
+                    pics_counter,
+                    file_ext,
+                    pic_num_len = pic_num_len
+                ),
+            };
+
+            trace!(
+                "Copying page '{}' from volume '{}' as '{}'...",
+                entry.name(),
+                input.to_string_lossy(),
+                name_in_zip
+            );
+
+            let entry_name = entry.name().to_owned();
+
+            zip_writer
+                .raw_copy_file_rename(entry, name_in_zip)
+                .map_err(|err| MergeError::FailedToCopyPage {
+                    volume: input.clone(),
+                    entry_name,
+                    err,
+                })?;
+
+            pics_counter += 1;
+        }
+    }
+
+    trace!("Closing ZIP archive...");
+
+    zip_writer
+        .finish()
+        .map_err(|err| MergeError::FailedToCloseOutputFile(staging_path.clone(), err))?;
+
+    fs::rename(&staging_path, &output)
+        .map_err(|err| MergeError::FailedToRenameOutputFile(output.clone(), err))?;
+
+    staging_guard.finalized = true;
+
+    info!(
+        "Successfully merged {} volume(s) into '{}', containing {} pages.",
+        sources.len(),
+        output.to_string_lossy(),
+        pics_counter
+    );
+
+    report.phase("build", build_started.elapsed());
+    report.count("pages", pics_counter);
+    report.outputs.push(output);
+
+    Ok(report)
+}
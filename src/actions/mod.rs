@@ -1,7 +1,35 @@
+mod append;
 mod compile;
+mod convert;
 mod decode;
+mod doctor;
+mod edit;
 mod encode_one;
+mod extract_cover;
+mod history;
+mod init;
+mod merge;
+mod number_pages;
+mod repack;
+mod sort;
+mod split;
+mod thumbnail;
+mod verify;
 
+pub use append::append;
 pub use compile::compile;
+pub use convert::convert;
 pub use decode::decode;
+pub use doctor::doctor;
+pub use edit::edit;
 pub use encode_one::encode_one;
+pub use extract_cover::extract_cover;
+pub use history::history;
+pub use init::init;
+pub use merge::merge;
+pub use number_pages::number_pages;
+pub use repack::repack;
+pub use sort::sort;
+pub use split::split;
+pub use thumbnail::thumbnail;
+pub use verify::verify;
@@ -0,0 +1,186 @@
+use crate::cli::error::NumberPagesError;
+use crate::cli::opts::NumberPages;
+use crate::lib::deter;
+use crate::lib::report::Report;
+use crate::lib::safety;
+use crate::lib::sort::natural_sort_paths;
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Minimum number of digits of the pages' new names
+pub const MIN_PAGE_NUM_LEN: usize = 4;
+
+/// Prefix of the temporary names pages are renamed to, to avoid collisions between their old and new names
+const TEMPORARY_NAME_PREFIX: &str = ".comic-enc-renaming-";
+
+/// Rename the pictures of a directory into a clean sequence (e.g. '0001.jpg', '0002.png'...) following the natural order
+pub fn number_pages(opts: &NumberPages) -> Result<Report, NumberPagesError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(NumberPagesError::FailedToGetCWD)?;
+
+    let input = cwd.join(&opts.input);
+
+    if !input.is_dir() {
+        return Err(NumberPagesError::DirectoryNotFound(input));
+    }
+
+    // Pictures must not be renamed in a directory typed by mistake
+    if safety::is_dangerous_dir(&input) {
+        return Err(NumberPagesError::DangerousDirectory(input));
+    }
+
+    // Get timestamp to measure the scanning phase
+    let scan_started = Instant::now();
+
+    // Pictures of each directory, which are numbered separately
+    let mut dirs: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+
+    for pic in list_pictures(opts, &input)? {
+        let parent = pic.parent().unwrap_or(&input).to_path_buf();
+        dirs.entry(parent).or_default().push(pic);
+    }
+
+    report.phase("scan", scan_started.elapsed());
+
+    // Get timestamp to measure the renaming phase
+    let rename_started = Instant::now();
+
+    let mut renamed = 0;
+
+    for (dir, mut pics) in dirs {
+        natural_sort_paths(&mut pics, opts.roman_numerals);
+
+        let page_num_len = pics.len().to_string().len().max(MIN_PAGE_NUM_LEN);
+
+        // Determine the new name of each picture
+        let renames = pics
+            .iter()
+            .enumerate()
+            .map(|(i, pic)| {
+                // Pictures are recognized by their extension, so they always have one
+                let new_name = format!(
+                    "{:0page_num_len$}.{}",
+                    i + 1,
+                    pic.extension().unwrap_or_default().to_string_lossy(),
+                    page_num_len = page_num_len
+                );
+
+                (pic.clone(), dir.join(new_name))
+            })
+            .filter(|(pic, new_path)| pic != new_path)
+            .collect::<Vec<_>>();
+
+        debug!(
+            "Renaming {} picture(s) out of {} in '{}'...",
+            renames.len(),
+            pics.len(),
+            dir.to_string_lossy()
+        );
+
+        // Ensure no file that is not renamed would be overwritten
+        let sources = pics.iter().collect::<HashSet<_>>();
+
+        for (_, new_path) in &renames {
+            if new_path.exists() && !sources.contains(new_path) {
+                return Err(NumberPagesError::TargetAlreadyExists(new_path.clone()));
+            }
+        }
+
+        for (pic, new_path) in &renames {
+            info!(
+                "{} -> {}",
+                relative(&input, pic).to_string_lossy(),
+                relative(&input, new_path).to_string_lossy()
+            );
+        }
+
+        if opts.dry_run {
+            continue;
+        }
+
+        // Rename the pictures in two steps, as the new name of a picture may be the old name of another one
+        let mut temporary_paths = vec![];
+
+        for (i, (pic, _)) in renames.iter().enumerate() {
+            let temporary_path = dir.join(format!("{}{}", TEMPORARY_NAME_PREFIX, i));
+
+            fs::rename(pic, &temporary_path).map_err(|err| NumberPagesError::FailedToRename {
+                from: pic.clone(),
+                to: temporary_path.clone(),
+                err,
+            })?;
+
+            temporary_paths.push(temporary_path);
+        }
+
+        for (temporary_path, (_, new_path)) in temporary_paths.iter().zip(renames.iter()) {
+            fs::rename(temporary_path, new_path).map_err(|err| {
+                NumberPagesError::FailedToRename {
+                    from: temporary_path.clone(),
+                    to: new_path.clone(),
+                    err,
+                }
+            })?;
+        }
+
+        renamed += renames.len();
+    }
+
+    report.phase("rename", rename_started.elapsed());
+    report.count("renamed pages", renamed);
+
+    if opts.dry_run {
+        info!("Nothing was renamed (--dry-run provided).");
+    } else {
+        info!(
+            "Successfully renamed {} picture(s) in '{}'.",
+            renamed,
+            input.to_string_lossy()
+        );
+    }
+
+    Ok(report)
+}
+
+/// List the pictures to rename, recursively if asked to
+fn list_pictures(opts: &NumberPages, input: &Path) -> Result<Vec<PathBuf>, NumberPagesError> {
+    let is_picture =
+        |path: &PathBuf| deter::has_image_ext(path, opts.accept_extended_image_formats);
+
+    if opts.recursive {
+        return deter::readdir_files_recursive(input, Some(&is_picture)).map_err(|err| match err {
+            deter::RecursiveFilesSearchErr::IOError(err) => {
+                NumberPagesError::FailedToReadDirectory(input.to_path_buf(), err)
+            }
+            deter::RecursiveFilesSearchErr::InvalidFileName(path) => {
+                NumberPagesError::InvalidFileName(path)
+            }
+        });
+    }
+
+    let mut pics = vec![];
+
+    for entry in fs::read_dir(input)
+        .map_err(|err| NumberPagesError::FailedToReadDirectory(input.to_path_buf(), err))?
+    {
+        let path = entry
+            .map_err(|err| NumberPagesError::FailedToReadDirectory(input.to_path_buf(), err))?
+            .path();
+
+        if path.is_file() && is_picture(&path) {
+            pics.push(path);
+        }
+    }
+
+    Ok(pics)
+}
+
+/// Get a path relative to the input directory, for display
+fn relative<'a>(input: &Path, path: &'a Path) -> &'a Path {
+    path.strip_prefix(input).unwrap_or(path)
+}
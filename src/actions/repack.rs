@@ -0,0 +1,214 @@
+use crate::cli::error::RepackError;
+use crate::cli::opts::Repack;
+use crate::lib::build_vol::{rewriting_zip_writer, StagingFile, STAGING_EXT};
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::prompt;
+use crate::lib::report::Report;
+use std::env;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path};
+use std::time::Instant;
+use zip::write::FileOptions;
+use zip::ZipArchive;
+
+/// Rewrite existing volumes with another compression method
+pub fn repack(opts: &Repack) -> Result<Report, RepackError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(RepackError::FailedToGetCWD)?;
+
+    let output_dir = opts.output.as_ref().map(|output| cwd.join(output));
+
+    if let Some(output_dir) = &output_dir {
+        if !output_dir.is_dir() {
+            return Err(RepackError::OutputDirectoryNotFound(output_dir.clone()));
+        }
+    }
+
+    // Get timestamp to measure the repacking phase
+    let repack_started = Instant::now();
+
+    // List of volumes that were repacked
+    let mut repacked = vec![];
+
+    // Number of entries that were rewritten
+    let mut entries = 0;
+
+    for (i, input) in opts.input.iter().enumerate() {
+        let path = cwd.join(input);
+
+        debug!(
+            "Repacking volume {}/{} at '{}'...",
+            i + 1,
+            opts.input.len(),
+            path.to_string_lossy()
+        );
+
+        // Repack the volume next to itself if no output directory was provided
+        let target = match (&output_dir, path.file_name()) {
+            (Some(output_dir), Some(file_name)) => output_dir.join(file_name),
+            _ => path.clone(),
+        };
+
+        match repack_volume(opts, &path, &target) {
+            Ok((volume_entries, old_size, new_size)) => {
+                info!(
+                    "Repacked volume '{}' to '{}' ({} entries, {} -> {} bytes).",
+                    path.to_string_lossy(),
+                    target.to_string_lossy(),
+                    volume_entries,
+                    old_size,
+                    new_size
+                );
+
+                entries += volume_entries;
+                repacked.push(target);
+            }
+
+            Err(err) => error!("{}", err),
+        }
+    }
+
+    if repacked.len() < opts.input.len() {
+        return Err(RepackError::SomeVolumesFailed(
+            opts.input.len() - repacked.len(),
+            opts.input.len(),
+        ));
+    }
+
+    report.phase("repack", repack_started.elapsed());
+    report.count("volumes", repacked.len());
+    report.count("entries", entries);
+    report.outputs = repacked;
+
+    Ok(report)
+}
+
+/// Repack a single volume
+/// Returns the number of entries written to the volume, as well as its size before and after repacking
+fn repack_volume(
+    opts: &Repack,
+    path: &Path,
+    target: &Path,
+) -> Result<(usize, u64, u64), RepackError> {
+    if !path.is_file() {
+        return Err(RepackError::VolumeNotFound(path.to_path_buf()));
+    }
+
+    if target != path && target.exists() && !opts.overwrite && !prompt::confirm_overwrite(&target) {
+        return Err(RepackError::OutputVolumeFileAlreadyExists(
+            target.to_path_buf(),
+        ));
+    }
+
+    // Only ZIP archives can be repacked for now
+    match deter::sniff_format(path)
+        .map_err(|err| RepackError::FailedToReadVolume(path.to_path_buf(), err))?
+    {
+        Some(ComicFormat::Zip) => {}
+        format => return Err(RepackError::UnsupportedFormat(path.to_path_buf(), format)),
+    }
+
+    let old_size = fs::metadata(path)
+        .map_err(|err| RepackError::FailedToReadVolume(path.to_path_buf(), err))?
+        .len();
+
+    let file =
+        File::open(path).map_err(|err| RepackError::FailedToReadVolume(path.to_path_buf(), err))?;
+
+    let mut zip = ZipArchive::new(file)
+        .map_err(|err| RepackError::InvalidZipArchive(path.to_path_buf(), err))?;
+
+    // Write the repacked volume to a staging file, so the volume is left untouched if something goes wrong
+    let staging_path = deter::add_extension(target, STAGING_EXT);
+
+    let staging_file = File::create(&staging_path)
+        .map_err(|err| RepackError::FailedToCreateVolumeFile(staging_path.clone(), err))?;
+
+    // Ensure the staging file is removed if anything goes wrong
+    let mut staging_guard = StagingFile {
+        path: staging_path.clone(),
+        finalized: false,
+    };
+
+    // Keep the volume's comment (e.g. its ComicBookInfo metadata)
+    let mut zip_writer = rewriting_zip_writer(&zip, staging_file);
+
+    let zip_options = FileOptions::default()
+        .compression_method(opts.compression.method())
+        .compression_level(opts.compression_level);
+
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|err| RepackError::FailedToReadEntry {
+                volume: path.to_path_buf(),
+                entry: format!("#{}", i),
+                err,
+            })?;
+
+        let name = normalize_entry_name(&entry.mangled_name());
+
+        trace!("Repacking entry '{}' as '{}'...", entry.name(), name);
+
+        let entry_options = zip_options.last_modified_time(entry.last_modified());
+
+        if entry.is_dir() {
+            zip_writer
+                .add_directory(name.clone(), entry_options)
+                .map_err(|err| RepackError::FailedToCreateEntry {
+                    volume: target.to_path_buf(),
+                    entry: name,
+                    err,
+                })?;
+
+            continue;
+        }
+
+        zip_writer
+            .start_file(name.clone(), entry_options)
+            .map_err(|err| RepackError::FailedToCreateEntry {
+                volume: target.to_path_buf(),
+                entry: name.clone(),
+                err,
+            })?;
+
+        // Copy the entry's content as it is, only its compression changes
+        io::copy(&mut entry, &mut zip_writer).map_err(|err| RepackError::FailedToWriteEntry {
+            volume: target.to_path_buf(),
+            entry: name,
+            err,
+        })?;
+    }
+
+    trace!("Closing ZIP archive...");
+
+    zip_writer
+        .finish()
+        .map_err(|err| RepackError::FailedToCloseVolume(staging_path.clone(), err))?;
+
+    // Replace the target with the repacked volume
+    fs::rename(&staging_path, target)
+        .map_err(|err| RepackError::FailedToRenameVolume(target.to_path_buf(), err))?;
+
+    staging_guard.finalized = true;
+
+    let new_size = fs::metadata(target)
+        .map_err(|err| RepackError::FailedToReadVolume(target.to_path_buf(), err))?
+        .len();
+
+    Ok((zip.len(), old_size, new_size))
+}
+
+/// Normalize the name of an entry, so it only uses forward slashes and contains no special component
+fn normalize_entry_name(path: &Path) -> String {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
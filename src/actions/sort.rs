@@ -0,0 +1,266 @@
+use crate::cli::error::SortError;
+use crate::cli::opts::Sort;
+use crate::lib::build_vol::{rewriting_zip_writer, StagingFile, STAGING_EXT};
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::report::Report;
+use crate::lib::safety;
+use crate::lib::sort::natural_sort_paths;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use zip::ZipArchive;
+
+/// Display the natural order of the files of a directory or the entries of an archive,
+/// and prefix their names with their position if asked to
+pub fn sort(opts: &Sort) -> Result<Report, SortError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(SortError::FailedToGetCWD)?;
+
+    let input = cwd.join(&opts.input);
+
+    if !input.exists() {
+        return Err(SortError::InputNotFound(input));
+    }
+
+    // Get timestamp to measure the sorting phase
+    let sort_started = Instant::now();
+
+    let sorted = if input.is_dir() {
+        // Files must not be renamed in a directory typed by mistake
+        if opts.apply && safety::is_dangerous_dir(&input) {
+            return Err(SortError::DangerousDirectory(input));
+        }
+
+        sort_directory(opts, &input)?
+    } else {
+        match deter::sniff_format(&input)
+            .map_err(|err| SortError::FailedToReadArchive(input.clone(), err))?
+        {
+            Some(ComicFormat::Zip) => sort_archive(opts, &input)?,
+            format => return Err(SortError::UnsupportedFormat(input, format)),
+        }
+    };
+
+    report.phase("sort", sort_started.elapsed());
+    report.count("files", sorted);
+
+    if opts.apply {
+        info!(
+            "Successfully prefixed the {} file(s) of '{}' with their position.",
+            sorted,
+            input.to_string_lossy()
+        );
+
+        report.outputs.push(input);
+    }
+
+    Ok(report)
+}
+
+/// Sort the pictures of a directory (not recursively), and rename them if asked to
+/// Returns the number of sorted pictures
+fn sort_directory(opts: &Sort, input: &Path) -> Result<usize, SortError> {
+    let mut items = vec![];
+
+    for entry in fs::read_dir(input)
+        .map_err(|err| SortError::FailedToReadDirectory(input.to_path_buf(), err))?
+    {
+        let entry =
+            entry.map_err(|err| SortError::FailedToReadDirectory(input.to_path_buf(), err))?;
+
+        let name = PathBuf::from(entry.file_name());
+
+        // Only pictures are sorted, so sub-directories and other files (e.g. ignore or metadata files) are left untouched
+        if !name.to_string_lossy().starts_with('.')
+            && entry.path().is_file()
+            && deter::has_image_ext(&name, opts.accept_extended_image_formats)
+        {
+            items.push(name);
+        }
+    }
+
+    natural_sort_paths(&mut items, opts.roman_numerals);
+
+    let num_len = items.len().to_string().len();
+
+    // Determine the new name of each picture, leaving out the ones already prefixed with their position
+    let renames = items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (item, prefixed_name(item, i, num_len)))
+        .filter(|(item, prefixed)| item != &prefixed)
+        .collect::<Vec<_>>();
+
+    if !opts.apply {
+        for item in &items {
+            info!("{}", item.to_string_lossy());
+        }
+
+        return Ok(items.len());
+    }
+
+    // Ensure no file would be overwritten before renaming anything, so the directory is left untouched on collision
+    for (_, prefixed) in &renames {
+        let to = input.join(prefixed);
+
+        if to.exists() {
+            return Err(SortError::TargetAlreadyExists(to));
+        }
+    }
+
+    for (item, prefixed) in renames {
+        info!(
+            "{} -> {}",
+            item.to_string_lossy(),
+            prefixed.to_string_lossy()
+        );
+
+        let from = input.join(item);
+        let to = input.join(&prefixed);
+
+        fs::rename(&from, &to).map_err(|err| SortError::FailedToRename { from, to, err })?;
+    }
+
+    Ok(items.len())
+}
+
+/// Sort the entries of an archive, and rewrite it with renamed entries if asked to
+/// Entries are numbered inside their own directory
+/// Returns the number of sorted entries
+fn sort_archive(opts: &Sort, input: &Path) -> Result<usize, SortError> {
+    let file = File::open(input)
+        .map_err(|err| SortError::FailedToReadArchive(input.to_path_buf(), err))?;
+
+    let mut archive = ZipArchive::new(file)
+        .map_err(|err| SortError::InvalidZipArchive(input.to_path_buf(), err))?;
+
+    // Index of each file entry in the archive, by its path
+    let mut indexes = HashMap::new();
+
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index(i)
+            .map_err(|err| SortError::InvalidZipArchive(input.to_path_buf(), err))?;
+
+        if entry.is_file() {
+            indexes.insert(entry.mangled_name(), i);
+        }
+    }
+
+    let mut items = indexes.keys().cloned().collect::<Vec<_>>();
+
+    natural_sort_paths(&mut items, opts.roman_numerals);
+
+    // Number of entries in each directory, to determine the length of their position
+    let mut dir_entries = HashMap::new();
+
+    for item in &items {
+        *dir_entries
+            .entry(item.parent().map(Path::to_path_buf))
+            .or_insert(0) += 1;
+    }
+
+    // Position of the next entry in each directory
+    let mut positions = HashMap::new();
+
+    let mut renamed = vec![];
+
+    for item in &items {
+        let parent = item.parent().map(Path::to_path_buf);
+        let num_len = dir_entries[&parent].to_string().len();
+
+        let position = positions.entry(parent).or_insert(0);
+        let prefixed = prefixed_name(item, *position, num_len);
+        *position += 1;
+
+        let new_path = match item.parent() {
+            Some(parent) => parent.join(&prefixed),
+            None => prefixed,
+        };
+
+        let new_name = new_path.to_string_lossy().replace('\\', "/");
+
+        info!(
+            "{}",
+            if opts.apply {
+                format!("{} -> {}", item.to_string_lossy(), new_name)
+            } else {
+                item.to_string_lossy().to_string()
+            }
+        );
+
+        renamed.push((indexes[item], new_name));
+    }
+
+    if !opts.apply {
+        return Ok(items.len());
+    }
+
+    // Write the renamed entries to a staging file, so the archive is left untouched if something goes wrong
+    let staging_path = deter::add_extension(input, STAGING_EXT);
+
+    let staging_file = File::create(&staging_path)
+        .map_err(|err| SortError::FailedToCreateOutputFile(staging_path.clone(), err))?;
+
+    // Ensure the staging file is removed if anything goes wrong
+    let mut staging_guard = StagingFile {
+        path: staging_path.clone(),
+        finalized: false,
+    };
+
+    // Keep the archive's comment (e.g. its ComicBookInfo metadata)
+    let mut zip_writer = rewriting_zip_writer(&archive, staging_file);
+
+    // Entries are copied as they are, without being decompressed
+    // Directory entries are not kept, as they are implied by the paths of the files
+    for (index, new_name) in renamed {
+        let entry = archive
+            .by_index(index)
+            .map_err(|err| SortError::InvalidZipArchive(input.to_path_buf(), err))?;
+
+        trace!("Copying entry '{}' as '{}'...", entry.name(), new_name);
+
+        let entry_name = entry.name().to_owned();
+
+        zip_writer
+            .raw_copy_file_rename(entry, new_name)
+            .map_err(|err| SortError::FailedToCopyEntry { entry_name, err })?;
+    }
+
+    trace!("Closing ZIP archive...");
+
+    zip_writer
+        .finish()
+        .map_err(|err| SortError::FailedToCloseOutputFile(staging_path.clone(), err))?;
+
+    fs::rename(&staging_path, input)
+        .map_err(|err| SortError::FailedToRenameOutputFile(input.to_path_buf(), err))?;
+
+    staging_guard.finalized = true;
+
+    Ok(items.len())
+}
+
+/// Prefix the file name of a path with its position (starting at 1), zero-padded to the provided length
+/// File names already starting with this prefix are kept as they are, so applying the same order twice doesn't stack prefixes
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(prefixed_name(Path::new("dir/page.jpg"), 4, 3), PathBuf::from("005_page.jpg"));
+/// assert_eq!(prefixed_name(Path::new("dir/005_page.jpg"), 4, 3), PathBuf::from("005_page.jpg"));
+/// ```
+fn prefixed_name(path: &Path, position: usize, num_len: usize) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let prefix = format!("{:0num_len$}_", position + 1, num_len = num_len);
+
+    if name.starts_with(&prefix) {
+        return PathBuf::from(name.as_ref());
+    }
+
+    PathBuf::from(format!("{}{}", prefix, name))
+}
@@ -0,0 +1,296 @@
+use crate::cli::error::SplitError;
+use crate::cli::opts::Split;
+use crate::lib::build_vol::{StagingFile, DEFAULT_VOLUME_EXT, STAGING_EXT};
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::pdf_images;
+use crate::lib::prompt;
+use crate::lib::report::Report;
+use crate::lib::sort;
+use std::env;
+use std::fs::{self, File};
+use std::io::Write;
+use std::ops::Range;
+use std::time::Instant;
+use zip::result::ZipError;
+use zip::write::{FileOptions, ZipWriter};
+use zip::ZipArchive;
+
+/// Where the pages of the volume to split come from
+enum SplitSource {
+    /// Entries of a ZIP archive, associated to their index in it
+    Archive(ZipArchive<File>, Vec<usize>),
+
+    /// Images extracted from a PDF document
+    Pdf(Vec<Vec<u8>>),
+}
+
+/// Split a volume into multiple ones
+pub fn split(opts: &Split) -> Result<Report, SplitError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(SplitError::FailedToGetCWD)?;
+
+    let input = cwd.join(&opts.input);
+
+    if !input.is_file() {
+        return Err(SplitError::VolumeNotFound(input));
+    }
+
+    // Put the split volumes next to the input if no output directory was provided
+    let output_dir = match &opts.output {
+        Some(output) => cwd.join(output),
+        None => input
+            .parent()
+            .ok_or_else(|| SplitError::VolumeNotFound(input.clone()))?
+            .to_path_buf(),
+    };
+
+    if !output_dir.is_dir() {
+        return Err(SplitError::OutputDirectoryNotFound(output_dir));
+    }
+
+    // Get timestamp to measure the scanning phase
+    let scan_started = Instant::now();
+
+    let format = deter::sniff_format(&input)
+        .map_err(|err| SplitError::FailedToReadVolume(input.clone(), err))?;
+
+    // Get the list of pages in the volume, with their name
+    let (mut source, names) = match format {
+        Some(ComicFormat::Zip) => {
+            let file = File::open(&input)
+                .map_err(|err| SplitError::FailedToReadVolume(input.clone(), err))?;
+
+            let mut archive = ZipArchive::new(file)
+                .map_err(|err| SplitError::InvalidZipArchive(input.clone(), err))?;
+
+            let mut pages = vec![];
+
+            for i in 0..archive.len() {
+                let entry = archive
+                    .by_index(i)
+                    .map_err(|err| SplitError::InvalidZipArchive(input.clone(), err))?;
+
+                let entry_path = entry.mangled_name();
+
+                if entry.is_file()
+                    && deter::has_image_ext(&entry_path, opts.accept_extended_image_formats)
+                {
+                    pages.push((entry_path, i));
+                }
+            }
+
+            sort::natural_sort_by_path(&mut pages, |(path, _)| path, false);
+
+            let names = pages
+                .iter()
+                .map(|(path, _)| path.to_string_lossy().replace('\\', "/"))
+                .collect::<Vec<_>>();
+
+            (
+                SplitSource::Archive(archive, pages.into_iter().map(|(_, i)| i).collect()),
+                names,
+            )
+        }
+
+        Some(ComicFormat::Pdf) => {
+            if opts.at_chapters {
+                return Err(SplitError::NoChaptersInPdf(input));
+            }
+
+            let images = pdf_images::extract_jpeg_images(&input, false)
+                .map_err(|_| SplitError::FailedToReadPdf(input.clone()))?;
+
+            // Name the images after their position in the document
+            let pic_num_len = images.len().to_string().len();
+
+            let names = (0..images.len())
+                .map(|i| format!("{:0pic_num_len$}.jpg", i + 1, pic_num_len = pic_num_len))
+                .collect();
+
+            (SplitSource::Pdf(images), names)
+        }
+
+        format => return Err(SplitError::UnsupportedFormat(input, format)),
+    };
+
+    // Determine the pages each split volume will contain
+    let groups: Vec<Range<usize>> = match (&opts.pages_per_volume, &opts.ranges) {
+        (Some(0), _) => return Err(SplitError::AtLeast1PagePerVolume),
+
+        (Some(pages_per_volume), _) => (0..names.len())
+            .step_by(*pages_per_volume)
+            .map(|start| start..std::cmp::min(start + pages_per_volume, names.len()))
+            .collect(),
+
+        (None, Some(ranges)) => parse_ranges(ranges, names.len())?,
+
+        (None, None) if opts.at_chapters => {
+            // Split each time the top-level directory of the pages changes
+            let mut groups = vec![];
+            let mut start = 0;
+
+            for i in 1..=names.len() {
+                let top_dir = |i: usize| match names[i].split_once('/') {
+                    Some((top_dir, _)) => top_dir,
+                    None => "",
+                };
+
+                if i == names.len() || top_dir(i) != top_dir(start) {
+                    groups.push(start..i);
+                    start = i;
+                }
+            }
+
+            groups
+        }
+
+        (None, None) => return Err(SplitError::NoSplitMethod),
+    };
+
+    report.phase("scan", scan_started.elapsed());
+
+    info!(
+        "Going to split '{}' ({} pages) into {} volume(s).",
+        input.to_string_lossy(),
+        names.len(),
+        groups.len()
+    );
+
+    // Get timestamp to measure the building phase
+    let build_started = Instant::now();
+
+    let input_name = input
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+
+    let vol_num_len = groups.len().to_string().len();
+
+    for (i, group) in groups.iter().enumerate() {
+        let output = output_dir.join(format!(
+            "{}-{:0vol_num_len$}.{}",
+            input_name,
+            i + 1,
+            DEFAULT_VOLUME_EXT,
+            vol_num_len = vol_num_len
+        ));
+
+        if output.exists() && !opts.overwrite && !prompt::confirm_overwrite(&output) {
+            return Err(SplitError::OutputFileAlreadyExists(output));
+        }
+
+        debug!(
+            "Writing pages {} to {} to '{}'...",
+            group.start + 1,
+            group.end,
+            output.to_string_lossy()
+        );
+
+        // Write the split volume to a staging file, so an existing output is left untouched if something goes wrong
+        let staging_path = deter::add_extension(&output, STAGING_EXT);
+
+        let staging_file = File::create(&staging_path)
+            .map_err(|err| SplitError::FailedToCreateOutputFile(staging_path.clone(), err))?;
+
+        // Ensure the staging file is removed if anything goes wrong
+        let mut staging_guard = StagingFile {
+            path: staging_path.clone(),
+            finalized: false,
+        };
+
+        let mut zip_writer = ZipWriter::new(staging_file);
+
+        for page in group.clone() {
+            trace!("Adding page '{}'...", names[page]);
+
+            match &mut source {
+                // Pages coming from an archive are copied as they are, without being decompressed
+                SplitSource::Archive(archive, indexes) => {
+                    let entry = archive
+                        .by_index(indexes[page])
+                        .map_err(|err| SplitError::InvalidZipArchive(input.clone(), err))?;
+
+                    zip_writer.raw_copy_file(entry).map_err(|err| {
+                        SplitError::FailedToWritePage {
+                            output: output.clone(),
+                            page_name: names[page].clone(),
+                            err,
+                        }
+                    })?;
+                }
+
+                SplitSource::Pdf(images) => {
+                    let write_err = |err| SplitError::FailedToWritePage {
+                        output: output.clone(),
+                        page_name: names[page].clone(),
+                        err,
+                    };
+
+                    zip_writer
+                        .start_file(names[page].clone(), FileOptions::default())
+                        .map_err(write_err)?;
+
+                    // Images extracted from a PDF are already in memory
+                    zip_writer
+                        .write_all(&images[page])
+                        .map_err(|err| write_err(ZipError::Io(err)))?;
+                }
+            }
+        }
+
+        trace!("Closing ZIP archive...");
+
+        zip_writer
+            .finish()
+            .map_err(|err| SplitError::FailedToCloseOutputFile(staging_path.clone(), err))?;
+
+        fs::rename(&staging_path, &output)
+            .map_err(|err| SplitError::FailedToRenameOutputFile(output.clone(), err))?;
+
+        staging_guard.finalized = true;
+
+        info!(
+            "Successfully written volume {} / {} to '{}', containing {} pages.",
+            i + 1,
+            groups.len(),
+            output.to_string_lossy(),
+            group.len()
+        );
+
+        report.outputs.push(output);
+    }
+
+    report.phase("build", build_started.elapsed());
+    report.count("volumes", groups.len());
+    report.count("pages", names.len());
+
+    Ok(report)
+}
+
+/// Parse a comma-separated list of page ranges (e.g. "1-50,51-120,121"), with pages starting at 1
+/// Returns the matching ranges of page indexes
+fn parse_ranges(ranges: &str, pages: usize) -> Result<Vec<Range<usize>>, SplitError> {
+    ranges
+        .split(',')
+        .map(|range| {
+            let invalid = || SplitError::InvalidRange(range.to_owned());
+
+            let (start, end) = match range.split_once('-') {
+                Some((start, end)) => (start, end),
+                None => (range, range),
+            };
+
+            let start = start.trim().parse::<usize>().map_err(|_| invalid())?;
+            let end = end.trim().parse::<usize>().map_err(|_| invalid())?;
+
+            if start == 0 || end < start || end > pages {
+                return Err(invalid());
+            }
+
+            Ok(start - 1..end)
+        })
+        .collect()
+}
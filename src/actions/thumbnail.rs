@@ -0,0 +1,178 @@
+use super::extract_cover::read_cover;
+use crate::cli::error::ThumbnailError;
+use crate::cli::opts::Thumbnail;
+use crate::lib::build_vol::STAGING_EXT;
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::report::Report;
+use crate::lib::sort;
+use crate::lib::transform;
+use image::ImageFormat;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+/// Name of the directory the thumbnails are written to, in the volumes' directory
+pub const THUMBNAILS_DIR_NAME: &str = ".thumbnails";
+
+/// Generate a thumbnail of every volume of a directory, without decoding their other pages
+pub fn thumbnail(opts: &Thumbnail) -> Result<Report, ThumbnailError> {
+    let mut report = Report::new();
+
+    if opts.page == 0 {
+        return Err(ThumbnailError::InvalidPageNumber);
+    }
+
+    if opts.width == 0 || opts.height == 0 {
+        return Err(ThumbnailError::InvalidSize);
+    }
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(ThumbnailError::FailedToGetCWD)?;
+
+    let input = cwd.join(&opts.input);
+
+    if !input.is_dir() {
+        return Err(ThumbnailError::InputNotFound(input));
+    }
+
+    // Only volumes whose pages can be reached without decoding the whole volume are listed
+    let mut volumes = fs::read_dir(&input)
+        .map_err(|err| ThumbnailError::FailedToReadInputDirectory(input.clone(), err))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| ThumbnailError::FailedToReadInputDirectory(input.clone(), err))?
+        .into_iter()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_supported_volume(path))
+        .collect::<Vec<_>>();
+
+    sort::natural_sort_paths(&mut volumes, false);
+
+    if volumes.is_empty() {
+        return Err(ThumbnailError::NoVolumeFound(input));
+    }
+
+    let output_dir = input.join(THUMBNAILS_DIR_NAME);
+
+    if !output_dir.is_dir() {
+        fs::create_dir(&output_dir).map_err(|err| {
+            ThumbnailError::FailedToCreateOutputDirectory(output_dir.clone(), err)
+        })?;
+    }
+
+    // Get timestamp to measure the generation phase
+    let generation_started = Instant::now();
+
+    let mut skipped = 0;
+    let mut failed = 0;
+
+    for (i, volume) in volumes.iter().enumerate() {
+        // The volume's extension is kept, so thumbnails of volumes with the same name in different formats don't collide
+        let output = output_dir.join(format!(
+            "{}.jpg",
+            volume.file_name().unwrap().to_string_lossy()
+        ));
+
+        if !opts.overwrite && is_up_to_date(volume, &output) {
+            trace!(
+                "Skipping volume '{}' as its thumbnail is up to date.",
+                volume.to_string_lossy()
+            );
+
+            skipped += 1;
+            continue;
+        }
+
+        debug!(
+            "Generating thumbnail {}/{} from '{}'...",
+            i + 1,
+            volumes.len(),
+            volume.to_string_lossy()
+        );
+
+        match generate_thumbnail(opts, volume, &output) {
+            Ok(()) => report.outputs.push(output),
+            Err(err) => {
+                error!("{}", err);
+                failed += 1;
+            }
+        }
+    }
+
+    report.phase("generate", generation_started.elapsed());
+    report.count("thumbnails", report.outputs.len());
+    report.count("up-to-date thumbnails", skipped);
+
+    if failed > 0 {
+        return Err(ThumbnailError::SomeThumbnailsFailed(failed, volumes.len()));
+    }
+
+    info!(
+        "Successfully generated {} thumbnail(s) in '{}' ({} already up to date).",
+        report.outputs.len(),
+        output_dir.to_string_lossy(),
+        skipped
+    );
+
+    Ok(report)
+}
+
+/// Generate the thumbnail of a single volume
+fn generate_thumbnail(
+    opts: &Thumbnail,
+    volume: &Path,
+    output: &Path,
+) -> Result<(), ThumbnailError> {
+    let (page_name, bytes) =
+        read_cover(volume, Some(opts.page), opts.accept_extended_image_formats)
+            .map_err(ThumbnailError::FailedToReadPage)?;
+
+    trace!(
+        "Resizing page '{}' to '{}'...",
+        page_name,
+        output.to_string_lossy()
+    );
+
+    let thumbnail = transform::load_picture(&bytes)
+        .map_err(|err| ThumbnailError::FailedToDecodePage {
+            volume: volume.to_path_buf(),
+            page_name,
+            err,
+        })?
+        .thumbnail(opts.width, opts.height)
+        .to_rgb8();
+
+    // Write the thumbnail to a staging file, so an existing one is left untouched if something goes wrong
+    let staging_path = deter::add_extension(output, STAGING_EXT);
+
+    thumbnail
+        .save_with_format(&staging_path, ImageFormat::Jpeg)
+        .map_err(|err| ThumbnailError::FailedToEncodeThumbnail(staging_path.clone(), err))?;
+
+    fs::rename(&staging_path, output)
+        .map_err(|err| ThumbnailError::FailedToRenameThumbnail(output.to_path_buf(), err))
+}
+
+/// Check if a file is a volume whose pages can be read directly, judging by its extension
+fn is_supported_volume(path: &Path) -> bool {
+    matches!(
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(ComicFormat::from_ext),
+        Some(ComicFormat::Zip) | Some(ComicFormat::Pdf)
+    )
+}
+
+/// Check if a thumbnail exists and was written after its volume was last modified
+fn is_up_to_date(volume: &Path, thumbnail: &Path) -> bool {
+    let modified = |path: &Path| {
+        fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    };
+
+    match (modified(volume), modified(thumbnail)) {
+        (Some(volume), Some(thumbnail)) => thumbnail >= volume,
+        _ => false,
+    }
+}
@@ -0,0 +1,318 @@
+use crate::cli::error::VerificationError;
+use crate::cli::opts::Verify;
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::manifest::{self, CHECKSUMS_FILE_NAME};
+use crate::lib::report::Report;
+use crate::lib::sevenz;
+use crate::lib::sign::{self, KeyLoadingErr, SignatureCheckErr};
+use crate::lib::tar;
+use ed25519_dalek::PublicKey;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::time::Instant;
+use zip::ZipArchive;
+
+/// Check the integrity of existing volumes
+pub fn verify(opts: &Verify) -> Result<Report, VerificationError> {
+    let mut report = Report::new();
+
+    // Get current directory
+    let cwd = env::current_dir().map_err(VerificationError::FailedToGetCWD)?;
+
+    // Load the public key before checking any volume
+    let public_key = match &opts.public_key {
+        Some(path) if opts.signatures => {
+            trace!("Loading public key...");
+
+            Some(sign::load_public_key(path).map_err(|err| match err {
+                KeyLoadingErr::IOError(err) => {
+                    VerificationError::FailedToLoadPublicKey(path.clone(), err.to_string())
+                }
+                KeyLoadingErr::InvalidKey(err) => {
+                    VerificationError::FailedToLoadPublicKey(path.clone(), err)
+                }
+            })?)
+        }
+
+        _ => None,
+    };
+
+    // Get timestamp to measure the verification phase
+    let verification_started = Instant::now();
+
+    // List of volumes that passed all checks
+    let mut verified = vec![];
+
+    for (i, input) in opts.input.iter().enumerate() {
+        let path = cwd.join(input);
+
+        debug!(
+            "Checking volume {}/{} at '{}'...",
+            i + 1,
+            opts.input.len(),
+            path.to_string_lossy()
+        );
+
+        match verify_volume(&path, public_key.as_ref()) {
+            Ok(entries) => {
+                info!(
+                    "Volume '{}' is valid ({} entries checked{}).",
+                    path.to_string_lossy(),
+                    entries,
+                    if public_key.is_some() {
+                        ", signature matches"
+                    } else {
+                        ""
+                    }
+                );
+
+                verified.push(path);
+            }
+
+            Err(err) => error!("{}", err),
+        }
+    }
+
+    if verified.len() < opts.input.len() {
+        return Err(VerificationError::SomeVolumesAreInvalid(
+            opts.input.len() - verified.len(),
+            opts.input.len(),
+        ));
+    }
+
+    report.phase("verify", verification_started.elapsed());
+    report.count("volumes", verified.len());
+    report.outputs = verified;
+
+    Ok(report)
+}
+
+/// Digests of the entries of a volume whose entries can only be read in order, along with the content of its checksums file
+#[derive(Default)]
+struct EntryDigests {
+    /// Digest of each entry read so far, by its name
+    digests: HashMap<String, String>,
+
+    /// Content of the checksums file, if it was read
+    checksums: Option<String>,
+
+    /// First entry which couldn't be read, if any
+    failure: Option<VerificationError>,
+}
+
+impl EntryDigests {
+    /// Read an entry entirely to compute its digest
+    fn read(&mut self, volume: &Path, entry: &str, content: &mut dyn Read) {
+        if self.failure.is_some() {
+            return;
+        }
+
+        trace!("Checking entry '{}'...", entry);
+
+        let mut bytes = vec![];
+
+        if let Err(err) = content.read_to_end(&mut bytes) {
+            self.failure = Some(VerificationError::CorruptedEntry {
+                volume: volume.to_path_buf(),
+                entry: entry.to_owned(),
+                err,
+            });
+
+            return;
+        }
+
+        if entry == CHECKSUMS_FILE_NAME {
+            self.checksums = Some(String::from_utf8_lossy(&bytes).into_owned());
+        }
+
+        self.digests
+            .insert(entry.to_owned(), manifest::sha256(&bytes));
+    }
+
+    /// Check the digests of the entries against the checksums file, if any
+    /// Returns the number of entries that were checked
+    fn check(self, volume: &Path) -> Result<usize, VerificationError> {
+        if let Some(err) = self.failure {
+            return Err(err);
+        }
+
+        if let Some(checksums) = &self.checksums {
+            for (entry, sha256) in parse_checksums(volume, checksums)?
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+            {
+                match self.digests.get(&entry) {
+                    Some(digest) if *digest == sha256 => {}
+
+                    Some(_) => {
+                        return Err(VerificationError::ChecksumMismatch {
+                            volume: volume.to_path_buf(),
+                            entry,
+                        })
+                    }
+
+                    None => {
+                        return Err(VerificationError::MissingEntry {
+                            volume: volume.to_path_buf(),
+                            entry,
+                        })
+                    }
+                }
+            }
+        }
+
+        Ok(self.digests.len())
+    }
+}
+
+/// Check a single volume
+/// Returns the number of entries that were checked in the archive
+fn verify_volume(path: &Path, public_key: Option<&PublicKey>) -> Result<usize, VerificationError> {
+    if !path.is_file() {
+        return Err(VerificationError::VolumeNotFound(path.to_path_buf()));
+    }
+
+    let format = deter::sniff_format(path)
+        .map_err(|err| VerificationError::FailedToOpenVolume(path.to_path_buf(), err))?;
+
+    let entries = match format {
+        Some(ComicFormat::SevenZip) => verify_7z_entries(path)?,
+        Some(ComicFormat::Tar) => verify_tar_entries(path)?,
+        _ => verify_zip_entries(path)?,
+    };
+
+    if let Some(public_key) = public_key {
+        trace!("Checking signature...");
+
+        sign::verify_file(public_key, path).map_err(|err| match err {
+            SignatureCheckErr::IOError(err) => {
+                VerificationError::FailedToReadSignature(path.to_path_buf(), err)
+            }
+            SignatureCheckErr::MissingSignature(sig_path) => {
+                VerificationError::MissingSignature(path.to_path_buf(), sig_path)
+            }
+            SignatureCheckErr::InvalidSignatureFile(sig_path) => {
+                VerificationError::InvalidSignatureFile(path.to_path_buf(), sig_path)
+            }
+            SignatureCheckErr::BadSignature => VerificationError::BadSignature(path.to_path_buf()),
+        })?;
+    }
+
+    Ok(entries)
+}
+
+/// Check the entries of a 7-Zip volume against their CRC, and against the checksums file if any
+/// Entries are compressed together so they can only be read in order, which is why they are compared once they are all read
+fn verify_7z_entries(path: &Path) -> Result<usize, VerificationError> {
+    let mut digests = EntryDigests::default();
+
+    sevenz::for_each_file(path, |entry, content| digests.read(path, entry, content)).map_err(
+        |err| VerificationError::InvalidSevenZipArchive(path.to_path_buf(), err.to_string()),
+    )?;
+
+    digests.check(path)
+}
+
+/// Check the entries of a TAR volume against the checksums file if any, as they don't have a CRC
+fn verify_tar_entries(path: &Path) -> Result<usize, VerificationError> {
+    let mut digests = EntryDigests::default();
+
+    tar::for_each_file(path, |entry, content| digests.read(path, entry, content))
+        .map_err(|err| VerificationError::InvalidTarArchive(path.to_path_buf(), err.to_string()))?;
+
+    digests.check(path)
+}
+
+/// Check the entries of a ZIP volume against their CRC, and against the checksums file if any
+/// Returns the number of entries that were checked
+fn verify_zip_entries(path: &Path) -> Result<usize, VerificationError> {
+    let file = File::open(path)
+        .map_err(|err| VerificationError::FailedToOpenVolume(path.to_path_buf(), err))?;
+
+    let mut zip = ZipArchive::new(file)
+        .map_err(|err| VerificationError::InvalidZipArchive(path.to_path_buf(), err))?;
+
+    // Digests of the pages, if the volume embeds them
+    let mut checksums = match zip.by_name(CHECKSUMS_FILE_NAME) {
+        Ok(mut entry) => {
+            trace!("Reading checksums file...");
+
+            let mut content = String::new();
+
+            entry.read_to_string(&mut content).map_err(|err| {
+                VerificationError::CorruptedEntry {
+                    volume: path.to_path_buf(),
+                    entry: CHECKSUMS_FILE_NAME.to_owned(),
+                    err,
+                }
+            })?;
+
+            parse_checksums(path, &content)?
+                .into_iter()
+                .collect::<HashMap<_, _>>()
+        }
+
+        Err(_) => HashMap::new(),
+    };
+
+    let mut content = vec![];
+
+    // Read every entry entirely, which makes the ZIP reader check its CRC
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|err| VerificationError::InvalidZipArchive(path.to_path_buf(), err))?;
+
+        let entry_name = entry.name().to_owned();
+
+        trace!("Checking entry '{}'...", entry_name);
+
+        let corrupted = |err| VerificationError::CorruptedEntry {
+            volume: path.to_path_buf(),
+            entry: entry_name.clone(),
+            err,
+        };
+
+        match checksums.remove(&entry_name) {
+            Some(sha256) => {
+                content.clear();
+                entry.read_to_end(&mut content).map_err(corrupted)?;
+
+                if manifest::sha256(&content) != sha256 {
+                    return Err(VerificationError::ChecksumMismatch {
+                        volume: path.to_path_buf(),
+                        entry: entry_name,
+                    });
+                }
+            }
+
+            None => {
+                io::copy(&mut entry, &mut io::sink()).map_err(corrupted)?;
+            }
+        }
+    }
+
+    // Every page listed in the checksums file must be in the volume
+    if let Some(entry) = checksums.into_iter().map(|(entry, _)| entry).min() {
+        return Err(VerificationError::MissingEntry {
+            volume: path.to_path_buf(),
+            entry,
+        });
+    }
+
+    Ok(zip.len())
+}
+
+/// Parse the content of a volume's checksums file
+fn parse_checksums(
+    volume: &Path,
+    content: &str,
+) -> Result<Vec<(String, String)>, VerificationError> {
+    manifest::parse_checksums(content).map_err(|line| VerificationError::InvalidChecksumsFile {
+        volume: volume.to_path_buf(),
+        line,
+    })
+}
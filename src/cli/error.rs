@@ -4,6 +4,9 @@ use std::path::PathBuf;
 use std::fmt;
 use zip::result::ZipError;
 use pdf::error::PdfError;
+use image::ImageError;
+use crate::lib::deter::ComicFormat;
+use crate::lib::transform::PageFormat;
 
 /// Error during in the "encode" action
 pub enum EncodingError {
@@ -16,6 +19,7 @@ pub enum EncodingError {
     FailedToGetCWD(IOError),
     ChaptersDirectoryNotFound,
     OutputDirectoryNotFound,
+    DangerousOutputDirectory(PathBuf),
     OutputFileHasInvalidUTF8Name(OsString),
     SingleInputDirectoryNotFound,
     SingleInputDirectoryIsNotADirectory,
@@ -27,7 +31,6 @@ pub enum EncodingError {
     FailedToCreateVolumeFile(usize, PathBuf, IOError),
     OutputVolumeFileAlreadyExists(usize, PathBuf),
     OutputVolumeFileIsADirectory(usize, PathBuf),
-    FailedToOverwriteOutputVolumeFile(usize, PathBuf, IOError),
     FailedToListChapterDirectoryFiles { volume: usize, chapter: usize, chapter_path: PathBuf, err: IOError },
     FoundItemWithInvalidName { volume: usize, chapter: usize, chapter_path: PathBuf, invalid_item_path: PathBuf },
     FailedToOpenImage { volume: usize, chapter: usize, chapter_path: PathBuf, image_path: PathBuf, err: IOError },
@@ -35,8 +38,77 @@ pub enum EncodingError {
     FailedToCreateImageFileInZip { volume: usize, chapter: usize, file_path: PathBuf, err: ZipError },
     FailedToReadImage { volume: usize, chapter: usize, chapter_path: PathBuf, image_path: PathBuf, err: IOError },
     FailedToWriteImageFileToZip { volume: usize, chapter: usize, chapter_path: PathBuf, image_path: PathBuf, err: IOError },
+    FailedToOpenChapterArchive { volume: usize, chapter: usize, chapter_path: PathBuf, err: IOError },
+    InvalidChapterArchive { volume: usize, chapter: usize, chapter_path: PathBuf, err: ZipError },
+    FailedToCopyArchiveEntry { volume: usize, chapter: usize, chapter_path: PathBuf, entry_path: PathBuf, err: ZipError },
+    FailedToReadChapterPdf { volume: usize, chapter: usize, chapter_path: PathBuf, page: Option<usize>, err: PdfError },
+    FailedToReadChapterArchive { volume: usize, chapter: usize, chapter_path: PathBuf, err: String },
     FailedToCloseZipArchive(usize, ZipError),
-    FailedToRenameCompleteArchive(usize, IOError)
+    FailedToRenameCompleteArchive(usize, IOError),
+    FailedToLoadSigningKey(PathBuf, String),
+    FailedToSignVolume(usize, PathBuf, IOError),
+    ExistingVolumeNotFound(PathBuf),
+    FailedToOpenExistingVolume(PathBuf, IOError),
+    InvalidExistingVolume(PathBuf, ZipError),
+    FailedToCopyExistingVolumeEntry { volume_path: PathBuf, entry_name: String, err: ZipError },
+    ChapterNotFoundInVolume(usize, PathBuf),
+    NothingToEdit,
+    InvalidIgnoreFile(PathBuf, String),
+    ConversionInputNotFound(PathBuf),
+    FailedToReadConversionInput(PathBuf, IOError),
+    UnsupportedConversionInput(PathBuf, Option<ComicFormat>),
+    UnsupportedConversionOutput(ComicFormat),
+    FailedToCreatePlanThumbnailsDirectory(PathBuf, IOError),
+    FailedToSavePlanThumbnail(PathBuf, ImageError),
+    MissingChaptersPerVolume,
+    AtLeast1PagePerVolume,
+    AtLeast1Volume,
+    FailedToCountChapterPages(PathBuf, String),
+    FailedToEditPlan(String),
+    ExtractedVolumeDirectoryAlreadyExists(usize, PathBuf),
+    FailedToOverwriteExtractedVolumeDirectory(usize, PathBuf, IOError),
+    FailedToCreateExtractedChapterDirectory(usize, PathBuf, IOError),
+    FailedToWriteExtractedPicture(usize, PathBuf, IOError),
+    FailedToReadComicInfo { volume: usize, chapter: usize, chapter_path: PathBuf, err: IOError },
+    FailedToWriteComicInfo(usize, ZipError),
+    InvalidMetadataFile(String),
+    InvalidSourcesFile(String),
+    FailedToWriteOpfFile(usize, PathBuf, IOError),
+    FailedToWriteSidecar(usize, PathBuf, IOError),
+    FailedToWriteReadingList(PathBuf, IOError),
+    FailedToWriteSeriesJson(PathBuf, IOError),
+    FailedToLoadManifest(PathBuf, String),
+    FailedToWriteIndex(PathBuf, IOError),
+    FailedToWriteOpdsCover(PathBuf, IOError),
+    FailedToWriteOpdsCatalog(PathBuf, IOError),
+    CoverIsNotAPicture(usize, PathBuf),
+    FailedToReadCover(usize, PathBuf, IOError),
+    FailedToWriteCover(usize, ZipError),
+    UnsupportedVolumeFormat(ComicFormat),
+    FailedToWrite7zVolume(usize, String),
+    FailedToWriteTarVolume(usize, String),
+    FailedToWriteEmbeddedManifest(usize, ZipError),
+    FailedToWriteChecksums(usize, ZipError),
+    FailedToWriteToc(usize, ZipError),
+    FailedToWriteContactSheet(usize, PathBuf, ImageError),
+    FailedToDownscalePicture { volume: usize, picture: String, err: ImageError },
+    FailedToRecompressPicture { volume: usize, picture: String, err: ImageError },
+    FailedToOptimizeJpeg { volume: usize, picture: String, err: String },
+    FailedToConvertPicture { volume: usize, picture: String, format: PageFormat, err: ImageError },
+    FailedToOrientPicture { volume: usize, picture: String, err: ImageError },
+    FailedToConvertPictureColors { volume: usize, picture: String, err: ImageError },
+    FailedToTrimPicture { volume: usize, picture: String, err: ImageError },
+    FailedToGrayscalePicture { volume: usize, picture: String, err: ImageError },
+    FailedToQuantizePicture { volume: usize, picture: String, err: ImageError },
+    FailedToUpscalePicture { volume: usize, picture: String, err: String },
+    FailedToFilterPicture { volume: usize, picture: String, err: String },
+    InvalidImage { volume: usize, chapter: usize, image_path: PathBuf, err: ImageError },
+    FailedToSplitSpread { volume: usize, picture: String, err: ImageError },
+    FailedToSplitTallPage { volume: usize, picture: String, err: ImageError },
+    FailedToJoinPages { volume: usize, first: String, second: String, err: ImageError },
+    FailedToOpenPageStore(PathBuf, IOError),
+    PreHookFailed(String),
+    PostHookFailed(usize, String)
 }
 
 impl fmt::Display for EncodingError {
@@ -69,6 +141,9 @@ impl fmt::Display for EncodingError {
             Self::OutputDirectoryNotFound =>
                 "Output directory was not found".to_string(),
 
+            Self::DangerousOutputDirectory(path) =>
+                format!("Refusing to use '{}' as the output directory as it is the root of a filesystem or the home directory (use '--force-dangerous-paths' to use it anyway)", path.to_string_lossy()),
+
             Self::OutputFileHasInvalidUTF8Name(name) =>
                 format!("Output file does not have a valid UTF-8 name ('{}')", name.to_string_lossy()),
 
@@ -102,9 +177,6 @@ impl fmt::Display for EncodingError {
             Self::OutputVolumeFileIsADirectory(volume, path) =>
                 format!("Failed to create the file of volume {} because path '{}' is a directory", volume, path.to_string_lossy()),
 
-            Self::FailedToOverwriteOutputVolumeFile(volume, path, err) =>
-                format!("Failed to overwrite the file of volume {} at path '{}': {}", volume, path.to_string_lossy(), err),
-
             Self::FailedToListChapterDirectoryFiles { volume, chapter, chapter_path, err } =>
                 format!(
                     "Failed to list files for chapter {} in volume {} at '{}': {}",
@@ -159,11 +231,261 @@ impl fmt::Display for EncodingError {
                     err
                 ),
 
+            Self::FailedToOpenChapterArchive { volume, chapter, chapter_path, err } =>
+                format!(
+                    "Failed to open archive of chapter {} in volume {} at '{}': {}",
+                    chapter,
+                    volume,
+                    chapter_path.to_string_lossy(),
+                    err
+                ),
+
+            Self::InvalidChapterArchive { volume, chapter, chapter_path, err } =>
+                format!(
+                    "Archive of chapter {} in volume {} at '{}' is not a valid ZIP archive: {}",
+                    chapter,
+                    volume,
+                    chapter_path.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToCopyArchiveEntry { volume, chapter, chapter_path: _, entry_path, err } =>
+                format!(
+                    "Failed to copy file '{}' from the archive of chapter {} in volume {}: {}",
+                    entry_path.to_string_lossy(),
+                    chapter,
+                    volume,
+                    err
+                ),
+
+            Self::FailedToReadChapterArchive { volume, chapter, chapter_path, err } =>
+                format!(
+                    "Failed to read archive of chapter {} in volume {} at '{}': {}",
+                    chapter,
+                    volume,
+                    chapter_path.to_string_lossy(),
+                    err
+                ),
+
+            Self::FailedToReadChapterPdf { volume, chapter, chapter_path, page, err } =>
+                format!(
+                    "Failed to read PDF of chapter {} in volume {} at '{}'{}: {}",
+                    chapter,
+                    volume,
+                    chapter_path.to_string_lossy(),
+                    match page {
+                        Some(page) => format!(" (page n°{})", page),
+                        None => String::new()
+                    },
+                    err
+                ),
+
             Self::FailedToCloseZipArchive(volume, err) =>
                 format!("Failed to close archive for volume {}: {}", volume, err),
 
             Self::FailedToRenameCompleteArchive(volume, err) =>
-                format!("Failed to rename complete archive for volume {}: {}", volume, err)
+                format!("Failed to rename complete archive for volume {}: {}", volume, err),
+
+            Self::FailedToLoadSigningKey(path, err) =>
+                format!("Failed to load signing key from '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToSignVolume(volume, path, err) =>
+                format!("Failed to sign volume {} at path '{}': {}", volume, path.to_string_lossy(), err),
+
+            Self::ExistingVolumeNotFound(path) =>
+                format!("Existing volume was not found at '{}'", path.to_string_lossy()),
+
+            Self::FailedToOpenExistingVolume(path, err) =>
+                format!("Failed to open existing volume at '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidExistingVolume(path, err) =>
+                format!("Existing volume at '{}' is not a valid ZIP archive: {}", path.to_string_lossy(), err),
+
+            Self::FailedToCopyExistingVolumeEntry { volume_path, entry_name, err } =>
+                format!("Failed to copy entry '{}' from existing volume '{}': {}", entry_name, volume_path.to_string_lossy(), err),
+
+            Self::ChapterNotFoundInVolume(chapter, path) =>
+                format!("Chapter {} was not found in volume '{}'", chapter, path.to_string_lossy()),
+
+            Self::NothingToEdit =>
+                "Nothing to edit (either '--replace-chapter' or '--remove-chapter' must be provided)".to_string(),
+
+            Self::InvalidIgnoreFile(path, err) =>
+                format!("Ignore file at '{}' is invalid: {}", path.to_string_lossy(), err),
+
+            Self::ConversionInputNotFound(path) =>
+                format!("Comic book to convert was not found at '{}'", path.to_string_lossy()),
+
+            Self::FailedToReadConversionInput(path, err) =>
+                format!("Failed to read comic book to convert at '{}': {}", path.to_string_lossy(), err),
+
+            Self::UnsupportedConversionInput(path, format) =>
+                match format {
+                    Some(format) => format!("Comic book '{}' cannot be converted as its format ({}) is not supported yet", path.to_string_lossy(), format),
+                    None => format!("Comic book '{}' cannot be converted as its format is unknown", path.to_string_lossy())
+                },
+
+            Self::UnsupportedConversionOutput(format) =>
+                format!("Converting to {} is not supported yet", format),
+
+            Self::FailedToCreatePlanThumbnailsDirectory(path, err) =>
+                format!("Failed to create plan thumbnails directory at '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToSavePlanThumbnail(path, err) =>
+                format!("Failed to save plan thumbnail at '{}': {}", path.to_string_lossy(), err),
+
+            Self::MissingChaptersPerVolume =>
+                "Please provide either a number of chapters per volume, '--target-pages-per-volume' or '--volumes'".to_string(),
+
+            Self::AtLeast1PagePerVolume =>
+                "Target number of pages per volume must be at least 1".to_string(),
+
+            Self::AtLeast1Volume =>
+                "There must be at least 1 volume".to_string(),
+
+            Self::FailedToCountChapterPages(path, err) =>
+                format!("Failed to count pages of chapter at '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToEditPlan(err) =>
+                format!("Failed to edit the plan: {}", err),
+
+            Self::ExtractedVolumeDirectoryAlreadyExists(volume, path) =>
+                format!("Extraction directory of volume {} already exists at path '{}' (use '--overwrite' to replace it)", volume, path.to_string_lossy()),
+
+            Self::FailedToOverwriteExtractedVolumeDirectory(volume, path, err) =>
+                format!("Failed to remove existing extraction directory of volume {} at path '{}': {}", volume, path.to_string_lossy(), err),
+
+            Self::FailedToCreateExtractedChapterDirectory(volume, path, err) =>
+                format!("Failed to create extracted chapter directory of volume {} at path '{}': {}", volume, path.to_string_lossy(), err),
+
+            Self::FailedToWriteExtractedPicture(volume, path, err) =>
+                format!("Failed to write extracted picture of volume {} at path '{}': {}", volume, path.to_string_lossy(), err),
+
+            Self::FailedToReadComicInfo { volume, chapter, chapter_path, err } =>
+                format!("Failed to read metadata file of chapter {} in volume {} at path '{}': {}", chapter, volume, chapter_path.to_string_lossy(), err),
+
+            Self::FailedToWriteComicInfo(volume, err) =>
+                format!("Failed to write metadata file to volume {}: {}", volume, err),
+
+            Self::InvalidMetadataFile(err) =>
+                format!("Failed to load metadata file: {}", err),
+
+            Self::InvalidSourcesFile(err) =>
+                format!("Failed to load sources file: {}", err),
+
+            Self::FailedToWriteOpfFile(volume, path, err) =>
+                format!("Failed to write Calibre metadata file of volume {} to '{}': {}", volume, path.to_string_lossy(), err),
+
+            Self::FailedToWriteSidecar(volume, path, err) =>
+                format!("Failed to write sidecar file of volume {} to '{}': {}", volume, path.to_string_lossy(), err),
+
+            Self::FailedToWriteReadingList(path, err) =>
+                format!("Failed to write reading list to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToWriteSeriesJson(path, err) =>
+                format!("Failed to write series manifest to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToWriteIndex(path, err) =>
+                format!("Failed to write summary of volumes to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToLoadManifest(dir, err) =>
+                format!("Failed to load manifest of directory '{}': {}", dir.to_string_lossy(), err),
+
+            Self::FailedToWriteOpdsCover(path, err) =>
+                format!("Failed to write cover of OPDS catalog to '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToWriteOpdsCatalog(path, err) =>
+                format!("Failed to write OPDS catalog to '{}': {}", path.to_string_lossy(), err),
+
+            Self::CoverIsNotAPicture(volume, path) =>
+                format!("Cover of volume {} at '{}' is not a supported picture", volume, path.to_string_lossy()),
+
+            Self::FailedToReadCover(volume, path, err) =>
+                format!("Failed to read cover of volume {} at '{}': {}", volume, path.to_string_lossy(), err),
+
+            Self::FailedToWriteCover(volume, err) =>
+                format!("Failed to write cover to volume {}: {}", volume, err),
+
+            Self::UnsupportedVolumeFormat(format) =>
+                format!("Volumes cannot be written in the {} format", format),
+
+            Self::FailedToWrite7zVolume(volume, err) =>
+                format!("Failed to write volume {} as a 7-Zip archive: {}", volume, err),
+
+            Self::FailedToWriteTarVolume(volume, err) =>
+                format!("Failed to write volume {} as a TAR archive: {}", volume, err),
+
+            Self::FailedToWriteEmbeddedManifest(volume, err) =>
+                format!("Failed to write embedded manifest to volume {}: {}", volume, err),
+
+            Self::FailedToWriteChecksums(volume, err) =>
+                format!("Failed to write checksums file to volume {}: {}", volume, err),
+
+            Self::FailedToWriteToc(volume, err) =>
+                format!("Failed to write table of contents to volume {}: {}", volume, err),
+
+            Self::FailedToWriteContactSheet(volume, path, err) =>
+                format!("Failed to write contact sheet of volume {} to '{}': {}", volume, path.to_string_lossy(), err),
+
+            Self::FailedToDownscalePicture { volume, picture, err } =>
+                format!("Failed to downscale picture '{}' in volume {}: {}", picture, volume, err),
+
+            Self::FailedToRecompressPicture { volume, picture, err } =>
+                format!("Failed to recompress picture '{}' in volume {} to JPEG: {}", picture, volume, err),
+
+            Self::FailedToOptimizeJpeg { volume, picture, err } =>
+                format!("Failed to optimize JPEG picture '{}' in volume {}: {}", picture, volume, err),
+
+            Self::FailedToConvertPicture { volume, picture, format, err } =>
+                format!("Failed to convert picture '{}' in volume {} to {}: {}", picture, volume, format, err),
+
+            Self::FailedToConvertPictureColors { volume, picture, err } =>
+                format!("Failed to convert colors of picture '{}' in volume {} to sRGB: {}", picture, volume, err),
+
+            Self::FailedToOrientPicture { volume, picture, err } =>
+                format!("Failed to rotate picture '{}' in volume {} according to its orientation: {}", picture, volume, err),
+
+            Self::FailedToTrimPicture { volume, picture, err } =>
+                format!("Failed to trim the margins of picture '{}' in volume {}: {}", picture, volume, err),
+
+            Self::FailedToGrayscalePicture { volume, picture, err } =>
+                format!("Failed to convert picture '{}' in volume {} to grayscale: {}", picture, volume, err),
+
+            Self::FailedToQuantizePicture { volume, picture, err } =>
+                format!("Failed to reduce the colors of picture '{}' in volume {}: {}", picture, volume, err),
+
+            Self::FailedToUpscalePicture { volume, picture, err } =>
+                format!("Failed to upscale picture '{}' in volume {}: {}", picture, volume, err),
+
+            Self::FailedToFilterPicture { volume, picture, err } =>
+                format!("Failed to run the page filter on picture '{}' in volume {}: {}", picture, volume, err),
+
+            Self::InvalidImage { volume, chapter, image_path, err } =>
+                format!(
+                    "Image file '{}' from chapter {} in volume {} cannot be decoded (use '--lenient' to skip it): {}",
+                    image_path.to_string_lossy(),
+                    chapter,
+                    volume,
+                    err
+                ),
+
+            Self::FailedToSplitSpread { volume, picture, err } =>
+                format!("Failed to split double-page spread '{}' in volume {}: {}", picture, volume, err),
+
+            Self::FailedToSplitTallPage { volume, picture, err } =>
+                format!("Failed to slice tall picture '{}' in volume {}: {}", picture, volume, err),
+
+            Self::FailedToJoinPages { volume, first, second, err } =>
+                format!("Failed to join pictures '{}' and '{}' in volume {}: {}", first, second, volume, err),
+
+            Self::FailedToOpenPageStore(path, err) =>
+                format!("Failed to open page store at '{}': {}", path.to_string_lossy(), err),
+
+            Self::PreHookFailed(err) =>
+                format!("Pre-build hook failed: {}", err),
+
+            Self::PostHookFailed(volume, err) =>
+                format!("Post-build hook failed for volume {}: {}", volume, err)
         })
     }
 }
@@ -176,19 +498,25 @@ pub enum DecodingError {
     FailedToGetCWD(IOError),
     FailedToCreateOutputDirectory(IOError),
     OutputDirectoryIsAFile,
+    DangerousOutputDirectory(PathBuf),
     InputFileHasInvalidUTF8FileExtension(OsString),
     UnsupportedFormat(String),
+    FailedToReadInputFile(IOError),
+    FormatMismatch { claimed: ComicFormat, detected: ComicFormat },
     FailedToOpenZipFile(IOError),
     InvalidZipArchive(ZipError),
     ZipError(ZipError),
     ZipFileHasInvalidUTF8FileExtension(PathBuf),
     FailedToCreateOutputFile(IOError, PathBuf),
     FailedToExtractZipFile { path_in_zip: PathBuf, extract_to: PathBuf, err: IOError },
-    FailedToRenameTemporaryFile { from: PathBuf, to: PathBuf, err: IOError },
+    InvalidSevenZipArchive(String),
+    FailedToExtractSevenZipFile { path_in_archive: String, extract_to: PathBuf, err: IOError },
     FailedToOpenPdfFile(PdfError),
     FailedToGetPdfPage(usize, PdfError),
     FailedToGetPdfPageResources(usize, PdfError),
-    FailedToExtractPdfImage(usize, PathBuf, IOError)
+    FailedToExtractPdfImage(usize, PathBuf, IOError),
+    FailedToWriteMetadataFile(PathBuf, IOError),
+    FailedToCreateChapterDirectory(PathBuf, IOError)
 }
 
 impl fmt::Display for DecodingError {
@@ -209,6 +537,9 @@ impl fmt::Display for DecodingError {
             Self::FailedToCreateOutputDirectory(err) =>
                 format!("Failed to create output directory: {}", err),
 
+            Self::DangerousOutputDirectory(path) =>
+                format!("Refusing to extract to '{}' as it is the root of a filesystem or the home directory (use '--force-dangerous-paths' to extract there anyway)", path.to_string_lossy()),
+
             Self::OutputDirectoryIsAFile =>
                 "Output directory is a file".to_string(),
 
@@ -216,7 +547,17 @@ impl fmt::Display for DecodingError {
                 format!("Input file has invalid UTF-8 file extension ('{}')", path.to_string_lossy()),
 
             Self::UnsupportedFormat(ext) =>
-                format!("Unsupported image format (based on file extension) '{}'", ext),
+                format!("Unsupported comic format '{}'", ext),
+
+            Self::FailedToReadInputFile(err) =>
+                format!("Failed to read input file: {}", err),
+
+            Self::FormatMismatch { claimed, detected } =>
+                format!(
+                    "Input file's extension indicates a {} file but its content is a {} file (use '--force-format' to decode it anyway)",
+                    claimed,
+                    detected
+                ),
 
             Self::FailedToOpenZipFile(err) =>
                 format!("Failed to open input ZIP file: {}", err),
@@ -236,8 +577,11 @@ impl fmt::Display for DecodingError {
             Self::FailedToExtractZipFile { path_in_zip, extract_to, err } =>
                 format!("Failed to extract ZIP file '{}' to '{}': {}", path_in_zip.to_string_lossy(), extract_to.to_string_lossy(), err),
 
-            Self::FailedToRenameTemporaryFile { from, to, err } =>
-                format!("Failed to rename temporary file '{}' to '{}': {}", from.to_string_lossy(), to.to_string_lossy(), err),
+            Self::InvalidSevenZipArchive(err) =>
+                format!("Invalid 7-Zip archive: {}", err),
+
+            Self::FailedToExtractSevenZipFile { path_in_archive, extract_to, err } =>
+                format!("Failed to extract 7-Zip file '{}' to '{}': {}", path_in_archive, extract_to.to_string_lossy(), err),
 
             Self::FailedToOpenPdfFile(err) =>
                 format!("Failed to open PDF file: {}", err),
@@ -249,7 +593,582 @@ impl fmt::Display for DecodingError {
                 format!("Failed to get resources from PDF page n°{}: {}", page, err),
 
             Self::FailedToExtractPdfImage(page, path, err) =>
-                format!("Failed extract PDF image from page n°{} to path '{}': {}", page, path.to_string_lossy(), err)
+                format!("Failed extract PDF image from page n°{} to path '{}': {}", page, path.to_string_lossy(), err),
+
+            Self::FailedToWriteMetadataFile(path, err) =>
+                format!("Failed to write metadata file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreateChapterDirectory(path, err) =>
+                format!("Failed to create chapter directory '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+/// Error during in the "verify" action
+pub enum VerificationError {
+    FailedToGetCWD(IOError),
+    FailedToLoadPublicKey(PathBuf, String),
+    VolumeNotFound(PathBuf),
+    FailedToOpenVolume(PathBuf, IOError),
+    InvalidZipArchive(PathBuf, ZipError),
+    InvalidSevenZipArchive(PathBuf, String),
+    InvalidTarArchive(PathBuf, String),
+    CorruptedEntry { volume: PathBuf, entry: String, err: IOError },
+    FailedToReadSignature(PathBuf, IOError),
+    MissingSignature(PathBuf, PathBuf),
+    InvalidSignatureFile(PathBuf, PathBuf),
+    BadSignature(PathBuf),
+    InvalidChecksumsFile { volume: PathBuf, line: String },
+    MissingEntry { volume: PathBuf, entry: String },
+    ChecksumMismatch { volume: PathBuf, entry: String },
+    SomeVolumesAreInvalid(usize, usize)
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::FailedToLoadPublicKey(path, err) =>
+                format!("Failed to load public key from '{}': {}", path.to_string_lossy(), err),
+
+            Self::VolumeNotFound(path) =>
+                format!("Volume '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToOpenVolume(path, err) =>
+                format!("Failed to open volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidZipArchive(path, err) =>
+                format!("Volume '{}' is not a valid ZIP archive: {}", path.to_string_lossy(), err),
+
+            Self::InvalidSevenZipArchive(path, err) =>
+                format!("Volume '{}' is not a valid 7-Zip archive: {}", path.to_string_lossy(), err),
+
+            Self::InvalidTarArchive(path, err) =>
+                format!("Volume '{}' is not a valid TAR archive: {}", path.to_string_lossy(), err),
+
+            Self::CorruptedEntry { volume, entry, err } =>
+                format!("Entry '{}' of volume '{}' is corrupted: {}", entry, volume.to_string_lossy(), err),
+
+            Self::FailedToReadSignature(path, err) =>
+                format!("Failed to read signature of volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::MissingSignature(path, sig_path) =>
+                format!("Volume '{}' has no signature (expected at '{}')", path.to_string_lossy(), sig_path.to_string_lossy()),
+
+            Self::InvalidSignatureFile(path, sig_path) =>
+                format!("Signature file '{}' of volume '{}' is invalid", sig_path.to_string_lossy(), path.to_string_lossy()),
+
+            Self::BadSignature(path) =>
+                format!("Signature of volume '{}' does not match its content", path.to_string_lossy()),
+
+            Self::InvalidChecksumsFile { volume, line } =>
+                format!("Checksums file of volume '{}' contains an invalid line: {}", volume.to_string_lossy(), line),
+
+            Self::MissingEntry { volume, entry } =>
+                format!("Entry '{}' listed in the checksums file of volume '{}' is missing", entry, volume.to_string_lossy()),
+
+            Self::ChecksumMismatch { volume, entry } =>
+                format!("Entry '{}' of volume '{}' does not match its checksum", entry, volume.to_string_lossy()),
+
+            Self::SomeVolumesAreInvalid(failed, total) =>
+                format!("{} out of {} volume(s) failed verification", failed, total)
+        })
+    }
+}
+
+/// Error during in the "repack" action
+pub enum RepackError {
+    FailedToGetCWD(IOError),
+    VolumeNotFound(PathBuf),
+    FailedToReadVolume(PathBuf, IOError),
+    UnsupportedFormat(PathBuf, Option<ComicFormat>),
+    OutputDirectoryNotFound(PathBuf),
+    OutputVolumeFileAlreadyExists(PathBuf),
+    InvalidZipArchive(PathBuf, ZipError),
+    FailedToCreateVolumeFile(PathBuf, IOError),
+    FailedToReadEntry { volume: PathBuf, entry: String, err: ZipError },
+    FailedToCreateEntry { volume: PathBuf, entry: String, err: ZipError },
+    FailedToWriteEntry { volume: PathBuf, entry: String, err: IOError },
+    FailedToCloseVolume(PathBuf, ZipError),
+    FailedToRenameVolume(PathBuf, IOError),
+    SomeVolumesFailed(usize, usize)
+}
+
+impl fmt::Display for RepackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::VolumeNotFound(path) =>
+                format!("Volume '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToReadVolume(path, err) =>
+                format!("Failed to read volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::UnsupportedFormat(path, format) =>
+                match format {
+                    Some(format) => format!("Volume '{}' cannot be repacked as its format ({}) is not supported yet", path.to_string_lossy(), format),
+                    None => format!("Volume '{}' cannot be repacked as its format is unknown", path.to_string_lossy())
+                },
+
+            Self::OutputDirectoryNotFound(path) =>
+                format!("Output directory '{}' was not found", path.to_string_lossy()),
+
+            Self::OutputVolumeFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to replace it)", path.to_string_lossy()),
+
+            Self::InvalidZipArchive(path, err) =>
+                format!("Volume '{}' is not a valid ZIP archive: {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreateVolumeFile(path, err) =>
+                format!("Failed to create repacked volume file at '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadEntry { volume, entry, err } =>
+                format!("Failed to read entry '{}' of volume '{}': {}", entry, volume.to_string_lossy(), err),
+
+            Self::FailedToCreateEntry { volume, entry, err } =>
+                format!("Failed to create entry '{}' in repacked volume '{}': {}", entry, volume.to_string_lossy(), err),
+
+            Self::FailedToWriteEntry { volume, entry, err } =>
+                format!("Failed to write entry '{}' to repacked volume '{}': {}", entry, volume.to_string_lossy(), err),
+
+            Self::FailedToCloseVolume(path, err) =>
+                format!("Failed to close repacked volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRenameVolume(path, err) =>
+                format!("Failed to move repacked volume to '{}': {}", path.to_string_lossy(), err),
+
+            Self::SomeVolumesFailed(failed, total) =>
+                format!("{} out of {} volume(s) could not be repacked", failed, total)
+        })
+    }
+}
+
+/// Error during in the "merge" action
+pub enum MergeError {
+    FailedToGetCWD(IOError),
+    VolumeNotFound(PathBuf),
+    FailedToReadVolume(PathBuf, IOError),
+    UnsupportedFormat(PathBuf, Option<ComicFormat>),
+    InvalidZipArchive(PathBuf, ZipError),
+    OutputFileAlreadyExists(PathBuf),
+    FailedToCreateOutputFile(PathBuf, IOError),
+    FailedToCreateDirectory { dir_name: String, err: ZipError },
+    FailedToCopyPage { volume: PathBuf, entry_name: String, err: ZipError },
+    FailedToCloseOutputFile(PathBuf, ZipError),
+    FailedToRenameOutputFile(PathBuf, IOError)
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::VolumeNotFound(path) =>
+                format!("Volume '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToReadVolume(path, err) =>
+                format!("Failed to read volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::UnsupportedFormat(path, format) =>
+                match format {
+                    Some(format) => format!("Volume '{}' cannot be merged as its format ({}) is not supported yet", path.to_string_lossy(), format),
+                    None => format!("Volume '{}' cannot be merged as its format is unknown", path.to_string_lossy())
+                },
+
+            Self::InvalidZipArchive(path, err) =>
+                format!("Volume '{}' is not a valid ZIP archive: {}", path.to_string_lossy(), err),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to replace it)", path.to_string_lossy()),
+
+            Self::FailedToCreateOutputFile(path, err) =>
+                format!("Failed to create merged volume file at '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCreateDirectory { dir_name, err } =>
+                format!("Failed to create directory '{}' in merged volume: {}", dir_name, err),
+
+            Self::FailedToCopyPage { volume, entry_name, err } =>
+                format!("Failed to copy page '{}' from volume '{}': {}", entry_name, volume.to_string_lossy(), err),
+
+            Self::FailedToCloseOutputFile(path, err) =>
+                format!("Failed to close merged volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRenameOutputFile(path, err) =>
+                format!("Failed to move merged volume to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+/// Error during in the "split" action
+pub enum SplitError {
+    FailedToGetCWD(IOError),
+    VolumeNotFound(PathBuf),
+    OutputDirectoryNotFound(PathBuf),
+    FailedToReadVolume(PathBuf, IOError),
+    UnsupportedFormat(PathBuf, Option<ComicFormat>),
+    InvalidZipArchive(PathBuf, ZipError),
+    FailedToReadPdf(PathBuf),
+    NoChaptersInPdf(PathBuf),
+    NoSplitMethod,
+    AtLeast1PagePerVolume,
+    InvalidRange(String),
+    OutputFileAlreadyExists(PathBuf),
+    FailedToCreateOutputFile(PathBuf, IOError),
+    FailedToWritePage { output: PathBuf, page_name: String, err: ZipError },
+    FailedToCloseOutputFile(PathBuf, ZipError),
+    FailedToRenameOutputFile(PathBuf, IOError)
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::VolumeNotFound(path) =>
+                format!("Volume '{}' was not found", path.to_string_lossy()),
+
+            Self::OutputDirectoryNotFound(path) =>
+                format!("Output directory '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToReadVolume(path, err) =>
+                format!("Failed to read volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::UnsupportedFormat(path, format) =>
+                match format {
+                    Some(format) => format!("Volume '{}' cannot be split as its format ({}) is not supported yet", path.to_string_lossy(), format),
+                    None => format!("Volume '{}' cannot be split as its format is unknown", path.to_string_lossy())
+                },
+
+            Self::InvalidZipArchive(path, err) =>
+                format!("Volume '{}' is not a valid ZIP archive: {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadPdf(path) =>
+                format!("Failed to extract the pages of PDF document '{}'", path.to_string_lossy()),
+
+            Self::NoChaptersInPdf(path) =>
+                format!("PDF document '{}' cannot be split at chapters as it contains no chapter directory", path.to_string_lossy()),
+
+            Self::NoSplitMethod =>
+                "Please provide either '--pages-per-volume', '--ranges' or '--at-chapters'".to_string(),
+
+            Self::AtLeast1PagePerVolume =>
+                "There must be at least 1 page per volume".to_string(),
+
+            Self::InvalidRange(range) =>
+                format!("Invalid page range '{}'", range),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Output file '{}' already exists (use '--overwrite' to replace it)", path.to_string_lossy()),
+
+            Self::FailedToCreateOutputFile(path, err) =>
+                format!("Failed to create split volume file at '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToWritePage { output, page_name, err } =>
+                format!("Failed to write page '{}' to split volume '{}': {}", page_name, output.to_string_lossy(), err),
+
+            Self::FailedToCloseOutputFile(path, err) =>
+                format!("Failed to close split volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRenameOutputFile(path, err) =>
+                format!("Failed to move split volume to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+/// Error during in the "extract-cover" action
+pub enum ExtractCoverError {
+    InvalidPageNumber,
+    FailedToGetCWD(IOError),
+    VolumeNotFound(PathBuf),
+    FailedToReadVolume(PathBuf, IOError),
+    UnsupportedFormat(PathBuf, Option<ComicFormat>),
+    InvalidZipArchive(PathBuf, ZipError),
+    FailedToReadPdf(PathBuf),
+    NoPageInVolume(PathBuf),
+    PageNotFound { volume: PathBuf, page: usize, pages: usize },
+    OutputFileAlreadyExists(PathBuf),
+    FailedToDecodePage { volume: PathBuf, page_name: String, err: ImageError },
+    FailedToWriteCover(PathBuf, IOError),
+    FailedToEncodeCover(PathBuf, ImageError),
+    FailedToRenameCover(PathBuf, IOError)
+}
+
+impl fmt::Display for ExtractCoverError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::InvalidPageNumber =>
+                "Page numbers start at 1".to_string(),
+
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::VolumeNotFound(path) =>
+                format!("Volume '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToReadVolume(path, err) =>
+                format!("Failed to read volume '{}': {}", path.to_string_lossy(), err),
+
+            Self::UnsupportedFormat(path, format) =>
+                match format {
+                    Some(format) => format!("Cannot extract the cover of volume '{}' as its format ({}) is not supported yet", path.to_string_lossy(), format),
+                    None => format!("Cannot extract the cover of volume '{}' as its format is unknown", path.to_string_lossy())
+                },
+
+            Self::InvalidZipArchive(path, err) =>
+                format!("Volume '{}' is not a valid ZIP archive: {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadPdf(path) =>
+                format!("Failed to extract the pages of PDF document '{}'", path.to_string_lossy()),
+
+            Self::NoPageInVolume(path) =>
+                format!("Volume '{}' does not contain any page", path.to_string_lossy()),
+
+            Self::PageNotFound { volume, page, pages } =>
+                format!("Cannot extract page {} of volume '{}' as it only contains {} page(s)", page, volume.to_string_lossy(), pages),
+
+            Self::OutputFileAlreadyExists(path) =>
+                format!("Cover file '{}' already exists (use '--overwrite' to replace it)", path.to_string_lossy()),
+
+            Self::FailedToDecodePage { volume, page_name, err } =>
+                format!("Failed to decode page '{}' of volume '{}': {}", page_name, volume.to_string_lossy(), err),
+
+            Self::FailedToWriteCover(path, err) =>
+                format!("Failed to write cover file at '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToEncodeCover(path, err) =>
+                format!("Failed to encode cover file at '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRenameCover(path, err) =>
+                format!("Failed to move cover file to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+/// Error during in the "thumbnail" action
+pub enum ThumbnailError {
+    InvalidPageNumber,
+    InvalidSize,
+    FailedToGetCWD(IOError),
+    InputNotFound(PathBuf),
+    FailedToReadInputDirectory(PathBuf, IOError),
+    NoVolumeFound(PathBuf),
+    FailedToCreateOutputDirectory(PathBuf, IOError),
+    FailedToReadPage(ExtractCoverError),
+    FailedToDecodePage { volume: PathBuf, page_name: String, err: ImageError },
+    FailedToEncodeThumbnail(PathBuf, ImageError),
+    FailedToRenameThumbnail(PathBuf, IOError),
+    SomeThumbnailsFailed(usize, usize)
+}
+
+impl fmt::Display for ThumbnailError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::InvalidPageNumber =>
+                "Page numbers start at 1".to_string(),
+
+            Self::InvalidSize =>
+                "Thumbnails must be at least 1 pixel wide and high".to_string(),
+
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::InputNotFound(path) =>
+                format!("Input directory '{}' was not found", path.to_string_lossy()),
+
+            Self::FailedToReadInputDirectory(path, err) =>
+                format!("Failed to read input directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::NoVolumeFound(path) =>
+                format!("No CBZ or PDF volume was found in directory '{}'", path.to_string_lossy()),
+
+            Self::FailedToCreateOutputDirectory(path, err) =>
+                format!("Failed to create thumbnails directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadPage(err) =>
+                format!("{}", err),
+
+            Self::FailedToDecodePage { volume, page_name, err } =>
+                format!("Failed to decode page '{}' of volume '{}': {}", page_name, volume.to_string_lossy(), err),
+
+            Self::FailedToEncodeThumbnail(path, err) =>
+                format!("Failed to encode thumbnail at '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRenameThumbnail(path, err) =>
+                format!("Failed to move thumbnail to '{}': {}", path.to_string_lossy(), err),
+
+            Self::SomeThumbnailsFailed(failed, total) =>
+                format!("Failed to generate {} out of {} thumbnail(s)", failed, total)
+        })
+    }
+}
+
+/// Error during in the "sort" action
+pub enum SortError {
+    FailedToGetCWD(IOError),
+    InputNotFound(PathBuf),
+    DangerousDirectory(PathBuf),
+    FailedToReadDirectory(PathBuf, IOError),
+    FailedToReadArchive(PathBuf, IOError),
+    UnsupportedFormat(PathBuf, Option<ComicFormat>),
+    InvalidZipArchive(PathBuf, ZipError),
+    TargetAlreadyExists(PathBuf),
+    FailedToRename { from: PathBuf, to: PathBuf, err: IOError },
+    FailedToCreateOutputFile(PathBuf, IOError),
+    FailedToCopyEntry { entry_name: String, err: ZipError },
+    FailedToCloseOutputFile(PathBuf, ZipError),
+    FailedToRenameOutputFile(PathBuf, IOError)
+}
+
+impl fmt::Display for SortError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::InputNotFound(path) =>
+                format!("Input '{}' was not found", path.to_string_lossy()),
+
+            Self::DangerousDirectory(path) =>
+                format!("Refusing to rename the files of '{}' as it is the root of a filesystem or the home directory (use '--force-dangerous-paths' to rename them anyway)", path.to_string_lossy()),
+
+            Self::FailedToReadDirectory(path, err) =>
+                format!("Failed to read directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToReadArchive(path, err) =>
+                format!("Failed to read archive '{}': {}", path.to_string_lossy(), err),
+
+            Self::UnsupportedFormat(path, format) =>
+                match format {
+                    Some(format) => format!("Cannot sort the entries of '{}' as its format ({}) is not supported yet", path.to_string_lossy(), format),
+                    None => format!("Cannot sort the entries of '{}' as its format is unknown", path.to_string_lossy())
+                },
+
+            Self::InvalidZipArchive(path, err) =>
+                format!("'{}' is not a valid ZIP archive: {}", path.to_string_lossy(), err),
+
+            Self::TargetAlreadyExists(path) =>
+                format!("Cannot rename a file to '{}' as it already exists", path.to_string_lossy()),
+
+            Self::FailedToRename { from, to, err } =>
+                format!("Failed to rename '{}' to '{}': {}", from.to_string_lossy(), to.to_string_lossy(), err),
+
+            Self::FailedToCreateOutputFile(path, err) =>
+                format!("Failed to create output file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToCopyEntry { entry_name, err } =>
+                format!("Failed to copy entry '{}': {}", entry_name, err),
+
+            Self::FailedToCloseOutputFile(path, err) =>
+                format!("Failed to close output file '{}': {}", path.to_string_lossy(), err),
+
+            Self::FailedToRenameOutputFile(path, err) =>
+                format!("Failed to move rewritten archive to '{}': {}", path.to_string_lossy(), err)
+        })
+    }
+}
+
+/// Error during in the "number-pages" action
+pub enum NumberPagesError {
+    FailedToGetCWD(IOError),
+    DirectoryNotFound(PathBuf),
+    DangerousDirectory(PathBuf),
+    FailedToReadDirectory(PathBuf, IOError),
+    InvalidFileName(PathBuf),
+    TargetAlreadyExists(PathBuf),
+    FailedToRename { from: PathBuf, to: PathBuf, err: IOError }
+}
+
+impl fmt::Display for NumberPagesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::DirectoryNotFound(path) =>
+                format!("Directory '{}' was not found", path.to_string_lossy()),
+
+            Self::DangerousDirectory(path) =>
+                format!("Refusing to rename the pictures of '{}' as it is the root of a filesystem or the home directory (use '--force-dangerous-paths' to rename them anyway)", path.to_string_lossy()),
+
+            Self::FailedToReadDirectory(path, err) =>
+                format!("Failed to read directory '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidFileName(path) =>
+                format!("Invalid file name '{}'", path.to_string_lossy()),
+
+            Self::TargetAlreadyExists(path) =>
+                format!("Cannot rename a picture to '{}' as another file already has this name", path.to_string_lossy()),
+
+            Self::FailedToRename { from, to, err } =>
+                format!("Failed to rename '{}' to '{}': {}", from.to_string_lossy(), to.to_string_lossy(), err)
+        })
+    }
+}
+
+/// Error during in the "history" action
+pub enum HistoryError {
+    FailedToLoadHistory(String),
+    RunNotFound(usize, usize)
+}
+
+impl fmt::Display for HistoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToLoadHistory(err) =>
+                format!("Failed to load history: {}", err),
+
+            Self::RunNotFound(nth, runs) =>
+                format!("Run {} was not found in the history, which contains {} run(s)", nth, runs)
+        })
+    }
+}
+
+/// Error during in the "init" action
+pub enum InitError {
+    FailedToGetCWD(IOError),
+    NoAnswer,
+    FailedToWriteScript(PathBuf, IOError),
+    InvalidCommand(String),
+    DryRunFailed(String)
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err),
+
+            Self::NoAnswer =>
+                "Questions can only be answered when the input is a terminal (and without '--yes' or '--no-input')".to_string(),
+
+            Self::FailedToWriteScript(path, err) =>
+                format!("Failed to write script to '{}': {}", path.to_string_lossy(), err),
+
+            Self::InvalidCommand(err) =>
+                format!("Generated command is invalid: {}", err),
+
+            Self::DryRunFailed(err) =>
+                format!("Dry run failed: {}", err)
+        })
+    }
+}
+
+/// Error during in the "doctor" action
+pub enum DoctorError {
+    FailedToGetCWD(IOError)
+}
+
+impl fmt::Display for DoctorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", match self {
+            Self::FailedToGetCWD(err) =>
+                format!("Failed to get current working directory: {}", err)
         })
     }
 }
@@ -1,4 +1,14 @@
+use crate::lib::cover::CoverDetection;
+use crate::lib::deter::{ComicFormat, ZipCompression};
+use crate::lib::entry_names::EntryEncoding;
+use crate::lib::naming::NamingPreset;
+use crate::lib::page_exclusions::PageExclusions;
+use crate::lib::planner::BalanceMode;
+use crate::lib::series_json::SeriesStatus;
+use crate::lib::transform::{ColorSpace, PageFormat, ReadingDirection};
+use crate::lib::upscale::UpscaleFailurePolicy;
 use clap::{crate_authors, crate_description, crate_version, Clap};
+use regex::Regex;
 use std::path::PathBuf;
 
 #[derive(Clap, Debug)]
@@ -34,6 +44,42 @@ pub struct Opts {
     )]
     pub debug: bool,
 
+    /// Display a progress bar of the volumes being built, with only warnings and errors above it (unless more messages are asked for)
+    #[clap(global = true, long, conflicts_with = "silent")]
+    pub quiet_progress: bool,
+
+    /// Write statistics about the run (time spent in each phase, counters, produced files) as JSON to the provided path
+    #[clap(global = true, long, parse(from_os_str))]
+    pub stats_file: Option<PathBuf>,
+
+    /// Write a full report of the run (statistics, built volumes, skipped chapters, warnings and error) as JSON to the provided path, even if it fails
+    #[clap(global = true, long, parse(from_os_str))]
+    pub report: Option<PathBuf>,
+
+    /// Answer "yes" to every question without asking (e.g. to overwrite existing files)
+    #[clap(global = true, long, short, conflicts_with = "no-input")]
+    pub yes: bool,
+
+    /// Answer "no" to every question without asking, for use in scripts
+    #[clap(global = true, long, conflicts_with = "yes")]
+    pub no_input: bool,
+
+    /// Accept the root of a filesystem and the home directory itself as the directory to write or rename files in, which is refused by default as it's usually a typo
+    #[clap(global = true, long)]
+    pub force_dangerous_paths: bool,
+
+    /// Refuse to decode pages wider or higher than the provided number of pixels (65535 by default), to protect against decompression bombs
+    #[clap(global = true, long)]
+    pub max_page_dimension: Option<u32>,
+
+    /// Refuse to decode pages which would take more than the provided amount of memory in megabytes (1024 by default)
+    #[clap(global = true, long)]
+    pub max_page_memory: Option<u64>,
+
+    /// Do not record this run in the history of recent runs
+    #[clap(global = true, long)]
+    pub no_history: bool,
+
     #[clap(subcommand)]
     pub action: Action,
 }
@@ -42,6 +88,19 @@ pub struct Opts {
 pub enum Action {
     Encode(Encode),
     Decode(Decode),
+    Verify(Verify),
+    Edit(Edit),
+    Repack(Repack),
+    Convert(Convert),
+    Merge(Merge),
+    Split(Split),
+    ExtractCover(ExtractCover),
+    Thumbnail(Thumbnail),
+    Sort(Sort),
+    NumberPages(NumberPages),
+    History(History),
+    Doctor(Doctor),
+    Init(Init),
 }
 
 #[derive(Clap, Debug)]
@@ -59,9 +118,10 @@ pub struct Encode {
 pub enum EncodingMethod {
     Compile(CompilationOptions),
     Single(EncodeSingle),
+    Append(EncodeAppend),
 }
 
-#[derive(Clap, Debug)]
+#[derive(Clap, Debug, Default)]
 pub struct EncodingOptions {
     /// Path to the directory containing the chapters or the volumes to encode
     #[clap(parse(from_os_str))]
@@ -79,6 +139,14 @@ pub struct EncodingOptions {
     #[clap(global = true, long)]
     pub append_pages_count: bool,
 
+    /// Shorten the pictures' paths inside volumes when they may be too long to be extracted on some platforms
+    #[clap(global = true, long)]
+    pub shorten_entry_names: bool,
+
+    /// Also write the pages of each volume, as they are named in the volume, to a folder inside the provided directory
+    #[clap(global = true, long, parse(from_os_str))]
+    pub also_extract: Option<PathBuf>,
+
     /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
     #[clap(global = true, short, long)]
     pub accept_extended_image_formats: bool,
@@ -87,9 +155,278 @@ pub struct EncodingOptions {
     #[clap(global = true, short, long)]
     pub simple_sorting: bool,
 
+    /// Sort roman numerals written as separate words in chapters' and pictures' names by their value (e.g. 'Part IV' before 'Part V')
+    #[clap(global = true, long, conflicts_with = "simple-sorting")]
+    pub roman_numerals: bool,
+
+    /// How the cover of each chapter is found: 'name' (default) to put the page named 'cover' (e.g. 'cover.jpg') or only made of zeroes (e.g. '000.png') first, 'first-page' to keep the pages' order
+    #[clap(global = true, long)]
+    pub cover_from: Option<CoverDetection>,
+
+    /// Add a picture as the first page of each volume, '{n}' being replaced by the volume's number (e.g. 'covers/vol_{n}.jpg')
+    #[clap(global = true, long)]
+    pub cover: Option<String>,
+
+    /// Guarantee the pages are written without any transformation, recording their SHA-256 digest in a manifest embedded in each volume
+    #[clap(global = true, long)]
+    pub bit_exact: bool,
+
+    /// Write the SHA-256 digest of each page to a 'checksums.txt' file in each volume, which the 'verify' action checks
+    #[clap(global = true, long)]
+    pub checksums: bool,
+
+    /// Remove the metadata of JPEG and PNG pages (EXIF, XMP, comments and embedded thumbnails), except their color profile and orientation
+    #[clap(global = true, long, conflicts_with = "bit-exact")]
+    pub strip_image_metadata: bool,
+
+    /// Warn about pages whose width or height exceeds the provided number of pixels, which some readers cannot display (e.g. 4096 for many Android devices)
+    #[clap(global = true, long)]
+    pub reader_limit: Option<u32>,
+
+    /// Downscale the pages exceeding the reader limit instead of only warning about them
+    #[clap(
+        global = true,
+        long,
+        requires = "reader-limit",
+        conflicts_with = "bit-exact"
+    )]
+    pub downscale_oversized: bool,
+
+    /// Transcode the pages which aren't JPEG pictures (e.g. PNG scans) to JPEG with the provided quality (from 1 to 100), which makes volumes much smaller
+    #[clap(global = true, long, conflicts_with = "bit-exact")]
+    pub recompress_jpeg: Option<u8>,
+
+    /// Rewrite the JPEG pages losslessly with optimized Huffman tables (using 'jpegtran'), which usually saves 5 to 10% of their size without changing a single pixel
+    #[clap(global = true, long, conflicts_with = "bit-exact")]
+    pub optimize_jpeg: bool,
+
+    /// With '--optimize-jpeg', also rewrite the JPEG pages as progressive ones, which are often a bit smaller
+    #[clap(global = true, long, requires = "optimize-jpeg")]
+    pub progressive_jpeg: bool,
+
+    /// Convert every page to the provided format ('jpg', 'png', 'webp' or 'webp-lossless'), WebP being smaller for readers supporting it
+    #[clap(
+        global = true,
+        long,
+        conflicts_with_all = &["bit-exact", "recompress-jpeg"]
+    )]
+    pub convert_to: Option<PageFormat>,
+
+    /// Convert the pages which aren't in the provided format ('jpg', 'png', 'webp' or 'webp-lossless') to it, keeping the other ones as they are, so every page has the same format and extension as some strict readers require
+    #[clap(
+        global = true,
+        long,
+        conflicts_with_all = &["bit-exact", "recompress-jpeg", "convert-to", "transcode-modern-formats"]
+    )]
+    pub normalize_format: Option<PageFormat>,
+
+    /// Transcode the AVIF and JPEG XL pages, which most readers cannot display, to the provided format ('jpg' or 'png') instead of ignoring them
+    #[clap(
+        global = true,
+        long,
+        conflicts_with_all = &["bit-exact", "accept-extended-image-formats"]
+    )]
+    pub transcode_modern_formats: Option<PageFormat>,
+
+    /// Split the landscape pages (double-page spreads) in two pages, put in the provided reading order ('ltr', or 'rtl' for manga), which is the one of '--reading-direction' by default (e.g. '--split-spreads=rtl')
+    #[clap(
+        global = true,
+        long,
+        require_equals = true,
+        conflicts_with = "bit-exact"
+    )]
+    pub split_spreads: Option<Option<ReadingDirection>>,
+
+    /// Slice the pages (e.g. webtoon strips) exceeding the provided height into pages of this height at most, from top to bottom, which is the reader limit by default (or 65500 pixels, the largest height of JPEG pictures, e.g. '--split-tall-pages=4096'); the pages to slice may be taller than '--max-page-dimension', but not take more memory than '--max-page-memory'; JPEG XL and AVIF pages are kept whole
+    #[clap(
+        global = true,
+        long,
+        require_equals = true,
+        conflicts_with_all = &["bit-exact", "join-spreads"]
+    )]
+    pub split_tall_pages: Option<Option<u32>>,
+
+    /// Convert the CMYK pages and the ones with a color profile (e.g. exported from print workflows) to the provided color space ('srgb'), so readers display their colors right
+    #[clap(global = true, long, conflicts_with = "bit-exact")]
+    pub color_convert: Option<ColorSpace>,
+
+    /// Rotate the pages providing an EXIF orientation (e.g. photographed ones) instead of relying on readers to do it, which is done by default when pages are transformed
+    #[clap(global = true, long, conflicts_with = "bit-exact")]
+    pub auto_orient: bool,
+
+    /// Don't rotate the transformed pages according to their EXIF orientation, which they lose when they are re-encoded
+    #[clap(global = true, long, conflicts_with = "auto-orient")]
+    pub no_auto_orient: bool,
+
+    /// Crop the uniform margins around the pages (e.g. of old scans), optionally with the maximum difference between their pixels and their color, from 0 to 255 (16 by default, e.g. '--trim-margins=32')
+    #[clap(
+        global = true,
+        long,
+        require_equals = true,
+        conflicts_with = "bit-exact"
+    )]
+    pub trim_margins: Option<Option<u8>>,
+
+    /// Convert every page to grayscale, which makes black-and-white scans smaller
+    #[clap(
+        global = true,
+        long,
+        conflicts_with_all = &["bit-exact", "auto-grayscale"]
+    )]
+    pub grayscale: bool,
+
+    /// Convert the pages which have (almost) no color to grayscale, leaving the color ones untouched
+    #[clap(global = true, long, conflicts_with = "bit-exact")]
+    pub auto_grayscale: bool,
+
+    /// Reduce every page to the provided number of colors (from 2 to 256, e.g. 16 levels of gray for line art) and write it as PNG, which makes volumes much smaller for e-ink readers
+    #[clap(
+        global = true,
+        long,
+        conflicts_with_all = &["bit-exact", "recompress-jpeg", "convert-to", "normalize-format"]
+    )]
+    pub quantize: Option<u16>,
+
+    /// Pipe every page through an external upscaler (e.g. waifu2x or Real-ESRGAN) before it is written, '{in}' and '{out}' being replaced by the paths of the original and of the upscaled pictures (e.g. 'realesrgan-ncnn-vulkan -i {in} -o {out}')
+    #[clap(global = true, long, conflicts_with = "bit-exact")]
+    pub upscale_cmd: Option<String>,
+
+    /// Number of pages to upscale at the same time (1 by default, as upscalers usually use the whole GPU)
+    #[clap(global = true, long, requires = "upscale-cmd")]
+    pub upscale_jobs: Option<usize>,
+
+    /// What to do with the pages the upscaler fails to upscale: 'fail' to stop building the volume (default), or 'keep' to keep them as they are
+    #[clap(global = true, long, requires = "upscale-cmd")]
+    pub upscale_on_failure: Option<UpscaleFailurePolicy>,
+
+    /// Pipe every page through a command (e.g. ImageMagick or a custom script) before the other transformations, which gets the page on its standard input and its path in the volume in the 'COMIC_ENC_PAGE' environment variable, and must write the new page to its standard output in the same format (e.g. 'convert - -level 10%,90% -')
+    #[clap(global = true, long, conflicts_with = "bit-exact")]
+    pub page_filter_cmd: Option<String>,
+
+    /// Drop the pages which are byte-identical to a previous page of the same volume (e.g. a credits page included in every chapter), reporting how many were dropped
+    #[clap(global = true, long)]
+    pub dedupe_pages: bool,
+
+    /// Detect the pages which look like a page of a previous chapter of the same volume (e.g. re-uploads or covers included twice) and list them in the run's report, optionally with the maximum number of differing bits between their perceptual hashes, from 0 to 64 (4 by default, e.g. '--detect-near-duplicates=8')
+    #[clap(global = true, long, require_equals = true)]
+    pub detect_near_duplicates: Option<Option<u32>>,
+
+    /// Drop the near-duplicate pages from the volumes instead of only listing them
+    #[clap(global = true, long, requires = "detect-near-duplicates")]
+    pub drop_near_duplicates: bool,
+
+    /// Exclude pages from each chapter, as a comma-separated list of patterns matching their name, page numbers and page ranges starting at 1, a dash followed by a number designating the last pages (e.g. 'credits*.png,1,-3' to exclude the credits, the first page and the last three pages)
+    #[clap(global = true, long, allow_hyphen_values = true)]
+    pub exclude_pages: Option<PageExclusions>,
+
+    /// Decode every page before adding it, failing on the truncated or corrupted ones which readers would fail to display
+    #[clap(global = true, long)]
+    pub validate_images: bool,
+
+    /// With '--validate-images', skip the pages which cannot be decoded with a warning instead of failing
+    #[clap(global = true, long, requires = "validate-images")]
+    pub lenient: bool,
+
+    /// Join the consecutive pages of each chapter side-by-side in pairs, put in the provided reading order ('ltr', or 'rtl' for manga), which is the one of '--reading-direction' by default, for reading on large screens (e.g. '--join-spreads=rtl')
+    #[clap(
+        global = true,
+        long,
+        require_equals = true,
+        conflicts_with_all = &["bit-exact", "split-spreads"]
+    )]
+    pub join_spreads: Option<Option<ReadingDirection>>,
+
+    /// Directory to keep the downscaled, recompressed and converted pages in, indexed by their content, so pages transformed the same way again (e.g. in another variant of the volumes) are taken from it instead
+    #[clap(global = true, long, parse(from_os_str))]
+    pub page_store: Option<PathBuf>,
+
+    /// Command to run through the shell before building, '{path}' being replaced by the output directory (or the output file when building a single volume or appending to one)
+    #[clap(global = true, long)]
+    pub pre_hook: Option<String>,
+
+    /// Command to run through the shell after each volume is written, '{path}' being replaced by the volume's path (e.g. 'rclone copy {path} remote:comics')
+    #[clap(global = true, long)]
+    pub post_hook: Option<String>,
+
+    /// Write a preview next to each volume (e.g. 'Volume-1.contact.jpg'), made of the thumbnails of all its pages, which requires to read them
+    #[clap(global = true, long)]
+    pub contact_sheet: bool,
+
+    /// Write a table of contents (Contents.html) to each volume, listing its chapters with the number of their first page
+    #[clap(global = true, long)]
+    pub toc: bool,
+
+    /// Parse the chapters' titles from their names (e.g. 'Ch 012 - The Long Night' becomes 'Chapter 12: The Long Night'), and bookmark the first page of each chapter with its title in the volume's metadata file as well as in the table of contents
+    #[clap(global = true, long)]
+    pub chapter_titles: bool,
+
+    /// Regular expression to parse the chapters' titles with, using a 'title' and an optional 'number' named group (e.g. '^\d+ (?P<title>.+)$')
+    #[clap(global = true, long, requires = "chapter-titles")]
+    pub chapter_title_pattern: Option<Regex>,
+
+    /// Format of the volumes: 'cbz' (default), 'cb7' (7-Zip archive compressed with LZMA2, smaller but slower to write and to read) or 'cbt' (uncompressed TAR archive, faster to write and to read from network drives)
+    #[clap(global = true, long)]
+    pub format: Option<ComicFormat>,
+
     /// Compress losslessly (a lot slower, save up about 5% of the final volumes' size)
     #[clap(global = true, long)]
     pub compress_losslessly: bool,
+
+    /// Write a detached ed25519 signature (in the minisign format) next to each volume, using the secret key at the provided path
+    #[clap(global = true, long, parse(from_os_str))]
+    pub sign_key: Option<PathBuf>,
+
+    /// Title to write in the metadata of each volume
+    #[clap(global = true, long)]
+    pub title: Option<String>,
+
+    /// Name of the series to write in the metadata of each volume
+    #[clap(global = true, long)]
+    pub series: Option<String>,
+
+    /// Writer to write in the metadata of each volume
+    #[clap(global = true, long)]
+    pub writer: Option<String>,
+
+    /// Number of the first volume to write in the metadata (next volumes are numbered incrementally)
+    #[clap(global = true, long)]
+    pub volume_number: Option<usize>,
+
+    /// Publication year to write in the metadata of each volume
+    #[clap(global = true, long)]
+    pub year: Option<u16>,
+
+    /// Reading direction of the volumes ('ltr', or 'rtl' for manga) to write in their metadata, so readers display the pages in the right order, and their double-page spreads as such
+    #[clap(global = true, long)]
+    pub reading_direction: Option<ReadingDirection>,
+
+    /// AniList identifier of the series to write in the metadata of each volume, so library managers can match it
+    #[clap(global = true, long)]
+    pub anilist_id: Option<String>,
+
+    /// MangaDex identifier of the series to write in the metadata of each volume, so library managers can match it
+    #[clap(global = true, long)]
+    pub mangadex_id: Option<String>,
+
+    /// ComicVine identifier of the series to write in the metadata of each volume (e.g. '4050-12345'), so library managers can match it
+    #[clap(global = true, long)]
+    pub comicvine_id: Option<String>,
+
+    /// Also write the metadata in the ComicBookInfo format to the comment of each volume's archive
+    #[clap(global = true, long)]
+    pub comic_book_info: bool,
+
+    /// Describe each page in the volume's metadata file (cover, double pages, dimensions and size), which requires to read them
+    #[clap(global = true, long)]
+    pub page_info: bool,
+
+    /// Write a Calibre metadata file (.opf) next to each volume, with its series and index
+    #[clap(global = true, long)]
+    pub opf: bool,
+
+    /// Write a JSON file next to each volume (e.g. 'Volume-1.json') with its metadata, its pages with their digest and the settings it was built with, for library tools which cannot read inside archives
+    #[clap(global = true, long)]
+    pub sidecar_json: bool,
 }
 
 #[derive(Clap, Debug, Clone)]
@@ -102,10 +439,22 @@ pub struct CompilationOptions {
     #[clap(global = true, long)]
     pub create_output_dir: bool,
 
-    /// Prefix in the name of the chapter directories
+    /// Prefix in the name of the chapter directories (multiple prefixes can be separated by commas, e.g. "Ch,Chapter,Chapitre")
     #[clap(global = true, short, long)]
     pub dirs_prefix: Option<String>,
 
+    /// Match the chapter directories' prefix case-insensitively
+    #[clap(global = true, long)]
+    pub prefix_ignore_case: bool,
+
+    /// Match the chapter directories' prefix after transliterating names to ASCII (e.g. 'Chapître' matches 'Chapitre')
+    #[clap(global = true, long)]
+    pub prefix_transliterate: bool,
+
+    /// Only keep chapter directories whose name matches the provided regular expression
+    #[clap(global = true, long)]
+    pub dirs_regex: Option<Regex>,
+
     /// Start at a specific chapter/volume (ignore every chapter before this one)
     #[clap(global = true, long)]
     pub start_chapter: Option<usize>,
@@ -113,6 +462,47 @@ pub struct CompilationOptions {
     /// End at a specific chapter/volume (ignore every chapter after this one)
     #[clap(global = true, long)]
     pub end_chapter: Option<usize>,
+
+    /// Only display the volumes that would be built, without building them
+    #[clap(global = true, long)]
+    pub dry_run: bool,
+
+    /// With '--dry-run', write a contact sheet of each planned volume (first page of each chapter) to the provided directory
+    #[clap(global = true, long, parse(from_os_str), requires = "dry-run")]
+    pub plan_thumbnails: Option<PathBuf>,
+
+    /// Edit the plan in a terminal UI before building the volumes: select the chapters to compile, reorder them and group them into volumes
+    #[clap(global = true, long)]
+    pub tui: bool,
+
+    /// Layout of the output directory and names of the volumes: 'plain' (default), 'komga' or 'kavita'
+    /// With 'komga' and 'kavita', volumes are put in a directory named after their series, with names these servers parse best
+    #[clap(global = true, long)]
+    pub naming_preset: Option<NamingPreset>,
+
+    /// Write a reading list (.cbl) referencing the built volumes in order to the provided path
+    #[clap(global = true, long, parse(from_os_str), conflicts_with = "dry-run")]
+    pub reading_list: Option<PathBuf>,
+
+    /// Write a series manifest (series.json) to the output directory, so comic servers pick up the series
+    #[clap(global = true, long, conflicts_with = "dry-run")]
+    pub series_json: bool,
+
+    /// Publisher of the series to write in its manifest
+    #[clap(global = true, long, requires = "series-json")]
+    pub publisher: Option<String>,
+
+    /// Publication status of the series to write in its manifest: 'continuing' or 'ended'
+    #[clap(global = true, long, requires = "series-json")]
+    pub series_status: Option<SeriesStatus>,
+
+    /// Write a summary of the volumes (index.json) to the output directory, with their chapters, number of pages and size
+    #[clap(global = true, long, conflicts_with = "dry-run")]
+    pub index: bool,
+
+    /// Write an OPDS catalog (catalog.xml) listing the volumes of the output directory, along with their covers, so readers can browse it over HTTP
+    #[clap(global = true, long, conflicts_with = "dry-run")]
+    pub opds: bool,
 }
 
 #[derive(Clap, Debug, Clone, Copy)]
@@ -125,7 +515,24 @@ pub enum CompilationMethod {
 /// Compile multiple chapters in single volumes (e.g. compile 10 to compile 10 chapters per volume)
 pub struct CompileRanges {
     #[clap(global = true, about = "Number of chapters per volume")]
-    pub chapters_per_volume: u16,
+    pub chapters_per_volume: Option<u16>,
+
+    /// Group chapters so each volume contains approximately the provided number of pages, instead of a fixed number of chapters
+    #[clap(global = true, long, conflicts_with = "chapters-per-volume")]
+    pub target_pages_per_volume: Option<usize>,
+
+    /// Divide the chapters into exactly the provided number of volumes, instead of a fixed number of chapters per volume
+    #[clap(
+        global = true,
+        long,
+        conflicts_with = "chapters-per-volume",
+        conflicts_with = "target-pages-per-volume"
+    )]
+    pub volumes: Option<usize>,
+
+    /// Measure to balance between the volumes provided with '--volumes': 'chapters' (default), 'size' or 'pages'
+    #[clap(global = true, long, requires = "volumes")]
+    pub balance: Option<BalanceMode>,
 
     /// Add the start and end chapter at the end of each volume's filename
     #[clap(global = true, long)]
@@ -140,7 +547,8 @@ pub struct CompileRanges {
 /// Compile directories to individual volumes
 pub struct CompileEach {
     /// Skip output chapter files that already exist
-    #[clap(global = true, long, conflicts_with = "append_pages_count")]
+    /// With '--append-pages-count', files are found through the output directory's manifest and skipped if they were not modified
+    #[clap(global = true, long)]
     pub skip_existing: bool,
 
     /// Display full file names (by default names are truncated above 50 characters)
@@ -150,7 +558,35 @@ pub struct CompileEach {
 
 #[derive(Clap, Debug, Clone, Copy)]
 /// Encode a single directory as a single volume
-pub struct EncodeSingle {}
+pub struct EncodeSingle {
+    /// Don't add the volume format's extension to the output file's name when it doesn't have one
+    #[clap(long)]
+    pub no_auto_extension: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Append chapter directories to an existing volume, without rebuilding it
+pub struct EncodeAppend {
+    /// The volume to append the chapters to (updated in place unless an output is provided)
+    #[clap(parse(from_os_str))]
+    pub volume: PathBuf,
+
+    /// Prefix in the name of the chapter directories (multiple prefixes can be separated by commas, e.g. "Ch,Chapter,Chapitre")
+    #[clap(short, long)]
+    pub dirs_prefix: Option<String>,
+
+    /// Match the chapter directories' prefix case-insensitively
+    #[clap(long)]
+    pub prefix_ignore_case: bool,
+
+    /// Match the chapter directories' prefix after transliterating names to ASCII (e.g. 'Chapître' matches 'Chapitre')
+    #[clap(long)]
+    pub prefix_transliterate: bool,
+
+    /// Only keep chapter directories whose name matches the provided regular expression
+    #[clap(long)]
+    pub dirs_regex: Option<Regex>,
+}
 
 #[derive(Clap, Debug, Clone)]
 /// Extract images from an existing comic book
@@ -179,7 +615,334 @@ pub struct Decode {
     #[clap(global = true, short, long)]
     pub simple_sorting: bool,
 
+    /// Sort roman numerals written as separate words in pictures' names by their value (e.g. 'Part IV' before 'Part V')
+    #[clap(global = true, long, conflicts_with = "simple-sorting")]
+    pub roman_numerals: bool,
+
     /// Continue extraction even if some pages cannot be extracted from the input PDF (only if input file is PDF)
     #[clap(global = true, long)]
     pub skip_bad_pdf_pages: bool,
+
+    /// Extract all pages of the input PDF in a single directory instead of one directory per bookmark (only if input file is PDF)
+    #[clap(global = true, long)]
+    pub ignore_pdf_bookmarks: bool,
+
+    /// Decode the input as the provided format (e.g. 'cbz' or 'pdf') instead of detecting it from its content
+    #[clap(global = true, long)]
+    pub force_format: Option<ComicFormat>,
+
+    /// Encoding of the entry names not flagged as UTF-8 in old archives, when they aren't valid UTF-8 ('cp437' or 'shift-jis')
+    #[clap(global = true, long, default_value = "cp437")]
+    pub fallback_encoding: EntryEncoding,
+
+    /// Encoding of all the entry names not flagged as UTF-8, even if they are valid UTF-8 ('cp437' or 'shift-jis'), taking precedence over '--fallback-encoding'
+    #[clap(global = true, long)]
+    pub entry_encoding: Option<EntryEncoding>,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Check the integrity of existing volumes
+pub struct Verify {
+    /// The volumes to check
+    #[clap(parse(from_os_str), required = true)]
+    pub input: Vec<PathBuf>,
+
+    /// Also check each volume against its detached signature
+    #[clap(long, requires = "public-key")]
+    pub signatures: bool,
+
+    /// Public key to check the signatures with
+    #[clap(long, parse(from_os_str))]
+    pub public_key: Option<PathBuf>,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Replace or remove a chapter inside an existing volume, without rebuilding the other chapters
+pub struct Edit {
+    /// The volume to edit (updated in place unless an output is provided)
+    #[clap(parse(from_os_str))]
+    pub volume: PathBuf,
+
+    /// Directory, archive or PDF containing the new chapter's pages (when using '--replace-chapter')
+    #[clap(parse(from_os_str))]
+    pub chapter: Option<PathBuf>,
+
+    /// Replace the chapter with the provided number by the provided chapter
+    #[clap(long, requires = "chapter", conflicts_with = "remove-chapter")]
+    pub replace_chapter: Option<usize>,
+
+    /// Remove the chapter with the provided number
+    #[clap(long)]
+    pub remove_chapter: Option<usize>,
+
+    /// Path to write the edited volume to
+    #[clap(short, long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Overwrite the output file if it already exists
+    #[clap(long)]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long)]
+    pub simple_sorting: bool,
+
+    /// Compress the new chapter losslessly
+    #[clap(long)]
+    pub compress_losslessly: bool,
+
+    /// Write a detached ed25519 signature (in the minisign format) next to the edited volume, using the secret key at the provided path
+    #[clap(long, parse(from_os_str))]
+    pub sign_key: Option<PathBuf>,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Rewrite existing volumes with another compression, without modifying their pages
+pub struct Repack {
+    /// The volumes to repack
+    #[clap(parse(from_os_str), required = true)]
+    pub input: Vec<PathBuf>,
+
+    /// Directory where repacked volumes should be put (volumes are repacked in place otherwise)
+    #[clap(short, long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Compression method to use ('stored', 'deflated', 'bzip2' or 'zstd')
+    #[clap(long, default_value = "stored")]
+    pub compression: ZipCompression,
+
+    /// Compression level to use (depends on the compression method)
+    #[clap(long)]
+    pub compression_level: Option<i32>,
+
+    /// Overwrite existing files in the output directory instead of failing
+    #[clap(long)]
+    pub overwrite: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Convert a comic book (e.g. a PDF or an EPUB) to another format
+pub struct Convert {
+    /// The comic book to convert
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Path to the converted file, or to the directory to put it in (defaults to the input's path with the new format's extension)
+    #[clap(short, long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Format to convert the comic book to
+    #[clap(long, default_value = "cbz")]
+    pub to: ComicFormat,
+
+    /// Overwrite the output file if it already exists
+    #[clap(long)]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+
+    /// Disable natural sorting for pictures (use default UTF-8 sorting, a bit faster but unintuitive)
+    #[clap(short, long)]
+    pub simple_sorting: bool,
+
+    /// Compress losslessly (a lot slower, save up about 5% of the final volume's size)
+    #[clap(long)]
+    pub compress_losslessly: bool,
+
+    /// Write a detached ed25519 signature (in the minisign format) next to the converted file, using the secret key at the provided path
+    #[clap(long, parse(from_os_str))]
+    pub sign_key: Option<PathBuf>,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Merge existing volumes into a single one, without recompressing their pages
+pub struct Merge {
+    /// The volumes to merge, in order
+    #[clap(parse(from_os_str), required = true)]
+    pub input: Vec<PathBuf>,
+
+    /// Path to the merged volume
+    #[clap(short, long, parse(from_os_str))]
+    pub output: PathBuf,
+
+    /// Merge the volumes in natural order of their paths instead of the provided order
+    #[clap(long)]
+    pub natural_sort: bool,
+
+    /// Put the pages of each volume in a separate directory named after it
+    #[clap(long)]
+    pub keep_source_dirs: bool,
+
+    /// Overwrite the output file if it already exists
+    #[clap(long)]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Split an existing volume into multiple ones, by page count, page ranges or chapters
+pub struct Split {
+    /// The volume to split
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Directory where the split volumes should be put (defaults to the input's directory)
+    #[clap(short, long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+
+    /// Split the volume every N pages
+    #[clap(long, conflicts_with = "ranges", conflicts_with = "at-chapters")]
+    pub pages_per_volume: Option<usize>,
+
+    /// Split the volume into the provided comma-separated page ranges, starting at 1 (e.g. "1-50,51-120,121-200")
+    #[clap(long, conflicts_with = "at-chapters")]
+    pub ranges: Option<String>,
+
+    /// Split the volume at each of its chapter directories
+    #[clap(long)]
+    pub at_chapters: bool,
+
+    /// Overwrite the output files if they already exist
+    #[clap(long)]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Extract the cover of volumes as 'cover.jpg' next to each of them, without decoding their other pages
+pub struct ExtractCover {
+    /// The volumes to extract the cover of
+    #[clap(parse(from_os_str), required = true)]
+    pub input: Vec<PathBuf>,
+
+    /// Extract the provided page (starting at 1) instead of detecting the cover
+    #[clap(long)]
+    pub page: Option<usize>,
+
+    /// Overwrite the cover files if they already exist
+    #[clap(long)]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Generate a thumbnail of every volume of a directory in its '.thumbnails' sub-directory, without decoding their other pages
+pub struct Thumbnail {
+    /// The directory containing the volumes
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Page to generate the thumbnails from (starting at 1)
+    #[clap(long, default_value = "1")]
+    pub page: usize,
+
+    /// Maximum width of the thumbnails
+    #[clap(long, default_value = "320")]
+    pub width: u32,
+
+    /// Maximum height of the thumbnails
+    #[clap(long, default_value = "480")]
+    pub height: u32,
+
+    /// Regenerate the thumbnails which are more recent than their volume
+    #[clap(long)]
+    pub overwrite: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Display the natural order of the files of a directory or the entries of an archive, or apply it by prefixing their names with their position
+pub struct Sort {
+    /// The directory or archive to sort
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Rename the pictures (or rewrite the archive's entries) with their zero-padded position as a prefix (e.g. '001_page.jpg')
+    #[clap(long)]
+    pub apply: bool,
+
+    /// Sort roman numerals written as separate words by their value (e.g. 'Part IV' before 'Part V')
+    #[clap(long)]
+    pub roman_numerals: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Rename the pictures of a directory into a clean sequence (e.g. '0001.jpg', '0002.png'...) following the natural order
+pub struct NumberPages {
+    /// The directory containing the pictures to rename
+    #[clap(parse(from_os_str))]
+    pub input: PathBuf,
+
+    /// Also rename the pictures of sub-directories, each directory being numbered separately
+    #[clap(short, long)]
+    pub recursive: bool,
+
+    /// Only display the new names of the pictures, without renaming them
+    #[clap(long)]
+    pub dry_run: bool,
+
+    /// Allow additional image formats that may not be supported by all readers (e.g. TIF / RAW / CR2 / ... files)
+    #[clap(short, long)]
+    pub accept_extended_image_formats: bool,
+
+    /// Sort roman numerals written as separate words by their value (e.g. 'Part IV' before 'Part V')
+    #[clap(long)]
+    pub roman_numerals: bool,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Display the recent runs (most recent first), or the details of one of them with 'history show <n>'
+pub struct History {
+    #[clap(subcommand)]
+    pub command: Option<HistoryCommand>,
+}
+
+#[derive(Clap, Debug, Clone)]
+pub enum HistoryCommand {
+    Show(HistoryShow),
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Display the details of a recent run
+pub struct HistoryShow {
+    /// Position of the run in the history, 1 being the most recent one
+    pub nth: usize,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Check the environment (writable directories, open files limit, terminal) and display what may prevent comic-enc from working
+pub struct Doctor {
+    /// The output directory to check (default: current directory)
+    #[clap(parse(from_os_str))]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Clap, Debug, Clone)]
+/// Answer a few questions about what to do (compile chapters into volumes, convert a library, device to read on) to get the matching command, written to a script and optionally previewed with a dry run
+pub struct Init {
+    /// Path to write the script running the command to (default: 'comic-enc.sh', or 'comic-enc.cmd' on Windows)
+    #[clap(parse(from_os_str))]
+    pub output: Option<PathBuf>,
 }
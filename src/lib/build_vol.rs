@@ -1,18 +1,178 @@
 use crate::cli::error::EncodingError;
 use crate::cli::opts::*;
-use crate::lib::deter;
+use crate::lib::comic_info::{self, ComicMetadata, PageInfo, COMIC_INFO_FILE_NAME};
+use crate::lib::contact_sheet::{self, CONTACT_SHEET_EXT, THUMBNAIL_HEIGHT, THUMBNAIL_WIDTH};
+use crate::lib::cover::{self, CoverDetection};
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::entry_names;
+use crate::lib::hooks;
+use crate::lib::ignore_file::{IgnoreFile, IgnoreFileErr, IGNORE_FILE_NAME};
+use crate::lib::image_meta;
+use crate::lib::jpegtran;
+use crate::lib::manifest::{
+    self, EmbeddedManifest, EmbeddedPage, Manifest, VolumeSidecar, CHECKSUMS_FILE_NAME,
+    EMBEDDED_MANIFEST_FILE_NAME,
+};
+use crate::lib::page_store::PageStore;
+use crate::lib::pdf_images::{self, PdfImagesErr};
+use crate::lib::planner;
+use crate::lib::prompt;
+use crate::lib::provenance::Sources;
+use crate::lib::report::NearDuplicate;
+use crate::lib::sevenz;
+use crate::lib::sign::{self, KeyLoadingErr};
+use crate::lib::sort;
+use crate::lib::source_meta::{self, SourceMetadata};
+use crate::lib::tar;
+use crate::lib::toc::{self, TocEntry, TOC_FILE_NAME};
+use crate::lib::transform::{self, PageFormat, ReadingDirection};
+use crate::lib::upscale::{self, UpscaleFailurePolicy};
+use ed25519_dalek::Keypair;
+use image::{DynamicImage, ImageError, ImageFormat};
+use regex::Regex;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::{self, Read, Seek, Write};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::thread;
+use std::time::{Duration, Instant};
+use zip::result::ZipError;
 use zip::write::{FileOptions, ZipWriter};
-use zip::CompressionMethod;
+use zip::{CompressionMethod, ZipArchive};
+
+/// Where the pages of a chapter come from
+enum ChapterSource {
+    /// Image files from a directory
+    Directory,
+
+    /// Entries of a ZIP archive
+    Archive(ZipArchive<File>),
+
+    /// Images extracted from a PDF document
+    Pdf(Vec<Vec<u8>>),
+
+    /// Files of a 7-Zip or TAR archive with their name, read in memory as their entries can't be accessed directly
+    Files(Vec<(String, Vec<u8>)>),
+}
+
+/// A volume's ZIP archive being written
+pub struct VolumeZip<'a, W: Write + Seek> {
+    pub zip_writer: ZipWriter<W>,
+    pub zip_options: FileOptions,
+    pub method: &'a BuildMethod<'a>,
+    pub enc_opts: &'a EncodingOptions,
+    pub volume: usize,
+    pub vol_num_len: usize,
+    pub chapter_num_len: usize,
+
+    /// How to display the volume's name in STDOUT
+    pub volume_display_name: String,
+
+    /// Common display name for individual chapters (only for the 'Each' method)
+    pub display_name_individual: Option<String>,
+
+    /// Number of pictures written to the volume so far
+    pub pics_counter: usize,
+
+    /// Patterns of pages to exclude from the chapters
+    pub ignore_file: &'a IgnoreFile,
+
+    /// Directory the pages are written to as well, if any
+    pub extract_dir: Option<PathBuf>,
+
+    /// Content of the metadata file found in the chapters, if any
+    pub comic_info: Option<String>,
+
+    /// Names of the entries in the volume so far, to detect collisions
+    pub entry_names: EntryNames,
+
+    /// Informations about the pages written so far, if they are described in the metadata file
+    pub pages: Option<Vec<PageInfo>>,
+
+    /// Record of the pages written so far, if the volume embeds a manifest or their checksums
+    pub embedded_manifest: Option<EmbeddedManifest>,
+
+    /// Thumbnails of the pages written so far, if a contact sheet is written next to the volume
+    pub contact_sheet: Option<Vec<DynamicImage>>,
+
+    /// Store to reuse the pages transformed the same way before from, if any
+    pub page_store: Option<PageStore>,
+
+    /// Page waiting for the next one of its chapter to be joined with, if spreads are joined
+    pub pending_page: Option<PreparedPage>,
+
+    /// Digests of the pages read so far along with their path in the volume, if duplicate pages are dropped
+    pub page_digests: Option<HashMap<String, String>>,
+
+    /// Number of duplicate pages dropped so far
+    pub duplicate_pages: usize,
+
+    /// Perceptual hashes of the pages read so far, along with their chapter and path, if near-duplicate pages are detected
+    pub page_hashes: Option<Vec<(u64, usize, String)>>,
+
+    /// Near-duplicate pages found so far
+    pub near_duplicates: Vec<NearDuplicate>,
+}
+
+/// A page ready to be written to a volume
+#[derive(Debug)]
+pub struct PreparedPage {
+    /// Path of the page in the volume
+    pub path_in_zip: String,
+
+    /// Content of the page's picture
+    pub content: Vec<u8>,
+
+    /// Path of the picture the page comes from
+    pub image_path: PathBuf,
+
+    /// Path of the picture in its chapter, as recorded in the manifest
+    pub source: String,
+
+    /// Is the page the cover of its chapter
+    pub cover: bool,
+}
+
+/// Names of the entries in a volume, to detect collisions
+#[derive(Debug, Default)]
+pub struct EntryNames {
+    /// Normalized names of the entries
+    names: HashSet<String>,
+
+    /// Number of entries that were renamed to avoid a collision
+    pub renamed: usize,
+}
+
+/// Extension of produced volumes, unless specified otherwise
+pub const DEFAULT_VOLUME_EXT: &str = "cbz";
+
+/// Extension of volumes that are being built
+pub const STAGING_EXT: &str = "comic-enc-partial";
+
+/// Extension of the Calibre metadata files written next to the volumes
+pub const OPF_EXT: &str = "opf";
+
+/// Extension of the JSON files describing the volumes written next to them
+pub const SIDECAR_EXT: &str = "json";
+
+/// Number of attempts to build a volume which cannot be moved to its final path
+pub const FINALIZE_ATTEMPTS: usize = 5;
+
+/// Delay before the first new attempt to build a volume which cannot be moved to its final path, doubled after each attempt
+const FINALIZE_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Maximum length of the pictures' paths inside volumes
+/// Paths are limited to 260 characters on Windows, which leaves 100 characters for the directory volumes are extracted to
+pub const MAX_SAFE_ENTRY_PATH_LEN: usize = 160;
 
 #[derive(Debug, Clone)]
 pub enum BuildMethod<'a> {
     Ranges(&'a CompileRanges, &'a CompilationOptions),
     Each(&'a CompileEach, &'a CompilationOptions),
     Single(&'a EncodeSingle),
+    Append(&'a EncodeAppend),
+    Edit(&'a Edit),
 }
 
 #[derive(Debug)]
@@ -26,6 +186,274 @@ pub struct BuildVolumeArgs<'a> {
     pub chapter_num_len: usize,
     pub start_chapter: usize,
     pub chapters: &'a Vec<(usize, PathBuf, String)>,
+    pub signing_key: Option<&'a Keypair>,
+    pub ignore_file: &'a IgnoreFile,
+    pub series_metadata: Option<&'a SourceMetadata>,
+    pub sources: Option<&'a Sources>,
+
+    /// Record of the pages of the volume being rebuilt from a single chapter (e.g. when converting it), to restore its chapters
+    pub previous_manifest: Option<&'a EmbeddedManifest>,
+}
+
+/// Numbering informations found in an existing volume
+#[derive(Debug)]
+pub struct VolumeNumbering {
+    /// Volume number
+    pub volume: usize,
+
+    /// Number of the last chapter in the volume
+    pub last_chapter: usize,
+
+    /// Number of digits the volume number is displayed with
+    pub vol_num_len: usize,
+
+    /// Number of digits chapter numbers are displayed with
+    pub chapter_num_len: usize,
+
+    /// Number of pages in the volume
+    pub pages: usize,
+
+    /// Chapters of the volume, as tuples containing: (chapter number, name of the chapter's top-level directory)
+    pub chapters: Vec<(usize, String)>,
+
+    /// Names of all entries in the volume
+    pub entry_names: Vec<String>,
+}
+
+/// A volume that has been built (or skipped)
+#[derive(Debug)]
+pub struct BuiltVolume {
+    /// Path to the volume's file
+    pub path: PathBuf,
+
+    /// Number of pages in the volume (unknown if it has been skipped)
+    pub pages: Option<usize>,
+
+    /// Number of entries that were renamed to avoid a collision
+    pub renamed_entries: usize,
+
+    /// Number of duplicate pages that were dropped
+    pub duplicate_pages: usize,
+
+    /// Pages which look like a page of a previous chapter
+    pub near_duplicates: Vec<NearDuplicate>,
+
+    /// Name of the volume's file, before the number of pages and the extension are added
+    pub base_name: String,
+}
+
+/// A volume's staging file, removed when dropped unless it has been finalized
+/// If it cannot be removed, it is registered in its directory's manifest to be removed later
+pub struct StagingFile {
+    pub path: PathBuf,
+    pub finalized: bool,
+}
+
+impl Drop for StagingFile {
+    fn drop(&mut self) {
+        if self.finalized || !self.path.exists() {
+            return;
+        }
+
+        debug!("Removing staging file '{}'...", self.path.to_string_lossy());
+
+        if let Err(err) = fs::remove_file(&self.path) {
+            warn!(
+                "Warning: failed to remove staging file '{}' ({}), it will be removed during the next compilation to this directory.",
+                self.path.to_string_lossy(),
+                err
+            );
+
+            if let (Some(dir), Some(file_name)) = (self.path.parent(), self.path.file_name()) {
+                let file_name = file_name.to_string_lossy().to_string();
+
+                if let Err(err) =
+                    Manifest::update(dir, |manifest| manifest.register_stranded(file_name))
+                {
+                    warn!(
+                        "Warning: failed to register staging file in manifest: {}",
+                        err
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Get the options to write volumes' files with
+pub fn zip_options(enc_opts: &EncodingOptions) -> FileOptions {
+    // Consider compression
+    FileOptions::default().compression_method(if enc_opts.compress_losslessly {
+        CompressionMethod::Deflated
+    } else {
+        CompressionMethod::Stored
+    })
+}
+
+/// Create the writer of an archive rewriting an existing one, keeping its comment (e.g. its ComicBookInfo metadata)
+pub fn rewriting_zip_writer<R: Read + Seek, W: Write + Seek>(
+    source: &ZipArchive<R>,
+    file: W,
+) -> ZipWriter<W> {
+    let mut zip_writer = ZipWriter::new(file);
+
+    if !source.comment().is_empty() {
+        zip_writer.set_comment(String::from_utf8_lossy(source.comment()));
+    }
+
+    zip_writer
+}
+
+/// Open the store to reuse transformed pages from, if any
+pub fn open_page_store(enc_opts: &EncodingOptions) -> Result<Option<PageStore>, EncodingError> {
+    enc_opts
+        .page_store
+        .as_ref()
+        .map(|dir| {
+            trace!("Opening page store...");
+
+            PageStore::open(dir)
+                .map_err(|err| EncodingError::FailedToOpenPageStore(dir.clone(), err))
+        })
+        .transpose()
+}
+
+/// Load the key volumes must be signed with, if any
+pub fn load_signing_key(enc_opts: &EncodingOptions) -> Result<Option<Keypair>, EncodingError> {
+    enc_opts
+        .sign_key
+        .as_ref()
+        .map(|path| {
+            trace!("Loading signing key...");
+
+            let keypair = sign::load_secret_key(path).map_err(|err| {
+                EncodingError::FailedToLoadSigningKey(
+                    path.clone(),
+                    match err {
+                        KeyLoadingErr::IOError(err) => err.to_string(),
+                        KeyLoadingErr::InvalidKey(err) => err,
+                    },
+                )
+            })?;
+
+            info!(
+                "Volumes will be signed, public key is: {} (minisign format: {})",
+                sign::to_hex(keypair.public.as_bytes()),
+                sign::minisign_public_key(&keypair.public)
+            );
+
+            Ok(keypair)
+        })
+        .transpose()
+}
+
+/// Load the ignore file located at the root of the provided input directory, if any
+pub fn load_ignore_file(input_dir: &Path) -> Result<IgnoreFile, EncodingError> {
+    let ignore_file = IgnoreFile::load(input_dir).map_err(|err| match err {
+        IgnoreFileErr::InvalidPatterns(path, err) => EncodingError::InvalidIgnoreFile(path, err),
+    })?;
+
+    if !ignore_file.is_empty() {
+        debug!(
+            "Loaded {} pattern(s) from '{}' file.",
+            ignore_file.len(),
+            IGNORE_FILE_NAME
+        );
+    }
+
+    Ok(ignore_file)
+}
+
+/// Determine the numbering of an existing volume from its entries' names
+/// Volumes produced by this tool contain directories named `Vol_<volume>_Chapter_<chapter>`,
+/// for other volumes every top-level directory is considered as a chapter, numbered in natural order
+pub fn read_volume_numbering(path: &Path) -> Result<VolumeNumbering, EncodingError> {
+    let file = File::open(path)
+        .map_err(|err| EncodingError::FailedToOpenExistingVolume(path.to_path_buf(), err))?;
+
+    let mut zip = ZipArchive::new(file)
+        .map_err(|err| EncodingError::InvalidExistingVolume(path.to_path_buf(), err))?;
+
+    let mut numbering = VolumeNumbering {
+        volume: 1,
+        last_chapter: 0,
+        vol_num_len: 1,
+        chapter_num_len: 1,
+        pages: 0,
+        chapters: vec![],
+        entry_names: vec![],
+    };
+
+    // Top-level directories of the archive
+    let mut top_dirs: Vec<String> = vec![];
+
+    for i in 0..zip.len() {
+        let entry = zip
+            .by_index(i)
+            .map_err(|err| EncodingError::InvalidExistingVolume(path.to_path_buf(), err))?;
+
+        let name = entry.name().to_owned();
+
+        if entry.is_file() {
+            numbering.pages += 1;
+        }
+
+        numbering.entry_names.push(name.clone());
+
+        let top_dir = match name.split('/').next() {
+            Some(top_dir) if top_dir != name || entry.is_dir() => top_dir,
+            _ => continue,
+        };
+
+        if !top_dirs.iter().any(|dir| dir == top_dir) {
+            top_dirs.push(top_dir.to_owned());
+        }
+    }
+
+    for top_dir in &top_dirs {
+        // Parse 'Vol_<volume>_Chapter_<chapter>' directory names
+        let numbers = top_dir
+            .strip_prefix("Vol_")
+            .and_then(|rest| rest.split_once("_Chapter_"));
+
+        if let Some((vol, chapter)) = numbers {
+            if let (Ok(vol_num), Ok(chapter_num)) = (vol.parse::<usize>(), chapter.parse::<usize>())
+            {
+                numbering.volume = vol_num;
+                numbering.vol_num_len = vol.len();
+                numbering.chapter_num_len = std::cmp::max(numbering.chapter_num_len, chapter.len());
+                numbering.chapters.push((chapter_num, top_dir.clone()));
+            }
+        }
+    }
+
+    // Fall back to the top-level directories' order for volumes not produced by this tool
+    if numbering.chapters.is_empty() {
+        sort::natural_sort_names(&mut top_dirs);
+
+        numbering.chapters = top_dirs
+            .into_iter()
+            .enumerate()
+            .map(|(i, top_dir)| (i + 1, top_dir))
+            .collect();
+    }
+
+    numbering.last_chapter = numbering
+        .chapters
+        .iter()
+        .map(|(chapter, _)| *chapter)
+        .max()
+        .unwrap_or(0);
+
+    Ok(numbering)
+}
+
+/// Get the name of a directory (e.g. the series' one when using a naming preset)
+fn dir_name(dir: &Path) -> String {
+    dir.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
 }
 
 /// Build a volume
@@ -35,8 +463,44 @@ pub struct BuildVolumeArgs<'a> {
 /// `vol_num_len` is the maximum string length of the volume number (e.g. 1520 volumes => `vol_num_len == 4`)
 /// `chapter_num_len` is like `vol_num_len` but for chapters
 /// `start_chapter` is the number of the first chapter in this volume
-/// `chapters` is a list of the chapters this volume contains. It's a vector of tuples containing: (chapter number, path to the chapter's directory, chapter's directory's file name)
-pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
+/// `chapters` is a list of the chapters this volume contains. It's a vector of tuples containing: (chapter number, path to the chapter's directory, archive or PDF, chapter's name)
+/// `signing_key` is the key to write a detached signature of the volume with, if any
+/// `ignore_file` contains the patterns of pages to exclude from the chapters
+/// `series_metadata` is the content of the metadata file of the directory containing the chapters, if any
+/// `sources` is the content of the sources file of this directory, if any
+/// If the volume cannot be moved to its final path (e.g. because a sync client locks the target), it is built again from scratch a few times
+pub fn build_volume(args: &BuildVolumeArgs) -> Result<BuiltVolume, EncodingError> {
+    let mut retry_delay = FINALIZE_RETRY_DELAY;
+    let mut attempt = 1;
+
+    loop {
+        match build_volume_attempt(args, attempt > 1) {
+            Err(EncodingError::FailedToRenameCompleteArchive(volume, err))
+                if attempt < FINALIZE_ATTEMPTS =>
+            {
+                warn!(
+                    "Warning: failed to finalize volume {} (attempt {} / {}): {}. Building it again in {} ms...",
+                    volume,
+                    attempt,
+                    FINALIZE_ATTEMPTS,
+                    err,
+                    retry_delay.as_millis()
+                );
+
+                thread::sleep(retry_delay);
+                retry_delay *= 2;
+                attempt += 1;
+            }
+
+            result => return result,
+        }
+    }
+}
+
+/// Build a volume once
+/// `retry` is set when a previous attempt failed to move the volume to its final path, in which case the files
+///  it left behind are overwritten and the user isn't asked again to replace the existing volume
+fn build_volume_attempt(args: &BuildVolumeArgs, retry: bool) -> Result<BuiltVolume, EncodingError> {
     let BuildVolumeArgs {
         method,
         enc_opts,
@@ -47,67 +511,163 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         chapter_num_len,
         start_chapter,
         chapters,
+        signing_key,
+        ignore_file,
+        series_metadata,
+        sources,
+        previous_manifest,
     } = args;
 
     // Dereference volume number to a simple 'usize'
     let volume = *volume;
 
+    // Volumes are always built as ZIP archives, which are converted afterwards if another format is requested
+    let format = enc_opts.format.unwrap_or(ComicFormat::Zip);
+
+    if !format.is_supported_for_encoding() {
+        return Err(EncodingError::UnsupportedVolumeFormat(format));
+    }
+
+    // Load the metadata files of the chapters
+    let chapters_metadata = chapters
+        .iter()
+        .map(|(_, chapter_path, _)| SourceMetadata::load(chapter_path))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| EncodingError::InvalidMetadataFile(err.to_string()))?;
+
+    // Name of the volumes' files, prefixed by the series' title if any
+    let volume_name_prefix = match series_metadata.and_then(|series| series.title.as_ref()) {
+        Some(title) => format!("{} - Volume", source_meta::sanitize_file_name(title)),
+        None => "Volume".to_string(),
+    };
+
     // Get timestamp to measure performance
     let build_started = Instant::now();
 
     // Get the file name for this volume
+    let mut output_ext = Some(format.comic_ext().to_string());
+
     let output_path_without_ext = match method {
-        BuildMethod::Ranges(opts, _) => {
+        BuildMethod::Ranges(opts, comp_opts) => {
+            // Use the naming preset if any, the output directory being named after the series
+            let name = comp_opts
+                .naming_preset
+                .and_then(|preset| preset.volume_name(&dir_name(output), volume, *vol_num_len))
+                .unwrap_or_else(|| {
+                    format!(
+                        "{}-{:0vol_num_len$}",
+                        volume_name_prefix,
+                        volume,
+                        vol_num_len = vol_num_len
+                    )
+                });
+
             if !opts.append_chapters_range || chapters.is_empty() {
-                output.join(format!(
-                    "Volume-{:0vol_num_len$}",
-                    volume,
-                    vol_num_len = vol_num_len
-                ))
+                output.join(name)
             } else {
                 output.join(format!(
-                    "Volume-{:0vol_num_len$} (c{:0chapter_num_len$}-c{:0chapter_num_len$})",
-                    volume,
+                    "{} (c{:0chapter_num_len$}-c{:0chapter_num_len$})",
+                    name,
                     start_chapter,
                     start_chapter + chapters.len() - 1,
-                    vol_num_len = vol_num_len,
                     chapter_num_len = chapter_num_len
                 ))
             }
         }
 
-        BuildMethod::Each(_, _) => {
+        BuildMethod::Each(_, comp_opts) => {
             assert_eq!(
                 chapters.len(),
                 1,
                 "Internal error: individual chapter's volume does contain exactly 1 chapter!"
             );
-            output.join(chapters[0].2.to_string())
+            // Name the volume after the naming preset if any, or after the chapter's title if it has one
+            let preset_name = comp_opts.naming_preset.and_then(|preset| {
+                preset.chapter_name(&dir_name(output), chapters[0].0, *chapter_num_len)
+            });
+
+            match preset_name.or_else(|| {
+                chapters_metadata[0]
+                    .as_ref()
+                    .and_then(SourceMetadata::volume_name)
+            }) {
+                Some(name) => output.join(name),
+                None => output.join(chapters[0].2.to_string()),
+            }
+        }
+
+        BuildMethod::Single(opts) => {
+            // Keep the extension provided by the user if it's a known comic format
+            let known_ext = output
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .filter(|ext| ComicFormat::from_ext(ext).is_some());
+
+            match known_ext {
+                Some(ext) => {
+                    output_ext = Some(ext.to_owned());
+                    output.with_extension("")
+                }
+
+                None => {
+                    if opts.no_auto_extension {
+                        output_ext = None;
+                    }
+
+                    output.to_path_buf()
+                }
+            }
         }
 
-        BuildMethod::Single(_) => output.with_extension(""),
+        BuildMethod::Append(_) | BuildMethod::Edit(_) => {
+            unreachable!("Internal error: volumes are not built when appending or editing chapters")
+        }
     };
 
+    // Disable mutability for this variable
+    let output_ext = output_ext;
+
     // If the number of pages won't be happened to the final name, we can predict the final name of the file
-    // Else we cannot as we don't know the number of pages in this volume, yet.
+    // Else we cannot as we don't know the number of pages in this volume, yet, so we look for the previously produced file in the manifest
+    // Existing volume to replace as its pages don't match its chapter's anymore, if any
+    let mut outdated_volume = None;
+
     if let BuildMethod::Each(opts, _) = method {
-        // And if 'skip_existing' is set, that means we don't have to append the number of pages as this argument
-        // conflicts with the 'append_pages_count'.
         if opts.skip_existing {
-            let complete_path = output_path_without_ext.with_extension("cbz");
+            let complete_path = if enc_opts.append_pages_count {
+                find_unmodified_volume(output, &base_name(&output_path_without_ext))
+            } else {
+                Some(deter::add_extension(
+                    &output_path_without_ext,
+                    output_ext.as_deref().unwrap_or(DEFAULT_VOLUME_EXT),
+                ))
+            };
 
-            if complete_path.exists() {
-                warn!("Warning: skipping volume {} containing chapters {} to {} as its output file '{}' already exists (--skip-existing provided)", volume, start_chapter, start_chapter + chapters.len() - 1, output.to_string_lossy());
-                return Ok(complete_path);
+            if let Some(complete_path) = complete_path.filter(|path| path.exists()) {
+                // Volumes whose number of pages doesn't match their chapter's are built again, as they may be partially built or outdated
+                if has_outdated_page_count(&complete_path, chapters, enc_opts, ignore_file) {
+                    warn!("Warning: building volume {} containing chapters {} to {} again as its output file '{}' doesn't contain the same number of pages as its chapters", volume, start_chapter, start_chapter + chapters.len() - 1, complete_path.to_string_lossy());
+                    outdated_volume = Some(complete_path);
+                } else {
+                    warn!("Warning: skipping volume {} containing chapters {} to {} as its output file '{}' already exists (--skip-existing provided)", volume, start_chapter, start_chapter + chapters.len() - 1, complete_path.to_string_lossy());
+                    return Ok(BuiltVolume {
+                        path: complete_path,
+                        pages: None,
+                        renamed_entries: 0,
+                        duplicate_pages: 0,
+                        near_duplicates: vec![],
+                        base_name: base_name(&output_path_without_ext),
+                    });
+                }
             }
         }
     }
 
     // Get the path to this volume's (staging) ZIP archive
-    let staging_path = output_path_without_ext.with_extension(".comic-enc-partial");
+    let staging_path = deter::add_extension(&output_path_without_ext, STAGING_EXT);
 
     // Fail if the target file already exists and '--overwrite' has not been specified
-    if staging_path.exists() && !enc_opts.overwrite {
+    if staging_path.exists() && !enc_opts.overwrite && !retry {
         return Err(EncodingError::OutputVolumeFileAlreadyExists(
             volume,
             staging_path,
@@ -119,14 +679,48 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         EncodingError::FailedToCreateVolumeFile(volume, staging_path.clone(), err)
     })?;
 
-    let mut zip_writer = ZipWriter::new(zip_file);
+    // Ensure the staging file is removed if anything goes wrong
+    let mut staging_file = StagingFile {
+        path: staging_path.clone(),
+        finalized: false,
+    };
 
-    // Consider compression
-    let zip_options = FileOptions::default().compression_method(if enc_opts.compress_losslessly {
-        CompressionMethod::Deflated
-    } else {
-        CompressionMethod::Stored
-    });
+    let zip_writer = ZipWriter::new(zip_file);
+
+    let zip_options = zip_options(enc_opts);
+
+    // Prepare the directory to write the volume's pages to as well, if asked to
+    let extract_dir = match &enc_opts.also_extract {
+        Some(also_extract) => {
+            let extract_dir = also_extract.join(
+                output_path_without_ext
+                    .file_name()
+                    .expect("Internal error: output path when building has no filename"),
+            );
+
+            if extract_dir.exists() {
+                if !enc_opts.overwrite && !retry {
+                    return Err(EncodingError::ExtractedVolumeDirectoryAlreadyExists(
+                        volume,
+                        extract_dir,
+                    ));
+                }
+
+                // Remove the previous pages as they may not have the same names
+                fs::remove_dir_all(&extract_dir).map_err(|err| {
+                    EncodingError::FailedToOverwriteExtractedVolumeDirectory(
+                        volume,
+                        extract_dir.clone(),
+                        err,
+                    )
+                })?;
+            }
+
+            Some(extract_dir)
+        }
+
+        None => None,
+    };
 
     // Determine the common display name for individual chapters
     let display_name_individual = match method {
@@ -149,7 +743,7 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
     let volume_display_name = match method {
         BuildMethod::Ranges(_, _) => format!("{:0vol_num_len$}", volume, vol_num_len = vol_num_len),
         BuildMethod::Each(_, _) => format!("'{}'", display_name_individual.as_ref().unwrap()),
-        BuildMethod::Single(_) => format!(
+        BuildMethod::Single(_) | BuildMethod::Append(_) | BuildMethod::Edit(_) => format!(
             "'{}'",
             output_path_without_ext
                 .file_name()
@@ -160,256 +754,344 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         ),
     };
 
-    // Prepare a buffer to store the picture's files
-    let mut buffer = Vec::new();
+    let mut volume_zip = VolumeZip {
+        zip_writer,
+        zip_options,
+        method: *method,
+        enc_opts: *enc_opts,
+        volume,
+        vol_num_len: *vol_num_len,
+        chapter_num_len: *chapter_num_len,
+        volume_display_name: volume_display_name.clone(),
+        display_name_individual,
+        pics_counter: 0,
+        ignore_file: *ignore_file,
+        extract_dir,
+        comic_info: None,
+        entry_names: EntryNames::default(),
+        // Readers need double-page spreads to be marked as such to display them right
+        pages: if enc_opts.page_info
+            || enc_opts.chapter_titles
+            || enc_opts.reading_direction.is_some()
+            || previous_manifest.is_some()
+        {
+            Some(vec![])
+        } else {
+            None
+        },
+        embedded_manifest: if enc_opts.bit_exact || enc_opts.checksums || enc_opts.sidecar_json {
+            Some(EmbeddedManifest {
+                bit_exact: enc_opts.bit_exact,
+                pages: vec![],
+                sources: vec![],
+            })
+        } else {
+            None
+        },
+        contact_sheet: if enc_opts.contact_sheet {
+            Some(vec![])
+        } else {
+            None
+        },
+        page_store: open_page_store(enc_opts)?,
+        pending_page: None,
+        page_digests: if enc_opts.dedupe_pages {
+            Some(HashMap::new())
+        } else {
+            None
+        },
+        duplicate_pages: 0,
+        page_hashes: if enc_opts.detect_near_duplicates.is_some() {
+            Some(vec![])
+        } else {
+            None
+        },
+        near_duplicates: vec![],
+    };
+
+    // Add the cover provided by the user before the chapters
+    if let Some(cover) = &enc_opts.cover {
+        volume_zip.add_cover(&PathBuf::from(cover.replace("{n}", &volume.to_string())))?;
+    }
+
+    // Chapters of the volume with their first page, for the table of contents
+    let mut toc_entries = vec![];
 
-    // Count the number of pictures in this volume
-    let mut pics_counter = 0;
+    // Pattern to parse the chapters' titles with, if asked to
+    let title_pattern = if enc_opts.chapter_titles {
+        Some(match &enc_opts.chapter_title_pattern {
+            Some(pattern) => pattern.clone(),
+            None => Regex::new(toc::DEFAULT_CHAPTER_TITLE_PATTERN).unwrap(),
+        })
+    } else {
+        None
+    };
 
     // Treat each chapter of the volume
     for (chapter, chapter_path, chapter_name) in chapters.iter() {
-        // Determine how to display the chapter's title in STDOUT
-        let chapter_display_name = match method {
-            BuildMethod::Each(_, _) => format!("'{}'", display_name_individual.as_ref().unwrap()),
-            _ => format!(
-                "{:0chapter_num_len$}",
-                chapter,
-                chapter_num_len = chapter_num_len
-            ),
-        };
-
-        trace!(
-            "Reading files recursively from chapter {}'s directory '{}'...",
-            chapter,
-            chapter_name
-        );
+        let title = match &title_pattern {
+            Some(pattern) => toc::chapter_title(chapter_name, pattern).unwrap_or_else(|| {
+                debug!(
+                    "Chapter name '{}' doesn't match the title pattern, keeping it as the title.",
+                    chapter_name
+                );
 
-        // Get the list of all image files in the chapter's directory, recursively
-        let mut chapter_pics = deter::readdir_files_recursive(
-            &chapter_path,
-            Some(&|path: &PathBuf| {
-                deter::has_image_ext(path, enc_opts.accept_extended_image_formats)
+                chapter_name.clone()
             }),
-        )
-        .map_err(|err| match err {
-            deter::RecursiveFilesSearchErr::IOError(err) => {
-                EncodingError::FailedToListChapterDirectoryFiles {
-                    volume,
-                    chapter: *chapter,
-                    chapter_path: chapter_path.to_path_buf(),
-                    err,
-                }
-            }
 
-            deter::RecursiveFilesSearchErr::InvalidFileName(path) => {
-                EncodingError::FoundItemWithInvalidName {
-                    volume,
-                    chapter: *chapter,
-                    chapter_path: chapter_path.to_path_buf(),
-                    invalid_item_path: path,
-                }
-            }
-        })?;
+            None => chapter_name.clone(),
+        };
 
-        trace!(
-            "Found '{}' picture files from chapter {}'s directory '{}'. Sorting them...",
-            chapter_pics.len(),
-            chapter,
-            chapter_name
-        );
+        toc_entries.push(TocEntry {
+            title,
+            first_page: volume_zip.pics_counter + 1,
+        });
 
-        match method {
-            BuildMethod::Ranges(opts, _) => {
-                if opts.debug_chapters_path {
-                    info!(
-                        "Adding chapter {} to volume {} from directory '{}'",
-                        chapter_display_name, volume_display_name, chapter_name
-                    );
-                } else {
-                    debug!(
-                        "Adding chapter {} to volume {}...",
-                        chapter_display_name, volume_display_name
-                    );
+        volume_zip.add_chapter(*chapter, chapter_path, chapter_name)?;
+    }
+
+    // Restore the chapters of the volume being rebuilt, if its pages are still the same
+    let restored_chapters = match previous_manifest {
+        Some(previous) if previous.pages.len() == volume_zip.pics_counter => {
+            toc_entries = previous.chapters();
+
+            if let Some(embedded_manifest) = &mut volume_zip.embedded_manifest {
+                for (page, previous_page) in embedded_manifest.pages.iter_mut().zip(&previous.pages)
+                {
+                    page.chapter = previous_page.chapter.clone();
+                    page.source = previous_page.source.clone();
                 }
             }
 
-            BuildMethod::Each(_, _) => debug!(
-                "Adding directory n°{} to volume {}",
-                chapter_display_name, volume_display_name
-            ),
+            debug!(
+                "Restored {} chapter(s) from the previous manifest.",
+                toc_entries.len()
+            );
+            true
+        }
 
-            BuildMethod::Single(_) => {}
+        Some(previous) => {
+            warn!(
+                "Warning: volume {} contains {} pages instead of the {} recorded in its manifest, its chapters cannot be restored",
+                volume_display_name,
+                volume_zip.pics_counter,
+                previous.pages.len()
+            );
+            false
         }
 
-        // Sort the image files by name
-        if enc_opts.simple_sorting {
-            chapter_pics.sort();
-        } else {
-            chapter_pics.sort_by(deter::natural_paths_cmp);
-        };
+        None => false,
+    };
 
-        // Disable mutability for this variable
-        let chapter_path = chapter_path;
+    // Get the chapter's metadata file, which only describes the volume if it contains a single chapter
+    let comic_info = match volume_zip.comic_info.take() {
+        Some(_) if chapters.len() > 1 => {
+            debug!("Ignoring metadata file as the volume contains multiple chapters.");
+            None
+        }
 
-        // Determine the name of this chapter's directory in the volume's ZIP
-        let zip_dir_name = match method {
-            BuildMethod::Each(_, _) => chapters[0].2.clone(),
+        comic_info => comic_info,
+    };
 
-            _ => format!(
-                "Vol_{:0vol_num_len$}_Chapter_{:0chapter_num_len$}",
-                volume,
-                chapter,
-                vol_num_len = vol_num_len,
-                chapter_num_len = chapter_num_len
-            ),
-        };
+    // Add the metadata provided by the user, completed by the metadata files
+    let mut metadata = ComicMetadata::from_options(enc_opts, volume);
 
-        trace!("Adding directory '{}' to ZIP archive...", zip_dir_name);
+    if let [Some(chapter_metadata)] = chapters_metadata.as_slice() {
+        metadata.complete_with(chapter_metadata, true);
+    }
 
-        // Create an empty directory for this chapter in the volume's ZIP
-        zip_writer
-            .add_directory(&zip_dir_name, zip_options)
-            .map_err(|err| EncodingError::FailedToCreateChapterDirectoryInZip {
-                volume,
-                chapter: *chapter,
-                dir_name: zip_dir_name.to_owned(),
-                err,
-            })?;
+    if let Some(series_metadata) = series_metadata {
+        metadata.complete_with(series_metadata, false);
+    }
 
-        // Compute the length of displayable picture number (e.g. 1520 pictures will give 4)
-        let pic_num_len = chapter_pics.len().to_string().len();
+    // Record the origin of the chapters listed in the sources file
+    if let Some(sources) = sources {
+        metadata.provenance = chapters
+            .iter()
+            .filter_map(|(_, chapter_path, chapter_name)| {
+                sources.provenance(chapter_path, chapter_name)
+            })
+            .collect();
+    }
 
-        // Iterate over each page
-        for (page_nb, file) in chapter_pics.iter().enumerate() {
-            // Determine the name of the file in the ZIP directory
-            let name_in_zip = match method {
-                BuildMethod::Each(_, _) => format!(
-                    "{}_Pic_{:0pic_num_len$}.{file_ext}",
-                    volume_display_name,
-                    page_nb,
-                    file_ext = file.extension().unwrap().to_str().ok_or_else(
-                        || EncodingError::ItemHasInvalidUTF8Name(file.file_name().unwrap().to_os_string())
-                    )?,
-                    pic_num_len = pic_num_len
-                ),
+    // List the chapters after the pages
+    if enc_opts.toc {
+        let title = metadata
+            .title
+            .clone()
+            .unwrap_or_else(|| base_name(&output_path_without_ext));
 
-                _ => format!(
-                    "Vol_{:0vol_num_len$}_Chapter_{:0chapter_num_len$}_Pic_{:0pic_num_len$}.{file_ext}",
-                    volume,
-                    chapter,
-                    page_nb,
-                    file_ext = file.extension().unwrap().to_str().ok_or_else(
-                        || EncodingError::ItemHasInvalidUTF8Name(file.file_name().unwrap().to_os_string())
-                    )?,
-                    vol_num_len = vol_num_len,
-                    chapter_num_len = chapter_num_len,
-                    pic_num_len = pic_num_len
-                ),
-            };
+        volume_zip.add_toc(&toc::render_toc_html(&title, &toc_entries))?;
+    }
 
-            trace!(
-                "Adding picture {:0pic_num_len$} at '{}' from chapter {} to volume {} as '{}/{}'...",
-                page_nb, file.to_string_lossy(), chapter_display_name, volume_display_name, zip_dir_name, name_in_zip, pic_num_len = pic_num_len
-            );
+    // Record the pages' digests in the volume
+    let embedded_manifest = volume_zip
+        .embedded_manifest
+        .take()
+        .map(|mut embedded_manifest| {
+            embedded_manifest.sources = metadata.provenance.clone();
+            embedded_manifest
+        });
 
-            // Determine the path of the file in the ZIP directory
-            let path_in_zip = &Path::new(&zip_dir_name).join(Path::new(&name_in_zip));
+    if let Some(embedded_manifest) = &embedded_manifest {
+        if enc_opts.bit_exact {
+            volume_zip.add_embedded_manifest(embedded_manifest)?;
+        }
 
-            // Create the empty file in the archive
-            zip_writer
-                .start_file_from_path(path_in_zip, zip_options)
-                .map_err(|err| EncodingError::FailedToCreateImageFileInZip {
-                    volume,
-                    chapter: *chapter,
-                    file_path: path_in_zip.to_path_buf(),
-                    err,
-                })?;
+        if enc_opts.checksums {
+            volume_zip.add_checksums(embedded_manifest)?;
+        }
+    }
 
-            // Read the real file
-            let mut f = File::open(file).map_err(|err| EncodingError::FailedToOpenImage {
-                volume,
-                chapter: *chapter,
-                chapter_path: chapter_path.to_path_buf(),
-                image_path: file.to_path_buf(),
-                err,
-            })?;
+    let pages = volume_zip.pages.take();
 
-            f.read_to_end(&mut buffer)
-                .map_err(|err| EncodingError::FailedToReadImage {
-                    volume,
-                    chapter: *chapter,
-                    chapter_path: chapter_path.to_path_buf(),
-                    image_path: file.to_path_buf(),
-                    err,
-                })?;
+    if !metadata.is_empty() || pages.is_some() {
+        let mut xml = metadata.apply(comic_info.as_deref());
 
-            // Write the file to the ZIP archive
-            zip_writer.write_all(&buffer).map_err(|err| {
-                EncodingError::FailedToWriteImageFileToZip {
-                    volume,
-                    chapter: *chapter,
-                    chapter_path: chapter_path.to_path_buf(),
-                    image_path: file.to_path_buf(),
-                    err,
-                }
-            })?;
+        // Describe the pages of the volume
+        if let Some(pages) = &pages {
+            xml = comic_info::set_pages(
+                &xml,
+                pages,
+                if enc_opts.chapter_titles || restored_chapters {
+                    &toc_entries[..]
+                } else {
+                    &[]
+                },
+            );
+        }
 
-            buffer.clear();
+        volume_zip.add_comic_info(&xml)?;
 
-            pics_counter += 1;
+        // Some readers only read the metadata from the archive's comment
+        if enc_opts.comic_book_info && !metadata.is_empty() {
+            trace!("Writing metadata to the archive's comment...");
+            volume_zip
+                .zip_writer
+                .set_comment(metadata.to_comic_book_info());
         }
+    } else if let Some(comic_info) = comic_info {
+        volume_zip.add_comic_info(&comic_info)?;
     }
 
     trace!("Closing ZIP archive...");
 
     // Close the archive
-    zip_writer
+    volume_zip
+        .zip_writer
         .finish()
         .map_err(|err| EncodingError::FailedToCloseZipArchive(volume, err))?;
 
+    // Convert the archive to the requested format
+    convert_archive(format, &staging_path, volume, enc_opts)?;
+
     // Determine the file's final path with the right (non-partial) extension + number of pages if asked to
-    let mut complete_path = output_path_without_ext.with_extension("cbz");
+    let mut complete_path_without_ext = output_path_without_ext.clone();
 
     if enc_opts.append_pages_count {
-        let mut filename_with_pages = complete_path
-            .with_extension("")
+        let mut filename_with_pages = output_path_without_ext
             .file_name()
             .expect("Internal error: output path when building has no filename")
             .to_os_string();
 
-        filename_with_pages.push(format!(" ({} pages).cbz", pics_counter));
+        filename_with_pages.push(format!(" ({} pages)", volume_zip.pics_counter));
 
-        complete_path = complete_path.with_file_name(filename_with_pages)
+        complete_path_without_ext = output_path_without_ext.with_file_name(filename_with_pages)
+    };
+
+    let complete_path = match &output_ext {
+        Some(ext) => deter::add_extension(&complete_path_without_ext, ext),
+        None => complete_path_without_ext,
     };
 
     // Check if final path exists
+    // An outdated volume is replaced without asking, as it was found by '--skip-existing'
     if complete_path.exists() {
-        if complete_path.exists() && !enc_opts.overwrite {
+        if !enc_opts.overwrite
+            && !retry
+            && outdated_volume.as_ref() != Some(&complete_path)
+            && !prompt::confirm_overwrite(&complete_path)
+        {
             return Err(EncodingError::OutputVolumeFileAlreadyExists(
                 volume,
                 complete_path,
             ));
         }
 
-        if !complete_path.is_dir() {
+        if complete_path.is_dir() {
             return Err(EncodingError::OutputVolumeFileIsADirectory(
                 volume,
                 complete_path,
             ));
         }
+    }
 
-        if let Err(err) = fs::remove_file(&complete_path) {
-            return Err(EncodingError::FailedToOverwriteOutputVolumeFile(
-                volume,
-                complete_path,
-                err,
-            ));
-        }
+    // Move the staging file to its complete path
+    finalize_volume(volume, &staging_path, &complete_path)?;
+
+    staging_file.finalized = true;
+
+    // Sign the complete volume file
+    if let Some(keypair) = signing_key {
+        trace!("Signing volume...");
+
+        let sig_path = sign::sign_file(keypair, &complete_path)
+            .map_err(|err| EncodingError::FailedToSignVolume(volume, complete_path.clone(), err))?;
+
+        debug!("Written signature to '{}'.", sig_path.to_string_lossy());
+    }
+
+    // Write the Calibre metadata file next to the volume
+    if enc_opts.opf {
+        let opf_path = complete_path.with_extension(OPF_EXT);
+
+        trace!(
+            "Writing Calibre metadata file to '{}'...",
+            opf_path.to_string_lossy()
+        );
+
+        fs::write(
+            &opf_path,
+            metadata.to_opf(&base_name(&output_path_without_ext)),
+        )
+        .map_err(|err| EncodingError::FailedToWriteOpfFile(volume, opf_path, err))?;
+    }
+
+    // Write the description of the volume next to it
+    if let (true, Some(embedded_manifest)) = (enc_opts.sidecar_json, embedded_manifest) {
+        let sidecar_path = complete_path.with_extension(SIDECAR_EXT);
+
+        trace!(
+            "Writing sidecar file to '{}'...",
+            sidecar_path.to_string_lossy()
+        );
+
+        let sidecar = VolumeSidecar {
+            volume,
+            metadata: metadata.clone(),
+            settings: describe_settings(enc_opts),
+            manifest: embedded_manifest,
+        };
+
+        serde_json::to_string_pretty(&sidecar)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+            .and_then(|json| fs::write(&sidecar_path, json))
+            .map_err(|err| EncodingError::FailedToWriteSidecar(volume, sidecar_path, err))?;
     }
 
-    // Rename the staging file to its complete name
-    if let Err(err) = fs::rename(&staging_path, &complete_path) {
-        return Err(EncodingError::FailedToRenameCompleteArchive(volume, err));
+    // Write the contact sheet next to the volume
+    if let Some(thumbnails) = volume_zip.contact_sheet.take() {
+        let sheet_path = complete_path.with_extension(CONTACT_SHEET_EXT);
+
+        trace!(
+            "Writing contact sheet of {} page(s) to '{}'...",
+            thumbnails.len(),
+            sheet_path.to_string_lossy()
+        );
+
+        contact_sheet::render_contact_sheet(&thumbnails)
+            .save_with_format(&sheet_path, ImageFormat::Jpeg)
+            .map_err(|err| EncodingError::FailedToWriteContactSheet(volume, sheet_path, err))?;
     }
 
     let complete_filename = complete_path
@@ -439,6 +1121,13 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
         String::new()
     };
 
+    if volume_zip.duplicate_pages > 0 {
+        info!(
+            "Dropped {} duplicate page(s) from volume {}.",
+            volume_zip.duplicate_pages, volume_display_name
+        );
+    }
+
     match method {
         BuildMethod::Each(_, _) => info!(
             "Successfully written volume {:0vol_num_len$} / {} to file '{}{}', containing {} pages in {}.",
@@ -446,7 +1135,7 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
             volumes,
             success_display_file_name,
             filename_right_padding,
-            pics_counter,
+            volume_zip.pics_counter,
             elapsed,
             vol_num_len = vol_num_len
         ),
@@ -459,11 +1148,2380 @@ pub fn build_volume(args: &BuildVolumeArgs) -> Result<PathBuf, EncodingError> {
             start_chapter + chapters.len() - 1,
             success_display_file_name,
             filename_right_padding,
-            pics_counter,
+            volume_zip.pics_counter,
             elapsed,
             chapter_num_len = chapter_num_len
         )
     }
 
-    Ok(staging_path)
+    // Remove the outdated volume if the new one has another name (e.g. with another number of pages)
+    if let Some(outdated_volume) = outdated_volume.filter(|path| path != &complete_path) {
+        if let Err(err) = fs::remove_file(&outdated_volume) {
+            warn!(
+                "Warning: failed to remove outdated volume '{}': {}",
+                outdated_volume.to_string_lossy(),
+                err
+            );
+        }
+    }
+
+    // Run the post-build hook now the volume is complete
+    if let Some(post_hook) = &enc_opts.post_hook {
+        hooks::run_hook(post_hook, &complete_path)
+            .map_err(|err| EncodingError::PostHookFailed(volume, err.to_string()))?;
+    }
+
+    Ok(BuiltVolume {
+        path: complete_path,
+        pages: Some(volume_zip.pics_counter),
+        renamed_entries: volume_zip.entry_names.renamed,
+        duplicate_pages: volume_zip.duplicate_pages,
+        near_duplicates: volume_zip.near_duplicates,
+        base_name: base_name(&output_path_without_ext),
+    })
+}
+
+/// Check if a file is a page to add to volumes, based on its extension
+/// Modern image formats are accepted even if extended formats are not when they are transcoded
+pub fn is_page(enc_opts: &EncodingOptions, path: &Path) -> bool {
+    deter::has_image_ext(path, enc_opts.accept_extended_image_formats)
+        || (enc_opts.transcode_modern_formats.is_some() && deter::has_modern_image_ext(path))
+}
+
+/// Read a page from the source of its chapter, appending its content to the provided buffer
+/// `location` is made of the volume's number, the chapter's number and the chapter's path
+fn read_page(
+    chapter_source: &mut ChapterSource,
+    file: &Path,
+    source_index: Option<usize>,
+    location: (usize, usize, &Path),
+    buffer: &mut Vec<u8>,
+) -> Result<(), EncodingError> {
+    let (volume, chapter, chapter_path) = location;
+
+    if let (ChapterSource::Pdf(images), Some(index)) = (&*chapter_source, source_index) {
+        // Images extracted from a PDF are already in memory
+        buffer.extend_from_slice(&images[index]);
+    } else if let (ChapterSource::Files(files), Some(index)) = (&*chapter_source, source_index) {
+        buffer.extend_from_slice(&files[index].1);
+    } else if let (ChapterSource::Archive(archive), Some(index)) = (chapter_source, source_index) {
+        archive
+            .by_index(index)
+            .and_then(|mut entry| entry.read_to_end(buffer).map_err(ZipError::Io))
+            .map_err(|err| EncodingError::FailedToCopyArchiveEntry {
+                volume,
+                chapter,
+                chapter_path: chapter_path.to_path_buf(),
+                entry_path: file.to_path_buf(),
+                err,
+            })?;
+    } else {
+        // Read the real file
+        let mut f = File::open(file).map_err(|err| EncodingError::FailedToOpenImage {
+            volume,
+            chapter,
+            chapter_path: chapter_path.to_path_buf(),
+            image_path: file.to_path_buf(),
+            err,
+        })?;
+
+        f.read_to_end(buffer)
+            .map_err(|err| EncodingError::FailedToReadImage {
+                volume,
+                chapter,
+                chapter_path: chapter_path.to_path_buf(),
+                image_path: file.to_path_buf(),
+                err,
+            })?;
+    }
+
+    Ok(())
+}
+
+/// Check if a page is decoded to be transformed, which makes it lose its EXIF orientation
+/// `recompress` and `conversion` tell if the page is recompressed or converted to another format, which depends on its own format
+fn transforms_pages(
+    enc_opts: &EncodingOptions,
+    recompress: bool,
+    conversion: Option<PageFormat>,
+) -> bool {
+    recompress
+        || conversion.is_some()
+        || enc_opts.auto_orient
+        || enc_opts.downscale_oversized
+        || enc_opts.trim_margins.is_some()
+        || enc_opts.grayscale
+        || enc_opts.auto_grayscale
+        || enc_opts.optimize_jpeg
+        || enc_opts.color_convert.is_some()
+        || enc_opts.quantize.is_some()
+        || enc_opts.split_spreads.is_some()
+        || enc_opts.split_tall_pages.is_some()
+        || enc_opts.join_spreads.is_some()
+}
+
+/// Get the format a page has to be converted to, if any
+/// Modern image formats are only transcoded if the pages are not already converted or recompressed to JPEG,
+///  and pages are only normalized if they aren't in the provided format yet
+fn conversion(enc_opts: &EncodingOptions, path: &Path) -> Option<PageFormat> {
+    enc_opts
+        .convert_to
+        .or_else(|| {
+            enc_opts
+                .transcode_modern_formats
+                .filter(|_| enc_opts.recompress_jpeg.is_none() && deter::has_modern_image_ext(path))
+        })
+        .or_else(|| {
+            enc_opts.normalize_format.filter(|format| {
+                !path
+                    .extension()
+                    .map_or(false, |ext| format.has_ext(&ext.to_string_lossy()))
+            })
+        })
+}
+
+/// Check if an existing volume doesn't contain as many pages as its chapters (and its custom cover)
+/// A volume which cannot be read is considered outdated
+/// When pages are split (or joined, or dropped), the volume may contain more (or fewer) pages than
+///  its chapters, so only missing (or additional) pages make it outdated
+/// Pages of the existing volume are recognized in any format they may have been written in, as they may have been converted
+fn has_outdated_page_count(
+    existing: &Path,
+    chapters: &[(usize, PathBuf, String)],
+    enc_opts: &EncodingOptions,
+    ignore_file: &IgnoreFile,
+) -> bool {
+    let existing_pages = match planner::count_chapter_pages(
+        existing,
+        &|path| deter::has_image_ext(path, true),
+        &IgnoreFile::default(),
+    ) {
+        Ok(pages) => pages,
+        Err(err) => {
+            debug!(
+                "Failed to count the pages of existing volume '{}': {}",
+                existing.to_string_lossy(),
+                err
+            );
+
+            return true;
+        }
+    };
+
+    let mut source_pages = if enc_opts.cover.is_some() { 1 } else { 0 };
+
+    for (_, chapter_path, _) in chapters {
+        match planner::count_chapter_pages(
+            chapter_path,
+            &|path| is_page(enc_opts, path),
+            ignore_file,
+        ) {
+            Ok(pages) => source_pages += pages,
+
+            // The chapter will fail to be read when building the volume as well, which will report the error
+            Err(_) => return true,
+        }
+    }
+
+    debug!(
+        "Existing volume '{}' contains {} pages, its chapters {}.",
+        existing.to_string_lossy(),
+        existing_pages,
+        source_pages
+    );
+
+    let may_have_fewer_pages = enc_opts.join_spreads.is_some()
+        || enc_opts.dedupe_pages
+        || enc_opts.drop_near_duplicates
+        || enc_opts.lenient
+        || enc_opts.exclude_pages.is_some();
+
+    let may_have_more_pages =
+        enc_opts.split_spreads.is_some() || enc_opts.split_tall_pages.is_some();
+
+    match (may_have_more_pages, may_have_fewer_pages) {
+        // The volume may contain any number of pages
+        (true, true) => false,
+        (true, false) => existing_pages < source_pages,
+        (false, true) => existing_pages > source_pages,
+        (false, false) => existing_pages != source_pages,
+    }
+}
+
+/// Get the order to put the pages of double-page spreads in, which is the volumes' reading direction unless provided
+fn spread_direction(
+    enc_opts: &EncodingOptions,
+    direction: Option<ReadingDirection>,
+) -> ReadingDirection {
+    direction
+        .or(enc_opts.reading_direction)
+        .unwrap_or(ReadingDirection::Ltr)
+}
+
+/// Describe the settings a volume is built with, only listing the ones which are enabled
+fn describe_settings(enc_opts: &EncodingOptions) -> BTreeMap<String, String> {
+    let mut settings = BTreeMap::new();
+
+    settings.insert("version".to_owned(), env!("CARGO_PKG_VERSION").to_owned());
+
+    let enabled = [
+        ("bit_exact", enc_opts.bit_exact),
+        ("strip_image_metadata", enc_opts.strip_image_metadata),
+        ("auto_orient", enc_opts.auto_orient),
+        ("no_auto_orient", enc_opts.no_auto_orient),
+        ("grayscale", enc_opts.grayscale),
+        ("auto_grayscale", enc_opts.auto_grayscale),
+        ("optimize_jpeg", enc_opts.optimize_jpeg),
+        ("progressive_jpeg", enc_opts.progressive_jpeg),
+        ("dedupe_pages", enc_opts.dedupe_pages),
+        ("drop_near_duplicates", enc_opts.drop_near_duplicates),
+        ("validate_images", enc_opts.validate_images),
+        ("lenient", enc_opts.lenient),
+    ];
+
+    for (name, _) in enabled.iter().filter(|(_, enabled)| *enabled) {
+        settings.insert((*name).to_owned(), "true".to_owned());
+    }
+
+    let values = [
+        (
+            "downscale_oversized",
+            enc_opts
+                .reader_limit
+                .filter(|_| enc_opts.downscale_oversized)
+                .map(|limit| limit.to_string()),
+        ),
+        (
+            "recompress_jpeg",
+            enc_opts.recompress_jpeg.map(|quality| quality.to_string()),
+        ),
+        (
+            "convert_to",
+            enc_opts.convert_to.map(|format| format.name().to_owned()),
+        ),
+        (
+            "quantize",
+            enc_opts.quantize.map(|colors| colors.to_string()),
+        ),
+        ("upscale_cmd", enc_opts.upscale_cmd.clone()),
+        ("page_filter_cmd", enc_opts.page_filter_cmd.clone()),
+        (
+            "exclude_pages",
+            enc_opts
+                .exclude_pages
+                .as_ref()
+                .map(|exclusions| exclusions.to_string()),
+        ),
+        (
+            "color_convert",
+            enc_opts.color_convert.map(|space| space.name().to_owned()),
+        ),
+        (
+            "normalize_format",
+            enc_opts
+                .normalize_format
+                .map(|format| format.name().to_owned()),
+        ),
+        (
+            "transcode_modern_formats",
+            enc_opts
+                .transcode_modern_formats
+                .map(|format| format.name().to_owned()),
+        ),
+        (
+            "trim_margins",
+            enc_opts.trim_margins.map(|threshold| {
+                threshold
+                    .unwrap_or(transform::DEFAULT_TRIM_THRESHOLD)
+                    .to_string()
+            }),
+        ),
+        (
+            "split_spreads",
+            enc_opts
+                .split_spreads
+                .map(|direction| spread_direction(enc_opts, direction).name().to_owned()),
+        ),
+        (
+            "split_tall_pages",
+            enc_opts.split_tall_pages.map(|max_height| {
+                max_height
+                    .or(enc_opts.reader_limit)
+                    .unwrap_or(transform::MAX_JPEG_DIMENSION)
+                    .to_string()
+            }),
+        ),
+        (
+            "join_spreads",
+            enc_opts
+                .join_spreads
+                .map(|direction| spread_direction(enc_opts, direction).name().to_owned()),
+        ),
+    ];
+
+    for (name, value) in values.iter() {
+        if let Some(value) = value {
+            settings.insert((*name).to_owned(), value.clone());
+        }
+    }
+
+    settings
+}
+
+/// Add a suffix to a file name, before its extension
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(with_name_suffix("Pic_005.jpg", "a"), "Pic_005a.jpg");
+/// ```
+fn with_name_suffix(name: &str, suffix: &str) -> String {
+    match name.rfind('.') {
+        Some(index) => format!("{}{}{}", &name[..index], suffix, &name[index..]),
+        None => format!("{}{}", name, suffix),
+    }
+}
+
+/// Find a volume previously produced in the provided directory from the directory's manifest
+/// Returns `None` if it's not registered, or if it has been modified or removed since
+fn find_unmodified_volume(dir: &Path, base_name: &str) -> Option<PathBuf> {
+    let manifest = match Manifest::load(dir) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            warn!("Warning: failed to load manifest: {}", err);
+            return None;
+        }
+    };
+
+    let registered = manifest.find_volume(base_name)?;
+    let path = dir.join(&registered.file_name);
+
+    match manifest::file_sha256(&path) {
+        Ok(sha256) if sha256 == registered.sha256 => Some(path),
+
+        Ok(_) => {
+            debug!(
+                "Volume file '{}' was modified since it was built, building it again.",
+                path.to_string_lossy()
+            );
+            None
+        }
+
+        Err(_) => None,
+    }
+}
+
+/// Get the name of a volume's file from its path without extension
+fn base_name(output_path_without_ext: &Path) -> String {
+    output_path_without_ext
+        .file_name()
+        .expect("Internal error: output path when building has no filename")
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Convert a volume's ZIP archive in place to the format volumes are written in, if it isn't ZIP
+fn convert_archive(
+    format: ComicFormat,
+    staging_path: &Path,
+    volume: usize,
+    enc_opts: &EncodingOptions,
+) -> Result<(), EncodingError> {
+    match format {
+        ComicFormat::SevenZip => {
+            if enc_opts.comic_book_info {
+                warn!("Warning: 7-Zip archives don't have a comment, so the ComicBookInfo metadata of volume {} is not written", volume);
+            }
+
+            trace!("Converting ZIP archive to a 7-Zip archive...");
+
+            let converted_path = deter::add_extension(staging_path, "7z");
+
+            sevenz::zip_to_7z(staging_path, &converted_path)
+                .and_then(|()| {
+                    fs::rename(&converted_path, staging_path).map_err(sevenz::SevenZErr::IOError)
+                })
+                .map_err(|err| {
+                    // Don't leave a partial archive behind
+                    let _ = fs::remove_file(&converted_path);
+                    EncodingError::FailedToWrite7zVolume(volume, err.to_string())
+                })
+        }
+
+        ComicFormat::Tar => {
+            if enc_opts.comic_book_info {
+                warn!("Warning: TAR archives don't have a comment, so the ComicBookInfo metadata of volume {} is not written", volume);
+            }
+
+            trace!("Converting ZIP archive to a TAR archive...");
+
+            let converted_path = deter::add_extension(staging_path, "tar");
+
+            tar::zip_to_tar(staging_path, &converted_path)
+                .and_then(|()| {
+                    fs::rename(&converted_path, staging_path).map_err(tar::TarErr::IOError)
+                })
+                .map_err(|err| {
+                    // Don't leave a partial archive behind
+                    let _ = fs::remove_file(&converted_path);
+                    EncodingError::FailedToWriteTarVolume(volume, err.to_string())
+                })
+        }
+
+        _ => Ok(()),
+    }
+}
+
+/// Move a volume's staging file to its complete path, replacing the existing file if any
+/// The existing file is replaced by the move itself, so it is kept if the move fails
+fn finalize_volume(
+    volume: usize,
+    staging_path: &Path,
+    complete_path: &Path,
+) -> Result<(), EncodingError> {
+    fs::rename(staging_path, complete_path)
+        .map_err(|err| EncodingError::FailedToRenameCompleteArchive(volume, err))
+}
+
+impl<'a, W: Write + Seek> VolumeZip<'a, W> {
+    /// Add a chapter's pages to the volume
+    /// `chapter` is the chapter's number, `chapter_path` the path to its directory, archive or PDF
+    pub fn add_chapter(
+        &mut self,
+        chapter: usize,
+        chapter_path: &Path,
+        chapter_name: &str,
+    ) -> Result<(), EncodingError> {
+        let volume = self.volume;
+        let method = self.method;
+        let enc_opts = self.enc_opts;
+        let vol_num_len = self.vol_num_len;
+        let chapter_num_len = self.chapter_num_len;
+        // The name is cloned, as the volume is written to while it is displayed
+        let volume_display_name = &self.volume_display_name.clone();
+        let display_name_individual = &self.display_name_individual;
+        let ignore_file = self.ignore_file;
+
+        // Prepare a buffer to store the picture's files
+        let mut buffer = Vec::new();
+
+        // Determine how to display the chapter's title in STDOUT
+        let chapter_display_name = match method {
+            BuildMethod::Each(_, _) => format!("'{}'", display_name_individual.as_ref().unwrap()),
+            _ => format!(
+                "{:0chapter_num_len$}",
+                chapter,
+                chapter_num_len = chapter_num_len
+            ),
+        };
+
+        let chapter_format = chapter_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(ComicFormat::from_ext);
+
+        // Determine where this chapter's pages come from
+        let mut chapter_source = if chapter_path.is_dir() {
+            ChapterSource::Directory
+        } else if chapter_format == Some(ComicFormat::Pdf) {
+            trace!(
+                "Extracting images from chapter {}'s PDF '{}'...",
+                chapter,
+                chapter_name
+            );
+
+            // Use the document's informations as the chapter's metadata file
+            if self.comic_info.is_none() {
+                match pdf_images::read_info(chapter_path) {
+                    Ok(info) => self.comic_info = info.to_comic_info(),
+                    Err(err) => warn!(
+                        "Warning: failed to read document informations of chapter {}'s PDF: {}",
+                        chapter, err
+                    ),
+                }
+            }
+
+            ChapterSource::Pdf(
+                pdf_images::extract_jpeg_images(chapter_path, false).map_err(|err| {
+                    let (page, err) = match err {
+                        PdfImagesErr::FailedToOpen(err) => (None, err),
+                        PdfImagesErr::FailedToGetPage(page, err) => (Some(page), err),
+                        PdfImagesErr::FailedToGetPageResources(page, err) => (Some(page), err),
+                    };
+
+                    EncodingError::FailedToReadChapterPdf {
+                        volume,
+                        chapter,
+                        chapter_path: chapter_path.to_path_buf(),
+                        page,
+                        err,
+                    }
+                })?,
+            )
+        } else if matches!(
+            chapter_format,
+            Some(ComicFormat::SevenZip) | Some(ComicFormat::Tar)
+        ) {
+            trace!(
+                "Reading files from chapter {}'s archive '{}'...",
+                chapter,
+                chapter_name
+            );
+
+            let mut files = vec![];
+
+            // Error met while reading the content of a file
+            let mut failure = None;
+
+            let mut read_file = |name: &str, content: &mut dyn Read| {
+                if failure.is_some() {
+                    return;
+                }
+
+                let mut buffer = vec![];
+
+                match content.read_to_end(&mut buffer) {
+                    Ok(_) => files.push((name.to_owned(), buffer)),
+                    Err(err) => failure = Some(err.to_string()),
+                }
+            };
+
+            let result = if chapter_format == Some(ComicFormat::SevenZip) {
+                sevenz::for_each_file(chapter_path, &mut read_file).map_err(|err| err.to_string())
+            } else {
+                tar::for_each_file(chapter_path, &mut read_file).map_err(|err| err.to_string())
+            };
+
+            if let Some(err) = result.err().or(failure) {
+                return Err(EncodingError::FailedToReadChapterArchive {
+                    volume,
+                    chapter,
+                    chapter_path: chapter_path.to_path_buf(),
+                    err,
+                });
+            }
+
+            ChapterSource::Files(files)
+        } else {
+            trace!(
+                "Opening chapter {}'s archive '{}'...",
+                chapter,
+                chapter_name
+            );
+
+            let file = File::open(chapter_path).map_err(|err| {
+                EncodingError::FailedToOpenChapterArchive {
+                    volume,
+                    chapter,
+                    chapter_path: chapter_path.to_path_buf(),
+                    err,
+                }
+            })?;
+
+            ChapterSource::Archive(ZipArchive::new(file).map_err(|err| {
+                EncodingError::InvalidChapterArchive {
+                    volume,
+                    chapter,
+                    chapter_path: chapter_path.to_path_buf(),
+                    err,
+                }
+            })?)
+        };
+
+        // Get the list of all image files in the chapter, recursively
+        // Pictures coming from an archive or a PDF are associated to their index inside it
+        let mut chapter_pics: Vec<(PathBuf, Option<usize>)> = match &mut chapter_source {
+            ChapterSource::Pdf(images) => {
+                // Name the images after their position in the document, so they keep their order when sorted
+                let pic_num_len = images.len().to_string().len();
+
+                (0..images.len())
+                    .map(|i| {
+                        (
+                            PathBuf::from(format!(
+                                "{:0pic_num_len$}.jpg",
+                                i + 1,
+                                pic_num_len = pic_num_len
+                            )),
+                            Some(i),
+                        )
+                    })
+                    .collect()
+            }
+
+            ChapterSource::Files(files) => {
+                let mut pics = vec![];
+
+                for (i, (name, content)) in files.iter().enumerate() {
+                    let entry_path = entry_names::sanitize_entry_name(name);
+
+                    if self.comic_info.is_none() && comic_info::is_comic_info(&entry_path) {
+                        trace!(
+                            "Reading metadata file from chapter {}'s archive...",
+                            chapter
+                        );
+
+                        self.comic_info = Some(String::from_utf8_lossy(content).into_owned());
+                        continue;
+                    }
+
+                    if is_page(enc_opts, &entry_path)
+                        && !ignore_file.is_entry_ignored(chapter_path, &entry_path)
+                    {
+                        pics.push((entry_path, Some(i)));
+                    }
+                }
+
+                pics
+            }
+
+            ChapterSource::Archive(archive) => {
+                trace!("Listing files from chapter {}'s archive...", chapter);
+
+                let mut pics = vec![];
+
+                for i in 0..archive.len() {
+                    let mut entry = archive.by_index(i).map_err(|err| {
+                        EncodingError::InvalidChapterArchive {
+                            volume,
+                            chapter,
+                            chapter_path: chapter_path.to_path_buf(),
+                            err,
+                        }
+                    })?;
+
+                    let entry_path = entry.mangled_name();
+
+                    if entry.is_file()
+                        && self.comic_info.is_none()
+                        && comic_info::is_comic_info(&entry_path)
+                    {
+                        trace!(
+                            "Reading metadata file from chapter {}'s archive...",
+                            chapter
+                        );
+
+                        let mut content = String::new();
+
+                        entry.read_to_string(&mut content).map_err(|err| {
+                            EncodingError::FailedToReadComicInfo {
+                                volume,
+                                chapter,
+                                chapter_path: chapter_path.to_path_buf(),
+                                err,
+                            }
+                        })?;
+
+                        self.comic_info = Some(content);
+                        continue;
+                    }
+
+                    if entry.is_file()
+                        && is_page(enc_opts, &entry_path)
+                        && !ignore_file.is_entry_ignored(chapter_path, &entry_path)
+                    {
+                        pics.push((entry_path, Some(i)));
+                    }
+                }
+
+                pics
+            }
+
+            ChapterSource::Directory => {
+                let comic_info_path = chapter_path.join(COMIC_INFO_FILE_NAME);
+
+                if self.comic_info.is_none() && comic_info_path.is_file() {
+                    trace!(
+                        "Reading metadata file from chapter {}'s directory...",
+                        chapter
+                    );
+
+                    self.comic_info =
+                        Some(fs::read_to_string(&comic_info_path).map_err(|err| {
+                            EncodingError::FailedToReadComicInfo {
+                                volume,
+                                chapter,
+                                chapter_path: chapter_path.to_path_buf(),
+                                err,
+                            }
+                        })?);
+                }
+
+                trace!(
+                    "Reading files recursively from chapter {}'s directory '{}'...",
+                    chapter,
+                    chapter_name
+                );
+
+                deter::readdir_files_recursive(
+                    &chapter_path,
+                    Some(&|path: &PathBuf| {
+                        is_page(enc_opts, path) && !ignore_file.is_ignored(path, false)
+                    }),
+                )
+                .map_err(|err| match err {
+                    deter::RecursiveFilesSearchErr::IOError(err) => {
+                        EncodingError::FailedToListChapterDirectoryFiles {
+                            volume,
+                            chapter,
+                            chapter_path: chapter_path.to_path_buf(),
+                            err,
+                        }
+                    }
+
+                    deter::RecursiveFilesSearchErr::InvalidFileName(path) => {
+                        EncodingError::FoundItemWithInvalidName {
+                            volume,
+                            chapter,
+                            chapter_path: chapter_path.to_path_buf(),
+                            invalid_item_path: path,
+                        }
+                    }
+                })?
+                .into_iter()
+                .map(|path| (path, None))
+                .collect()
+            }
+        };
+
+        trace!(
+            "Found '{}' picture files from chapter {} '{}'. Sorting them...",
+            chapter_pics.len(),
+            chapter,
+            chapter_name
+        );
+
+        match method {
+            BuildMethod::Ranges(opts, _) => {
+                if opts.debug_chapters_path {
+                    info!(
+                        "Adding chapter {} to volume {} from directory '{}'",
+                        chapter_display_name, volume_display_name, chapter_name
+                    );
+                } else {
+                    debug!(
+                        "Adding chapter {} to volume {}...",
+                        chapter_display_name, volume_display_name
+                    );
+                }
+            }
+
+            BuildMethod::Each(_, _) => debug!(
+                "Adding directory n°{} to volume {}",
+                chapter_display_name, volume_display_name
+            ),
+
+            BuildMethod::Single(_) => {}
+
+            BuildMethod::Append(_) => debug!(
+                "Appending chapter {} to volume {}...",
+                chapter_display_name, volume_display_name
+            ),
+
+            BuildMethod::Edit(_) => debug!(
+                "Replacing chapter {} in volume {}...",
+                chapter_display_name, volume_display_name
+            ),
+        }
+
+        // Sort the image files by name
+        if enc_opts.simple_sorting {
+            chapter_pics.sort();
+        } else {
+            sort::natural_sort_by_path(
+                &mut chapter_pics,
+                |(path, _)| path,
+                enc_opts.roman_numerals,
+            );
+        };
+
+        // Exclude the pages designated by their name or by their position in the chapter
+        if let Some(exclusions) = &enc_opts.exclude_pages {
+            let count = chapter_pics.len();
+            let mut index = 0;
+
+            chapter_pics.retain(|(path, _)| {
+                let excluded = exclusions.is_excluded(
+                    path.strip_prefix(chapter_path).unwrap_or(path),
+                    index,
+                    count,
+                );
+
+                if excluded {
+                    debug!(
+                        "Excluding picture '{}' from chapter {}.",
+                        path.to_string_lossy(),
+                        chapter_display_name
+                    );
+                }
+
+                index += 1;
+                !excluded
+            });
+        }
+
+        // Put the chapter's cover before its other pages, if it is named as such
+        let has_cover = match enc_opts.cover_from.unwrap_or(CoverDetection::Name) {
+            CoverDetection::Name => cover::move_cover_first(&mut chapter_pics, |(path, _)| path),
+            CoverDetection::FirstPage => false,
+        };
+
+        if has_cover {
+            debug!(
+                "Found cover '{}' in chapter {}.",
+                chapter_pics[0].0.to_string_lossy(),
+                chapter_display_name
+            );
+        }
+
+        // Disable mutability for this variable
+        let chapter_path = chapter_path;
+
+        // Determine the name of this chapter's directory in the volume's ZIP
+        let mut zip_dir_name = match method {
+            BuildMethod::Each(_, _) => chapter_name.to_string(),
+
+            _ => format!(
+                "Vol_{:0vol_num_len$}_Chapter_{:0chapter_num_len$}",
+                volume,
+                chapter,
+                vol_num_len = vol_num_len,
+                chapter_num_len = chapter_num_len
+            ),
+        };
+
+        // Compute the length of displayable picture number (e.g. 1520 pictures will give 4)
+        let pic_num_len = chapter_pics.len().to_string().len();
+
+        // Determine the prefix of the pictures' names in the ZIP directory
+        let mut pic_prefix = Some(match method {
+            BuildMethod::Each(_, _) => volume_display_name.clone(),
+            _ => zip_dir_name.clone(),
+        });
+
+        // Check the pictures' paths in the ZIP are not too long to be extracted on all platforms
+        let ext_len = chapter_pics
+            .iter()
+            .filter_map(|(file, _)| file.extension())
+            .map(|ext| ext.len())
+            .max()
+            .unwrap_or(0);
+
+        let entry_path_len = |dir_name: &str, pic_prefix: Option<&String>| {
+            dir_name.chars().count()
+                + pic_prefix
+                    .map(|prefix| prefix.chars().count() + 1)
+                    .unwrap_or(0)
+                + "/Pic_.".len()
+                + pic_num_len
+                + ext_len
+        };
+
+        let longest_entry_path = entry_path_len(&zip_dir_name, pic_prefix.as_ref());
+
+        if longest_entry_path > MAX_SAFE_ENTRY_PATH_LEN {
+            if enc_opts.shorten_entry_names {
+                // Don't repeat the directory's name in the pictures' names, then truncate it if needed
+                pic_prefix = None;
+
+                let excess =
+                    entry_path_len(&zip_dir_name, None).saturating_sub(MAX_SAFE_ENTRY_PATH_LEN);
+
+                if excess > 0 {
+                    let keep =
+                        std::cmp::max(zip_dir_name.chars().count().saturating_sub(excess), 1);
+
+                    // Windows doesn't allow names ending with spaces or dots
+                    zip_dir_name = zip_dir_name
+                        .chars()
+                        .take(keep)
+                        .collect::<String>()
+                        .trim_end_matches(|c| c == ' ' || c == '.')
+                        .to_string();
+                }
+
+                debug!(
+                    "Shortened pictures' paths of chapter {} in volume {} to '{}/Pic_*'.",
+                    chapter_display_name, volume_display_name, zip_dir_name
+                );
+            } else {
+                warn!(
+                    "Warning: pictures' paths of chapter {} in volume {} are up to {} characters long, which may be too long for them to be extracted on some platforms (use '--shorten-entry-names' to shorten them)",
+                    chapter_display_name, volume_display_name, longest_entry_path
+                );
+            }
+        }
+
+        // Ensure the directory doesn't collide with an existing entry
+        let zip_dir_name = self
+            .entry_names
+            .unique(&format!("{}/", zip_dir_name), volume_display_name)
+            .trim_end_matches('/')
+            .to_owned();
+
+        trace!("Adding directory '{}' to ZIP archive...", zip_dir_name);
+
+        // Create an empty directory for this chapter in the volume's ZIP
+        self.zip_writer
+            .add_directory(&zip_dir_name, self.zip_options)
+            .map_err(|err| EncodingError::FailedToCreateChapterDirectoryInZip {
+                volume,
+                chapter,
+                dir_name: zip_dir_name.to_owned(),
+                err,
+            })?;
+
+        // Create the same directory in the extraction directory
+        if let Some(extract_dir) = &self.extract_dir {
+            let chapter_extract_dir = extract_dir.join(&zip_dir_name);
+
+            fs::create_dir_all(&chapter_extract_dir).map_err(|err| {
+                EncodingError::FailedToCreateExtractedChapterDirectory(
+                    volume,
+                    chapter_extract_dir.clone(),
+                    err,
+                )
+            })?;
+        }
+
+        // Upscale the pages before adding them, as several of them can be upscaled at the same time
+        let mut upscaled_pages = match &enc_opts.upscale_cmd {
+            Some(command) => self.upscale_pages(
+                command,
+                &mut chapter_source,
+                &chapter_pics,
+                (volume, chapter, chapter_path),
+            )?,
+            None => vec![],
+        };
+
+        // Iterate over each page
+        for (page_nb, (file, source_index)) in chapter_pics.iter().enumerate() {
+            let file_ext = file.extension().unwrap().to_str().ok_or_else(|| {
+                EncodingError::ItemHasInvalidUTF8Name(file.file_name().unwrap().to_os_string())
+            })?;
+
+            // Pictures transcoded to another format get the matching extension
+            let recompress =
+                enc_opts.recompress_jpeg.is_some() && !transform::is_jpeg_ext(file_ext);
+            let conversion = conversion(enc_opts, file);
+            let file_ext = match conversion {
+                _ if enc_opts.quantize.is_some() => "png",
+                Some(format) => format.ext(),
+                None if recompress => "jpg",
+                None => enc_opts.normalize_format.map_or(file_ext, PageFormat::ext),
+            };
+
+            // Determine the name of the file in the ZIP directory
+            let name_in_zip = match &pic_prefix {
+                Some(pic_prefix) => format!(
+                    "{}_Pic_{:0pic_num_len$}.{}",
+                    pic_prefix,
+                    page_nb,
+                    file_ext,
+                    pic_num_len = pic_num_len
+                ),
+
+                None => format!(
+                    "Pic_{:0pic_num_len$}.{}",
+                    page_nb,
+                    file_ext,
+                    pic_num_len = pic_num_len
+                ),
+            };
+
+            trace!(
+                "Adding picture {:0pic_num_len$} at '{}' from chapter {} to volume {} as '{}/{}'...",
+                page_nb, file.to_string_lossy(), chapter_display_name, volume_display_name, zip_dir_name, name_in_zip, pic_num_len = pic_num_len
+            );
+
+            // Determine the path of the file in the ZIP directory
+            let path_in_zip = self.entry_names.unique(
+                &format!("{}/{}", zip_dir_name, name_in_zip),
+                volume_display_name,
+            );
+
+            // Pictures coming from an archive are copied as they are, without being decompressed, unless they are transformed
+            if let (ChapterSource::Archive(archive), Some(index), false) = (
+                &mut chapter_source,
+                source_index,
+                enc_opts.strip_image_metadata
+                    || transforms_pages(enc_opts, recompress, conversion)
+                    || enc_opts.upscale_cmd.is_some()
+                    || enc_opts.page_filter_cmd.is_some()
+                    || enc_opts.dedupe_pages
+                    || enc_opts.detect_near_duplicates.is_some()
+                    || enc_opts.validate_images,
+            ) {
+                let entry = archive.by_index(*index).map_err(|err| {
+                    EncodingError::FailedToCopyArchiveEntry {
+                        volume,
+                        chapter,
+                        chapter_path: chapter_path.to_path_buf(),
+                        entry_path: file.to_path_buf(),
+                        err,
+                    }
+                })?;
+
+                self.zip_writer
+                    .raw_copy_file_rename(entry, path_in_zip.clone())
+                    .map_err(|err| EncodingError::FailedToCopyArchiveEntry {
+                        volume,
+                        chapter,
+                        chapter_path: chapter_path.to_path_buf(),
+                        entry_path: file.to_path_buf(),
+                        err,
+                    })?;
+
+                // Extracting the picture, reading its dimensions, computing its digest or its thumbnail requires to decompress it
+                if self.extract_dir.is_some()
+                    || self.pages.is_some()
+                    || self.embedded_manifest.is_some()
+                    || self.contact_sheet.is_some()
+                    || enc_opts.reader_limit.is_some()
+                {
+                    archive
+                        .by_index(*index)
+                        .and_then(|mut entry| entry.read_to_end(&mut buffer).map_err(ZipError::Io))
+                        .map_err(|err| EncodingError::FailedToCopyArchiveEntry {
+                            volume,
+                            chapter,
+                            chapter_path: chapter_path.to_path_buf(),
+                            entry_path: file.to_path_buf(),
+                            err,
+                        })?;
+
+                    self.fit_reader_limit(&mut buffer, &path_in_zip)?;
+                    self.extract_picture(&path_in_zip, &buffer)?;
+                    self.describe_page(&buffer, has_cover && page_nb == 0);
+                    self.add_thumbnail(&buffer);
+                    self.record_page(&path_in_zip, chapter_name, &file.to_string_lossy(), &buffer);
+                    buffer.clear();
+                }
+
+                self.pics_counter += 1;
+                continue;
+            }
+
+            // Upscaled pictures were already read
+            if upscaled_pages.is_empty() {
+                read_page(
+                    &mut chapter_source,
+                    file,
+                    *source_index,
+                    (volume, chapter, chapter_path),
+                    &mut buffer,
+                )?;
+            } else {
+                buffer = std::mem::take(&mut upscaled_pages[page_nb]);
+            }
+
+            // Skip the pages which cannot be decoded if asked to
+            if !self.validate_picture(&buffer, file, chapter)? {
+                buffer.clear();
+                continue;
+            }
+
+            // Drop the pages identical to one read before in the volume
+            if self.is_duplicate(&buffer, file, &path_in_zip)? {
+                buffer.clear();
+                continue;
+            }
+
+            // Detect the pages looking like a page of a previous chapter, dropping them if asked to
+            if self.is_near_duplicate(&buffer, file, chapter) {
+                buffer.clear();
+                continue;
+            }
+
+            // Run the user's filter before the built-in transformations
+            if let Some(command) = &enc_opts.page_filter_cmd {
+                self.filter_picture(&mut buffer, &path_in_zip, command)?;
+            }
+
+            // Pictures from a directory are recorded with their path inside it
+            let source = file
+                .strip_prefix(chapter_path)
+                .unwrap_or(file)
+                .to_string_lossy()
+                .into_owned();
+
+            // Pictures are rotated before being sliced, so the slices follow the picture's EXIF orientation
+            if enc_opts.split_tall_pages.is_some() && !enc_opts.no_auto_orient {
+                let orientation = image_meta::orientation(&buffer);
+                self.apply_orientation(&mut buffer, &path_in_zip, orientation)?;
+            }
+
+            // Slice the picture in multiple pages if it's too tall, each slice getting a numbered suffix
+            let slices = match self.split_tall_page(&buffer, &path_in_zip)? {
+                Some(slices) => {
+                    let slice_num_len = slices.len().to_string().len();
+                    let mut named_slices = Vec::with_capacity(slices.len());
+
+                    for (slice_nb, slice) in slices.into_iter().enumerate() {
+                        let suffix = format!(
+                            "_{:0slice_num_len$}",
+                            slice_nb + 1,
+                            slice_num_len = slice_num_len
+                        );
+
+                        let slice_name = with_name_suffix(&name_in_zip, &suffix);
+
+                        named_slices.push((
+                            self.entry_names.unique(
+                                &format!("{}/{}", zip_dir_name, slice_name),
+                                volume_display_name,
+                            ),
+                            slice_name,
+                            slice,
+                        ));
+                    }
+
+                    named_slices
+                }
+
+                None => vec![(path_in_zip, name_in_zip, std::mem::take(&mut buffer))],
+            };
+
+            for (slice_nb, (path_in_zip, name_in_zip, mut slice)) in slices.into_iter().enumerate()
+            {
+                self.transform_picture(&mut slice, &path_in_zip, recompress, conversion)?;
+
+                // Remove the picture's metadata, once its EXIF orientation has been read to transform it
+                if enc_opts.strip_image_metadata {
+                    if let Some(stripped) = image_meta::strip_metadata(&slice) {
+                        trace!(
+                            "Removed {} bytes of metadata from picture {:0pic_num_len$}.",
+                            slice.len() - stripped.len(),
+                            page_nb,
+                            pic_num_len = pic_num_len
+                        );
+
+                        slice = stripped;
+                    }
+                }
+
+                // Split the picture in two pages if it's a double-page spread, each half getting a suffix in reading order
+                let parts = match self.split_spread(&slice, &path_in_zip)? {
+                    Some((first, second)) => vec![
+                        (
+                            self.entry_names.unique(
+                                &format!(
+                                    "{}/{}",
+                                    zip_dir_name,
+                                    with_name_suffix(&name_in_zip, "a")
+                                ),
+                                volume_display_name,
+                            ),
+                            first,
+                        ),
+                        (
+                            self.entry_names.unique(
+                                &format!(
+                                    "{}/{}",
+                                    zip_dir_name,
+                                    with_name_suffix(&name_in_zip, "b")
+                                ),
+                                volume_display_name,
+                            ),
+                            second,
+                        ),
+                    ],
+
+                    None => vec![(path_in_zip, slice)],
+                };
+
+                for (part_nb, (path_in_zip, content)) in parts.into_iter().enumerate() {
+                    let page = PreparedPage {
+                        path_in_zip,
+                        content,
+                        image_path: file.to_path_buf(),
+                        source: source.clone(),
+                        cover: has_cover && page_nb == 0 && slice_nb == 0 && part_nb == 0,
+                    };
+
+                    if enc_opts.join_spreads.is_some() {
+                        self.join_page(chapter, chapter_path, chapter_name, page)?;
+                    } else {
+                        self.write_page(chapter, chapter_path, chapter_name, page)?;
+                    }
+                }
+            }
+
+            buffer.clear();
+        }
+
+        // Pages are only joined with pages from the same chapter
+        if let Some(pending) = self.pending_page.take() {
+            self.write_page(chapter, chapter_path, chapter_name, pending)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write a page to the volume
+    fn write_page(
+        &mut self,
+        chapter: usize,
+        chapter_path: &Path,
+        chapter_name: &str,
+        page: PreparedPage,
+    ) -> Result<(), EncodingError> {
+        let volume = self.volume;
+
+        // Create the file in the archive
+        self.zip_writer
+            .start_file(page.path_in_zip.clone(), self.zip_options)
+            .map_err(|err| EncodingError::FailedToCreateImageFileInZip {
+                volume,
+                chapter,
+                file_path: PathBuf::from(&page.path_in_zip),
+                err,
+            })?;
+
+        // Write the picture to the ZIP archive
+        self.zip_writer.write_all(&page.content).map_err(|err| {
+            EncodingError::FailedToWriteImageFileToZip {
+                volume,
+                chapter,
+                chapter_path: chapter_path.to_path_buf(),
+                image_path: page.image_path.clone(),
+                err,
+            }
+        })?;
+
+        self.extract_picture(&page.path_in_zip, &page.content)?;
+        self.describe_page(&page.content, page.cover);
+        self.add_thumbnail(&page.content);
+        self.record_page(&page.path_in_zip, chapter_name, &page.source, &page.content);
+
+        self.pics_counter += 1;
+
+        Ok(())
+    }
+
+    /// Join a page with the previous one of its chapter side-by-side, or keep it until the next one
+    /// Covers and pages which are already landscape stay alone, and break the pairs
+    fn join_page(
+        &mut self,
+        chapter: usize,
+        chapter_path: &Path,
+        chapter_name: &str,
+        page: PreparedPage,
+    ) -> Result<(), EncodingError> {
+        let direction = spread_direction(self.enc_opts, self.enc_opts.join_spreads.unwrap());
+        let joinable = !page.cover && !PageInfo::read(&page.content).is_double_page();
+
+        match self.pending_page.take() {
+            Some(pending) if joinable => {
+                let joined = transform::join_pages(&pending.content, &page.content, direction)
+                    .map_err(|err| EncodingError::FailedToJoinPages {
+                        volume: self.volume,
+                        first: pending.path_in_zip.clone(),
+                        second: page.path_in_zip.clone(),
+                        err,
+                    })?;
+
+                debug!(
+                    "Joined pictures '{}' and '{}' in a single page.",
+                    pending.path_in_zip, page.path_in_zip
+                );
+
+                // The joined page takes the place of the first one
+                self.write_page(
+                    chapter,
+                    chapter_path,
+                    chapter_name,
+                    PreparedPage {
+                        content: joined,
+                        source: format!("{} + {}", pending.source, page.source),
+                        ..pending
+                    },
+                )
+            }
+
+            pending => {
+                if let Some(pending) = pending {
+                    self.write_page(chapter, chapter_path, chapter_name, pending)?;
+                }
+
+                if joinable {
+                    self.pending_page = Some(page);
+                    Ok(())
+                } else {
+                    self.write_page(chapter, chapter_path, chapter_name, page)
+                }
+            }
+        }
+    }
+
+    /// Add a picture at the root of the volume, which is expected to be its first page
+    pub fn add_cover(&mut self, cover_path: &Path) -> Result<(), EncodingError> {
+        let volume = self.volume;
+
+        if !is_page(self.enc_opts, cover_path) {
+            return Err(EncodingError::CoverIsNotAPicture(
+                volume,
+                cover_path.to_path_buf(),
+            ));
+        }
+
+        let mut content = fs::read(cover_path).map_err(|err| {
+            EncodingError::FailedToReadCover(volume, cover_path.to_path_buf(), err)
+        })?;
+
+        let cover_ext = cover_path
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        // Covers transcoded to another format get the matching extension
+        let recompress =
+            self.enc_opts.recompress_jpeg.is_some() && !transform::is_jpeg_ext(&cover_ext);
+        let conversion = conversion(self.enc_opts, cover_path);
+        let cover_ext = match conversion {
+            _ if self.enc_opts.quantize.is_some() => "png".to_owned(),
+            Some(format) => format.ext().to_owned(),
+            None if recompress => "jpg".to_owned(),
+            None => self
+                .enc_opts
+                .normalize_format
+                .map_or(cover_ext, |format| format.ext().to_owned()),
+        };
+
+        // Prefixed with zeroes so readers sort it before the chapters' directories
+        let path_in_zip = self.entry_names.unique(
+            &format!("000_Cover.{}", cover_ext),
+            &self.volume_display_name,
+        );
+
+        self.transform_picture(&mut content, &path_in_zip, recompress, conversion)?;
+
+        // Remove the cover's metadata, once its EXIF orientation has been read to transform it
+        if self.enc_opts.strip_image_metadata {
+            if let Some(stripped) = image_meta::strip_metadata(&content) {
+                content = stripped;
+            }
+        }
+
+        trace!(
+            "Adding cover '{}' to volume {} as '{}'...",
+            cover_path.to_string_lossy(),
+            self.volume_display_name,
+            path_in_zip
+        );
+
+        self.zip_writer
+            .start_file(path_in_zip.clone(), self.zip_options)
+            .and_then(|()| self.zip_writer.write_all(&content).map_err(ZipError::Io))
+            .map_err(|err| EncodingError::FailedToWriteCover(volume, err))?;
+
+        self.extract_picture(&path_in_zip, &content)?;
+        self.describe_page(&content, true);
+        self.add_thumbnail(&content);
+        self.record_page(
+            &path_in_zip,
+            &cover_path.to_string_lossy(),
+            &cover_path.file_name().unwrap_or_default().to_string_lossy(),
+            &content,
+        );
+
+        self.pics_counter += 1;
+
+        Ok(())
+    }
+
+    /// Add a metadata file at the root of the volume, with the number of pages written so far
+    pub fn add_comic_info(&mut self, xml: &str) -> Result<(), EncodingError> {
+        trace!("Adding metadata file to ZIP archive...");
+
+        self.write_comic_info(&comic_info::update_page_count(xml, self.pics_counter))
+    }
+
+    /// Add the metadata file of an existing volume being rewritten (e.g. when editing it), with its new number of pages
+    /// Its description of the pages is dropped, as the pages it describes may have been moved, removed or added
+    pub fn add_existing_comic_info(
+        &mut self,
+        xml: &str,
+        pages: usize,
+    ) -> Result<(), EncodingError> {
+        trace!("Adding updated metadata file to ZIP archive...");
+
+        self.write_comic_info(&comic_info::remove_pages(&comic_info::update_page_count(
+            xml, pages,
+        )))
+    }
+
+    /// Write a metadata file at the root of the volume
+    fn write_comic_info(&mut self, xml: &str) -> Result<(), EncodingError> {
+        self.entry_names.reserve(COMIC_INFO_FILE_NAME);
+
+        self.zip_writer
+            .start_file(COMIC_INFO_FILE_NAME, self.zip_options)
+            .and_then(|()| {
+                self.zip_writer
+                    .write_all(xml.as_bytes())
+                    .map_err(ZipError::Io)
+            })
+            .map_err(|err| EncodingError::FailedToWriteComicInfo(self.volume, err))
+    }
+
+    /// Read the informations of a picture if the pages are described in the metadata file
+    /// `cover` indicates if the picture is the cover of its chapter
+    fn describe_page(&mut self, content: &[u8], cover: bool) {
+        if let Some(pages) = &mut self.pages {
+            let mut page = PageInfo::read(content);
+            page.cover = cover;
+
+            if page.dimensions.is_none() {
+                warn!(
+                    "Warning: failed to read the dimensions of picture {} in volume {}",
+                    self.pics_counter + 1,
+                    self.volume_display_name
+                );
+            }
+
+            pages.push(page);
+        }
+    }
+
+    /// Read and upscale all pages of a chapter through the provided command
+    /// Returns the content of each page, the ones which failed to be upscaled being kept as they are if asked to
+    fn upscale_pages(
+        &self,
+        command: &str,
+        chapter_source: &mut ChapterSource,
+        chapter_pics: &[(PathBuf, Option<usize>)],
+        location: (usize, usize, &Path),
+    ) -> Result<Vec<Vec<u8>>, EncodingError> {
+        let mut pictures = Vec::with_capacity(chapter_pics.len());
+
+        for (file, source_index) in chapter_pics {
+            let mut content = vec![];
+            read_page(chapter_source, file, *source_index, location, &mut content)?;
+
+            let ext = file
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+
+            pictures.push((content, ext));
+        }
+
+        debug!(
+            "Upscaling {} pictures of volume {}...",
+            pictures.len(),
+            self.volume_display_name
+        );
+
+        let results = upscale::upscale_all(
+            command,
+            &pictures,
+            self.enc_opts.upscale_jobs.unwrap_or(1),
+            self.page_store.as_ref(),
+        );
+
+        let mut upscaled_pages = Vec::with_capacity(results.len());
+
+        for (((file, _), (original, _)), result) in chapter_pics.iter().zip(pictures).zip(results) {
+            match result {
+                Ok(upscaled) => upscaled_pages.push(upscaled),
+
+                Err(err) => match self.enc_opts.upscale_on_failure {
+                    Some(UpscaleFailurePolicy::Keep) => {
+                        warn!(
+                            "Warning: failed to upscale picture '{}' of volume {}, keeping it as it is: {}",
+                            file.to_string_lossy(),
+                            self.volume_display_name,
+                            err
+                        );
+
+                        upscaled_pages.push(original);
+                    }
+
+                    Some(UpscaleFailurePolicy::Fail) | None => {
+                        return Err(EncodingError::FailedToUpscalePicture {
+                            volume: self.volume,
+                            picture: file.to_string_lossy().into_owned(),
+                            err: err.to_string(),
+                        })
+                    }
+                },
+            }
+        }
+
+        Ok(upscaled_pages)
+    }
+
+    /// Check if a page can be decoded, if asked to
+    /// Returns `false` if it cannot and has to be skipped
+    fn validate_picture(
+        &self,
+        content: &[u8],
+        file: &Path,
+        chapter: usize,
+    ) -> Result<bool, EncodingError> {
+        if !self.enc_opts.validate_images {
+            return Ok(true);
+        }
+
+        let err = match transform::load_picture(content) {
+            Ok(_) => return Ok(true),
+            Err(err) => err,
+        };
+
+        if !self.enc_opts.lenient {
+            return Err(EncodingError::InvalidImage {
+                volume: self.volume,
+                chapter,
+                image_path: file.to_path_buf(),
+                err,
+            });
+        }
+
+        warn!(
+            "Warning: skipping picture '{}' of volume {} as it cannot be decoded: {}",
+            file.to_string_lossy(),
+            self.volume_display_name,
+            err
+        );
+
+        Ok(false)
+    }
+
+    /// Check if a page is identical to one read before in the volume, registering it otherwise
+    /// Duplicate pages are hard-linked to the previous page in the extraction directory, if any
+    /// Always returns `false` if duplicate pages are not dropped
+    fn is_duplicate(
+        &mut self,
+        content: &[u8],
+        file: &Path,
+        path_in_zip: &str,
+    ) -> Result<bool, EncodingError> {
+        let page_digests = match &mut self.page_digests {
+            Some(page_digests) => page_digests,
+            None => return Ok(false),
+        };
+
+        let previous_path = match page_digests.entry(manifest::sha256(content)) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => {
+                entry.insert(path_in_zip.to_owned());
+                return Ok(false);
+            }
+        };
+
+        debug!(
+            "Dropping picture '{}' as it is identical to a previous page of volume {}.",
+            file.to_string_lossy(),
+            self.volume_display_name
+        );
+
+        self.duplicate_pages += 1;
+
+        // Pages split or joined with another one are not extracted under their own path, so they can't be linked to
+        if let Some(extract_dir) = &self.extract_dir {
+            let previous_path = extract_dir.join(previous_path);
+
+            if previous_path.is_file() {
+                let extracted_path = extract_dir.join(path_in_zip);
+
+                trace!(
+                    "Linking picture '{}' to '{}'...",
+                    extracted_path.to_string_lossy(),
+                    previous_path.to_string_lossy()
+                );
+
+                // Filesystems which don't support hard links get a copy instead
+                fs::hard_link(&previous_path, &extracted_path)
+                    .or_else(|_| fs::copy(&previous_path, &extracted_path).map(|_| ()))
+                    .map_err(|err| {
+                        EncodingError::FailedToWriteExtractedPicture(
+                            self.volume,
+                            extracted_path,
+                            err,
+                        )
+                    })?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Check if a page looks like a page of a previous chapter of the volume, registering it as a near-duplicate if so
+    /// Returns `true` if the page has to be dropped, which is only the case for near-duplicates if asked to
+    fn is_near_duplicate(&mut self, content: &[u8], file: &Path, chapter: usize) -> bool {
+        let page_hashes = match &mut self.page_hashes {
+            Some(page_hashes) => page_hashes,
+            None => return false,
+        };
+
+        let max_distance = self
+            .enc_opts
+            .detect_near_duplicates
+            .flatten()
+            .unwrap_or(transform::DEFAULT_NEAR_DUPLICATE_DISTANCE);
+
+        let hash = match transform::perceptual_hash(content) {
+            Ok(Some(hash)) => hash,
+            Ok(None) => return false,
+            Err(err) => {
+                debug!(
+                    "Failed to compute the perceptual hash of picture '{}': {}",
+                    file.to_string_lossy(),
+                    err
+                );
+                return false;
+            }
+        };
+
+        let page = file.to_string_lossy().into_owned();
+
+        // Find the most similar page of the previous chapters
+        let original = page_hashes
+            .iter()
+            .filter(|(_, page_chapter, _)| *page_chapter != chapter)
+            .map(|(page_hash, _, page)| (transform::hash_distance(hash, *page_hash), page))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(distance, page)| (distance, page.clone()));
+
+        let (distance, original) = match original {
+            Some(original) => original,
+            None => {
+                page_hashes.push((hash, chapter, page));
+                return false;
+            }
+        };
+
+        let dropped = self.enc_opts.drop_near_duplicates;
+
+        warn!(
+            "Warning: picture '{}' of volume {} looks like picture '{}' ({} differing bits){}",
+            page,
+            self.volume_display_name,
+            original,
+            distance,
+            if dropped { ", dropping it" } else { "" }
+        );
+
+        if !dropped {
+            page_hashes.push((hash, chapter, page.clone()));
+        }
+
+        self.near_duplicates.push(NearDuplicate {
+            volume: self.volume,
+            page,
+            original,
+            distance,
+            dropped,
+        });
+
+        dropped
+    }
+
+    /// Replace a picture by the output of the page filter command
+    fn filter_picture(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+        command: &str,
+    ) -> Result<(), EncodingError> {
+        let filtered = hooks::run_page_filter(command, content, path_in_zip).map_err(|err| {
+            EncodingError::FailedToFilterPicture {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                err: err.to_string(),
+            }
+        })?;
+
+        debug!(
+            "Filtered picture '{}' ({} bytes instead of {}).",
+            path_in_zip,
+            filtered.len(),
+            content.len()
+        );
+
+        *content = filtered;
+
+        Ok(())
+    }
+
+    /// Rotate, downscale, recompress and convert a picture as asked to
+    /// If a page store is used, a picture transformed the same way before is taken from it instead of being transformed again
+    fn transform_picture(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+        recompress: bool,
+        conversion: Option<PageFormat>,
+    ) -> Result<(), EncodingError> {
+        let enc_opts = self.enc_opts;
+
+        let grayscale = enc_opts.grayscale || enc_opts.auto_grayscale;
+
+        let trim_threshold = enc_opts
+            .trim_margins
+            .map(|threshold| threshold.unwrap_or(transform::DEFAULT_TRIM_THRESHOLD));
+
+        // Pictures which are decoded lose their EXIF orientation, so it is applied to them unless asked not to
+        let transformed = transforms_pages(enc_opts, recompress, conversion);
+        let orient = !enc_opts.no_auto_orient && transformed;
+
+        // Describe the transformations to find the picture in the page store, unless it isn't transformed at all
+        let store_key = match &self.page_store {
+            Some(_) if transformed => {
+                Some(PageStore::key(
+                    content,
+                    &format!(
+                        "color={:?};orient={};trim={:?};grayscale={};auto_grayscale={};downscale={:?};jpeg={:?};convert={:?};quantize={:?};optimize={:?}",
+                        enc_opts.color_convert,
+                        orient,
+                        trim_threshold,
+                        enc_opts.grayscale,
+                        enc_opts.auto_grayscale,
+                        enc_opts
+                            .reader_limit
+                            .filter(|_| enc_opts.downscale_oversized),
+                        enc_opts.recompress_jpeg.filter(|_| recompress),
+                        conversion,
+                        enc_opts.quantize,
+                        Some(enc_opts.progressive_jpeg).filter(|_| enc_opts.optimize_jpeg)
+                    ),
+                ))
+            }
+            _ => None,
+        };
+
+        if let (Some(page_store), Some(store_key)) = (&self.page_store, &store_key) {
+            if let Some(stored) = page_store.get(store_key) {
+                trace!("Reusing picture '{}' from the page store.", path_in_zip);
+                *content = stored;
+                return Ok(());
+            }
+        }
+
+        // Converting the colors drops the EXIF orientation, so it is read beforehand
+        let orientation = image_meta::orientation(content);
+
+        if enc_opts.color_convert.is_some() {
+            self.convert_colors(content, path_in_zip)?;
+        }
+
+        if orient {
+            self.apply_orientation(content, path_in_zip, orientation)?;
+        }
+
+        if let Some(threshold) = trim_threshold {
+            self.trim_picture(content, path_in_zip, threshold)?;
+        }
+
+        if grayscale {
+            self.grayscale_picture(content, path_in_zip)?;
+        }
+
+        self.fit_reader_limit(content, path_in_zip)?;
+
+        if recompress {
+            self.recompress_picture(content, path_in_zip)?;
+        }
+
+        if let Some(format) = conversion {
+            self.convert_picture(content, path_in_zip, format)?;
+        }
+
+        if let Some(colors) = enc_opts.quantize {
+            self.quantize_picture(content, path_in_zip, colors)?;
+        }
+
+        if enc_opts.optimize_jpeg {
+            self.optimize_jpeg(content, path_in_zip)?;
+        }
+
+        if let (Some(page_store), Some(store_key)) = (&self.page_store, &store_key) {
+            if let Err(err) = page_store.put(store_key, content) {
+                warn!(
+                    "Warning: failed to add picture '{}' of volume {} to the page store: {}",
+                    path_in_zip, self.volume_display_name, err
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Warn if a picture exceeds the reader limit, or downscale it if asked to
+    fn fit_reader_limit(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+    ) -> Result<(), EncodingError> {
+        let reader_limit = match self.enc_opts.reader_limit {
+            Some(reader_limit) => reader_limit,
+            None => return Ok(()),
+        };
+
+        let (width, height) = match PageInfo::read(content).dimensions {
+            Some(dimensions) => dimensions,
+            None => return Ok(()),
+        };
+
+        if width <= reader_limit && height <= reader_limit {
+            return Ok(());
+        }
+
+        if !self.enc_opts.downscale_oversized {
+            warn!(
+                "Warning: picture '{}' in volume {} is {}x{} pixels, which exceeds the reader limit of {} pixels",
+                path_in_zip, self.volume_display_name, width, height, reader_limit
+            );
+
+            return Ok(());
+        }
+
+        debug!(
+            "Downscaling picture '{}' ({}x{} pixels) to fit in {} pixels...",
+            path_in_zip, width, height, reader_limit
+        );
+
+        *content = transform::downscale(content, reader_limit).map_err(|err| {
+            EncodingError::FailedToDownscalePicture {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                err,
+            }
+        })?;
+
+        Ok(())
+    }
+
+    /// Rotate a picture as its EXIF orientation asks readers to, as many of them ignore it
+    fn apply_orientation(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+        orientation: Option<u16>,
+    ) -> Result<(), EncodingError> {
+        let orientation = match orientation {
+            Some(orientation) if orientation != 1 => orientation,
+            _ => return Ok(()),
+        };
+
+        let oriented = transform::apply_orientation(content, orientation).map_err(|err| {
+            EncodingError::FailedToOrientPicture {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                err,
+            }
+        })?;
+
+        debug!(
+            "Rotated picture '{}' according to its EXIF orientation ({}).",
+            path_in_zip, orientation
+        );
+
+        *content = oriented;
+
+        Ok(())
+    }
+
+    /// Convert the colors of a CMYK picture or of a picture with a color profile to sRGB
+    fn convert_colors(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+    ) -> Result<(), EncodingError> {
+        let converted = transform::to_srgb(content).map_err(|err| {
+            EncodingError::FailedToConvertPictureColors {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                err,
+            }
+        })?;
+
+        if let Some(converted) = converted {
+            debug!("Converted the colors of picture '{}' to sRGB.", path_in_zip);
+            *content = converted;
+        }
+
+        Ok(())
+    }
+
+    /// Crop the uniform margins around a picture, if it has any
+    fn trim_picture(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+        threshold: u8,
+    ) -> Result<(), EncodingError> {
+        let trimmed = transform::trim_margins(content, threshold).map_err(|err| {
+            EncodingError::FailedToTrimPicture {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                err,
+            }
+        })?;
+
+        if let Some(trimmed) = trimmed {
+            debug!("Trimmed the margins of picture '{}'.", path_in_zip);
+            *content = trimmed;
+        }
+
+        Ok(())
+    }
+
+    /// Convert a picture to grayscale, or only if it has (almost) no color when it's automatically detected
+    fn grayscale_picture(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+    ) -> Result<(), EncodingError> {
+        let converted =
+            transform::grayscale(content, self.enc_opts.auto_grayscale).map_err(|err| {
+                EncodingError::FailedToGrayscalePicture {
+                    volume: self.volume,
+                    picture: path_in_zip.to_owned(),
+                    err,
+                }
+            })?;
+
+        if let Some(converted) = converted {
+            debug!(
+                "Converted picture '{}' to grayscale ({} bytes instead of {}).",
+                path_in_zip,
+                converted.len(),
+                content.len()
+            );
+
+            *content = converted;
+        }
+
+        Ok(())
+    }
+
+    /// Transcode a picture to JPEG with the quality provided by '--recompress-jpeg'
+    fn recompress_picture(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+    ) -> Result<(), EncodingError> {
+        let quality = match self.enc_opts.recompress_jpeg {
+            Some(quality) => quality,
+            None => return Ok(()),
+        };
+
+        let recompressed = transform::recompress_jpeg(content, quality).map_err(|err| {
+            EncodingError::FailedToRecompressPicture {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                err,
+            }
+        })?;
+
+        debug!(
+            "Recompressed picture '{}' to JPEG ({} bytes instead of {}).",
+            path_in_zip,
+            recompressed.len(),
+            content.len()
+        );
+
+        *content = recompressed;
+
+        Ok(())
+    }
+
+    /// Reduce a picture to the provided number of colors, as a PNG picture
+    fn quantize_picture(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+        colors: u16,
+    ) -> Result<(), EncodingError> {
+        let quantized = transform::quantize(content, colors).map_err(|err| {
+            EncodingError::FailedToQuantizePicture {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                err,
+            }
+        })?;
+
+        debug!(
+            "Reduced picture '{}' to {} colors ({} bytes instead of {}).",
+            path_in_zip,
+            colors,
+            quantized.len(),
+            content.len()
+        );
+
+        *content = quantized;
+
+        Ok(())
+    }
+
+    /// Rewrite a JPEG picture losslessly with optimized Huffman tables, leaving the other pictures untouched
+    fn optimize_jpeg(&self, content: &mut Vec<u8>, path_in_zip: &str) -> Result<(), EncodingError> {
+        if !jpegtran::is_jpeg(content) {
+            return Ok(());
+        }
+
+        let optimized =
+            jpegtran::optimize(content, self.enc_opts.progressive_jpeg).map_err(|err| {
+                EncodingError::FailedToOptimizeJpeg {
+                    volume: self.volume,
+                    picture: path_in_zip.to_owned(),
+                    err: err.to_string(),
+                }
+            })?;
+
+        if let Some(optimized) = optimized {
+            debug!(
+                "Optimized JPEG picture '{}' ({} bytes instead of {}).",
+                path_in_zip,
+                optimized.len(),
+                content.len()
+            );
+
+            *content = optimized;
+        }
+
+        Ok(())
+    }
+
+    /// Convert a picture to the provided format
+    fn convert_picture(
+        &self,
+        content: &mut Vec<u8>,
+        path_in_zip: &str,
+        format: PageFormat,
+    ) -> Result<(), EncodingError> {
+        let converted = transform::convert(content, format).map_err(|err| {
+            EncodingError::FailedToConvertPicture {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                format,
+                err,
+            }
+        })?;
+
+        debug!(
+            "Converted picture '{}' to {} ({} bytes instead of {}).",
+            path_in_zip,
+            format,
+            converted.len(),
+            content.len()
+        );
+
+        *content = converted;
+
+        Ok(())
+    }
+
+    /// Split a picture in two pages in reading order if it's a double-page spread and spreads are split
+    fn split_spread(
+        &self,
+        content: &[u8],
+        path_in_zip: &str,
+    ) -> Result<Option<(Vec<u8>, Vec<u8>)>, EncodingError> {
+        let direction = match self.enc_opts.split_spreads {
+            Some(direction) => spread_direction(self.enc_opts, direction),
+            None => return Ok(None),
+        };
+
+        let halves = transform::split_spread(content, direction).map_err(|err| {
+            EncodingError::FailedToSplitSpread {
+                volume: self.volume,
+                picture: path_in_zip.to_owned(),
+                err,
+            }
+        })?;
+
+        if halves.is_some() {
+            debug!("Split double-page spread '{}' in two pages.", path_in_zip);
+        }
+
+        Ok(halves)
+    }
+
+    /// Slice a picture in multiple pages if it is too tall, as asked to
+    fn split_tall_page(
+        &self,
+        content: &[u8],
+        path_in_zip: &str,
+    ) -> Result<Option<Vec<Vec<u8>>>, EncodingError> {
+        let max_height = match self.enc_opts.split_tall_pages {
+            Some(max_height) => max_height
+                .or(self.enc_opts.reader_limit)
+                .unwrap_or(transform::MAX_JPEG_DIMENSION),
+            None => return Ok(None),
+        };
+
+        let slices = match transform::split_tall(content, max_height) {
+            Ok(slices) => slices,
+
+            // Pictures which cannot be re-encoded in their format (e.g. JPEG XL or AVIF) are kept whole
+            Err(ImageError::Unsupported(err)) => {
+                warn!(
+                    "Warning: picture '{}' in volume {} cannot be sliced as its format is not supported ({}), it is kept whole",
+                    path_in_zip, self.volume_display_name, err
+                );
+
+                return Ok(None);
+            }
+
+            Err(err) => {
+                return Err(EncodingError::FailedToSplitTallPage {
+                    volume: self.volume,
+                    picture: path_in_zip.to_owned(),
+                    err,
+                })
+            }
+        };
+
+        if let Some(slices) = &slices {
+            debug!(
+                "Sliced tall picture '{}' in {} pages.",
+                path_in_zip,
+                slices.len()
+            );
+        }
+
+        Ok(slices)
+    }
+
+    /// Add the thumbnail of a picture to the contact sheet, if any
+    fn add_thumbnail(&mut self, content: &[u8]) {
+        if let Some(thumbnails) = &mut self.contact_sheet {
+            match transform::load_picture(content) {
+                Ok(picture) => thumbnails.push(picture.thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)),
+                Err(err) => warn!(
+                    "Warning: failed to decode picture {} in volume {}, it won't appear in the contact sheet: {}",
+                    self.pics_counter + 1,
+                    self.volume_display_name,
+                    err
+                ),
+            }
+        }
+    }
+
+    /// Record a picture in the embedded manifest, if any
+    fn record_page(&mut self, path_in_zip: &str, chapter: &str, source: &str, content: &[u8]) {
+        if let Some(embedded_manifest) = &mut self.embedded_manifest {
+            embedded_manifest.pages.push(EmbeddedPage {
+                path_in_zip: path_in_zip.to_owned(),
+                chapter: chapter.to_owned(),
+                source: source.to_owned(),
+                sha256: manifest::sha256(content),
+            });
+        }
+    }
+
+    /// Copy an entry of an existing volume as it is, without decompressing it
+    /// Its embedded manifest, checksums file and metadata file are left out, as they must be written again once the volume is complete
+    /// Pages are recorded in the embedded manifest if any, from their previous record or else from their content
+    /// Returns if the entry is a page
+    pub fn copy_existing_entry<R: Read + Seek>(
+        &mut self,
+        source_zip: &mut ZipArchive<R>,
+        index: usize,
+        source: &Path,
+        previous_pages: &mut HashMap<String, EmbeddedPage>,
+    ) -> Result<bool, EncodingError> {
+        let copy_err = |entry_name: String, err| EncodingError::FailedToCopyExistingVolumeEntry {
+            volume_path: source.to_path_buf(),
+            entry_name,
+            err,
+        };
+
+        let entry = source_zip
+            .by_index(index)
+            .map_err(|err| copy_err(format!("#{}", index), err))?;
+
+        let entry_name = entry.name().to_owned();
+
+        if entry_name == EMBEDDED_MANIFEST_FILE_NAME
+            || entry_name == CHECKSUMS_FILE_NAME
+            || entry_name == COMIC_INFO_FILE_NAME
+        {
+            trace!("Leaving out entry '{}' to write it again...", entry_name);
+            return Ok(false);
+        }
+
+        let is_page = entry.is_file()
+            && (previous_pages.contains_key(&entry_name)
+                || deter::has_image_ext(&entry_name, true));
+
+        trace!("Copying entry '{}'...", entry_name);
+
+        self.zip_writer
+            .raw_copy_file(entry)
+            .map_err(|err| copy_err(entry_name.clone(), err))?;
+
+        if !is_page || self.embedded_manifest.is_none() {
+            return Ok(is_page);
+        }
+
+        let page = match previous_pages.remove(&entry_name) {
+            Some(page) => page,
+
+            // Pages without a record must be decompressed to compute their digest
+            None => {
+                let mut content = vec![];
+
+                source_zip
+                    .by_index(index)
+                    .and_then(|mut entry| entry.read_to_end(&mut content).map_err(ZipError::Io))
+                    .map_err(|err| copy_err(entry_name.clone(), err))?;
+
+                EmbeddedPage::from_path_in_zip(&entry_name, manifest::sha256(&content))
+            }
+        };
+
+        if let Some(embedded_manifest) = &mut self.embedded_manifest {
+            embedded_manifest.pages.push(page);
+        }
+
+        Ok(true)
+    }
+
+    /// Add the manifest recording the volume's pages at its root
+    pub fn add_embedded_manifest(
+        &mut self,
+        embedded_manifest: &EmbeddedManifest,
+    ) -> Result<(), EncodingError> {
+        trace!("Adding embedded manifest to ZIP archive...");
+
+        let json = serde_json::to_string_pretty(embedded_manifest)
+            .map_err(|err| ZipError::Io(io::Error::new(io::ErrorKind::Other, err)))
+            .map_err(|err| EncodingError::FailedToWriteEmbeddedManifest(self.volume, err))?;
+
+        self.entry_names.reserve(EMBEDDED_MANIFEST_FILE_NAME);
+
+        self.zip_writer
+            .start_file(EMBEDDED_MANIFEST_FILE_NAME, self.zip_options)
+            .and_then(|()| {
+                self.zip_writer
+                    .write_all(json.as_bytes())
+                    .map_err(ZipError::Io)
+            })
+            .map_err(|err| EncodingError::FailedToWriteEmbeddedManifest(self.volume, err))
+    }
+
+    /// Add the checksums of the volume's pages at its root, in the format of the 'sha256sum' tool
+    pub fn add_checksums(
+        &mut self,
+        embedded_manifest: &EmbeddedManifest,
+    ) -> Result<(), EncodingError> {
+        trace!("Adding checksums file to ZIP archive...");
+
+        let checksums = manifest::render_checksums(&embedded_manifest.pages);
+
+        self.entry_names.reserve(CHECKSUMS_FILE_NAME);
+
+        self.zip_writer
+            .start_file(CHECKSUMS_FILE_NAME, self.zip_options)
+            .and_then(|()| {
+                self.zip_writer
+                    .write_all(checksums.as_bytes())
+                    .map_err(ZipError::Io)
+            })
+            .map_err(|err| EncodingError::FailedToWriteChecksums(self.volume, err))
+    }
+
+    /// Add a table of contents at the root of the volume
+    pub fn add_toc(&mut self, html: &str) -> Result<(), EncodingError> {
+        trace!("Adding table of contents to ZIP archive...");
+
+        self.entry_names.reserve(TOC_FILE_NAME);
+
+        self.zip_writer
+            .start_file(TOC_FILE_NAME, self.zip_options)
+            .and_then(|()| {
+                self.zip_writer
+                    .write_all(html.as_bytes())
+                    .map_err(ZipError::Io)
+            })
+            .map_err(|err| EncodingError::FailedToWriteToc(self.volume, err))
+    }
+
+    /// Write a picture to the extraction directory, if any
+    /// `path_in_zip` is the path of the picture in the volume
+    fn extract_picture(&self, path_in_zip: &str, content: &[u8]) -> Result<(), EncodingError> {
+        if let Some(extract_dir) = &self.extract_dir {
+            let extracted_path = extract_dir.join(path_in_zip);
+
+            trace!(
+                "Writing picture to '{}'...",
+                extracted_path.to_string_lossy()
+            );
+
+            fs::write(&extracted_path, content).map_err(|err| {
+                EncodingError::FailedToWriteExtractedPicture(self.volume, extracted_path, err)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl EntryNames {
+    /// Mark an entry's name as used in the volume
+    pub fn reserve(&mut self, name: &str) {
+        self.names.insert(normalize_entry_name(name));
+    }
+
+    /// Get a name for a new entry that doesn't collide with the existing ones (case-insensitively)
+    /// Colliding names get a numbered suffix (e.g. 'Pic_1 (2).jpg'), and a warning is displayed
+    /// Names of directories must end with a '/'
+    pub fn unique(&mut self, name: &str, volume_display_name: &str) -> String {
+        let is_dir = name.ends_with('/');
+        let name = name.trim_end_matches('/');
+
+        // Split the name to put the suffix before the file's extension
+        let (parent, file_name) = match name.rsplit_once('/') {
+            Some((parent, file_name)) => (format!("{}/", parent), file_name),
+            None => (String::new(), name),
+        };
+
+        let (stem, ext) = match file_name.rfind('.') {
+            Some(dot) if dot > 0 && !is_dir => file_name.split_at(dot),
+            _ => (file_name, ""),
+        };
+
+        let mut unique_name = name.to_owned();
+        let mut suffix = 1;
+
+        while self.names.contains(&normalize_entry_name(&unique_name)) {
+            suffix += 1;
+            unique_name = format!("{}{} ({}){}", parent, stem, suffix, ext);
+        }
+
+        if suffix > 1 {
+            warn!(
+                "Warning: entry '{}' collides with another entry of volume {}, writing it as '{}' instead.",
+                name, volume_display_name, unique_name
+            );
+
+            self.renamed += 1;
+        }
+
+        self.reserve(&unique_name);
+
+        if is_dir {
+            unique_name.push('/');
+        }
+
+        unique_name
+    }
+}
+
+/// Normalize an entry's name to detect collisions: readers and file systems may ignore case and trailing slashes
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(normalize_entry_name("Vol_1_Chapter_1/"), "vol_1_chapter_1");
+/// ```
+fn normalize_entry_name(name: &str) -> String {
+    name.trim_end_matches('/').to_lowercase()
 }
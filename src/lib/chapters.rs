@@ -0,0 +1,204 @@
+use crate::cli::error::EncodingError;
+use crate::lib::deter::ComicFormat;
+use crate::lib::ignore_file::IgnoreFile;
+use crate::lib::manifest::Manifest;
+use crate::lib::sort;
+use deunicode::deunicode;
+use regex::Regex;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Filter on the chapters' file names
+pub struct ChapterFilter {
+    /// Accepted prefixes (normalized), an empty list accepts every name
+    prefixes: Vec<String>,
+
+    /// Pattern names must match, if any
+    regex: Option<Regex>,
+
+    /// Compare names case-insensitively
+    ignore_case: bool,
+
+    /// Compare names after transliterating them to ASCII (e.g. 'Chapître' matches 'Chapitre')
+    transliterate: bool,
+}
+
+impl ChapterFilter {
+    /// Create a filter from a comma-separated list of prefixes (e.g. "Ch,Chapter,Chapitre") and an optional pattern
+    pub fn new(
+        prefixes: Option<&str>,
+        ignore_case: bool,
+        transliterate: bool,
+        regex: Option<Regex>,
+    ) -> Self {
+        let mut filter = Self {
+            prefixes: vec![],
+            regex,
+            ignore_case,
+            transliterate,
+        };
+
+        if let Some(prefixes) = prefixes {
+            filter.prefixes = prefixes
+                .split(',')
+                .filter(|prefix| !prefix.is_empty())
+                .map(|prefix| filter.normalize(prefix))
+                .collect();
+        }
+
+        filter
+    }
+
+    /// Check if a name starts with one of the accepted prefixes and matches the pattern
+    pub fn matches(&self, name: &str) -> bool {
+        if let Some(regex) = &self.regex {
+            if !regex.is_match(name) {
+                return false;
+            }
+        }
+
+        if self.prefixes.is_empty() {
+            return true;
+        }
+
+        let name = self.normalize(name);
+
+        self.prefixes.iter().any(|prefix| name.starts_with(prefix))
+    }
+
+    /// Normalize a name according to the filter's settings
+    fn normalize(&self, name: &str) -> String {
+        let name = if self.transliterate {
+            deunicode(name)
+        } else {
+            name.to_owned()
+        };
+
+        if self.ignore_case {
+            name.to_lowercase()
+        } else {
+            name
+        }
+    }
+}
+
+/// List the chapters inside a directory, sorted by name
+/// Chapters are either directories, archives (ZIP, 7-Zip or TAR) or PDF documents
+/// Only chapters whose file name is accepted by the filter and which are not excluded by the ignore file are kept
+/// If `output_dir` is the input directory, the volumes written to it by a previous run are not taken for chapters
+/// Returns a vector of tuples containing: (path to the chapter, chapter's name)
+pub fn collect_chapters(
+    input_dir: &Path,
+    output_dir: Option<&Path>,
+    filter: &ChapterFilter,
+    ignore_file: &IgnoreFile,
+    simple_sorting: bool,
+    roman_numerals: bool,
+) -> Result<Vec<(PathBuf, String)>, EncodingError> {
+    // List of chapter directories
+    let mut chapter_dirs: Vec<(PathBuf, String)> = vec![];
+
+    // Volumes previously built in the input directory, if volumes are written to it
+    let previous_volumes = match output_dir {
+        Some(output_dir) if output_dir == input_dir => Some(previous_volumes(output_dir)),
+        _ => None,
+    };
+
+    trace!("Reading chapter directories...");
+
+    // Iterate over all items in the input directory
+    for entry in fs::read_dir(input_dir).map_err(EncodingError::FailedToReadChaptersDirectory)? {
+        let entry = entry.map_err(EncodingError::FailedToReadChaptersDirectory)?;
+        let path = entry.path();
+
+        // Ignore files, except archives and PDFs which are treated as chapters as well
+        let is_archive = path.is_file()
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .and_then(ComicFormat::from_ext)
+                .map_or(false, ComicFormat::is_supported_for_chapters);
+
+        if (path.is_dir() || is_archive) && ignore_file.is_ignored(&path, path.is_dir()) {
+            debug!("Ignored chapter '{}'.", path.to_string_lossy());
+        } else if is_archive
+            && previous_volumes
+                .as_ref()
+                .map_or(false, |previous| is_previous_volume(&path, previous))
+        {
+            debug!(
+                "Ignored chapter '{}' as it is a volume built in the same directory.",
+                path.to_string_lossy()
+            );
+        } else if path.is_dir() || is_archive {
+            let entry_name = entry
+                .file_name()
+                .into_string()
+                .map_err(|_| EncodingError::ItemHasInvalidUTF8Name(entry.file_name()))?;
+
+            // Ignore directories not accepted by the filter
+            if filter.matches(&entry_name) {
+                debug!("Accepted chapter '{}'.", entry_name);
+
+                // Archives and PDFs are named after their file name without extension
+                let chapter_name = if is_archive {
+                    path.file_stem().unwrap().to_string_lossy().to_string()
+                } else {
+                    entry_name
+                };
+
+                chapter_dirs.push((path, chapter_name));
+            } else {
+                debug!("Rejected chapter '{}'.", entry_name);
+            }
+        }
+    }
+
+    trace!("Sorting chapter directories by name...");
+
+    if simple_sorting {
+        chapter_dirs.sort_by(|a, b| a.0.cmp(&b.0));
+    } else {
+        sort::natural_sort_by_path(&mut chapter_dirs, |(path, _)| path, roman_numerals);
+    }
+
+    Ok(chapter_dirs)
+}
+
+/// Get the names of the volumes built in a directory by a previous run, registered in its manifest
+fn previous_volumes(dir: &Path) -> Vec<String> {
+    match Manifest::load(dir) {
+        Ok(manifest) => manifest
+            .volumes
+            .into_iter()
+            .flat_map(|volume| vec![volume.file_name, volume.base_name])
+            .collect(),
+        Err(err) => {
+            warn!(
+                "Warning: failed to load the manifest of '{}', volumes built previously may be taken for chapters: {}",
+                dir.to_string_lossy(),
+                err
+            );
+            vec![]
+        }
+    }
+}
+
+/// Check if a file is a volume built by a previous run, either registered in the manifest or named like the volumes are by default
+/// (e.g. 'Volume-01.cbz', 'Series - Volume-01 (c01-c10) (120 pages).cbz')
+/// Staging files don't need to be checked, as their extension isn't the one of a chapter
+fn is_previous_volume(path: &Path, previous_volumes: &[String]) -> bool {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+    if previous_volumes
+        .iter()
+        .any(|name| *name == file_name || *name == stem || stem.starts_with(&format!("{} (", name)))
+    {
+        return true;
+    }
+
+    Regex::new(r"^(.+ - )?Volume-\d+( \(c\d+-c\d+\))?( \(\d+ pages\))?$")
+        .unwrap()
+        .is_match(&stem)
+}
@@ -0,0 +1,560 @@
+use crate::cli::opts::EncodingOptions;
+use crate::lib::provenance::{self, ChapterProvenance};
+use crate::lib::source_meta::SourceMetadata;
+use crate::lib::toc::TocEntry;
+use crate::lib::transform::ReadingDirection;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::io::Cursor;
+use std::path::Path;
+
+/// Name of the metadata file of comic archives
+pub const COMIC_INFO_FILE_NAME: &str = "ComicInfo.xml";
+
+/// Name of the file the metadata embedded in a volume is written to when decoding it
+pub const EMBEDDED_METADATA_FILE_NAME: &str = "metadata.json";
+
+/// Beginning of an OPF metadata file, before the fields
+const OPF_HEADER: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"2.0\">\n  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:opf=\"http://www.idpf.org/2007/opf\">\n";
+
+/// End of an OPF metadata file, after the fields
+const OPF_FOOTER: &str = "  </metadata>\n</package>\n";
+
+/// Content of a metadata file without any field
+const EMPTY_COMIC_INFO: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<ComicInfo xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n</ComicInfo>\n";
+
+/// Metadata provided by the user to embed in volumes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ComicMetadata {
+    pub title: Option<String>,
+    pub series: Option<String>,
+    pub writer: Option<String>,
+    pub number: Option<String>,
+    pub year: Option<u16>,
+    pub translator: Option<String>,
+    pub language: Option<String>,
+
+    /// Order the pages are read in, which readers need to display right-to-left volumes (manga) right
+    pub reading_direction: Option<ReadingDirection>,
+
+    /// Identifiers of the series in external databases
+    pub anilist_id: Option<String>,
+    pub mangadex_id: Option<String>,
+    pub comicvine_id: Option<String>,
+
+    /// Origin of the volume's chapters, which manifests record separately
+    #[serde(skip)]
+    pub provenance: Vec<ChapterProvenance>,
+}
+
+impl ComicMetadata {
+    /// Get the metadata of a volume from the encoding options
+    /// `volume` is the volume's number, starting at 1, which offsets the provided volume number
+    pub fn from_options(enc_opts: &EncodingOptions, volume: usize) -> Self {
+        Self {
+            title: enc_opts.title.clone(),
+            series: enc_opts.series.clone(),
+            writer: enc_opts.writer.clone(),
+            number: enc_opts
+                .volume_number
+                .map(|number| (number + volume - 1).to_string()),
+            year: enc_opts.year,
+            translator: None,
+            language: None,
+            reading_direction: enc_opts.reading_direction,
+            anilist_id: enc_opts.anilist_id.clone(),
+            mangadex_id: enc_opts.mangadex_id.clone(),
+            comicvine_id: enc_opts.comicvine_id.clone(),
+            provenance: vec![],
+        }
+    }
+
+    /// Complete the missing fields with the ones of a metadata file
+    /// `of_chapter` indicates if the file describes the volume's (single) chapter, otherwise it describes the series
+    pub fn complete_with(&mut self, source: &SourceMetadata, of_chapter: bool) {
+        if of_chapter {
+            self.title = self.title.take().or_else(|| source.title.clone());
+            self.number = self.number.take().or_else(|| source.display_number());
+        } else {
+            self.series = self.series.take().or_else(|| source.title.clone());
+        }
+
+        self.translator = self.translator.take().or_else(|| source.translator.clone());
+        self.language = self.language.take().or_else(|| source.language.clone());
+
+        // Identifiers point to the series, whatever the file describes
+        self.anilist_id = self.anilist_id.take().or_else(|| source.anilist_id.clone());
+        self.mangadex_id = self
+            .mangadex_id
+            .take()
+            .or_else(|| source.mangadex_id.clone());
+        self.comicvine_id = self
+            .comicvine_id
+            .take()
+            .or_else(|| source.comicvine_id.clone());
+    }
+
+    /// Check if no metadata was provided
+    pub fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.series.is_none()
+            && self.writer.is_none()
+            && self.number.is_none()
+            && self.year.is_none()
+            && self.translator.is_none()
+            && self.language.is_none()
+            && self.reading_direction.is_none()
+            && self.anilist_id.is_none()
+            && self.mangadex_id.is_none()
+            && self.comicvine_id.is_none()
+            && self.provenance.is_empty()
+    }
+
+    /// Set the provided metadata in a metadata file's content, or in an empty one if none is provided
+    /// Fields already present are replaced, other ones are left untouched
+    pub fn apply(&self, xml: Option<&str>) -> String {
+        let mut xml = xml.unwrap_or(EMPTY_COMIC_INFO).to_owned();
+
+        // Fields are added in the schema's order
+        let fields = [
+            ("Title", self.title.clone()),
+            ("Series", self.series.clone()),
+            ("Number", self.number.clone()),
+            ("Notes", self.notes()),
+            ("Year", self.year.map(|year| year.to_string())),
+            ("Writer", self.writer.clone()),
+            ("Translator", self.translator.clone()),
+            ("Web", self.external_ids_urls()),
+            ("LanguageISO", self.language.clone()),
+            (
+                "Manga",
+                self.reading_direction.map(|direction| match direction {
+                    ReadingDirection::Ltr => "No".to_owned(),
+                    ReadingDirection::Rtl => "YesAndRightToLeft".to_owned(),
+                }),
+            ),
+        ];
+
+        for (tag, value) in fields.iter() {
+            if let Some(value) = value {
+                xml = set_field(&xml, tag, &escape(value));
+            }
+        }
+
+        xml
+    }
+
+    /// Get the notes of a metadata file: the identifiers of the series in external databases, then the origin of the chapters
+    /// Returns `None` if there is nothing to note
+    fn notes(&self) -> Option<String> {
+        let notes = [
+            self.external_ids_notes(),
+            provenance::render_notes(&self.provenance),
+        ];
+
+        let notes = notes.iter().flatten().cloned().collect::<Vec<_>>();
+
+        if notes.is_empty() {
+            None
+        } else {
+            Some(notes.join("; "))
+        }
+    }
+
+    /// Get the identifiers of the series in external databases, as displayed in the notes of a metadata file
+    /// Returns `None` if no identifier was provided
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let metadata = ComicMetadata { anilist_id: Some("30013".to_owned()), comicvine_id: Some("4050-1234".to_owned()), ..ComicMetadata::default() };
+    /// assert_eq!(metadata.external_ids_notes(), Some("AniList ID: 30013; ComicVine ID: 4050-1234".to_owned()));
+    /// ```
+    fn external_ids_notes(&self) -> Option<String> {
+        let ids = [
+            ("AniList", &self.anilist_id),
+            ("MangaDex", &self.mangadex_id),
+            ("ComicVine", &self.comicvine_id),
+        ];
+
+        let notes = ids
+            .iter()
+            .filter_map(|(database, id)| id.as_ref().map(|id| format!("{} ID: {}", database, id)))
+            .collect::<Vec<_>>();
+
+        if notes.is_empty() {
+            None
+        } else {
+            Some(notes.join("; "))
+        }
+    }
+
+    /// Get the pages of the series in external databases, separated by spaces as expected in a metadata file
+    /// Returns `None` if no identifier was provided
+    fn external_ids_urls(&self) -> Option<String> {
+        let mut urls = vec![];
+
+        if let Some(id) = &self.anilist_id {
+            urls.push(format!("https://anilist.co/manga/{}", id));
+        }
+
+        if let Some(id) = &self.mangadex_id {
+            urls.push(format!("https://mangadex.org/title/{}", id));
+        }
+
+        if let Some(id) = &self.comicvine_id {
+            // Volumes' identifiers are prefixed by the type of resource
+            let id = if id.contains('-') {
+                id.clone()
+            } else {
+                format!("4050-{}", id)
+            };
+
+            urls.push(format!("https://comicvine.gamespot.com/volume/{}/", id));
+        }
+
+        if urls.is_empty() {
+            None
+        } else {
+            Some(urls.join(" "))
+        }
+    }
+}
+
+impl ComicMetadata {
+    /// Serialize the metadata in the ComicBookInfo format, which is stored in the archive's comment
+    pub fn to_comic_book_info(&self) -> String {
+        let mut credits = vec![];
+
+        if let Some(writer) = &self.writer {
+            credits.push(json!({ "person": writer, "role": "Writer" }));
+        }
+
+        if let Some(translator) = &self.translator {
+            credits.push(json!({ "person": translator, "role": "Translator" }));
+        }
+
+        let mut info = Map::new();
+
+        // Only write the fields that are provided
+        let fields = [
+            ("series", json!(self.series)),
+            ("title", json!(self.title)),
+            ("issue", json!(self.number)),
+            ("publicationYear", json!(self.year)),
+            ("language", json!(self.language)),
+        ];
+
+        for (key, value) in fields.iter() {
+            if !value.is_null() {
+                info.insert((*key).to_owned(), value.clone());
+            }
+        }
+
+        info.insert("credits".to_owned(), json!(credits));
+
+        json!({
+            "appID": format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            "ComicBookInfo/1.0": info,
+        })
+        .to_string()
+    }
+
+    /// Serialize the metadata in the OPF format, which is read by Calibre when importing a volume
+    /// `fallback_title` is used if no title was provided, as it is required by the format
+    pub fn to_opf(&self, fallback_title: &str) -> String {
+        let mut fields = vec![format!(
+            "<dc:title>{}</dc:title>",
+            escape(self.title.as_deref().unwrap_or(fallback_title))
+        )];
+
+        if let Some(writer) = &self.writer {
+            fields.push(format!(
+                "<dc:creator opf:role=\"aut\">{}</dc:creator>",
+                escape(writer)
+            ));
+        }
+
+        if let Some(translator) = &self.translator {
+            fields.push(format!(
+                "<dc:contributor opf:role=\"trl\">{}</dc:contributor>",
+                escape(translator)
+            ));
+        }
+
+        if let Some(year) = self.year {
+            fields.push(format!("<dc:date>{:04}-01-01</dc:date>", year));
+        }
+
+        if let Some(language) = &self.language {
+            fields.push(format!("<dc:language>{}</dc:language>", escape(language)));
+        }
+
+        if let Some(series) = &self.series {
+            fields.push(format!(
+                "<meta name=\"calibre:series\" content=\"{}\"/>",
+                escape(series)
+            ));
+
+            if let Some(number) = &self.number {
+                fields.push(format!(
+                    "<meta name=\"calibre:series_index\" content=\"{}\"/>",
+                    escape(number)
+                ));
+            }
+        }
+
+        let fields = fields
+            .iter()
+            .map(|field| format!("    {}\n", field))
+            .collect::<String>();
+
+        format!("{}{}{}", OPF_HEADER, fields, OPF_FOOTER)
+    }
+}
+
+/// Metadata embedded in an existing volume
+#[derive(Debug, Default, Serialize)]
+pub struct EmbeddedMetadata {
+    /// Comment of the volume's archive
+    pub comment: Option<String>,
+
+    /// ComicBookInfo metadata, if the archive's comment contains some
+    pub comic_book_info: Option<Value>,
+
+    /// Content of the volume's metadata file
+    pub comic_info: Option<String>,
+}
+
+impl EmbeddedMetadata {
+    /// Get the metadata from an archive's comment and metadata file
+    /// Returns `None` if the volume doesn't contain any metadata
+    pub fn from_parts(comment: &[u8], comic_info: Option<String>) -> Option<Self> {
+        let comment = String::from_utf8_lossy(comment).trim().to_owned();
+
+        let comment = if comment.is_empty() {
+            None
+        } else {
+            Some(comment)
+        };
+
+        // Only keep the comment's content if it's actual ComicBookInfo metadata
+        let comic_book_info = comment
+            .as_deref()
+            .and_then(|comment| serde_json::from_str::<Value>(comment).ok())
+            .and_then(|value| value.get("ComicBookInfo/1.0").cloned());
+
+        if comment.is_none() && comic_info.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            comment,
+            comic_book_info,
+            comic_info,
+        })
+    }
+}
+
+/// Check if a path (relative to the root of a volume or a chapter) is a metadata file
+/// Only files located at the root are considered
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(is_comic_info(Path::new("comicinfo.xml")), true);
+/// assert_eq!(is_comic_info(Path::new("Chapter 1/ComicInfo.xml")), false);
+/// ```
+pub fn is_comic_info(path: &Path) -> bool {
+    path.parent()
+        .map(|parent| parent.as_os_str().is_empty())
+        .unwrap_or(true)
+        && path
+            .to_str()
+            .map(|name| name.eq_ignore_ascii_case(COMIC_INFO_FILE_NAME))
+            .unwrap_or(false)
+}
+
+/// Set the number of pages in a metadata file's content
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(
+///     update_page_count("<ComicInfo><PageCount>12</PageCount></ComicInfo>", 15),
+///     "<ComicInfo><PageCount>15</PageCount></ComicInfo>"
+/// );
+/// ```
+pub fn update_page_count(xml: &str, pages: usize) -> String {
+    set_field(xml, "PageCount", &pages.to_string())
+}
+
+/// Informations about a page of a volume, described in its metadata file
+#[derive(Debug, Clone, Copy)]
+pub struct PageInfo {
+    /// Dimensions of the page, if they could be read
+    pub dimensions: Option<(u32, u32)>,
+
+    /// Size of the page's file, in bytes
+    pub size: usize,
+
+    /// Is the page the cover of its chapter
+    pub cover: bool,
+}
+
+impl PageInfo {
+    /// Read the informations of a page from its content, without decoding the whole image
+    pub fn read(content: &[u8]) -> Self {
+        let dimensions = image::io::Reader::new(Cursor::new(content))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok());
+
+        Self {
+            dimensions,
+            size: content.len(),
+            cover: false,
+        }
+    }
+
+    /// Check if the page is a double page (spread), which is wider than high
+    pub fn is_double_page(&self) -> bool {
+        matches!(self.dimensions, Some((width, height)) if width > height)
+    }
+}
+
+/// Remove the description of the pages from a metadata file's content, e.g. once they don't match the volume's pages anymore
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(
+///     remove_pages("<ComicInfo>\n  <PageCount>2</PageCount>\n  <Pages><Page Image=\"0\" /></Pages>\n</ComicInfo>"),
+///     "<ComicInfo>\n  <PageCount>2</PageCount>\n</ComicInfo>"
+/// );
+/// ```
+pub fn remove_pages(xml: &str) -> String {
+    Regex::new(r"(?s)\n?[ \t]*(<Pages>.*?</Pages>|<Pages\s*/>)")
+        .unwrap()
+        .replace(xml, "")
+        .into_owned()
+}
+
+/// Set the summary in a metadata file's content
+pub fn set_summary(xml: &str, summary: &str) -> String {
+    set_field(xml, "Summary", &escape(summary))
+}
+
+/// Set the description of the pages in a metadata file's content
+/// The first page is marked as the front cover, the covers of the next chapters as inner covers, and pages wider than high as double pages
+/// The first page of each provided chapter is bookmarked with the chapter's title
+pub fn set_pages(xml: &str, pages: &[PageInfo], bookmarks: &[TocEntry]) -> String {
+    let mut entries = String::new();
+
+    for (i, page) in pages.iter().enumerate() {
+        entries.push_str(&format!("\n    <Page Image=\"{}\"", i));
+
+        if i == 0 {
+            entries.push_str(" Type=\"FrontCover\"");
+        } else if page.cover {
+            entries.push_str(" Type=\"InnerCover\"");
+        }
+
+        if let Some(bookmark) = bookmarks.iter().find(|entry| entry.first_page == i + 1) {
+            entries.push_str(&format!(" Bookmark=\"{}\"", escape(&bookmark.title)));
+        }
+
+        if page.is_double_page() {
+            entries.push_str(" DoublePage=\"true\"");
+        }
+
+        entries.push_str(&format!(" ImageSize=\"{}\"", page.size));
+
+        if let Some((width, height)) = page.dimensions {
+            entries.push_str(&format!(
+                " ImageWidth=\"{}\" ImageHeight=\"{}\"",
+                width, height
+            ));
+        }
+
+        entries.push_str(" />");
+    }
+
+    entries.push_str("\n  ");
+
+    set_field(xml, "Pages", &entries)
+}
+
+/// Get the (unescaped) value of a field in a metadata file's content
+/// Returns `None` if the field is missing or empty
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(
+///     get_field("<ComicInfo><Title>Tom &amp; Jerry</Title></ComicInfo>", "Title"),
+///     Some("Tom & Jerry".to_owned())
+/// );
+/// ```
+pub fn get_field(xml: &str, tag: &str) -> Option<String> {
+    let field = Regex::new(&format!(r"(?s)<{tag}>(.*?)</{tag}>", tag = tag)).unwrap();
+
+    field
+        .captures(xml)
+        .map(|captures| unescape(captures[1].trim()))
+        .filter(|value| !value.is_empty())
+}
+
+/// Set the value of a field in a metadata file's content
+/// The field is replaced if it already exists, or added at the end of the file otherwise
+/// `value` must already be escaped
+fn set_field(xml: &str, tag: &str, value: &str) -> String {
+    let field = Regex::new(&format!(r"(?s)<{tag}>.*?</{tag}>|<{tag}\s*/>", tag = tag)).unwrap();
+    let replacement = format!("<{tag}>{}</{tag}>", value, tag = tag);
+
+    if field.is_match(xml) {
+        return field
+            .replace(xml, regex::NoExpand(&replacement))
+            .into_owned();
+    }
+
+    // Add the field if the file doesn't contain it yet
+    match xml.rfind("</ComicInfo>") {
+        Some(end) => format!("{}  {}\n{}", &xml[..end], replacement, &xml[end..]),
+        None => xml.to_owned(),
+    }
+}
+
+/// Escape a value to put it in an XML document
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(escape("Tom & Jerry"), "Tom &amp; Jerry");
+/// ```
+pub fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Unescape a value taken from an XML document
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(unescape("Tom &amp; Jerry"), "Tom & Jerry");
+/// ```
+pub fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,186 @@
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::ignore_file::IgnoreFile;
+use crate::lib::pdf_images;
+use crate::lib::sort;
+use crate::lib::transform;
+use image::{imageops, DynamicImage, ImageError, Rgb, RgbImage};
+use std::cmp;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use zip::ZipArchive;
+
+/// Maximum width of each thumbnail in a contact sheet
+pub const THUMBNAIL_WIDTH: u32 = 160;
+
+/// Maximum height of each thumbnail in a contact sheet
+pub const THUMBNAIL_HEIGHT: u32 = 240;
+
+/// Extension of the contact sheets written next to the volumes
+pub const CONTACT_SHEET_EXT: &str = "contact.jpg";
+
+/// Space between thumbnails in a contact sheet
+const MARGIN: u32 = 8;
+
+/// Maximum number of thumbnails per row in a contact sheet
+const COLUMNS: u32 = 5;
+
+/// Render a contact sheet from the provided pages, laid out in rows
+pub fn render_contact_sheet(pages: &[DynamicImage]) -> RgbImage {
+    let columns = cmp::max(cmp::min(pages.len() as u32, COLUMNS), 1);
+    let rows = cmp::max(deter::ceil_div(pages.len() as u32, columns), 1);
+
+    let mut sheet = RgbImage::from_pixel(
+        MARGIN + columns * (THUMBNAIL_WIDTH + MARGIN),
+        MARGIN + rows * (THUMBNAIL_HEIGHT + MARGIN),
+        Rgb([255, 255, 255]),
+    );
+
+    for (i, page) in pages.iter().enumerate() {
+        let thumbnail = page.thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT).to_rgb8();
+
+        // Center the thumbnail in its cell
+        let x = MARGIN
+            + (i as u32 % columns) * (THUMBNAIL_WIDTH + MARGIN)
+            + (THUMBNAIL_WIDTH - thumbnail.width()) / 2;
+
+        let y = MARGIN
+            + (i as u32 / columns) * (THUMBNAIL_HEIGHT + MARGIN)
+            + (THUMBNAIL_HEIGHT - thumbnail.height()) / 2;
+
+        imageops::overlay(&mut sheet, &thumbnail, x.into(), y.into());
+    }
+
+    sheet
+}
+
+/// Read and decode the first page of a chapter (directory, archive or PDF)
+/// Returns `None` if the chapter doesn't contain any page
+pub fn read_first_page(
+    chapter_path: &Path,
+    accept_extended_image_formats: bool,
+    simple_sorting: bool,
+    roman_numerals: bool,
+    ignore_file: &IgnoreFile,
+) -> Result<Option<DynamicImage>, ContactSheetErr> {
+    let sort = |pics: &mut Vec<PathBuf>| {
+        if simple_sorting {
+            pics.sort();
+        } else {
+            sort::natural_sort_paths(pics, roman_numerals);
+        }
+    };
+
+    let bytes = if chapter_path.is_dir() {
+        let mut pics = deter::readdir_files_recursive(
+            chapter_path,
+            Some(&|path: &PathBuf| {
+                deter::has_image_ext(path, accept_extended_image_formats)
+                    && !ignore_file.is_ignored(path, false)
+            }),
+        )
+        .map_err(|err| match err {
+            deter::RecursiveFilesSearchErr::IOError(err) => {
+                ContactSheetErr::FailedToReadChapter(chapter_path.to_path_buf(), err)
+            }
+            deter::RecursiveFilesSearchErr::InvalidFileName(path) => {
+                ContactSheetErr::FailedToReadChapter(
+                    path,
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid file name"),
+                )
+            }
+        })?;
+
+        sort(&mut pics);
+
+        match pics.first() {
+            Some(path) => fs::read(path)
+                .map_err(|err| ContactSheetErr::FailedToReadChapter(path.clone(), err))?,
+            None => return Ok(None),
+        }
+    } else if chapter_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(ComicFormat::from_ext)
+        == Some(ComicFormat::Pdf)
+    {
+        match pdf_images::extract_jpeg_images(chapter_path, true) {
+            Ok(images) => match images.into_iter().next() {
+                Some(image) => image,
+                None => return Ok(None),
+            },
+            Err(_) => {
+                return Err(ContactSheetErr::FailedToReadChapter(
+                    chapter_path.to_path_buf(),
+                    io::Error::new(io::ErrorKind::InvalidData, "invalid PDF document"),
+                ))
+            }
+        }
+    } else {
+        let file = File::open(chapter_path)
+            .map_err(|err| ContactSheetErr::FailedToReadChapter(chapter_path.to_path_buf(), err))?;
+
+        let mut archive = ZipArchive::new(file).map_err(|err| {
+            ContactSheetErr::FailedToReadChapter(chapter_path.to_path_buf(), err.into())
+        })?;
+
+        let mut pics: Vec<PathBuf> = archive
+            .file_names()
+            .map(PathBuf::from)
+            .filter(|path| {
+                deter::has_image_ext(path, accept_extended_image_formats)
+                    && !ignore_file.is_entry_ignored(chapter_path, path)
+            })
+            .collect();
+
+        sort(&mut pics);
+
+        let first = match pics.first() {
+            Some(first) => first.to_string_lossy().to_string(),
+            None => return Ok(None),
+        };
+
+        let mut entry = archive.by_name(&first).map_err(|err| {
+            ContactSheetErr::FailedToReadChapter(chapter_path.to_path_buf(), err.into())
+        })?;
+
+        let mut bytes = vec![];
+
+        entry
+            .read_to_end(&mut bytes)
+            .map_err(|err| ContactSheetErr::FailedToReadChapter(chapter_path.to_path_buf(), err))?;
+
+        bytes
+    };
+
+    transform::load_picture(&bytes)
+        .map(Some)
+        .map_err(|err| ContactSheetErr::FailedToDecodePage(chapter_path.to_path_buf(), err))
+}
+
+/// Contact sheet rendering error
+pub enum ContactSheetErr {
+    FailedToReadChapter(PathBuf, io::Error),
+    FailedToDecodePage(PathBuf, ImageError),
+}
+
+impl fmt::Display for ContactSheetErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToReadChapter(path, err) => write!(
+                f,
+                "failed to read chapter at '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+
+            Self::FailedToDecodePage(path, err) => write!(
+                f,
+                "failed to decode first page of chapter at '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+        }
+    }
+}
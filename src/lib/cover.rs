@@ -0,0 +1,51 @@
+use std::path::Path;
+use std::str::FromStr;
+
+/// How the cover of a chapter is found among its pages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverDetection {
+    /// A page named as the cover (e.g. "cover.jpg" or "000.png") is moved first, before the other pages
+    Name,
+
+    /// The first page is the cover, following the pages' order
+    FirstPage,
+}
+
+impl FromStr for CoverDetection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "name" => Ok(Self::Name),
+            "first-page" => Ok(Self::FirstPage),
+            _ => Err(format!(
+                "Unknown cover detection '{}' (expected 'name' or 'first-page')",
+                s
+            )),
+        }
+    }
+}
+
+/// Check if a page is named as a cover: "cover" (e.g. "cover.jpg", "Cover.png") or only zeroes (e.g. "000.jpg")
+pub fn is_cover_name(path: &Path) -> bool {
+    let stem = match path.file_stem() {
+        Some(stem) => stem.to_string_lossy().to_lowercase(),
+        None => return false,
+    };
+
+    stem == "cover" || (!stem.is_empty() && stem.chars().all(|c| c == '0'))
+}
+
+/// Move the cover of a chapter before its other pages, which are otherwise kept in order
+/// `pages` must already be sorted
+/// Returns `true` if a page named as the cover was found
+pub fn move_cover_first<T>(pages: &mut [T], path: impl Fn(&T) -> &Path) -> bool {
+    match pages.iter().position(|page| is_cover_name(path(page))) {
+        Some(index) => {
+            pages[..=index].rotate_right(1);
+            true
+        }
+
+        None => false,
+    }
+}
@@ -1,10 +1,12 @@
 use std::cmp::{Ordering, PartialEq};
+use std::fmt;
 use std::fs;
-use std::io;
+use std::io::{self, Read};
 use std::iter::Peekable;
 use std::ops::{Add, Div, Rem};
 use std::path::{Path, PathBuf};
-use std::str::Chars;
+use std::str::{Chars, FromStr};
+use zip::CompressionMethod;
 
 /// Perform a ceiling division of the provided number by the divider
 ///
@@ -53,7 +55,7 @@ pub fn has_image_ext(path: impl AsRef<Path>, extended: bool) -> bool {
 
                 "tif" | "tiff" | "gif" | "eps" | "raw" | "cr2" | "nef" | "orf" | "sr2" | "ppm"
                 | "webp" | "pgm" | "pbm" | "pnm" | "ico" | "flif" | "pam" | "pcx" | "pgf"
-                | "sgi" | "sid" | "bgp" => extended,
+                | "sgi" | "sid" | "bgp" | "avif" | "jxl" => extended,
 
                 _ => false,
             },
@@ -61,7 +63,153 @@ pub fn has_image_ext(path: impl AsRef<Path>, extended: bool) -> bool {
     }
 }
 
-/// Check if a comic format is supported for decoding
+/// Check if a path has the extension of a modern image format most readers cannot display yet (AVIF or JPEG XL)
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(has_modern_image_ext(Path::new("file.AVIF")), true);
+/// assert_eq!(has_modern_image_ext(Path::new("file.png")), false);
+/// ```
+pub fn has_modern_image_ext(path: impl AsRef<Path>) -> bool {
+    match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => matches!(ext.to_lowercase().as_str(), "avif" | "jxl"),
+        None => false,
+    }
+}
+
+/// Comic book format, determined either from a file's extension or from its content
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComicFormat {
+    Zip,
+    Pdf,
+    Rar,
+    SevenZip,
+    Tar,
+}
+
+impl ComicFormat {
+    /// Get the format matching a file extension
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert_eq!(ComicFormat::from_ext("CBZ"), Some(ComicFormat::Zip));
+    /// assert_eq!(ComicFormat::from_ext("cbr"), Some(ComicFormat::Rar));
+    /// assert_eq!(ComicFormat::from_ext("mp3"), None);
+    /// ```
+    pub fn from_ext(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            // Common archive formats, with either their usual or comic-related extension
+            "zip" | "cbz" => Some(Self::Zip),
+            "rar" | "cbr" => Some(Self::Rar),
+            "7z" | "cb7" => Some(Self::SevenZip),
+            "tar" | "cbt" => Some(Self::Tar),
+
+            // Non-archive formats
+            "pdf" => Some(Self::Pdf),
+
+            // Every other format is unknown
+            _ => None,
+        }
+    }
+
+    /// Check if this format is supported for decoding
+    pub fn is_supported_for_decoding(self) -> bool {
+        match self {
+            Self::Zip | Self::Pdf | Self::SevenZip => true,
+            Self::Rar | Self::Tar => false,
+        }
+    }
+
+    /// Check if this format is supported for chapters and conversion inputs, whose pages are read without being extracted first
+    pub fn is_supported_for_chapters(self) -> bool {
+        match self {
+            Self::Zip | Self::Pdf | Self::SevenZip | Self::Tar => true,
+            Self::Rar => false,
+        }
+    }
+
+    /// Check if this format is supported for encoding
+    pub fn is_supported_for_encoding(self) -> bool {
+        match self {
+            Self::Zip | Self::SevenZip | Self::Tar => true,
+            Self::Pdf | Self::Rar => false,
+        }
+    }
+
+    /// Get the comic-related extension of this format
+    pub fn comic_ext(self) -> &'static str {
+        match self {
+            Self::Zip => "cbz",
+            Self::Pdf => "pdf",
+            Self::Rar => "cbr",
+            Self::SevenZip => "cb7",
+            Self::Tar => "cbt",
+        }
+    }
+}
+
+impl fmt::Display for ComicFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Zip => "ZIP / CBZ",
+                Self::Pdf => "PDF",
+                Self::Rar => "RAR / CBR",
+                Self::SevenZip => "7-Zip / CB7",
+                Self::Tar => "TAR / CBT",
+            }
+        )
+    }
+}
+
+impl FromStr for ComicFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_ext(s).ok_or_else(|| format!("Unknown comic format '{}'", s))
+    }
+}
+
+/// Compression method of the entries in a ZIP archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZipCompression {
+    Stored,
+    Deflated,
+    Bzip2,
+    Zstd,
+}
+
+impl ZipCompression {
+    /// Get the matching compression method of the ZIP library
+    pub fn method(self) -> CompressionMethod {
+        match self {
+            Self::Stored => CompressionMethod::Stored,
+            Self::Deflated => CompressionMethod::Deflated,
+            Self::Bzip2 => CompressionMethod::Bzip2,
+            Self::Zstd => CompressionMethod::Zstd,
+        }
+    }
+}
+
+impl FromStr for ZipCompression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "stored" | "none" => Ok(Self::Stored),
+            "deflated" | "deflate" => Ok(Self::Deflated),
+            "bzip2" => Ok(Self::Bzip2),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!("Unknown compression method '{}'", s)),
+        }
+    }
+}
+
+/// Check if a comic format is supported for decoding, based on its file extension
 ///
 /// # Examples
 ///
@@ -71,30 +219,62 @@ pub fn has_image_ext(path: impl AsRef<Path>, extended: bool) -> bool {
 /// assert_eq!(is_supported_for_decoding("mp3"), false);
 /// ```
 pub fn is_supported_for_decoding(ext: &str) -> bool {
-    match ext.to_lowercase().as_str() {
-        // Common archive formats
-        "zip" => true,
+    ComicFormat::from_ext(ext)
+        .map(ComicFormat::is_supported_for_decoding)
+        .unwrap_or(false)
+}
 
-        // Common archive formats with comic-related extension
-        "cbz" => true,
+/// Detect the format of a comic file from its first bytes (magic numbers)
+/// Returns `None` if the content does not match any known format
+pub fn sniff_format(path: impl AsRef<Path>) -> io::Result<Option<ComicFormat>> {
+    // TAR archives are only recognized after their first header's name and attributes
+    let mut header = [0; 263];
+    let mut file = fs::File::open(path)?;
 
-        // Non-archive formats
-        "pdf" => true,
+    // Read as many bytes as possible, as the file may be shorter than the header
+    let mut read = 0;
 
-        // Every other format is not supported
-        _ => false,
+    while read < header.len() {
+        match file.read(&mut header[read..])? {
+            0 => break,
+            bytes => read += bytes,
+        }
     }
+
+    let header = &header[..read];
+
+    let format = if header.starts_with(b"PK\x03\x04")
+        || header.starts_with(b"PK\x05\x06")
+        || header.starts_with(b"PK\x07\x08")
+    {
+        Some(ComicFormat::Zip)
+    } else if header.starts_with(b"%PDF-") {
+        Some(ComicFormat::Pdf)
+    } else if header.starts_with(b"Rar!\x1A\x07") {
+        Some(ComicFormat::Rar)
+    } else if header.starts_with(b"7z\xBC\xAF\x27\x1C") {
+        Some(ComicFormat::SevenZip)
+    } else if header.get(257..262) == Some(&b"ustar"[..]) {
+        Some(ComicFormat::Tar)
+    } else {
+        None
+    };
+
+    Ok(format)
 }
 
 /// Get the largest possible number from the first characters of the provided characters iterator
 /// The iterator *will* advance up to the first non-digit character
 /// Only integers are supported, but there is no size limit
-/// A vector containing the digits of the number are returned, the first digit in the string being the first digit in the returned vector
-fn take_num(chars: &mut Peekable<Chars>) -> Vec<u8> {
+/// Commas followed by exactly three digits are considered as thousands separators (e.g. "1,000" is read as "1000")
+/// Returns a vector containing the digits of the number without its leading zeros, the first digit in the string being
+/// the first digit in the returned vector, as well as the number of characters the number took in the string
+fn take_num(chars: &mut Peekable<Chars>) -> (Vec<u8>, usize) {
     let mut digits = vec![];
+    let mut len = 0;
 
     loop {
-        match chars.peek() {
+        match chars.peek().copied() {
             Some(c) if c.is_ascii_digit() => {
                 let code = u32::from(chars.next().unwrap());
                 assert!(code >= 0x30 && code <= 0x39);
@@ -104,18 +284,39 @@ fn take_num(chars: &mut Peekable<Chars>) -> Vec<u8> {
                 if num != 0 || !digits.is_empty() {
                     digits.push(code as u8 - 0x30);
                 }
+
+                len += 1;
+            }
+
+            Some(',') if is_thousands_separator(chars) => {
+                chars.next().unwrap();
+                len += 1;
             }
 
             _ => break,
         }
     }
 
-    digits
+    (digits, len)
+}
+
+/// Check if the comma the provided characters iterator is on is a thousands separator,
+/// which means it is followed by exactly three digits
+fn is_thousands_separator(chars: &Peekable<Chars>) -> bool {
+    let mut next = chars.clone().skip(1);
+
+    next.by_ref().take(3).filter(|c| c.is_ascii_digit()).count() == 3
+        && !matches!(next.next(), Some(c) if c.is_ascii_digit())
 }
 
 /// Compare two strings using natural order, which is equivalent to traditional UTF-8 sorting \
 /// but compares whole numbers instead of single digits
 ///
+/// Numbers are compared by value, so leading zeros and thousands separators are ignored (e.g. "007" equals "7"
+/// and "1,000" equals "1000"), and the comparison goes on with the rest of the strings.
+/// If the strings are equal except for the way their numbers are written, the first number written
+/// with fewer characters comes first (e.g. "7" before "007"), so the order doesn't depend on the input's order.
+///
 /// # Examples
 ///
 /// ```
@@ -130,19 +331,29 @@ fn take_num(chars: &mut Peekable<Chars>) -> Vec<u8> {
 /// println!("{:?}", directories); // ["Folder 1", "Folder 20", "Folder 100"]
 /// ```
 ///
+/// Two different strings never compare as equal: if natural order considers them equal (e.g. "Folder" and "folder"),
+/// they are compared byte by byte, so the resulting order doesn't depend on the platform or the input's order
+///
 pub fn natural_cmp(left: &str, right: &str) -> Ordering {
-    let left = left.to_lowercase();
-    let right = right.to_lowercase();
+    natural_cmp_lowercase(&left.to_lowercase(), &right.to_lowercase()).then_with(|| left.cmp(right))
+}
 
+/// Compare two already lowercased strings using natural order, without allocating
+/// Unlike "natural_cmp", different strings may compare as equal, the caller being responsible for breaking ties
+/// See the "natural_cmp" function for more informations
+pub fn natural_cmp_lowercase(left: &str, right: &str) -> Ordering {
     let mut left = left.chars().peekable();
     let mut right = right.chars().peekable();
 
+    // Ordering to use if the strings are equal except for the way their numbers are written
+    let mut tie = Ordering::Equal;
+
     loop {
         return match (left.peek().copied(), right.peek().copied()) {
             (Some(lc), Some(rc)) => {
                 if lc.is_ascii_digit() && rc.is_ascii_digit() {
-                    let lnum = take_num(&mut left);
-                    let rnum = take_num(&mut right);
+                    let (lnum, llen) = take_num(&mut left);
+                    let (rnum, rlen) = take_num(&mut right);
 
                     let cmp = lnum.len().cmp(&rnum.len());
 
@@ -158,7 +369,11 @@ pub fn natural_cmp(left: &str, right: &str) -> Ordering {
                         }
                     }
 
-                    Ordering::Equal
+                    if tie == Ordering::Equal {
+                        tie = llen.cmp(&rlen);
+                    }
+
+                    continue;
                 } else {
                     left.next().unwrap();
                     right.next().unwrap();
@@ -171,7 +386,7 @@ pub fn natural_cmp(left: &str, right: &str) -> Ordering {
             }
             (Some(_), None) => Ordering::Greater,
             (None, Some(_)) => Ordering::Less,
-            (None, None) => Ordering::Equal,
+            (None, None) => tie,
         };
     }
 }
@@ -202,24 +417,41 @@ pub fn readdir_files_recursive<F: Fn(&PathBuf) -> bool>(
     Ok(files)
 }
 
+/// Add an extension to a path, without replacing its existing one if any
+/// This is useful for file names containing dots, which `Path::with_extension` would truncate
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(add_extension("Vol. 1", "cbz"), PathBuf::from("Vol. 1.cbz"));
+/// ```
+pub fn add_extension(path: impl AsRef<Path>, ext: &str) -> PathBuf {
+    let mut path = path.as_ref().as_os_str().to_os_string();
+    path.push(".");
+    path.push(ext);
+    PathBuf::from(path)
+}
+
 /// Compare two paths using natural order
 /// See the "natural_cmp" function for more informations
+/// Like "natural_cmp", two different paths never compare as equal: ties are broken by comparing the whole paths byte by byte
+/// To sort many paths, prefer the functions of the "sort" module which don't lowercase the paths on each comparison
 pub fn natural_paths_cmp(a: &PathBuf, b: &PathBuf) -> Ordering {
-    let mut a = a.components();
-    let mut b = b.components();
+    let mut a_components = a.components();
+    let mut b_components = b.components();
 
     loop {
-        return match (a.next(), b.next()) {
-            (Some(a_cp), Some(b_cp)) => match natural_cmp(
-                &a_cp.as_os_str().to_string_lossy(),
-                &b_cp.as_os_str().to_string_lossy(),
+        return match (a_components.next(), b_components.next()) {
+            (Some(a_cp), Some(b_cp)) => match natural_cmp_lowercase(
+                &a_cp.as_os_str().to_string_lossy().to_lowercase(),
+                &b_cp.as_os_str().to_string_lossy().to_lowercase(),
             ) {
                 Ordering::Equal => continue,
                 ordering => ordering,
             },
             (Some(_), None) => Ordering::Greater,
             (None, Some(_)) => Ordering::Less,
-            (None, None) => Ordering::Equal,
+            (None, None) => a.as_os_str().cmp(b.as_os_str()),
         };
     }
 }
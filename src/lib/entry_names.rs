@@ -0,0 +1,73 @@
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+use zip::read::ZipFile;
+
+/// Legacy encoding of the entry names in ZIP archives not flagged as UTF-8
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryEncoding {
+    Cp437,
+    ShiftJis,
+}
+
+impl FromStr for EntryEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "cp437" | "ibm437" => Ok(Self::Cp437),
+            "shift-jis" | "sjis" | "cp932" => Ok(Self::ShiftJis),
+            _ => Err(format!("Unknown entry names encoding '{}'", s)),
+        }
+    }
+}
+
+/// Decode the name of a ZIP entry
+/// Names flagged as UTF-8 are kept as they are. Other names are decoded with the forced encoding if one is provided,
+///  else names which happen to be valid UTF-8 are decoded as such (some tools don't set the flag) and the other ones
+///  with the provided fallback encoding
+pub fn decode_entry_name(
+    file: &ZipFile,
+    forced: Option<EntryEncoding>,
+    fallback: EntryEncoding,
+) -> String {
+    let raw = file.name_raw();
+
+    // The library decodes the names not flagged as UTF-8 as CP437, so a name flagged as UTF-8 (or a plain ASCII one)
+    //  is one whose decoded form matches its raw bytes
+    if file.name().as_bytes() == raw {
+        return file.name().to_owned();
+    }
+
+    let encoding = match forced {
+        Some(encoding) => encoding,
+        None => match std::str::from_utf8(raw) {
+            Ok(name) => return name.to_owned(),
+            Err(_) => fallback,
+        },
+    };
+
+    match encoding {
+        EntryEncoding::Cp437 => file.name().to_owned(),
+        EntryEncoding::ShiftJis => encoding_rs::SHIFT_JIS
+            .decode_without_bom_handling(raw)
+            .0
+            .into_owned(),
+    }
+}
+
+/// Turn a decoded entry name into a relative path which can't escape the output directory,
+///  like the library's `mangled_name()` does
+pub fn sanitize_entry_name(name: &str) -> PathBuf {
+    let name = match name.find('\0') {
+        Some(index) => &name[..index],
+        None => name,
+    };
+
+    Path::new(name)
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .fold(PathBuf::new(), |mut path, component| {
+            path.push(component.as_os_str());
+            path
+        })
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Name of the directory containing the configuration and the history, inside the platform's configuration directory
+pub const CONFIG_DIR_NAME: &str = "comic-enc";
+
+/// Name of the file the history of recent runs is stored in
+pub const HISTORY_FILE_NAME: &str = "history.json";
+
+/// Number of runs kept in the history, older ones being removed
+pub const MAX_HISTORY_ENTRIES: usize = 50;
+
+/// History of the recent runs, the most recent one being the last
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunHistory {
+    pub runs: Vec<HistoryEntry>,
+}
+
+/// A run recorded in the history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Time the run started at, in seconds since the UNIX epoch
+    pub started_at: u64,
+
+    /// Command-line arguments of the run, without the program's name
+    pub args: Vec<String>,
+
+    /// Working directory of the run, which relative paths in the arguments are relative to
+    pub cwd: PathBuf,
+
+    /// Files or directories produced by the run
+    pub outputs: Vec<PathBuf>,
+
+    /// Total duration of the run, in milliseconds
+    pub duration_ms: u64,
+
+    /// Error message if the run failed
+    pub error: Option<String>,
+}
+
+impl RunHistory {
+    /// Get the path to the history file, in the platform's configuration directory
+    /// Returns `None` if this directory cannot be determined
+    pub fn path() -> Option<PathBuf> {
+        let config_dir = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+
+        Some(config_dir.join(CONFIG_DIR_NAME).join(HISTORY_FILE_NAME))
+    }
+
+    /// Load the history
+    /// Returns an empty history if no run was recorded yet
+    pub fn load() -> Result<Self, HistoryErr> {
+        let path = Self::path().ok_or(HistoryErr::ConfigDirNotFound)?;
+
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(HistoryErr::IOError)?;
+
+        serde_json::from_str(&content).map_err(|err| HistoryErr::InvalidContent(err.to_string()))
+    }
+
+    /// Record a run in the history, removing the oldest ones if there are too many
+    pub fn record(entry: HistoryEntry) -> Result<(), HistoryErr> {
+        let path = Self::path().ok_or(HistoryErr::ConfigDirNotFound)?;
+
+        let mut history = Self::load()?;
+        history.runs.push(entry);
+
+        if history.runs.len() > MAX_HISTORY_ENTRIES {
+            let excess = history.runs.len() - MAX_HISTORY_ENTRIES;
+            history.runs.drain(..excess);
+        }
+
+        let json = serde_json::to_string_pretty(&history)
+            .map_err(|err| HistoryErr::InvalidContent(err.to_string()))?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(HistoryErr::IOError)?;
+        }
+
+        fs::write(&path, json).map_err(HistoryErr::IOError)
+    }
+
+    /// Get a run by its position, starting at 1 for the most recent one
+    pub fn get(&self, nth: usize) -> Option<&HistoryEntry> {
+        if nth == 0 {
+            return None;
+        }
+
+        self.runs.iter().rev().nth(nth - 1)
+    }
+}
+
+/// History loading or saving error
+pub enum HistoryErr {
+    ConfigDirNotFound,
+    IOError(io::Error),
+    InvalidContent(String),
+}
+
+impl fmt::Display for HistoryErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ConfigDirNotFound => write!(f, "configuration directory could not be determined"),
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidContent(err) => write!(f, "invalid history file: {}", err),
+        }
+    }
+}
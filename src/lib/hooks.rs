@@ -0,0 +1,143 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Placeholder replaced by the path in hook commands
+pub const PATH_PLACEHOLDER: &str = "{path}";
+
+/// Name of the environment variable providing the path of the page in the volume to page filter commands
+pub const PAGE_ENV_VAR: &str = "COMIC_ENC_PAGE";
+
+/// Run a hook command through the platform's shell, replacing the placeholder with the provided path
+/// The path is quoted, so the placeholder must not be put between quotes in the command
+pub fn run_hook(command: &str, path: &Path) -> Result<(), HookErr> {
+    let command = command.replace(PATH_PLACEHOLDER, &quote(&path.to_string_lossy()));
+
+    debug!("Running hook: {}", command);
+
+    let status = shell(&command).status().map_err(HookErr::FailedToStart)?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(HookErr::Failed(status.code()))
+    }
+}
+
+/// Run a page filter command through the platform's shell, providing it the content of a page on its standard input
+/// The page is replaced by what the command writes to its standard output
+pub fn run_page_filter(
+    command: &str,
+    content: &[u8],
+    page: &str,
+) -> Result<Vec<u8>, PageFilterErr> {
+    trace!("Running page filter for '{}': {}", page, command);
+
+    let mut child = shell(command)
+        .env(PAGE_ENV_VAR, page)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(PageFilterErr::FailedToStart)?;
+
+    // The page is written from another thread, as the command may fill its output before reading all of it
+    let mut stdin = child.stdin.take().unwrap();
+    let input = content.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+
+    let output = child.wait_with_output().map_err(PageFilterErr::IO)?;
+
+    let written = writer.join().unwrap_or_else(|_| {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "writer thread panicked",
+        ))
+    });
+
+    // A command which failed may not have read its input, so its own error is the relevant one
+    if !output.status.success() {
+        return Err(PageFilterErr::Failed(output.status.code()));
+    }
+
+    // Commands are free not to read the page (e.g. to replace it with another picture)
+    if let Err(err) = written {
+        if err.kind() != io::ErrorKind::BrokenPipe {
+            return Err(PageFilterErr::IO(err));
+        }
+    }
+
+    if output.stdout.is_empty() {
+        return Err(PageFilterErr::NoOutput);
+    }
+
+    Ok(output.stdout)
+}
+
+/// Prepare a command line to be run through the platform's shell
+pub fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+
+    shell.arg(command);
+    shell
+}
+
+/// Quote a path for the platform's shell
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(quote("It's.cbz"), "'It'\''s.cbz'"); // On Unix-like platforms
+/// ```
+pub fn quote(path: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", path)
+    } else {
+        format!("'{}'", path.replace('\'', "'\\''"))
+    }
+}
+
+/// Hook running error
+pub enum HookErr {
+    FailedToStart(io::Error),
+    Failed(Option<i32>),
+}
+
+impl fmt::Display for HookErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToStart(err) => write!(f, "failed to start the shell: {}", err),
+            Self::Failed(Some(code)) => write!(f, "command exited with code {}", code),
+            Self::Failed(None) => write!(f, "command was terminated by a signal"),
+        }
+    }
+}
+
+/// Page filter error
+pub enum PageFilterErr {
+    FailedToStart(io::Error),
+    IO(io::Error),
+    Failed(Option<i32>),
+    NoOutput,
+}
+
+impl fmt::Display for PageFilterErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToStart(err) => write!(f, "failed to start the shell: {}", err),
+            Self::IO(err) => write!(f, "failed to communicate with the command: {}", err),
+            Self::Failed(Some(code)) => write!(f, "command exited with code {}", code),
+            Self::Failed(None) => write!(f, "command was terminated by a signal"),
+            Self::NoOutput => write!(f, "command didn't write anything to its standard output"),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+
+/// Name of the files containing patterns of items to ignore
+pub const IGNORE_FILE_NAME: &str = ".ceignore";
+
+/// Gitignore-style patterns excluding chapters, directories or pages from scanning
+#[derive(Default)]
+pub struct IgnoreFile {
+    /// Directory the patterns are relative to
+    root: PathBuf,
+
+    /// Patterns matcher (`None` if there is no ignore file)
+    matcher: Option<Gitignore>,
+}
+
+impl IgnoreFile {
+    /// Load the ignore file located at the root of the provided directory, if any
+    pub fn load(root: &Path) -> Result<Self, IgnoreFileErr> {
+        let path = root.join(IGNORE_FILE_NAME);
+
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let mut builder = GitignoreBuilder::new(root);
+
+        if let Some(err) = builder.add(&path) {
+            return Err(IgnoreFileErr::InvalidPatterns(path, err.to_string()));
+        }
+
+        let matcher = builder
+            .build()
+            .map_err(|err| IgnoreFileErr::InvalidPatterns(path, err.to_string()))?;
+
+        Ok(Self {
+            root: root.to_path_buf(),
+            matcher: Some(matcher),
+        })
+    }
+
+    /// Get the number of patterns in the ignore file
+    pub fn len(&self) -> usize {
+        self.matcher
+            .as_ref()
+            .map(|matcher| matcher.num_ignores() as usize)
+            .unwrap_or(0)
+    }
+
+    /// Check if the ignore file contains no pattern
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check if an item is ignored, either directly or because one of its parent directories is
+    /// Items outside of the ignore file's root directory are never ignored
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        match &self.matcher {
+            Some(matcher) if path.starts_with(&self.root) => matcher
+                .matched_path_or_any_parents(path, is_dir)
+                .is_ignore(),
+
+            _ => false,
+        }
+    }
+
+    /// Check if an entry of a chapter's archive is ignored, the archive being matched as if it was a directory
+    pub fn is_entry_ignored(&self, archive_path: &Path, entry_path: &Path) -> bool {
+        self.is_ignored(&archive_path.join(entry_path), false)
+    }
+}
+
+/// Ignore file loading error
+pub enum IgnoreFileErr {
+    InvalidPatterns(PathBuf, String),
+}
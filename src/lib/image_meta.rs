@@ -0,0 +1,287 @@
+/// Signature of PNG files
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// Identifier at the beginning of JPEG segments containing EXIF metadata
+const EXIF_ID: &[u8] = b"Exif\0\0";
+
+/// Types of the PNG chunks containing metadata
+const PNG_METADATA_CHUNKS: [&[u8]; 5] = [b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// Remove the metadata (EXIF, XMP, IPTC, comments and embedded thumbnails) of a JPEG or PNG picture
+/// Color profiles are kept, as well as EXIF metadata providing an orientation other than the default one, as readers rely on it
+/// Returns `None` if the picture is in another format, is malformed, or doesn't contain any metadata to remove
+pub fn strip_metadata(content: &[u8]) -> Option<Vec<u8>> {
+    if content.starts_with(b"\xFF\xD8") {
+        strip_jpeg_metadata(content)
+    } else if content.starts_with(PNG_SIGNATURE) {
+        strip_png_metadata(content)
+    } else {
+        None
+    }
+}
+
+/// Remove the metadata segments of a JPEG picture
+fn strip_jpeg_metadata(content: &[u8]) -> Option<Vec<u8>> {
+    let mut stripped = Vec::with_capacity(content.len());
+    stripped.extend_from_slice(&content[..2]);
+
+    // Position after the start of image marker
+    let mut pos = 2;
+
+    loop {
+        if *content.get(pos)? != 0xFF {
+            return None;
+        }
+
+        let marker = *content.get(pos + 1)?;
+
+        // Fill bytes and standalone markers don't have a length
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            stripped.extend_from_slice(&content[pos..pos + 2]);
+            pos += 2;
+            continue;
+        }
+
+        // The compressed data comes after the start of scan segment, and is kept as it is
+        if marker == 0xDA {
+            stripped.extend_from_slice(&content[pos..]);
+            break;
+        }
+
+        let len = usize::from(u16::from_be_bytes([
+            *content.get(pos + 2)?,
+            *content.get(pos + 3)?,
+        ]));
+        let segment = content.get(pos..pos + 2 + len)?;
+        let data = segment.get(4..)?;
+
+        let is_metadata = match marker {
+            // EXIF (which contains the thumbnail) and XMP segments
+            0xE1 => {
+                !(data.starts_with(EXIF_ID)
+                    && exif_orientation(&data[EXIF_ID.len()..]).map_or(false, |o| o != 1))
+            }
+
+            // IPTC segments and comments
+            0xED | 0xFE => true,
+
+            _ => false,
+        };
+
+        if !is_metadata {
+            stripped.extend_from_slice(segment);
+        }
+
+        pos += 2 + len;
+    }
+
+    if stripped.len() == content.len() {
+        None
+    } else {
+        Some(stripped)
+    }
+}
+
+/// Get the EXIF orientation of a JPEG or PNG picture, from 1 (the default one) to 8
+/// Returns `None` if the picture doesn't provide one, or if it's in another format
+pub fn orientation(content: &[u8]) -> Option<u16> {
+    if content.starts_with(b"\xFF\xD8") {
+        jpeg_orientation(content)
+    } else if content.starts_with(PNG_SIGNATURE) {
+        png_orientation(content)
+    } else {
+        None
+    }
+}
+
+/// Get the orientation provided by the EXIF segment of a JPEG picture
+fn jpeg_orientation(content: &[u8]) -> Option<u16> {
+    // Position after the start of image marker
+    let mut pos = 2;
+
+    loop {
+        if *content.get(pos)? != 0xFF {
+            return None;
+        }
+
+        let marker = *content.get(pos + 1)?;
+
+        // Fill bytes and standalone markers don't have a length
+        if marker == 0xFF {
+            pos += 1;
+            continue;
+        }
+
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            pos += 2;
+            continue;
+        }
+
+        // Metadata segments all come before the start of scan segment
+        if marker == 0xDA {
+            return None;
+        }
+
+        let len = usize::from(u16::from_be_bytes([
+            *content.get(pos + 2)?,
+            *content.get(pos + 3)?,
+        ]));
+        let data = content.get(pos + 4..pos + 2 + len)?;
+
+        if marker == 0xE1 && data.starts_with(EXIF_ID) {
+            return exif_orientation(&data[EXIF_ID.len()..]);
+        }
+
+        pos += 2 + len;
+    }
+}
+
+/// Get the orientation provided by the EXIF chunk of a PNG picture
+fn png_orientation(content: &[u8]) -> Option<u16> {
+    let mut pos = PNG_SIGNATURE.len();
+
+    while pos < content.len() {
+        let len = u32::from_be_bytes([
+            *content.get(pos)?,
+            *content.get(pos + 1)?,
+            *content.get(pos + 2)?,
+            *content.get(pos + 3)?,
+        ]) as usize;
+
+        let chunk = content.get(pos..pos + 12 + len)?;
+
+        if &chunk[4..8] == b"eXIf" {
+            return exif_orientation(&chunk[8..8 + len]);
+        }
+
+        pos += 12 + len;
+    }
+
+    None
+}
+
+/// Get the orientation provided by EXIF metadata (in the TIFF format), if any
+fn exif_orientation(tiff: &[u8]) -> Option<u16> {
+    let big_endian = match tiff.get(..2)? {
+        b"MM" => true,
+        b"II" => false,
+        _ => return None,
+    };
+
+    let read_u16 = |pos: usize| {
+        tiff.get(pos..pos + 2).map(|bytes| {
+            let bytes = [bytes[0], bytes[1]];
+            if big_endian {
+                u16::from_be_bytes(bytes)
+            } else {
+                u16::from_le_bytes(bytes)
+            }
+        })
+    };
+
+    let read_u32 = |pos: usize| {
+        tiff.get(pos..pos + 4).map(|bytes| {
+            let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+            if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            }
+        })
+    };
+
+    // Look for the orientation tag in the first directory
+    let ifd = read_u32(4)? as usize;
+    let entries = read_u16(ifd).unwrap_or(0) as usize;
+
+    (0..entries)
+        .map(|i| ifd + 2 + i * 12)
+        .find(|entry| read_u16(*entry) == Some(0x0112))
+        .and_then(|entry| read_u16(entry + 8))
+}
+
+/// Get the description of an ICC color profile (e.g. 'sRGB IEC61966-2.1'), from its first language for ICC 4 profiles
+pub fn icc_description(profile: &[u8]) -> Option<String> {
+    let read_u32 = |bytes: &[u8], pos: usize| {
+        bytes
+            .get(pos..pos + 4)
+            .map(|bytes| u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+    };
+
+    // Tags are listed after the 128-byte header
+    let tags = read_u32(profile, 128)?;
+
+    let tag = (0..tags)
+        .map(|i| 132 + i * 12)
+        .find(|tag| profile.get(*tag..*tag + 4) == Some(&b"desc"[..]))?;
+
+    let offset = read_u32(profile, tag + 4)?;
+    let data = profile.get(offset..offset + read_u32(profile, tag + 8)?)?;
+
+    match data.get(..4)? {
+        // ICC 2 profiles provide an ASCII description, with a terminating NUL character
+        b"desc" => {
+            let len = read_u32(data, 8)?;
+            let text = data.get(12..12 + len)?;
+
+            Some(
+                String::from_utf8_lossy(text)
+                    .trim_end_matches('\0')
+                    .to_owned(),
+            )
+        }
+
+        // ICC 4 profiles provide UTF-16 descriptions in several languages, described by 12-byte records
+        b"mluc" => {
+            let len = read_u32(data, 20)?;
+            let offset = read_u32(data, 24)?;
+            let text = data.get(offset..offset + len)?;
+
+            let units = text
+                .chunks_exact(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                .collect::<Vec<_>>();
+
+            Some(String::from_utf16_lossy(&units))
+        }
+
+        _ => None,
+    }
+}
+
+/// Remove the metadata chunks of a PNG picture
+fn strip_png_metadata(content: &[u8]) -> Option<Vec<u8>> {
+    let mut stripped = Vec::with_capacity(content.len());
+    stripped.extend_from_slice(PNG_SIGNATURE);
+
+    let mut pos = PNG_SIGNATURE.len();
+
+    while pos < content.len() {
+        let len = u32::from_be_bytes([
+            *content.get(pos)?,
+            *content.get(pos + 1)?,
+            *content.get(pos + 2)?,
+            *content.get(pos + 3)?,
+        ]) as usize;
+
+        // Chunks are made of their length, type, data and checksum
+        let chunk = content.get(pos..pos + 12 + len)?;
+
+        if !PNG_METADATA_CHUNKS.contains(&&chunk[4..8]) {
+            stripped.extend_from_slice(chunk);
+        }
+
+        pos += 12 + len;
+    }
+
+    if stripped.len() == content.len() {
+        None
+    } else {
+        Some(stripped)
+    }
+}
@@ -0,0 +1,46 @@
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Name of the summary of the produced volumes written to the output directory
+pub const INDEX_FILE_NAME: &str = "index.json";
+
+/// Summary of the volumes produced by a compilation, for use by other programs
+#[derive(Debug, Serialize)]
+pub struct Index {
+    pub volumes: Vec<IndexVolume>,
+}
+
+/// A volume listed in the summary of a compilation
+#[derive(Debug, Serialize)]
+pub struct IndexVolume {
+    /// Number of the volume
+    pub volume: usize,
+
+    /// Path to the volume's file
+    pub path: PathBuf,
+
+    /// Number of the volume's first chapter, starting at 1
+    pub first_chapter: usize,
+
+    /// Number of the volume's last chapter
+    pub last_chapter: usize,
+
+    /// Names of the volume's chapters, in order
+    pub chapters: Vec<String>,
+
+    /// Number of pages in the volume, unknown if it has been skipped
+    pub pages: Option<usize>,
+
+    /// Size of the volume's file, in bytes
+    pub size: Option<u64>,
+
+    /// Indicates the volume already existed and was not built again
+    pub skipped: bool,
+}
+
+impl Index {
+    /// Serialize the summary as JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_else(|_| "{}".to_owned())
+    }
+}
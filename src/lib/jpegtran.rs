@@ -0,0 +1,97 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Name of the program used to optimize JPEG pictures, provided by libjpeg-turbo and mozjpeg
+pub const JPEGTRAN_PROGRAM: &str = "jpegtran";
+
+/// Check if the content of a picture is a JPEG one
+pub fn is_jpeg(content: &[u8]) -> bool {
+    content.starts_with(&[0xFF, 0xD8, 0xFF])
+}
+
+/// Check if the program used to optimize JPEG pictures is available
+pub fn is_available() -> bool {
+    Command::new(JPEGTRAN_PROGRAM)
+        .arg("-version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok()
+}
+
+/// Rewrite a JPEG picture losslessly with optimized Huffman tables, and optionally as a progressive one
+/// Pixels are not decoded, so the picture is exactly the same, and its metadata is kept
+/// Returns `None` if the rewritten picture isn't smaller
+pub fn optimize(content: &[u8], progressive: bool) -> Result<Option<Vec<u8>>, JpegtranErr> {
+    let mut command = Command::new(JPEGTRAN_PROGRAM);
+
+    command.arg("-optimize").arg("-copy").arg("all");
+
+    if progressive {
+        command.arg("-progressive");
+    }
+
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(JpegtranErr::FailedToStart)?;
+
+    // The picture is written from another thread, as the program may fill its output before reading all of it
+    let mut stdin = child.stdin.take().unwrap();
+    let input = content.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+
+    let output = child.wait_with_output().map_err(JpegtranErr::IO)?;
+
+    let written = writer.join().unwrap_or_else(|_| {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "writer thread panicked",
+        ))
+    });
+
+    // A program which failed stops reading its input, so its own error is the relevant one
+    if !output.status.success() {
+        return Err(JpegtranErr::Failed(
+            String::from_utf8_lossy(&output.stderr).trim().to_owned(),
+        ));
+    }
+
+    written.map_err(JpegtranErr::IO)?;
+
+    if output.stdout.is_empty() || output.stdout.len() >= content.len() {
+        Ok(None)
+    } else {
+        Ok(Some(output.stdout))
+    }
+}
+
+/// JPEG optimization error
+pub enum JpegtranErr {
+    FailedToStart(io::Error),
+    IO(io::Error),
+    Failed(String),
+}
+
+impl fmt::Display for JpegtranErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToStart(err) => write!(
+                f,
+                "failed to start '{}' (is libjpeg-turbo or mozjpeg installed?): {}",
+                JPEGTRAN_PROGRAM, err
+            ),
+            Self::IO(err) => write!(
+                f,
+                "failed to communicate with '{}': {}",
+                JPEGTRAN_PROGRAM, err
+            ),
+            Self::Failed(err) => write!(f, "'{}' failed: {}", JPEGTRAN_PROGRAM, err),
+        }
+    }
+}
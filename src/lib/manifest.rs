@@ -0,0 +1,411 @@
+use crate::lib::build_vol::SIDECAR_EXT;
+use crate::lib::comic_info::{ComicMetadata, COMIC_INFO_FILE_NAME};
+use crate::lib::provenance::ChapterProvenance;
+use crate::lib::sign;
+use crate::lib::toc::TocEntry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek};
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Name of the manifest file written in output directories
+pub const MANIFEST_FILE_NAME: &str = ".comic-enc-manifest.json";
+
+/// Name of the manifest file embedded in volumes built with '--bit-exact'
+pub const EMBEDDED_MANIFEST_FILE_NAME: &str = "comic-enc-manifest.json";
+
+/// Name of the file listing the digests of the pages embedded in volumes built with '--checksums'
+pub const CHECKSUMS_FILE_NAME: &str = "checksums.txt";
+
+/// Record of the pages of a volume, embedded in it
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddedManifest {
+    /// Indicates the pages were written without any transformation, so their bytes are the ones of their source
+    pub bit_exact: bool,
+
+    /// Pages of the volume, in order
+    pub pages: Vec<EmbeddedPage>,
+
+    /// Origin of the volume's chapters, if provided in a sources file
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<ChapterProvenance>,
+}
+
+/// A page embedded in a volume
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmbeddedPage {
+    /// Path of the page in the volume
+    pub path_in_zip: String,
+
+    /// Name of the chapter the page comes from, or path to the picture for a custom cover
+    pub chapter: String,
+
+    /// Path of the page in its chapter (directory, archive or PDF)
+    pub source: String,
+
+    /// SHA-256 digest of the page, as an hexadecimal string
+    pub sha256: String,
+}
+
+/// Records of the pages embedded in an existing volume, to write them again when the volume is rewritten (e.g. when editing it)
+#[derive(Debug, Default)]
+pub struct EmbeddedRecords {
+    /// Does the volume embed a manifest
+    pub has_manifest: bool,
+
+    /// Does the volume embed the checksums of its pages
+    pub has_checksums: bool,
+
+    /// Were the pages written without any transformation, as recorded in the manifest
+    pub bit_exact: bool,
+
+    /// Origin of the volume's chapters, as recorded in the manifest
+    pub sources: Vec<ChapterProvenance>,
+
+    /// Record of each page, by its path in the volume
+    pub pages: HashMap<String, EmbeddedPage>,
+
+    /// Content of the volume's metadata file, if any, whose number of pages must be updated
+    pub comic_info: Option<String>,
+}
+
+/// Description of a volume written next to it with '--sidecar-json', for library tools which cannot read inside archives
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VolumeSidecar {
+    /// Number of the volume
+    pub volume: usize,
+
+    /// Metadata of the volume
+    pub metadata: ComicMetadata,
+
+    /// Settings the volume was built with, only listing the ones which were enabled
+    pub settings: BTreeMap<String, String>,
+
+    /// Pages of the volume and origin of its chapters, like in the embedded manifest
+    #[serde(flatten)]
+    pub manifest: EmbeddedManifest,
+}
+
+impl EmbeddedPage {
+    /// Describe a page of an existing volume from its path, without record of its origin
+    /// Its chapter is the volume's directory containing it
+    pub fn from_path_in_zip(path_in_zip: &str, sha256: String) -> Self {
+        let (chapter, source) = path_in_zip.split_once('/').unwrap_or(("", path_in_zip));
+
+        Self {
+            path_in_zip: path_in_zip.to_owned(),
+            chapter: chapter.to_owned(),
+            source: source.to_owned(),
+            sha256,
+        }
+    }
+}
+
+impl EmbeddedManifest {
+    /// Get the chapters of the volume with their first page, from the chapter each page comes from
+    /// Pages at the root of the volume (custom covers) are not part of any chapter
+    pub fn chapters(&self) -> Vec<TocEntry> {
+        let mut chapters: Vec<TocEntry> = vec![];
+
+        for (i, page) in self.pages.iter().enumerate() {
+            if !page.path_in_zip.contains('/') {
+                continue;
+            }
+
+            if chapters.last().map(|chapter| &chapter.title) != Some(&page.chapter) {
+                chapters.push(TocEntry {
+                    title: page.chapter.clone(),
+                    first_page: i + 1,
+                });
+            }
+        }
+
+        chapters
+    }
+}
+
+/// Load the description of a volume from the sidecar file next to it, or else from the manifest embedded in it
+/// Returns `None` if the volume has neither, or if they cannot be read
+pub fn load_volume_record(volume: &Path) -> Option<VolumeSidecar> {
+    let sidecar_path = volume.with_extension(SIDECAR_EXT);
+
+    if sidecar_path.is_file() {
+        match fs::read_to_string(&sidecar_path)
+            .map_err(|err| err.to_string())
+            .and_then(|json| serde_json::from_str(&json).map_err(|err| err.to_string()))
+        {
+            Ok(sidecar) => return Some(sidecar),
+            Err(err) => warn!(
+                "Warning: failed to read sidecar file '{}': {}",
+                sidecar_path.to_string_lossy(),
+                err
+            ),
+        }
+    }
+
+    let mut archive = ZipArchive::new(File::open(volume).ok()?).ok()?;
+    let entry = archive.by_name(EMBEDDED_MANIFEST_FILE_NAME).ok()?;
+
+    match serde_json::from_reader(entry) {
+        Ok(manifest) => Some(VolumeSidecar {
+            volume: 1,
+            metadata: ComicMetadata::default(),
+            settings: BTreeMap::new(),
+            manifest,
+        }),
+
+        Err(err) => {
+            warn!(
+                "Warning: failed to read the manifest embedded in '{}': {}",
+                volume.to_string_lossy(),
+                err
+            );
+
+            None
+        }
+    }
+}
+
+/// Read the records of the pages embedded in an existing volume, from its manifest and its checksums file
+/// Records which cannot be read are ignored with a warning, and are then not written again
+pub fn read_embedded_records<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    volume: &Path,
+) -> EmbeddedRecords {
+    let mut records = EmbeddedRecords::default();
+
+    if let Ok(entry) = archive.by_name(EMBEDDED_MANIFEST_FILE_NAME) {
+        match serde_json::from_reader::<_, EmbeddedManifest>(entry) {
+            Ok(manifest) => {
+                records.has_manifest = true;
+                records.bit_exact = manifest.bit_exact;
+                records.sources = manifest.sources;
+                records.pages = manifest
+                    .pages
+                    .into_iter()
+                    .map(|page| (page.path_in_zip.clone(), page))
+                    .collect();
+            }
+
+            Err(err) => warn!(
+                "Warning: failed to read the manifest embedded in '{}', it will be dropped: {}",
+                volume.to_string_lossy(),
+                err
+            ),
+        }
+    }
+
+    if let Ok(mut entry) = archive.by_name(CHECKSUMS_FILE_NAME) {
+        let mut content = String::new();
+
+        match entry
+            .read_to_string(&mut content)
+            .map_err(|err| err.to_string())
+            .and_then(|_| {
+                parse_checksums(&content).map_err(|line| format!("invalid line '{}'", line))
+            }) {
+            Ok(checksums) => {
+                records.has_checksums = true;
+
+                // Pages recorded in the manifest already have their digest
+                for (path, sha256) in checksums {
+                    records
+                        .pages
+                        .entry(path.clone())
+                        .or_insert_with(|| EmbeddedPage::from_path_in_zip(&path, sha256));
+                }
+            }
+
+            Err(err) => warn!(
+                "Warning: failed to read the checksums embedded in '{}', they will be dropped: {}",
+                volume.to_string_lossy(),
+                err
+            ),
+        }
+    }
+
+    if let Ok(mut entry) = archive.by_name(COMIC_INFO_FILE_NAME) {
+        let mut content = String::new();
+
+        match entry.read_to_string(&mut content) {
+            Ok(_) => records.comic_info = Some(content),
+            Err(err) => warn!(
+                "Warning: failed to read the metadata file of '{}', it will be dropped: {}",
+                volume.to_string_lossy(),
+                err
+            ),
+        }
+    }
+
+    records
+}
+
+/// Record of the volumes produced in an output directory, used to resume compilations
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Manifest {
+    /// Volumes produced in the directory
+    pub volumes: Vec<ManifestVolume>,
+
+    /// Names of the staging files that could not be removed
+    pub stranded: Vec<String>,
+}
+
+/// A volume produced in an output directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestVolume {
+    /// Name of the volume's file, before the number of pages and the extension are added
+    pub base_name: String,
+
+    /// Actual name of the volume's file
+    pub file_name: String,
+
+    /// SHA-256 digest of the volume's file, as an hexadecimal string
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// Load the manifest of a directory
+    /// Returns an empty manifest if the directory doesn't contain one
+    pub fn load(dir: &Path) -> Result<Self, ManifestErr> {
+        let path = dir.join(MANIFEST_FILE_NAME);
+
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(&path).map_err(ManifestErr::IOError)?;
+
+        serde_json::from_str(&content).map_err(|err| ManifestErr::InvalidContent(err.to_string()))
+    }
+
+    /// Save the manifest in a directory
+    pub fn save(&self, dir: &Path) -> Result<(), ManifestErr> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|err| ManifestErr::InvalidContent(err.to_string()))?;
+
+        fs::write(dir.join(MANIFEST_FILE_NAME), json).map_err(ManifestErr::IOError)
+    }
+
+    /// Load the manifest of a directory, update it and save it back
+    pub fn update(dir: &Path, update: impl FnOnce(&mut Self)) -> Result<(), ManifestErr> {
+        let mut manifest = Self::load(dir)?;
+        update(&mut manifest);
+        manifest.save(dir)
+    }
+
+    /// Find a volume by the name of its file before the number of pages and the extension are added
+    pub fn find_volume(&self, base_name: &str) -> Option<&ManifestVolume> {
+        self.volumes
+            .iter()
+            .find(|volume| volume.base_name == base_name)
+    }
+
+    /// Register a produced volume, replacing the previous one with the same base name
+    pub fn register_volume(&mut self, volume: ManifestVolume) {
+        self.volumes
+            .retain(|existing| existing.base_name != volume.base_name);
+
+        self.volumes.push(volume);
+    }
+
+    /// Register a staging file that could not be removed
+    pub fn register_stranded(&mut self, file_name: String) {
+        if !self.stranded.contains(&file_name) {
+            self.stranded.push(file_name);
+        }
+    }
+
+    /// Try to remove the staging files that could not be removed previously
+    /// Files that still cannot be removed are kept in the manifest
+    pub fn clean_stranded(&mut self, dir: &Path) {
+        self.stranded.retain(|file_name| {
+            let path = dir.join(file_name);
+
+            if !path.exists() {
+                return false;
+            }
+
+            debug!(
+                "Removing stranded staging file '{}'...",
+                path.to_string_lossy()
+            );
+
+            match fs::remove_file(&path) {
+                Ok(()) => false,
+                Err(err) => {
+                    warn!(
+                        "Warning: failed to remove stranded staging file '{}': {}",
+                        path.to_string_lossy(),
+                        err
+                    );
+                    true
+                }
+            }
+        });
+    }
+}
+
+/// Compute the SHA-256 digest of a file, as an hexadecimal string
+pub fn file_sha256(path: impl AsRef<Path>) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0; 64 * 1024];
+
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+
+    Ok(sign::to_hex(&hasher.finalize()))
+}
+
+/// Compute the SHA-256 digest of some bytes, as an hexadecimal string
+pub fn sha256(content: &[u8]) -> String {
+    sign::to_hex(&Sha256::digest(content))
+}
+
+/// Render the digests of pages in the format of the 'sha256sum' tool (e.g. "<digest>  <path>" on each line)
+pub fn render_checksums(pages: &[EmbeddedPage]) -> String {
+    pages
+        .iter()
+        .map(|page| format!("{}  {}\n", page.sha256, page.path_in_zip))
+        .collect()
+}
+
+/// Parse digests in the format of the 'sha256sum' tool
+/// Returns the path and digest of each entry, or the invalid line
+pub fn parse_checksums(content: &str) -> Result<Vec<(String, String)>, String> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_once("  ") {
+            Some((sha256, path)) if sha256.len() == 64 && !path.is_empty() => {
+                Ok((path.to_owned(), sha256.to_lowercase()))
+            }
+            _ => Err(line.to_owned()),
+        })
+        .collect()
+}
+
+/// Manifest loading or saving error
+pub enum ManifestErr {
+    IOError(io::Error),
+    InvalidContent(String),
+}
+
+impl fmt::Display for ManifestErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidContent(err) => write!(f, "invalid manifest: {}", err),
+        }
+    }
+}
@@ -1,2 +1,35 @@
 pub mod build_vol;
+pub mod chapters;
+pub mod comic_info;
+pub mod contact_sheet;
+pub mod cover;
 pub mod deter;
+pub mod entry_names;
+pub mod history;
+pub mod hooks;
+pub mod ignore_file;
+pub mod image_meta;
+pub mod index;
+pub mod jpegtran;
+pub mod manifest;
+pub mod naming;
+pub mod opds;
+pub mod page_exclusions;
+pub mod page_store;
+pub mod pdf_images;
+pub mod plan_editor;
+pub mod planner;
+pub mod prompt;
+pub mod provenance;
+pub mod reading_list;
+pub mod report;
+pub mod safety;
+pub mod series_json;
+pub mod sevenz;
+pub mod sign;
+pub mod sort;
+pub mod source_meta;
+pub mod tar;
+pub mod toc;
+pub mod transform;
+pub mod upscale;
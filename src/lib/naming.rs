@@ -0,0 +1,88 @@
+use std::str::FromStr;
+
+/// Layout of the output directory and names of the volumes' files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamingPreset {
+    /// Volumes are put directly in the output directory, with the default names (e.g. "Volume-01.cbz")
+    Plain,
+
+    /// Volumes are put in a directory named after their series, in the format parsed by Komga
+    /// (e.g. "Series Name/Series Name - Volume 01.cbz")
+    Komga,
+
+    /// Volumes are put in a directory named after their series, in the format parsed by Kavita
+    /// (e.g. "Series Name/Series Name Vol. 01.cbz")
+    Kavita,
+}
+
+impl FromStr for NamingPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "plain" => Ok(Self::Plain),
+            "komga" => Ok(Self::Komga),
+            "kavita" => Ok(Self::Kavita),
+            _ => Err(format!(
+                "Unknown naming preset '{}' (expected 'komga', 'kavita' or 'plain')",
+                s
+            )),
+        }
+    }
+}
+
+impl NamingPreset {
+    /// Check if the volumes are put in a directory named after their series
+    pub fn uses_series_dir(self) -> bool {
+        self != Self::Plain
+    }
+
+    /// Get the name of a volume's file, without its extension
+    /// Returns `None` if the default name should be used
+    pub fn volume_name(self, series: &str, volume: usize, vol_num_len: usize) -> Option<String> {
+        let vol_num_len = vol_num_len.max(2);
+
+        match self {
+            Self::Plain => None,
+            Self::Komga => Some(format!(
+                "{} - Volume {:0vol_num_len$}",
+                series,
+                volume,
+                vol_num_len = vol_num_len
+            )),
+            Self::Kavita => Some(format!(
+                "{} Vol. {:0vol_num_len$}",
+                series,
+                volume,
+                vol_num_len = vol_num_len
+            )),
+        }
+    }
+
+    /// Get the name of the file of a volume containing a single chapter, without its extension
+    /// Returns `None` if the default name should be used
+    pub fn chapter_name(
+        self,
+        series: &str,
+        chapter: usize,
+        chapter_num_len: usize,
+    ) -> Option<String> {
+        let chapter_num_len = chapter_num_len.max(3);
+
+        match self {
+            Self::Plain => None,
+            Self::Komga => Some(format!(
+                "{} - Chapter {:0chapter_num_len$}",
+                series,
+                chapter,
+                chapter_num_len = chapter_num_len
+            )),
+            Self::Kavita => Some(format!(
+                "{} Ch. {:0chapter_num_len$}",
+                series,
+                chapter,
+                chapter_num_len = chapter_num_len
+            )),
+        }
+    }
+}
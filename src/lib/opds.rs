@@ -0,0 +1,292 @@
+use crate::lib::comic_info::{self, COMIC_INFO_FILE_NAME};
+use crate::lib::cover;
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::report;
+use crate::lib::sort;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Name of the OPDS catalog written to the output directory
+pub const OPDS_CATALOG_FILE_NAME: &str = "catalog.xml";
+
+/// Name of the directory the covers referenced by the OPDS catalog are written to
+pub const OPDS_COVERS_DIR_NAME: &str = "covers";
+
+/// Media type of an OPDS acquisition feed
+const ACQUISITION_FEED_TYPE: &str = "application/atom+xml;profile=opds-catalog;kind=acquisition";
+
+/// A volume listed in an OPDS catalog
+pub struct OpdsEntry {
+    /// Unique identifier of the volume (e.g. 'urn:sha256:...')
+    pub id: String,
+
+    /// Name of the volume's file, relative to the catalog
+    pub file_name: String,
+
+    /// Format of the volume, if known
+    pub format: Option<ComicFormat>,
+
+    /// Size of the volume's file, in bytes
+    pub size: u64,
+
+    /// Last modification of the volume's file
+    pub updated: SystemTime,
+
+    /// Metadata of the volume
+    pub metadata: VolumeInfo,
+
+    /// Path to the volume's cover, relative to the catalog
+    pub cover: Option<String>,
+}
+
+/// Metadata of a volume, read from its metadata file
+#[derive(Debug, Default)]
+pub struct VolumeInfo {
+    pub title: Option<String>,
+    pub series: Option<String>,
+    pub number: Option<String>,
+    pub writer: Option<String>,
+    pub summary: Option<String>,
+    pub year: Option<String>,
+    pub language: Option<String>,
+}
+
+impl VolumeInfo {
+    /// Read the metadata of a volume from its metadata file's content
+    pub fn from_comic_info(xml: &str) -> Self {
+        Self {
+            title: comic_info::get_field(xml, "Title"),
+            series: comic_info::get_field(xml, "Series"),
+            number: comic_info::get_field(xml, "Number"),
+            writer: comic_info::get_field(xml, "Writer"),
+            summary: comic_info::get_field(xml, "Summary"),
+            year: comic_info::get_field(xml, "Year"),
+            language: comic_info::get_field(xml, "LanguageISO"),
+        }
+    }
+
+    /// Get the title to display for the volume, falling back to the series and number, then to the provided name
+    pub fn display_title(&self, fallback: &str) -> String {
+        match (&self.title, &self.series, &self.number) {
+            (Some(title), _, _) => title.clone(),
+            (None, Some(series), Some(number)) => format!("{} #{}", series, number),
+            (None, Some(series), None) => series.clone(),
+            (None, None, _) => fallback.to_owned(),
+        }
+    }
+}
+
+/// Read the metadata and the cover of a ZIP volume
+/// Returns the metadata along with the cover's extension and content, if the volume contains any page
+pub fn read_volume(
+    path: &Path,
+    accept_extended_image_formats: bool,
+) -> Result<(VolumeInfo, Option<(String, Vec<u8>)>), OpdsErr> {
+    let file = File::open(path).map_err(OpdsErr::IOError)?;
+    let mut archive = ZipArchive::new(file).map_err(OpdsErr::InvalidArchive)?;
+
+    let comic_info = match archive.by_name(COMIC_INFO_FILE_NAME) {
+        Ok(mut entry) => {
+            let mut xml = String::new();
+            entry.read_to_string(&mut xml).map_err(OpdsErr::IOError)?;
+            Some(xml)
+        }
+        Err(ZipError::FileNotFound) => None,
+        Err(err) => return Err(OpdsErr::InvalidArchive(err)),
+    };
+
+    // Only list the entries, without decompressing them
+    let mut pages: Vec<PathBuf> = archive
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .map(PathBuf::from)
+        .filter(|path| deter::has_image_ext(path, accept_extended_image_formats))
+        .collect();
+
+    sort::natural_sort_paths(&mut pages, false);
+    cover::move_cover_first(&mut pages, |page| page);
+
+    let cover = match pages.first() {
+        Some(page) => {
+            let mut entry = archive
+                .by_name(&page.to_string_lossy())
+                .map_err(OpdsErr::InvalidArchive)?;
+
+            let mut content = vec![];
+            entry.read_to_end(&mut content).map_err(OpdsErr::IOError)?;
+
+            let ext = page
+                .extension()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_lowercase();
+
+            Some((ext, content))
+        }
+
+        None => None,
+    };
+
+    let info = comic_info
+        .as_deref()
+        .map(VolumeInfo::from_comic_info)
+        .unwrap_or_default();
+
+    Ok((info, cover))
+}
+
+/// Render an OPDS 1.2 acquisition feed listing the provided volumes
+pub fn render_catalog(id: &str, title: &str, entries: &[OpdsEntry]) -> String {
+    let updated = entries
+        .iter()
+        .map(|entry| entry.updated)
+        .max()
+        .unwrap_or_else(SystemTime::now);
+
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\" xmlns:dc=\"http://purl.org/dc/terms/\" xmlns:opds=\"http://opds-spec.org/2010/catalog\">\n  <id>{}</id>\n  <title>{}</title>\n  <updated>{}</updated>\n  <author>\n    <name>comic-enc</name>\n  </author>\n  <link rel=\"self\" href=\"{}\" type=\"{}\" />\n  <link rel=\"start\" href=\"{}\" type=\"{}\" />\n",
+        comic_info::escape(id),
+        comic_info::escape(title),
+        report::format_timestamp(updated),
+        OPDS_CATALOG_FILE_NAME,
+        ACQUISITION_FEED_TYPE,
+        OPDS_CATALOG_FILE_NAME,
+        ACQUISITION_FEED_TYPE
+    );
+
+    for entry in entries {
+        let fallback_title = Path::new(&entry.file_name)
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        xml.push_str(&format!(
+            "  <entry>\n    <id>{}</id>\n    <title>{}</title>\n    <updated>{}</updated>\n",
+            comic_info::escape(&entry.id),
+            comic_info::escape(&entry.metadata.display_title(&fallback_title)),
+            report::format_timestamp(entry.updated)
+        ));
+
+        if let Some(writer) = &entry.metadata.writer {
+            xml.push_str(&format!(
+                "    <author>\n      <name>{}</name>\n    </author>\n",
+                comic_info::escape(writer)
+            ));
+        }
+
+        if let Some(language) = &entry.metadata.language {
+            xml.push_str(&format!(
+                "    <dc:language>{}</dc:language>\n",
+                comic_info::escape(language)
+            ));
+        }
+
+        if let Some(year) = &entry.metadata.year {
+            xml.push_str(&format!(
+                "    <dc:issued>{}</dc:issued>\n",
+                comic_info::escape(year)
+            ));
+        }
+
+        if let Some(summary) = &entry.metadata.summary {
+            xml.push_str(&format!(
+                "    <summary type=\"text\">{}</summary>\n",
+                comic_info::escape(summary)
+            ));
+        }
+
+        if let Some(cover) = &entry.cover {
+            let media_type = image_media_type(cover);
+
+            for rel in &[
+                "http://opds-spec.org/image",
+                "http://opds-spec.org/image/thumbnail",
+            ] {
+                xml.push_str(&format!(
+                    "    <link rel=\"{}\" href=\"{}\" type=\"{}\" />\n",
+                    rel,
+                    url_encode_path(cover),
+                    media_type
+                ));
+            }
+        }
+
+        xml.push_str(&format!(
+            "    <link rel=\"http://opds-spec.org/acquisition\" href=\"{}\" type=\"{}\" length=\"{}\" />\n  </entry>\n",
+            url_encode_path(&entry.file_name),
+            volume_media_type(entry.format),
+            entry.size
+        ));
+    }
+
+    xml.push_str("</feed>\n");
+    xml
+}
+
+/// Get the media type of a volume from its format
+fn volume_media_type(format: Option<ComicFormat>) -> &'static str {
+    match format {
+        Some(ComicFormat::Zip) => "application/vnd.comicbook+zip",
+        Some(ComicFormat::Tar) => "application/x-cbt",
+        Some(ComicFormat::Pdf) => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Get the media type of a picture from its extension
+fn image_media_type(path: &str) -> &'static str {
+    match Path::new(path)
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase()
+        .as_str()
+    {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Percent-encode a relative path to put it in a link, keeping its separators
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(url_encode_path("covers/Vol 1.jpg"), "covers/Vol%201.jpg");
+/// ```
+fn url_encode_path(path: &str) -> String {
+    path.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// OPDS catalog generation error
+pub enum OpdsErr {
+    IOError(io::Error),
+    InvalidArchive(ZipError),
+}
+
+impl fmt::Display for OpdsErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidArchive(err) => write!(f, "invalid ZIP archive: {}", err),
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Pages to exclude from each chapter, designated by their name or their position
+#[derive(Debug, Clone)]
+pub struct PageExclusions {
+    /// List the exclusions were parsed from
+    list: String,
+
+    /// Gitignore-style patterns matching the pages' paths in their chapter (`None` if there is no pattern)
+    patterns: Option<Gitignore>,
+
+    /// Positions of the pages in their chapter
+    positions: Vec<PagePosition>,
+}
+
+/// Position of excluded pages in their chapter
+#[derive(Debug, Clone, Copy)]
+enum PagePosition {
+    /// Pages from the first to the second one (inclusive), starting at 1
+    Range(usize, usize),
+
+    /// Last pages of the chapter
+    Last(usize),
+}
+
+impl PageExclusions {
+    /// Check if a page is excluded
+    /// `path` is the page's path in its chapter, `index` its position (starting at 0) and `count` the chapter's number of pages
+    pub fn is_excluded(&self, path: &Path, index: usize, count: usize) -> bool {
+        let excluded_position = self.positions.iter().any(|position| match *position {
+            PagePosition::Range(start, end) => index + 1 >= start && index < end,
+            PagePosition::Last(last) => index + last >= count,
+        });
+
+        excluded_position
+            || self
+                .patterns
+                .as_ref()
+                .map(|patterns| patterns.matched(path, false).is_ignore())
+                .unwrap_or(false)
+    }
+}
+
+impl fmt::Display for PageExclusions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.list)
+    }
+}
+
+impl FromStr for PageExclusions {
+    type Err = String;
+
+    /// Parse a comma-separated list of patterns (e.g. 'credits*.png'), page numbers and page ranges (e.g. '1-2'),
+    ///  a dash followed by a number designating the last pages of each chapter (e.g. '-3')
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut builder = GitignoreBuilder::new("");
+        let mut has_patterns = false;
+        let mut positions = vec![];
+
+        builder
+            .case_insensitive(true)
+            .map_err(|err| err.to_string())?;
+
+        let is_number = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+        for part in s.split(',').map(str::trim).filter(|part| !part.is_empty()) {
+            let position = match part.split_once('-') {
+                Some(("", last)) if is_number(last) => {
+                    Some(PagePosition::Last(parse_number(last)?))
+                }
+
+                Some((start, end)) if is_number(start) && is_number(end) => {
+                    let (start, end) = (parse_number(start)?, parse_number(end)?);
+
+                    if end < start {
+                        return Err(format!("Invalid range of pages '{}'", part));
+                    }
+
+                    Some(PagePosition::Range(start, end))
+                }
+
+                None if is_number(part) => {
+                    let page = parse_number(part)?;
+                    Some(PagePosition::Range(page, page))
+                }
+
+                _ => None,
+            };
+
+            match position {
+                Some(position) => positions.push(position),
+
+                None => {
+                    builder
+                        .add_line(None, part)
+                        .map_err(|err| format!("Invalid pattern '{}': {}", part, err))?;
+
+                    has_patterns = true;
+                }
+            }
+        }
+
+        let patterns = if has_patterns {
+            Some(builder.build().map_err(|err| err.to_string())?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            list: s.to_owned(),
+            patterns,
+            positions,
+        })
+    }
+}
+
+/// Parse a page number, starting at 1
+fn parse_number(s: &str) -> Result<usize, String> {
+    s.parse::<usize>()
+        .ok()
+        .filter(|number| *number > 0)
+        .ok_or_else(|| format!("Invalid page number '{}' (pages start at 1)", s))
+}
@@ -0,0 +1,65 @@
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Store of transformed pages, indexed by the digest of their original content and of the transformations applied to them
+/// Stored pages are shared by all the volumes built with the same store, including in later runs
+#[derive(Debug)]
+pub struct PageStore {
+    dir: PathBuf,
+}
+
+impl PageStore {
+    /// Open the store in the provided directory, creating it if it doesn't exist yet
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Compute the key of a page in the store, from its original content and a description of its transformations
+    /// The tool's version is part of the key, as the transformations' output may change between versions
+    pub fn key(content: &[u8], transformations: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.update(b"\0");
+        hasher.update(transformations.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(content);
+
+        hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Get a page from the store, if it's there
+    pub fn get(&self, key: &str) -> Option<Vec<u8>> {
+        fs::read(self.path(key)).ok()
+    }
+
+    /// Add a page to the store
+    /// The page is written to a temporary file first, so other runs never read a partially written page
+    pub fn put(&self, key: &str, content: &[u8]) -> io::Result<()> {
+        let path = self.path(key);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let tmp_path = path.with_extension(format!("tmp-{}", process::id()));
+
+        fs::write(&tmp_path, content)?;
+        fs::rename(&tmp_path, &path)
+    }
+
+    /// Get the path of a page in the store, pages being spread in subdirectories named after the first characters of their key
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(&key[..2]).join(key)
+    }
+}
@@ -0,0 +1,243 @@
+use crate::lib::comic_info::{self, ComicMetadata};
+use pdf::error::PdfError;
+use pdf::file::File as PDFFile;
+use pdf::object::{PlainRef, Resolve, XObject};
+use pdf::primitive::Primitive;
+use std::path::Path;
+
+/// Fields of a PDF document's information dictionary
+#[derive(Debug, Clone, Default)]
+pub struct PdfInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+}
+
+impl PdfInfo {
+    /// Build a metadata file from the document's informations
+    /// Returns `None` if the document doesn't provide any of them
+    pub fn to_comic_info(&self) -> Option<String> {
+        if self.title.is_none() && self.author.is_none() && self.subject.is_none() {
+            return None;
+        }
+
+        let metadata = ComicMetadata {
+            title: self.title.clone(),
+            writer: self.author.clone(),
+            ..ComicMetadata::default()
+        };
+
+        let xml = metadata.apply(None);
+
+        Some(match &self.subject {
+            Some(subject) => comic_info::set_summary(&xml, subject),
+            None => xml,
+        })
+    }
+}
+
+/// Extract all JPEG images from a PDF document, in page order
+/// If `skip_bad_pages` is set, pages that cannot be read are skipped with a warning instead of failing
+/// Images that are not stored as JPEG are skipped with a warning as well
+pub fn extract_jpeg_images(
+    path: impl AsRef<Path>,
+    skip_bad_pages: bool,
+) -> Result<Vec<Vec<u8>>, PdfImagesErr> {
+    extract_jpeg_images_with_pages(path, skip_bad_pages)
+        .map(|images| images.into_iter().map(|(_, image)| image).collect())
+}
+
+/// Extract all JPEG images from a PDF document like `extract_jpeg_images`, along with the page they come from (starting at 1)
+pub fn extract_jpeg_images_with_pages(
+    path: impl AsRef<Path>,
+    skip_bad_pages: bool,
+) -> Result<Vec<(usize, Vec<u8>)>, PdfImagesErr> {
+    let pdf = PDFFile::open(path.as_ref()).map_err(PdfImagesErr::FailedToOpen)?;
+
+    let mut images = vec![];
+
+    // List all images in the PDF
+    for (i, page) in pdf.pages().enumerate() {
+        trace!("Counting images from page {}...", i);
+
+        match page.map_err(|err| PdfImagesErr::FailedToGetPage(i + 1, err)) {
+            Err(PdfImagesErr::FailedToGetPage(page, err)) if skip_bad_pages => {
+                warn!("Skipping PDF page n°{} as it cannot be read: {}", page, err)
+            }
+            Err(err) => return Err(err),
+            Ok(page) => match page
+                .resources(&pdf)
+                .map_err(|err| PdfImagesErr::FailedToGetPageResources(i + 1, err))
+            {
+                Err(PdfImagesErr::FailedToGetPageResources(page, err)) if skip_bad_pages => warn!(
+                    "Skipping PDF page n°{} as its resources cannot be read: {}",
+                    page, err
+                ),
+                Err(err) => return Err(err),
+                Ok(resources) => {
+                    images.extend(resources.xobjects.iter().filter_map(|(_, o)| match o {
+                        XObject::Image(im) => Some((i + 1, im.clone())),
+                        _ => None,
+                    }));
+                }
+            },
+        }
+    }
+
+    Ok(images
+        .iter()
+        .filter_map(|(page, image)| match image.as_jpeg() {
+            Some(data) => Some((*page, data.to_vec())),
+            None => {
+                warn!(
+                    "Skipping an image from PDF page n°{} as it is not stored as JPEG",
+                    page
+                );
+                None
+            }
+        })
+        .collect())
+}
+
+/// Read the title, author and subject of a PDF document from its information dictionary
+pub fn read_info(path: impl AsRef<Path>) -> Result<PdfInfo, PdfError> {
+    let pdf = PDFFile::open(path.as_ref())?;
+
+    let info = match &pdf.trailer.info_dict {
+        Some(info) => info,
+        None => return Ok(PdfInfo::default()),
+    };
+
+    let field = |key: &str| match info.get(key) {
+        Some(Primitive::String(string)) => decode_text_string(string.as_bytes()),
+        _ => None,
+    };
+
+    Ok(PdfInfo {
+        title: field("Title"),
+        author: field("Author"),
+        subject: field("Subject"),
+    })
+}
+
+/// Decode a PDF text string, which is either encoded in UTF-16BE with a byte order mark or in PDFDocEncoding
+/// PDFDocEncoding is decoded as Latin-1, which it matches for all printable characters but a few symbols
+/// Returns `None` if the string is empty
+fn decode_text_string(bytes: &[u8]) -> Option<String> {
+    let string = match bytes {
+        [0xFE, 0xFF, rest @ ..] => String::from_utf16_lossy(
+            &rest
+                .chunks(2)
+                .map(|pair| u16::from_be_bytes([pair[0], *pair.get(1).unwrap_or(&0)]))
+                .collect::<Vec<_>>(),
+        ),
+        _ => bytes.iter().map(|byte| char::from(*byte)).collect(),
+    };
+
+    let string = string.trim();
+
+    if string.is_empty() {
+        None
+    } else {
+        Some(string.to_owned())
+    }
+}
+
+/// Count the pages of a PDF document
+pub fn count_pages(path: impl AsRef<Path>) -> Result<usize, PdfError> {
+    let pdf = PDFFile::open(path.as_ref())?;
+
+    Ok(pdf.num_pages() as usize)
+}
+
+/// Bookmark of a PDF document
+pub struct PdfBookmark {
+    /// Title of the bookmark (`None` if it is empty)
+    pub title: Option<String>,
+
+    /// Page the bookmark points to, starting at 1
+    pub page: usize,
+}
+
+/// Read the top-level bookmarks (outline) of a PDF document, in page order
+/// Only bookmarks with an explicit destination are supported, the ones using named destinations or actions are skipped
+pub fn read_bookmarks(path: impl AsRef<Path>) -> Result<Vec<PdfBookmark>, PdfError> {
+    let pdf = PDFFile::open(path.as_ref())?;
+
+    let outlines = match &pdf.get_root().outlines {
+        Some(outlines) => outlines,
+        None => return Ok(vec![]),
+    };
+
+    // Get the reference of each page to find the ones bookmarks point to
+    let pages = pdf
+        .pages()
+        .map(|page| page.map(|page| page.get_ref().get_inner()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Get the page an explicit destination points to, which is the first element of its array
+    let dest_page = |dest: &Primitive| -> Option<PlainRef> {
+        let dest = match dest {
+            // Destinations can be stored in their own object
+            Primitive::Reference(dest) => pdf.resolve(*dest).ok()?,
+            dest => dest.clone(),
+        };
+
+        match dest {
+            Primitive::Array(items) => match items.first() {
+                Some(Primitive::Reference(page)) => Some(*page),
+                _ => None,
+            },
+            _ => None,
+        }
+    };
+
+    let mut bookmarks = vec![];
+    let mut visited = vec![];
+    let mut next = outlines.first;
+
+    while let Some(item_ref) = next {
+        // Malformed documents may link bookmarks in a loop
+        if visited.contains(&item_ref.get_inner()) {
+            warn!("Warning: PDF bookmarks are linked in a loop, ignoring the remaining ones");
+            break;
+        }
+
+        visited.push(item_ref.get_inner());
+
+        let item = pdf.get(item_ref)?;
+        next = item.next;
+
+        let title = item
+            .title
+            .as_ref()
+            .and_then(|title| decode_text_string(title.as_bytes()));
+
+        match item
+            .dest
+            .as_ref()
+            .and_then(dest_page)
+            .and_then(|page_ref| pages.iter().position(|page| *page == page_ref))
+        {
+            Some(page) => bookmarks.push(PdfBookmark {
+                title,
+                page: page + 1,
+            }),
+            None => debug!(
+                "Ignoring PDF bookmark '{}' as it doesn't point to a page explicitly",
+                title.as_deref().unwrap_or("")
+            ),
+        }
+    }
+
+    bookmarks.sort_by_key(|bookmark| bookmark.page);
+
+    Ok(bookmarks)
+}
+
+/// PDF images extraction error
+pub enum PdfImagesErr {
+    FailedToOpen(PdfError),
+    FailedToGetPage(usize, PdfError),
+    FailedToGetPageResources(usize, PdfError),
+}
@@ -0,0 +1,438 @@
+use crate::lib::prompt::{self, PromptMode};
+use crate::logger;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::fmt;
+use std::io::{self, IsTerminal, Stdout, Write};
+use std::path::PathBuf;
+/// A chapter in the plan being edited
+#[derive(Debug, Clone)]
+pub struct PlanChapter {
+    /// Path to the chapter
+    pub path: PathBuf,
+
+    /// Name of the chapter
+    pub name: String,
+
+    /// Number of pages in the chapter
+    pub pages: usize,
+
+    /// Is the chapter going to be compiled?
+    pub selected: bool,
+
+    /// Does the chapter start a new volume?
+    pub starts_volume: bool,
+}
+
+/// Number of lines above the list of chapters
+const HEADER_LINES: u16 = 2;
+
+/// Number of lines below the list of chapters
+const FOOTER_LINES: u16 = 3;
+
+/// Let the user edit the plan in a terminal UI: select the chapters to compile, reorder them and group them into volumes
+/// `chapters` is the list of chapters in their default order, with the volumes of the automatic plan
+/// If `allow_grouping` is false, every chapter stays in its own volume
+/// Returns the chapters of each volume, or `None` if the user aborted
+pub fn edit_plan(
+    chapters: Vec<PlanChapter>,
+    allow_grouping: bool,
+) -> Result<Option<Vec<Vec<PlanChapter>>>, PlanEditorErr> {
+    // The plan cannot be edited without someone to edit it
+    if prompt::mode() != PromptMode::Interactive
+        || !io::stdin().is_terminal()
+        || !io::stdout().is_terminal()
+    {
+        return Err(PlanEditorErr::NotATerminal);
+    }
+
+    // Don't leave the progress bar behind the editor
+    logger::clear_progress();
+
+    let mut editor = Editor {
+        chapters,
+        allow_grouping,
+        cursor: 0,
+        scroll: 0,
+        group_input: None,
+        message: None,
+    };
+
+    let mut screen = Screen::enter().map_err(PlanEditorErr::IO)?;
+
+    loop {
+        editor.draw(&mut screen.stdout).map_err(PlanEditorErr::IO)?;
+
+        let key = match event::read().map_err(PlanEditorErr::IO)? {
+            // Only key presses are handled, as some terminals report releases as well
+            Event::Key(key) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        match editor.handle_key(key) {
+            Action::Continue => {}
+            Action::Abort => return Ok(None),
+            Action::Done => {
+                let volumes = volumes(editor.chapters.clone(), allow_grouping);
+
+                // Building nothing is most likely a mistake, so the plan can be fixed instead
+                if volumes.is_empty() {
+                    editor.message =
+                        Some("No chapter is selected, select at least one with Space".to_owned());
+                    continue;
+                }
+
+                drop(screen);
+                return Ok(Some(volumes));
+            }
+        }
+    }
+}
+
+/// Terminal switched to the alternate screen in raw mode while the plan is edited
+/// It is restored when dropped, including when the edition fails
+struct Screen {
+    stdout: Stdout,
+}
+
+impl Screen {
+    /// Switch the terminal to the alternate screen in raw mode
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+
+        let mut screen = Self {
+            stdout: io::stdout(),
+        };
+
+        execute!(screen.stdout, EnterAlternateScreen, Hide)?;
+
+        Ok(screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        // Failing to restore the terminal cannot be handled any better than by ignoring it
+        let _ = execute!(self.stdout, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// What to do after a key was pressed
+enum Action {
+    Continue,
+    Abort,
+    Done,
+}
+
+/// State of the plan editor
+struct Editor {
+    /// Chapters in their current order
+    chapters: Vec<PlanChapter>,
+
+    /// Can chapters be grouped into volumes
+    allow_grouping: bool,
+
+    /// Index of the highlighted chapter
+    cursor: usize,
+
+    /// Index of the first displayed chapter
+    scroll: usize,
+
+    /// Number of chapters per volume being typed, if any
+    group_input: Option<String>,
+
+    /// Message to display until the next key is pressed, if any
+    message: Option<String>,
+}
+
+impl Editor {
+    /// Update the plan according to a key press
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        self.message = None;
+
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Abort;
+        }
+
+        // Typing the number of chapters per volume
+        if let Some(input) = &mut self.group_input {
+            match key.code {
+                KeyCode::Char(digit) if digit.is_ascii_digit() && input.len() < 6 => {
+                    input.push(digit)
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Esc => self.group_input = None,
+                KeyCode::Enter => {
+                    match input
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|per_volume| *per_volume > 0)
+                    {
+                        Some(per_volume) => group(&mut self.chapters, per_volume),
+                        None => {
+                            self.message = Some("Invalid number of chapters per volume".to_owned())
+                        }
+                    }
+
+                    self.group_input = None;
+                }
+                _ => {}
+            }
+
+            return Action::Continue;
+        }
+
+        let last = self.chapters.len().saturating_sub(1);
+        let moving = key.modifiers.contains(KeyModifiers::SHIFT);
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') if !moving => {
+                self.cursor = self.cursor.saturating_sub(1)
+            }
+            KeyCode::Down | KeyCode::Char('j') if !moving => {
+                self.cursor = (self.cursor + 1).min(last)
+            }
+            KeyCode::PageUp => self.cursor = self.cursor.saturating_sub(self.page_height()),
+            KeyCode::PageDown => self.cursor = (self.cursor + self.page_height()).min(last),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = last,
+
+            // Move the highlighted chapter along with the cursor
+            KeyCode::Up | KeyCode::Char('K') if self.cursor > 0 => {
+                self.chapters.swap(self.cursor, self.cursor - 1);
+                self.cursor -= 1;
+            }
+            KeyCode::Down | KeyCode::Char('J') if self.cursor < last => {
+                self.chapters.swap(self.cursor, self.cursor + 1);
+                self.cursor += 1;
+            }
+
+            KeyCode::Char(' ') => {
+                if let Some(chapter) = self.chapters.get_mut(self.cursor) {
+                    chapter.selected = !chapter.selected;
+                }
+            }
+            KeyCode::Char('a') => {
+                let select = self.chapters.iter().any(|chapter| !chapter.selected);
+                self.chapters
+                    .iter_mut()
+                    .for_each(|chapter| chapter.selected = select);
+            }
+
+            KeyCode::Char('b') if self.allow_grouping => {
+                if let Some(chapter) = self.chapters.get_mut(self.cursor) {
+                    chapter.starts_volume = !chapter.starts_volume;
+                }
+            }
+            KeyCode::Char('g') if self.allow_grouping => self.group_input = Some(String::new()),
+
+            KeyCode::Enter => return Action::Done,
+            KeyCode::Esc | KeyCode::Char('q') => return Action::Abort,
+
+            _ => {}
+        }
+
+        Action::Continue
+    }
+
+    /// Number of chapters displayed at once
+    fn page_height(&self) -> usize {
+        let (_, rows) = terminal::size().unwrap_or((80, 24));
+        usize::from(rows.saturating_sub(HEADER_LINES + FOOTER_LINES).max(1))
+    }
+
+    /// Draw the whole editor
+    fn draw(&mut self, stdout: &mut Stdout) -> io::Result<()> {
+        let (columns, _) = terminal::size()?;
+        let page_height = self.page_height();
+
+        // Keep the highlighted chapter visible
+        if self.cursor < self.scroll {
+            self.scroll = self.cursor;
+        } else if self.cursor >= self.scroll + page_height {
+            self.scroll = self.cursor + 1 - page_height;
+        }
+
+        let numbers = volume_numbers(&self.chapters, self.allow_grouping);
+        let volumes = numbers.iter().flatten().max().copied().unwrap_or(0);
+        let selected_pages = self
+            .chapters
+            .iter()
+            .filter(|chapter| chapter.selected)
+            .map(|chapter| chapter.pages)
+            .sum::<usize>();
+
+        queue!(stdout, Clear(ClearType::All), MoveTo(0, 0))?;
+
+        queue!(
+            stdout,
+            SetAttribute(Attribute::Bold),
+            Print(fit(
+                &format!(
+                    "Edit the plan: {} volume{}, {} page{}",
+                    volumes,
+                    if volumes > 1 { "s" } else { "" },
+                    selected_pages,
+                    if selected_pages > 1 { "s" } else { "" }
+                ),
+                columns
+            )),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        for (row, i) in (self.scroll..self.chapters.len())
+            .take(page_height)
+            .enumerate()
+        {
+            let chapter = &self.chapters[i];
+
+            // Volume numbers are only displayed on the first chapter of each volume
+            let volume = match numbers[i] {
+                Some(volume) if !numbers[..i].contains(&Some(volume)) => {
+                    format!("Vol. {:<3}", volume)
+                }
+                Some(_) => "        ".to_owned(),
+                None => "   -    ".to_owned(),
+            };
+
+            let line = format!(
+                "{} [{}] {:>3}. {} ({} page{})",
+                volume,
+                if chapter.selected { "x" } else { " " },
+                i + 1,
+                chapter.name,
+                chapter.pages,
+                if chapter.pages > 1 { "s" } else { "" }
+            );
+
+            queue!(stdout, MoveTo(0, HEADER_LINES + row as u16))?;
+
+            if i == self.cursor {
+                queue!(
+                    stdout,
+                    SetAttribute(Attribute::Reverse),
+                    Print(fit(&line, columns)),
+                    SetAttribute(Attribute::Reset)
+                )?;
+            } else {
+                queue!(stdout, Print(fit(&line, columns)))?;
+            }
+        }
+
+        let footer = HEADER_LINES + page_height as u16 + 1;
+
+        let keys = if self.allow_grouping {
+            "Up/Down: move cursor, Shift+Up/Down: move chapter, Space: select, a: select all, b: start a volume here, g: group by n chapters, Enter: build, q: quit"
+        } else {
+            "Up/Down: move cursor, Shift+Up/Down: move chapter, Space: select, a: select all, Enter: build, q: quit"
+        };
+
+        queue!(stdout, MoveTo(0, footer), Print(fit(keys, columns)))?;
+
+        let status = match (&self.group_input, &self.message) {
+            (Some(input), _) => format!(
+                "Chapters per volume (Enter to apply, Esc to cancel): {}",
+                input
+            ),
+            (None, Some(message)) => message.clone(),
+            (None, None) => String::new(),
+        };
+
+        queue!(
+            stdout,
+            MoveTo(0, footer + 1),
+            SetAttribute(Attribute::Bold),
+            Print(fit(&status, columns)),
+            SetAttribute(Attribute::Reset)
+        )?;
+
+        stdout.flush()
+    }
+}
+
+/// Cut a line so it fits in the terminal's width
+fn fit(line: &str, columns: u16) -> String {
+    line.chars().take(usize::from(columns)).collect()
+}
+
+/// Group the selected chapters by a fixed number of chapters per volume
+fn group(chapters: &mut [PlanChapter], per_volume: usize) {
+    let mut selected = 0;
+
+    for chapter in chapters.iter_mut() {
+        chapter.starts_volume = chapter.selected && selected % per_volume == 0;
+
+        if chapter.selected {
+            selected += 1;
+        }
+    }
+}
+
+/// Get the number of the volume each chapter is put in, or `None` for the unselected chapters
+fn volume_numbers(chapters: &[PlanChapter], allow_grouping: bool) -> Vec<Option<usize>> {
+    let mut volume = 0;
+    let mut volume_pending = true;
+
+    chapters
+        .iter()
+        .map(|chapter| {
+            // A volume starting at an unselected chapter starts at the next selected one instead
+            if chapter.starts_volume || !allow_grouping {
+                volume_pending = true;
+            }
+
+            if !chapter.selected {
+                return None;
+            }
+
+            if volume_pending {
+                volume += 1;
+                volume_pending = false;
+            }
+
+            Some(volume)
+        })
+        .collect()
+}
+
+/// Get the chapters of each volume, skipping the unselected chapters
+fn volumes(chapters: Vec<PlanChapter>, allow_grouping: bool) -> Vec<Vec<PlanChapter>> {
+    let numbers = volume_numbers(&chapters, allow_grouping);
+    let mut volumes: Vec<Vec<PlanChapter>> = vec![];
+
+    for (chapter, number) in chapters.into_iter().zip(numbers) {
+        if let Some(volume) = number {
+            if volumes.len() < volume {
+                volumes.push(vec![]);
+            }
+
+            volumes[volume - 1].push(chapter);
+        }
+    }
+
+    volumes
+}
+
+/// Plan edition error
+pub enum PlanEditorErr {
+    NotATerminal,
+    IO(io::Error),
+}
+
+impl fmt::Display for PlanEditorErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotATerminal => write!(
+                f,
+                "the plan can only be edited when the input is a terminal"
+            ),
+            Self::IO(err) => write!(f, "terminal error: {}", err),
+        }
+    }
+}
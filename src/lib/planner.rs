@@ -0,0 +1,358 @@
+use crate::lib::deter::{self, ComicFormat};
+use crate::lib::ignore_file::IgnoreFile;
+use crate::lib::pdf_images;
+use crate::lib::sevenz::{self, SevenZErr};
+use crate::lib::tar::{self, TarErr};
+use pdf::error::PdfError;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// How chapters are grouped into volumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Fixed number of chapters per volume
+    ChaptersPerVolume(usize),
+
+    /// Approximate number of pages per volume
+    TargetPages(usize),
+
+    /// Fixed number of volumes, with the provided measure balanced between them
+    Balance(BalanceMode, usize),
+}
+
+/// Measure of chapters to balance between volumes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceMode {
+    /// Number of chapters
+    Chapters,
+
+    /// Size of the chapters' files
+    Size,
+
+    /// Number of pages in the chapters
+    Pages,
+}
+
+impl FromStr for BalanceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chapters" => Ok(Self::Chapters),
+            "size" => Ok(Self::Size),
+            "pages" => Ok(Self::Pages),
+            _ => Err(format!(
+                "Unknown balance mode '{}' (expected 'chapters', 'size' or 'pages')",
+                s
+            )),
+        }
+    }
+}
+
+/// Determine how many chapters each volume will contain, following the provided strategy
+/// `chapters` is the list of paths to the chapters to encode, in order
+/// `is_page` tells if a file of a directory or an entry of an archive is a page, based on its path
+/// Returns the number of chapters of each volume, or the path to the chapter that could not be measured
+pub fn plan(
+    strategy: Strategy,
+    chapters: &[&Path],
+    is_page: &dyn Fn(&Path) -> bool,
+    ignore_file: &IgnoreFile,
+) -> Result<Vec<usize>, (PathBuf, PageCountErr)> {
+    // Measure each chapter if needed
+    let measure = |measure: &dyn Fn(&Path) -> Result<u64, PageCountErr>| {
+        chapters
+            .iter()
+            .map(|path| measure(path).map_err(|err| (path.to_path_buf(), err)))
+            .collect::<Result<Vec<_>, _>>()
+    };
+
+    let count_pages =
+        |path: &Path| count_chapter_pages(path, is_page, ignore_file).map(|pages| pages as u64);
+
+    let sizes = match strategy {
+        Strategy::ChaptersPerVolume(chapters_per_volume) => {
+            group_by_chapters(chapters.len(), chapters_per_volume)
+        }
+
+        Strategy::TargetPages(target_pages) => {
+            trace!("Counting pages of each chapter...");
+
+            let page_counts = measure(&count_pages)?
+                .into_iter()
+                .map(|pages| pages as usize)
+                .collect::<Vec<_>>();
+
+            group_by_pages(&page_counts, target_pages)
+        }
+
+        Strategy::Balance(BalanceMode::Chapters, volumes) => {
+            balance(&vec![1; chapters.len()], volumes)
+        }
+
+        Strategy::Balance(BalanceMode::Pages, volumes) => {
+            trace!("Counting pages of each chapter...");
+            balance(&measure(&count_pages)?, volumes)
+        }
+
+        Strategy::Balance(BalanceMode::Size, volumes) => {
+            trace!("Computing size of each chapter...");
+            balance(
+                &measure(&|path| chapter_size(path, is_page, ignore_file))?,
+                volumes,
+            )
+        }
+    };
+
+    Ok(sizes)
+}
+
+/// Group chapters in volumes containing the same number of chapters (except for the last one)
+/// Returns the number of chapters of each volume
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(group_by_chapters(7, 3), vec![3, 3, 1]);
+/// ```
+pub fn group_by_chapters(chapters: usize, chapters_per_volume: usize) -> Vec<usize> {
+    let mut sizes = vec![chapters_per_volume; chapters / chapters_per_volume];
+
+    if chapters % chapters_per_volume != 0 {
+        sizes.push(chapters % chapters_per_volume);
+    }
+
+    sizes
+}
+
+/// Group chapters in volumes containing approximately the provided number of pages
+/// Chapters are assigned greedily: a volume is closed when adding the next chapter would take it further from the target
+/// Returns the number of chapters of each volume
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(group_by_pages(&[20, 20, 20, 50, 10], 45), vec![2, 2, 1]);
+/// ```
+pub fn group_by_pages(page_counts: &[usize], target_pages: usize) -> Vec<usize> {
+    let mut sizes = vec![];
+
+    // Chapters and pages in the current volume
+    let mut chapters = 0;
+    let mut pages = 0;
+
+    for count in page_counts {
+        let distance_without = target_pages.max(pages) - target_pages.min(pages);
+        let distance_with = target_pages.max(pages + count) - target_pages.min(pages + count);
+
+        if chapters > 0 && distance_with > distance_without {
+            sizes.push(chapters);
+            chapters = 0;
+            pages = 0;
+        }
+
+        chapters += 1;
+        pages += count;
+    }
+
+    if chapters > 0 {
+        sizes.push(chapters);
+    }
+
+    sizes
+}
+
+/// Split chapters into the provided number of volumes (keeping their order), minimizing the variance of the volumes' weights
+/// Minimizing the sum of squared weights is equivalent, as the total weight is fixed
+/// Returns the number of chapters of each volume
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(balance(&[10, 10, 10, 30], 2), vec![3, 1]);
+/// ```
+pub fn balance(weights: &[u64], volumes: usize) -> Vec<usize> {
+    let chapters = weights.len();
+
+    if chapters == 0 {
+        return vec![];
+    }
+
+    // There cannot be more volumes than chapters
+    let volumes = volumes.max(1).min(chapters);
+
+    // Sum of the weights of the first chapters
+    let mut prefix = vec![0u128; chapters + 1];
+
+    for (i, weight) in weights.iter().enumerate() {
+        prefix[i + 1] = prefix[i] + u128::from(*weight);
+    }
+
+    // Lowest cost of putting the first `i` chapters in `j` volumes, with the start of the last volume
+    let mut cost = vec![vec![u128::MAX; chapters + 1]; volumes + 1];
+    let mut last_start = vec![vec![0; chapters + 1]; volumes + 1];
+
+    cost[0][0] = 0;
+
+    for j in 1..=volumes {
+        for i in j..=chapters {
+            for start in (j - 1)..i {
+                if cost[j - 1][start] == u128::MAX {
+                    continue;
+                }
+
+                let weight = prefix[i] - prefix[start];
+                let candidate = cost[j - 1][start] + weight * weight;
+
+                if candidate < cost[j][i] {
+                    cost[j][i] = candidate;
+                    last_start[j][i] = start;
+                }
+            }
+        }
+    }
+
+    // Go back through the volumes' starts
+    let mut sizes = vec![];
+    let mut end = chapters;
+
+    for j in (1..=volumes).rev() {
+        let start = last_start[j][end];
+        sizes.push(end - start);
+        end = start;
+    }
+
+    sizes.reverse();
+    sizes
+}
+
+/// Get the size of a chapter: total size of its pages for directories, size of the file otherwise
+/// `is_page` tells if a file of a directory is a page, based on its path
+pub fn chapter_size(
+    chapter_path: &Path,
+    is_page: &dyn Fn(&Path) -> bool,
+    ignore_file: &IgnoreFile,
+) -> Result<u64, PageCountErr> {
+    if !chapter_path.is_dir() {
+        return Ok(fs::metadata(chapter_path)
+            .map_err(PageCountErr::IOError)?
+            .len());
+    }
+
+    let pics = deter::readdir_files_recursive(
+        chapter_path,
+        Some(&|path: &PathBuf| is_page(path) && !ignore_file.is_ignored(path, false)),
+    )
+    .map_err(|err| match err {
+        deter::RecursiveFilesSearchErr::IOError(err) => PageCountErr::IOError(err),
+        deter::RecursiveFilesSearchErr::InvalidFileName(path) => {
+            PageCountErr::InvalidFileName(path)
+        }
+    })?;
+
+    let mut size = 0;
+
+    for pic in pics {
+        size += fs::metadata(&pic).map_err(PageCountErr::IOError)?.len();
+    }
+
+    Ok(size)
+}
+
+/// Count the pages of a chapter (directory, archive or PDF)
+/// `is_page` tells if a file of a directory or an entry of an archive is a page, based on its path
+pub fn count_chapter_pages(
+    chapter_path: &Path,
+    is_page: &dyn Fn(&Path) -> bool,
+    ignore_file: &IgnoreFile,
+) -> Result<usize, PageCountErr> {
+    if chapter_path.is_dir() {
+        let pics = deter::readdir_files_recursive(
+            chapter_path,
+            Some(&|path: &PathBuf| is_page(path) && !ignore_file.is_ignored(path, false)),
+        )
+        .map_err(|err| match err {
+            deter::RecursiveFilesSearchErr::IOError(err) => PageCountErr::IOError(err),
+            deter::RecursiveFilesSearchErr::InvalidFileName(path) => {
+                PageCountErr::InvalidFileName(path)
+            }
+        })?;
+
+        return Ok(pics.len());
+    }
+
+    let is_counted = |name: &str| {
+        !name.ends_with('/')
+            && is_page(Path::new(name))
+            && !ignore_file.is_entry_ignored(chapter_path, Path::new(name))
+    };
+
+    match chapter_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .and_then(ComicFormat::from_ext)
+    {
+        Some(ComicFormat::Pdf) => {
+            return pdf_images::count_pages(chapter_path).map_err(PageCountErr::InvalidPdf);
+        }
+
+        Some(ComicFormat::SevenZip) => {
+            return Ok(sevenz::list_files(chapter_path)
+                .map_err(PageCountErr::InvalidSevenZipArchive)?
+                .iter()
+                .filter(|name| is_counted(name))
+                .count());
+        }
+
+        // TAR archives have no index, so their headers have to be walked through
+        Some(ComicFormat::Tar) => {
+            let mut pages = 0;
+
+            tar::for_each_file(chapter_path, |name, _| {
+                if is_counted(name) {
+                    pages += 1;
+                }
+            })
+            .map_err(PageCountErr::InvalidTarArchive)?;
+
+            return Ok(pages);
+        }
+
+        _ => {}
+    }
+
+    let file = File::open(chapter_path).map_err(PageCountErr::IOError)?;
+    let archive = ZipArchive::new(file).map_err(PageCountErr::InvalidArchive)?;
+
+    Ok(archive.file_names().filter(|name| is_counted(name)).count())
+}
+
+/// Chapter pages counting error
+pub enum PageCountErr {
+    IOError(io::Error),
+    InvalidFileName(PathBuf),
+    InvalidArchive(ZipError),
+    InvalidPdf(PdfError),
+    InvalidSevenZipArchive(SevenZErr),
+    InvalidTarArchive(TarErr),
+}
+
+impl fmt::Display for PageCountErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidFileName(path) => {
+                write!(f, "invalid file name '{}'", path.to_string_lossy())
+            }
+            Self::InvalidArchive(err) => write!(f, "invalid ZIP archive: {}", err),
+            Self::InvalidPdf(err) => write!(f, "invalid PDF document: {}", err),
+            Self::InvalidSevenZipArchive(err) => write!(f, "invalid 7-Zip archive: {}", err),
+            Self::InvalidTarArchive(err) => write!(f, "invalid TAR archive: {}", err),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::logger;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How questions are answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptMode {
+    /// Ask the user if the input is a terminal, otherwise answer "no"
+    Interactive,
+
+    /// Answer "yes" to every question without asking
+    AssumeYes,
+
+    /// Answer "no" to every question without asking
+    NoInput,
+}
+
+/// Current prompt mode, shared by all actions
+static MODE: AtomicU8 = AtomicU8::new(PromptMode::Interactive as u8);
+
+/// Set how questions are answered from now on
+pub fn set_mode(mode: PromptMode) {
+    MODE.store(mode as u8, Ordering::SeqCst);
+}
+
+/// Get how questions are answered
+pub fn mode() -> PromptMode {
+    match MODE.load(Ordering::SeqCst) {
+        mode if mode == PromptMode::AssumeYes as u8 => PromptMode::AssumeYes,
+        mode if mode == PromptMode::NoInput as u8 => PromptMode::NoInput,
+        _ => PromptMode::Interactive,
+    }
+}
+
+/// Ask a yes/no question, "no" being the default answer
+pub fn confirm(question: &str) -> bool {
+    match mode() {
+        PromptMode::AssumeYes => {
+            debug!("{} Assuming yes.", question);
+            return true;
+        }
+
+        PromptMode::NoInput => return false,
+
+        PromptMode::Interactive => {}
+    }
+
+    // Nobody can answer if the input is not a terminal (e.g. in scripts)
+    if !io::stdin().is_terminal() {
+        return false;
+    }
+
+    // Don't ask the question on the same line as the progress bar
+    logger::clear_progress();
+
+    print!("{} [y/N] ", question);
+
+    // The question cannot be asked if STDOUT is not writable
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+
+    if io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Ask a question the user answers with a line of text, the default answer being used if the line is empty
+/// Returns `None` if the question cannot be asked (e.g. the input is not a terminal) or if the input ended
+pub fn ask(question: &str, default: Option<&str>) -> Option<String> {
+    if mode() != PromptMode::Interactive || !io::stdin().is_terminal() {
+        return None;
+    }
+
+    // Don't ask the question on the same line as the progress bar
+    logger::clear_progress();
+
+    match default {
+        Some(default) => print!("{} [{}] ", question, default),
+        None => print!("{} ", question),
+    }
+
+    io::stdout().flush().ok()?;
+
+    let mut answer = String::new();
+
+    // Nothing is read once the input ended
+    if io::stdin().lock().read_line(&mut answer).ok()? == 0 {
+        return None;
+    }
+
+    match answer.trim() {
+        "" => default.map(str::to_owned).or_else(|| Some(String::new())),
+        answer => Some(answer.to_owned()),
+    }
+}
+
+/// Ask if an existing file or directory should be overwritten
+pub fn confirm_overwrite(path: &Path) -> bool {
+    confirm(&format!(
+        "'{}' already exists, overwrite it?",
+        path.to_string_lossy()
+    ))
+}
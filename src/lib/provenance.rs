@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Name of the file mapping chapter directories to their origin, in the directory containing the chapters
+pub const SOURCES_FILE_NAME: &str = "sources.toml";
+
+/// Origin of a chapter, as provided in the sources file
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChapterOrigin {
+    /// URL the chapter was downloaded from
+    pub url: Option<String>,
+
+    /// Group which released the chapter (e.g. a scanlation group)
+    pub group: Option<String>,
+}
+
+/// Origin of a chapter in a volume, recorded in its metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChapterProvenance {
+    /// Name of the chapter
+    pub chapter: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+}
+
+/// Origins of the chapters, indexed by the name of their directory (or archive)
+#[derive(Debug, Default)]
+pub struct Sources {
+    chapters: BTreeMap<String, ChapterOrigin>,
+}
+
+impl Sources {
+    /// Load the sources file of a directory, if any
+    ///
+    /// # Examples
+    ///
+    /// ```toml
+    /// ["Chapter 1"]
+    /// url = "https://example.com/chapter-1"
+    /// group = "Some Scans"
+    /// ```
+    pub fn load(dir: &Path) -> Result<Option<Self>, SourcesErr> {
+        let path = dir.join(SOURCES_FILE_NAME);
+
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        trace!("Reading sources file '{}'...", path.to_string_lossy());
+
+        let content =
+            fs::read_to_string(&path).map_err(|err| SourcesErr::FailedToRead(path.clone(), err))?;
+
+        let chapters = toml::from_str(&content)
+            .map_err(|err| SourcesErr::InvalidContent(path, err.to_string()))?;
+
+        Ok(Some(Self { chapters }))
+    }
+
+    /// Get the provenance of a chapter from its path, if it is listed
+    pub fn provenance(&self, chapter_path: &Path, chapter_name: &str) -> Option<ChapterProvenance> {
+        let dir_name = chapter_path.file_name()?.to_string_lossy();
+
+        self.chapters
+            .get(dir_name.as_ref())
+            .map(|origin| ChapterProvenance {
+                chapter: chapter_name.to_owned(),
+                url: origin.url.clone(),
+                group: origin.group.clone(),
+            })
+    }
+}
+
+/// Describe the origin of chapters, as displayed in the notes of a metadata file
+/// Returns `None` if no chapter has a known origin
+///
+/// # Examples
+///
+/// ```
+/// let provenance = ChapterProvenance { chapter: "Chapter 1".to_owned(), url: Some("https://example.com/1".to_owned()), group: Some("Some Scans".to_owned()) };
+/// assert_eq!(render_notes(&[provenance]), Some("Chapter 1 from https://example.com/1 by Some Scans".to_owned()));
+/// ```
+pub fn render_notes(provenance: &[ChapterProvenance]) -> Option<String> {
+    let notes = provenance
+        .iter()
+        .filter(|chapter| chapter.url.is_some() || chapter.group.is_some())
+        .map(|chapter| {
+            let mut note = chapter.chapter.clone();
+
+            if let Some(url) = &chapter.url {
+                note.push_str(&format!(" from {}", url));
+            }
+
+            if let Some(group) = &chapter.group {
+                note.push_str(&format!(" by {}", group));
+            }
+
+            note
+        })
+        .collect::<Vec<_>>();
+
+    if notes.is_empty() {
+        None
+    } else {
+        Some(notes.join("; "))
+    }
+}
+
+/// Sources file loading error
+pub enum SourcesErr {
+    FailedToRead(PathBuf, io::Error),
+    InvalidContent(PathBuf, String),
+}
+
+impl fmt::Display for SourcesErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToRead(path, err) => write!(
+                f,
+                "failed to read sources file '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+
+            Self::InvalidContent(path, err) => write!(
+                f,
+                "invalid sources file '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::lib::comic_info;
+
+/// A volume referenced in a reading list
+pub struct ReadingListEntry {
+    /// Name of the volume's series
+    pub series: String,
+
+    /// Number of the volume in its series
+    pub number: String,
+
+    /// Publication year of the volume, if known
+    pub year: Option<u16>,
+
+    /// Name of the volume's file
+    pub file_name: String,
+}
+
+/// Render a reading list in the ComicRack (.cbl) format, referencing the provided volumes in order
+/// Readers match the volumes by their series and number, the file name is only provided as a hint
+pub fn render_cbl(name: &str, entries: &[ReadingListEntry]) -> String {
+    let books = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "    <Book Series=\"{}\" Number=\"{}\"{} FileName=\"{}\" />\n",
+                comic_info::escape(&entry.series),
+                comic_info::escape(&entry.number),
+                match entry.year {
+                    Some(year) => format!(" Year=\"{}\"", year),
+                    None => String::new(),
+                },
+                comic_info::escape(&entry.file_name)
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<ReadingList xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xmlns:xsd=\"http://www.w3.org/2001/XMLSchema\">\n  <Name>{}</Name>\n  <Books>\n{}  </Books>\n</ReadingList>\n",
+        comic_info::escape(name),
+        books
+    )
+}
@@ -0,0 +1,214 @@
+use crate::lib::comic_info::EmbeddedMetadata;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Report of an action's run, providing statistics about it
+/// All durations are measured with a monotonic clock, so they are not affected by system time changes
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    /// Files or directories produced by the action
+    pub outputs: Vec<PathBuf>,
+
+    /// Time spent in each phase of the action, in order
+    pub phases: Vec<Phase>,
+
+    /// Named counters (e.g. number of chapters, volumes or pages)
+    pub counts: Vec<Count>,
+
+    /// Metadata found in the decoded volume, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<EmbeddedMetadata>,
+
+    /// Volumes built by the action, in order
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub volumes: Vec<VolumeStats>,
+
+    /// Names of the chapters found but not encoded
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped_chapters: Vec<String>,
+
+    /// Pages which look like a page of a previous chapter of their volume
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub near_duplicates: Vec<NearDuplicate>,
+}
+
+/// A measured phase of an action
+#[derive(Debug, Serialize)]
+pub struct Phase {
+    pub name: &'static str,
+    pub elapsed_ms: u64,
+}
+
+/// A named counter
+#[derive(Debug, Serialize)]
+pub struct Count {
+    pub name: &'static str,
+    pub value: usize,
+}
+
+/// Statistics about a built volume
+#[derive(Debug, Serialize)]
+pub struct VolumeStats {
+    /// Number of the volume
+    pub volume: usize,
+
+    /// Path to the volume's file
+    pub path: PathBuf,
+
+    /// Number of chapters in the volume
+    pub chapters: usize,
+
+    /// Number of pages added to the volume, unknown if it has been skipped
+    pub pages: Option<usize>,
+
+    /// Time spent building the volume, in milliseconds
+    pub elapsed_ms: u64,
+
+    /// Indicates the volume already existed and was not built again
+    pub skipped: bool,
+}
+
+/// A page which looks like a page of a previous chapter of its volume
+#[derive(Debug, Clone, Serialize)]
+pub struct NearDuplicate {
+    /// Number of the volume
+    pub volume: usize,
+
+    /// Path to the page
+    pub page: String,
+
+    /// Path to the page it looks like
+    pub original: String,
+
+    /// Number of differing bits between the perceptual hashes of both pages, out of 64
+    pub distance: u32,
+
+    /// Indicates the page was not added to the volume
+    pub dropped: bool,
+}
+
+impl Report {
+    /// Create an empty report
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the time spent in a phase
+    pub fn phase(&mut self, name: &'static str, elapsed: Duration) {
+        self.phases.push(Phase {
+            name,
+            elapsed_ms: elapsed.as_millis() as u64,
+        });
+    }
+
+    /// Register a counter
+    pub fn count(&mut self, name: &'static str, value: usize) {
+        self.counts.push(Count { name, value });
+    }
+
+    /// Write the report's statistics as JSON to the provided path
+    /// `total` is the total duration of the run
+    pub fn write_stats_file(&self, path: impl AsRef<Path>, total: Duration) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(&Stats {
+            total_ms: total.as_millis() as u64,
+            report: self,
+        })
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+        fs::write(path, json)
+    }
+}
+
+/// Write a full report of a run as JSON to the provided path, whether it succeeded or not
+/// `total` is the total duration of the run, and `warnings` the warnings emitted during it
+pub fn write_run_report(
+    path: impl AsRef<Path>,
+    result: &Result<Report, String>,
+    total: Duration,
+    warnings: Vec<String>,
+) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(&RunReport {
+        succeeded: result.is_ok(),
+        total_ms: total.as_millis() as u64,
+        report: result.as_ref().ok(),
+        warnings,
+        error: result.as_ref().err(),
+    })
+    .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+    fs::write(path, json)
+}
+
+/// Content of a statistics file
+#[derive(Serialize)]
+struct Stats<'a> {
+    total_ms: u64,
+
+    #[serde(flatten)]
+    report: &'a Report,
+}
+
+/// Content of a run report
+#[derive(Serialize)]
+struct RunReport<'a> {
+    succeeded: bool,
+    total_ms: u64,
+
+    #[serde(flatten)]
+    report: Option<&'a Report>,
+
+    warnings: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<&'a String>,
+}
+
+/// Format a duration for display (e.g. "1.250 s")
+pub fn format_duration(elapsed: Duration) -> String {
+    format!("{}.{:03} s", elapsed.as_secs(), elapsed.subsec_millis())
+}
+
+/// Format a number of milliseconds for display (e.g. "1.250 s")
+pub fn format_millis(millis: u64) -> String {
+    format_duration(Duration::from_millis(millis))
+}
+
+/// Format a timestamp in the RFC 3339 format, in UTC
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(format_timestamp(UNIX_EPOCH + Duration::from_secs(951782400)), "2000-02-29T00:00:00Z");
+/// ```
+pub fn format_timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Convert the number of days since the epoch to a civil date
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
@@ -0,0 +1,33 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Are dangerous paths accepted, shared by all actions
+static FORCE_DANGEROUS_PATHS: AtomicBool = AtomicBool::new(false);
+
+/// Accept dangerous paths from now on
+pub fn force_dangerous_paths() {
+    FORCE_DANGEROUS_PATHS.store(true, Ordering::SeqCst);
+}
+
+/// Check if a directory is too dangerous to create or rename many files in, which is usually caused by a typo
+/// The root of a filesystem (or of a drive) and the user's home directory itself are dangerous, unless they were forced
+pub fn is_dangerous_dir(dir: &Path) -> bool {
+    if FORCE_DANGEROUS_PATHS.load(Ordering::SeqCst) {
+        return false;
+    }
+
+    // Resolve symbolic links and relative components like '..'
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+
+    if dir.parent().is_none() {
+        return true;
+    }
+
+    let home = env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).map(|home| {
+        let home = PathBuf::from(home);
+        home.canonicalize().unwrap_or(home)
+    });
+
+    matches!(home, Some(home) if home == dir)
+}
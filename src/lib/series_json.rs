@@ -0,0 +1,59 @@
+use serde_json::json;
+use std::str::FromStr;
+
+/// Name of the series manifest read by comic servers (e.g. Mylar or Komga)
+pub const SERIES_JSON_FILE_NAME: &str = "series.json";
+
+/// Publication status of a series
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeriesStatus {
+    Continuing,
+    Ended,
+}
+
+impl FromStr for SeriesStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "continuing" => Ok(Self::Continuing),
+            "ended" => Ok(Self::Ended),
+            _ => Err(format!(
+                "Unknown series status '{}' (expected 'continuing' or 'ended')",
+                s
+            )),
+        }
+    }
+}
+
+/// Informations about a series to write in its manifest
+pub struct SeriesInfo<'a> {
+    pub name: &'a str,
+    pub publisher: Option<&'a str>,
+    pub year: Option<u16>,
+    pub volumes: usize,
+    pub status: Option<SeriesStatus>,
+}
+
+/// Render the manifest of a series in the format used by Mylar, which is also read by other comic servers
+pub fn render_series_json(info: &SeriesInfo) -> String {
+    let status = info.status.map(|status| match status {
+        SeriesStatus::Continuing => "Continuing",
+        SeriesStatus::Ended => "Ended",
+    });
+
+    let manifest = json!({
+        "version": "1.0.2",
+        "metadata": {
+            "type": "comicSeries",
+            "name": info.name,
+            "publisher": info.publisher,
+            "year": info.year,
+            "booktype": "Print",
+            "total_issues": info.volumes,
+            "status": status,
+        }
+    });
+
+    serde_json::to_string_pretty(&manifest).unwrap_or_else(|_| manifest.to_string())
+}
@@ -0,0 +1,121 @@
+use sevenz_rust::{Password, SevenZArchiveEntry, SevenZReader, SevenZWriter};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Write the entries of a ZIP archive to a 7-Zip archive compressed with LZMA2, keeping their order
+/// Entries are decompressed one by one, so the archive doesn't need to fit in memory
+pub fn zip_to_7z(zip_path: &Path, sevenz_path: &Path) -> Result<(), SevenZErr> {
+    let mut archive = ZipArchive::new(File::open(zip_path).map_err(SevenZErr::IOError)?)
+        .map_err(SevenZErr::InvalidArchive)?;
+
+    let mut writer = SevenZWriter::create(sevenz_path).map_err(SevenZErr::Failed)?;
+
+    let mut buffer = vec![];
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(SevenZErr::InvalidArchive)?;
+
+        let mut sevenz_entry = SevenZArchiveEntry::new();
+        sevenz_entry.name = entry.name().trim_end_matches('/').to_owned();
+
+        if entry.is_dir() {
+            sevenz_entry.is_directory = true;
+
+            writer
+                .push_archive_entry::<&[u8]>(sevenz_entry, None)
+                .map_err(SevenZErr::Failed)?;
+
+            continue;
+        }
+
+        entry.read_to_end(&mut buffer).map_err(SevenZErr::IOError)?;
+
+        sevenz_entry.has_stream = true;
+
+        writer
+            .push_archive_entry(sevenz_entry, Some(buffer.as_slice()))
+            .map_err(SevenZErr::Failed)?;
+
+        buffer.clear();
+    }
+
+    writer.finish().map(|_| ()).map_err(SevenZErr::Failed)
+}
+
+/// List the files of a 7-Zip archive, in their order in the archive
+pub fn list_files(path: &Path) -> Result<Vec<String>, SevenZErr> {
+    let reader = SevenZReader::open(path, Password::empty()).map_err(SevenZErr::Failed)?;
+
+    Ok(reader
+        .archive()
+        .files
+        .iter()
+        .filter(|entry| !entry.is_directory)
+        .map(|entry| entry.name.clone())
+        .collect())
+}
+
+/// Check if a 7-Zip archive is solid, which means some of its blocks contain multiple files compressed together
+/// Files of a solid block can only be read by decompressing all the ones coming before them in this block
+pub fn is_solid(path: &Path) -> Result<bool, SevenZErr> {
+    let reader = SevenZReader::open(path, Password::empty()).map_err(SevenZErr::Failed)?;
+
+    Ok(reader
+        .archive()
+        .folders
+        .iter()
+        .any(|folder| folder.num_unpack_sub_streams > 1))
+}
+
+/// Read the files of a 7-Zip archive in their order in the archive, which is the only efficient one as they are compressed together
+/// `each` is given the name and the content of each file, and what it doesn't read of it is skipped
+pub fn for_each_file(
+    path: &Path,
+    mut each: impl FnMut(&str, &mut dyn Read),
+) -> Result<(), SevenZErr> {
+    let mut reader = SevenZReader::open(path, Password::empty()).map_err(SevenZErr::Failed)?;
+
+    // Error met while skipping the content of a file, which stops the reading
+    let mut failure = None;
+
+    reader
+        .for_each_entries(|entry, content| {
+            if !entry.is_directory {
+                each(&entry.name, content);
+            }
+
+            if let Err(err) = io::copy(content, &mut io::sink()) {
+                failure = Some(err);
+                return Ok(false);
+            }
+
+            Ok(true)
+        })
+        .map_err(SevenZErr::Failed)?;
+
+    match failure {
+        Some(err) => Err(SevenZErr::IOError(err)),
+        None => Ok(()),
+    }
+}
+
+/// 7-Zip archive reading or writing error
+pub enum SevenZErr {
+    IOError(io::Error),
+    InvalidArchive(ZipError),
+    Failed(sevenz_rust::Error),
+}
+
+impl fmt::Display for SevenZErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidArchive(err) => write!(f, "invalid ZIP archive: {}", err),
+            Self::Failed(err) => write!(f, "{}", err),
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::lib::deter;
+use blake2::{Blake2b, Digest};
+use ed25519_dalek::{Keypair, PublicKey, SecretKey, Signature, Signer, Verifier};
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Extension added to a volume's path to get the path of its detached signature, which is the one minisign looks for
+pub const SIGNATURE_EXT: &str = "minisig";
+
+/// Algorithm of minisign signatures made over the BLAKE2b-512 digest of the file
+const PREHASHED_ALG: &[u8; 2] = b"ED";
+
+/// Algorithm of minisign signatures made over the file itself, and of minisign public keys
+const LEGACY_ALG: &[u8; 2] = b"Ed";
+
+/// Load a secret key from a file
+/// The file must either contain the 32 raw bytes of the key or their hexadecimal representation
+pub fn load_secret_key(path: impl AsRef<Path>) -> Result<Keypair, KeyLoadingErr> {
+    let bytes = read_key_file(path)?;
+
+    let secret =
+        SecretKey::from_bytes(&bytes).map_err(|err| KeyLoadingErr::InvalidKey(err.to_string()))?;
+
+    let public = PublicKey::from(&secret);
+
+    Ok(Keypair { secret, public })
+}
+
+/// Load a public key from a file
+/// The file must either contain the 32 raw bytes of the key, their hexadecimal representation or the key in the minisign format
+pub fn load_public_key(path: impl AsRef<Path>) -> Result<PublicKey, KeyLoadingErr> {
+    let content = fs::read(&path).map_err(KeyLoadingErr::IOError)?;
+
+    // Minisign public keys are made of the algorithm, the key's ID and the key itself, optionally preceded by a comment
+    let minisign_key = std::str::from_utf8(&content)
+        .ok()
+        .and_then(|content| {
+            content
+                .lines()
+                .map(str::trim)
+                .rfind(|line| !line.is_empty())
+        })
+        .and_then(|line| base64::decode(line).ok())
+        .filter(|bytes| bytes.len() == 42 && bytes.starts_with(LEGACY_ALG));
+
+    let bytes = match minisign_key {
+        Some(bytes) => bytes[10..].to_vec(),
+        None => read_key_file(path)?,
+    };
+
+    PublicKey::from_bytes(&bytes).map_err(|err| KeyLoadingErr::InvalidKey(err.to_string()))
+}
+
+/// Get a public key in the minisign format, to verify the signatures with minisign
+pub fn minisign_public_key(public_key: &PublicKey) -> String {
+    let mut bytes = LEGACY_ALG.to_vec();
+    bytes.extend_from_slice(&key_id(public_key));
+    bytes.extend_from_slice(public_key.as_bytes());
+
+    base64::encode(bytes)
+}
+
+/// Get the path of a file's detached signature
+pub fn signature_path(path: impl AsRef<Path>) -> PathBuf {
+    deter::add_extension(path, SIGNATURE_EXT)
+}
+
+/// Sign a file and write its detached signature next to it, in the minisign format
+/// What is signed is the BLAKE2b-512 digest of the file, so large volumes don't have to be loaded in memory
+/// The trusted comment, signed along with the signature, contains the signing date and the file's name
+/// Returns the path to the signature file
+pub fn sign_file(keypair: &Keypair, path: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let digest = file_digest(&path)?;
+    let signature: Signature = keypair.sign(&digest);
+
+    let mut signature_bytes = PREHASHED_ALG.to_vec();
+    signature_bytes.extend_from_slice(&key_id(&keypair.public));
+    signature_bytes.extend_from_slice(&signature.to_bytes());
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    let trusted_comment = format!(
+        "timestamp:{}\tfile:{}\thashed",
+        timestamp,
+        path.as_ref()
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+    );
+
+    let mut global_message = signature.to_bytes().to_vec();
+    global_message.extend_from_slice(trusted_comment.as_bytes());
+
+    let global_signature: Signature = keypair.sign(&global_message);
+
+    let sig_path = signature_path(&path);
+
+    fs::write(
+        &sig_path,
+        format!(
+            "untrusted comment: signature from comic-enc secret key\n{}\ntrusted comment: {}\n{}\n",
+            base64::encode(signature_bytes),
+            trusted_comment,
+            base64::encode(global_signature.to_bytes())
+        ),
+    )?;
+
+    Ok(sig_path)
+}
+
+/// Check a file against its detached signature
+pub fn verify_file(
+    public_key: &PublicKey,
+    path: impl AsRef<Path>,
+) -> Result<(), SignatureCheckErr> {
+    let sig_path = signature_path(&path);
+
+    if !sig_path.is_file() {
+        return Err(SignatureCheckErr::MissingSignature(sig_path));
+    }
+
+    let content = fs::read_to_string(&sig_path).map_err(SignatureCheckErr::IOError)?;
+
+    let (alg, signature, trusted_comment, global_signature) =
+        parse_signature_file(&content).ok_or(SignatureCheckErr::InvalidSignatureFile(sig_path))?;
+
+    // Signatures made by minisign without '-H' are made over the file itself
+    let message = if alg == *PREHASHED_ALG {
+        file_digest(&path).map_err(SignatureCheckErr::IOError)?
+    } else {
+        fs::read(&path).map_err(SignatureCheckErr::IOError)?
+    };
+
+    public_key
+        .verify(&message, &signature)
+        .map_err(|_| SignatureCheckErr::BadSignature)?;
+
+    // The trusted comment must not have been modified either
+    let mut global_message = signature.to_bytes().to_vec();
+    global_message.extend_from_slice(trusted_comment.as_bytes());
+
+    public_key
+        .verify(&global_message, &global_signature)
+        .map_err(|_| SignatureCheckErr::BadSignature)
+}
+
+/// Parse a signature file in the minisign format
+/// Returns the signature's algorithm, the signature, the trusted comment and the signature of both
+/// The key's ID isn't checked, as it is only used by minisign to tell keys apart
+fn parse_signature_file(content: &str) -> Option<([u8; 2], Signature, &str, Signature)> {
+    let mut lines = content.lines();
+
+    lines.next()?.strip_prefix("untrusted comment:")?;
+
+    let signature_bytes = base64::decode(lines.next()?.trim()).ok()?;
+
+    if signature_bytes.len() != 74 {
+        return None;
+    }
+
+    let alg = [signature_bytes[0], signature_bytes[1]];
+
+    if alg != *PREHASHED_ALG && alg != *LEGACY_ALG {
+        return None;
+    }
+
+    let signature = Signature::try_from(&signature_bytes[10..]).ok()?;
+
+    let trusted_comment = lines.next()?.strip_prefix("trusted comment: ")?;
+
+    let global_signature =
+        Signature::try_from(base64::decode(lines.next()?.trim()).ok()?.as_slice()).ok()?;
+
+    Some((alg, signature, trusted_comment, global_signature))
+}
+
+/// Get the ID of a key, which minisign stores along with it and its signatures
+/// Keys used by this tool don't have one, so it is derived from the public key
+fn key_id(public_key: &PublicKey) -> [u8; 8] {
+    let mut id = [0; 8];
+    id.copy_from_slice(&Blake2b::digest(public_key.as_bytes())[..8]);
+    id
+}
+
+/// Encode bytes as a lowercase hexadecimal string
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Decode an hexadecimal string
+/// Returns `None` if the string is not a valid hexadecimal representation
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Compute the BLAKE2b-512 digest of a file
+fn file_digest(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let mut hasher = Blake2b::new();
+    let mut buffer = [0; 64 * 1024];
+
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Read a key file, either raw or hex-encoded
+fn read_key_file(path: impl AsRef<Path>) -> Result<Vec<u8>, KeyLoadingErr> {
+    let content = fs::read(path).map_err(KeyLoadingErr::IOError)?;
+
+    if content.len() == 32 {
+        return Ok(content);
+    }
+
+    std::str::from_utf8(&content)
+        .ok()
+        .and_then(|hex| from_hex(hex.trim()))
+        .ok_or_else(|| {
+            KeyLoadingErr::InvalidKey(
+                "key file must contain either 32 raw bytes or their hexadecimal representation"
+                    .to_string(),
+            )
+        })
+}
+
+/// Key loading error
+pub enum KeyLoadingErr {
+    IOError(io::Error),
+    InvalidKey(String),
+}
+
+/// Signature check error
+pub enum SignatureCheckErr {
+    IOError(io::Error),
+    MissingSignature(PathBuf),
+    InvalidSignatureFile(PathBuf),
+    BadSignature,
+}
@@ -0,0 +1,191 @@
+use crate::lib::deter;
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Precomputed key to sort a path in natural order
+/// Comparing two paths with "deter::natural_paths_cmp" lowercases all their components each time,
+/// so the key does it once and for all when sorting many paths
+/// Keys of different paths never compare as equal, ties being broken by comparing the original paths byte by byte
+#[derive(Debug, Clone)]
+pub struct NaturalKey {
+    /// Lowercased components of the path
+    components: Vec<String>,
+
+    /// Original path, to break ties
+    raw: OsString,
+}
+
+impl NaturalKey {
+    /// Compute the key of a path
+    /// With `roman_numerals`, roman numerals written as separate words are compared by their value
+    pub fn new(path: &Path, roman_numerals: bool) -> Self {
+        Self {
+            components: path
+                .components()
+                .map(|component| {
+                    let component = component.as_os_str().to_string_lossy().to_lowercase();
+
+                    if roman_numerals {
+                        replace_roman_numerals(&component)
+                    } else {
+                        component
+                    }
+                })
+                .collect(),
+            raw: path.as_os_str().to_os_string(),
+        }
+    }
+
+    /// Compute the key of a single name (e.g. a directory's name)
+    pub fn from_name(name: &str) -> Self {
+        Self {
+            components: vec![name.to_lowercase()],
+            raw: OsString::from(name),
+        }
+    }
+}
+
+impl Ord for NaturalKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let mut a = self.components.iter();
+        let mut b = other.components.iter();
+
+        loop {
+            return match (a.next(), b.next()) {
+                (Some(a_cp), Some(b_cp)) => match deter::natural_cmp_lowercase(a_cp, b_cp) {
+                    Ordering::Equal => continue,
+                    ordering => ordering,
+                },
+                (Some(_), None) => Ordering::Greater,
+                (None, Some(_)) => Ordering::Less,
+                (None, None) => self.raw.cmp(&other.raw),
+            };
+        }
+    }
+}
+
+impl PartialEq for NaturalKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for NaturalKey {}
+
+impl PartialOrd for NaturalKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Sort items in the natural order of their path
+/// Each path's key is only computed once, which makes sorting hundreds of thousands of pages much faster
+/// The resulting order is the same on all platforms, as different paths never compare as equal
+/// Items with identical paths keep their original order
+/// With `roman_numerals`, roman numerals written as separate words are sorted by their value (e.g. "Part IV" before "Part V")
+pub fn natural_sort_by_path<T>(items: &mut [T], path: impl Fn(&T) -> &Path, roman_numerals: bool) {
+    items.sort_by_cached_key(|item| NaturalKey::new(path(item), roman_numerals))
+}
+
+/// Sort paths in natural order
+/// See the "natural_sort_by_path" function for more informations
+pub fn natural_sort_paths<P: AsRef<Path>>(paths: &mut [P], roman_numerals: bool) {
+    natural_sort_by_path(paths, |path| path.as_ref(), roman_numerals)
+}
+
+/// Sort names in natural order
+/// See the "natural_sort_by_path" function for more informations
+pub fn natural_sort_names<S: AsRef<str>>(names: &mut [S]) {
+    names.sort_by_cached_key(|name| NaturalKey::from_name(name.as_ref()))
+}
+
+/// Roman numerals symbols, from the highest to the lowest value, including subtractive pairs
+const ROMAN_SYMBOLS: [(u32, &str); 13] = [
+    (1000, "m"),
+    (900, "cm"),
+    (500, "d"),
+    (400, "cd"),
+    (100, "c"),
+    (90, "xc"),
+    (50, "l"),
+    (40, "xl"),
+    (10, "x"),
+    (9, "ix"),
+    (5, "v"),
+    (4, "iv"),
+    (1, "i"),
+];
+
+/// Replace the roman numerals written as separate words in a lowercased name by their value
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(replace_roman_numerals("part iv - vivid"), "part 4 - vivid");
+/// ```
+fn replace_roman_numerals(name: &str) -> String {
+    let mut replaced = String::with_capacity(name.len());
+    let mut word = String::new();
+
+    for c in name.chars() {
+        if c.is_alphanumeric() {
+            word.push(c);
+            continue;
+        }
+
+        push_word(&mut replaced, &word);
+        word.clear();
+        replaced.push(c);
+    }
+
+    push_word(&mut replaced, &word);
+    replaced
+}
+
+/// Push a word to a name, replacing it by its value if it is a roman numeral
+fn push_word(name: &mut String, word: &str) {
+    match roman_value(word) {
+        Some(value) => name.push_str(&value.to_string()),
+        None => name.push_str(word),
+    }
+}
+
+/// Get the value of a lowercased roman numeral
+/// Only numerals written in their canonical form are accepted (e.g. "iv" but not "iiii"),
+/// so most regular words made of the same letters (e.g. "did" or "mild") are not considered as numerals
+fn roman_value(word: &str) -> Option<u32> {
+    if word.is_empty() {
+        return None;
+    }
+
+    let mut value = 0;
+    let mut rest = word;
+
+    for (symbol_value, symbol) in ROMAN_SYMBOLS.iter() {
+        while let Some(stripped) = rest.strip_prefix(symbol) {
+            value += symbol_value;
+            rest = stripped;
+        }
+    }
+
+    if !rest.is_empty() || to_roman(value) != word {
+        return None;
+    }
+
+    Some(value)
+}
+
+/// Write a number as a lowercased roman numeral, in its canonical form
+fn to_roman(mut value: u32) -> String {
+    let mut numeral = String::new();
+
+    for (symbol_value, symbol) in ROMAN_SYMBOLS.iter() {
+        while value >= *symbol_value {
+            numeral.push_str(symbol);
+            value -= symbol_value;
+        }
+    }
+
+    numeral
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Names of the metadata files that can be put in chapter or series directories, by order of preference
+pub const SOURCE_METADATA_FILE_NAMES: [&str; 5] = [
+    "metadata.json",
+    "metadata.yaml",
+    "metadata.yml",
+    "book.yaml",
+    "book.yml",
+];
+
+/// Metadata read from a file put in a chapter or series directory (e.g. by a scraper)
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SourceMetadata {
+    /// Title of the chapter or of the series
+    pub title: Option<String>,
+
+    /// Number of the chapter
+    pub number: Option<f64>,
+
+    /// Translator of the chapter or of the series
+    pub translator: Option<String>,
+
+    /// Language of the chapter or of the series (e.g. 'en')
+    pub language: Option<String>,
+
+    /// Identifier of the series on AniList
+    #[serde(deserialize_with = "deserialize_id")]
+    pub anilist_id: Option<String>,
+
+    /// Identifier of the series on MangaDex
+    #[serde(deserialize_with = "deserialize_id")]
+    pub mangadex_id: Option<String>,
+
+    /// Identifier of the series on ComicVine
+    #[serde(deserialize_with = "deserialize_id")]
+    pub comicvine_id: Option<String>,
+}
+
+/// Deserialize an identifier provided either as a number or as a string
+fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        Number(u64),
+        Text(String),
+    }
+
+    Ok(Option::<Id>::deserialize(deserializer)?.map(|id| match id {
+        Id::Number(number) => number.to_string(),
+        Id::Text(text) => text,
+    }))
+}
+
+impl SourceMetadata {
+    /// Load the metadata file of a directory, if any
+    /// Returns `None` if the path is not a directory or if it doesn't contain any metadata file
+    pub fn load(dir: &Path) -> Result<Option<Self>, SourceMetadataErr> {
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+
+        let path = match SOURCE_METADATA_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+        {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+
+        trace!("Reading metadata file '{}'...", path.to_string_lossy());
+
+        let content = fs::read_to_string(&path)
+            .map_err(|err| SourceMetadataErr::FailedToRead(path.clone(), err))?;
+
+        let is_json = path.extension().and_then(|ext| ext.to_str()) == Some("json");
+
+        let metadata = if is_json {
+            serde_json::from_str(&content).map_err(|err| err.to_string())
+        } else {
+            serde_yaml::from_str(&content).map_err(|err| err.to_string())
+        };
+
+        metadata
+            .map(Some)
+            .map_err(|err| SourceMetadataErr::InvalidContent(path, err))
+    }
+
+    /// Get the chapter's number as it should be displayed (e.g. '12' or '12.5')
+    pub fn display_number(&self) -> Option<String> {
+        self.number.map(|number| number.to_string())
+    }
+
+    /// Get the name to give to a volume made of this chapter, if it has a title
+    /// The name is made of the chapter's number (if any) and its title, without characters that aren't allowed in file names
+    pub fn volume_name(&self) -> Option<String> {
+        let title = self.title.as_ref()?;
+
+        let name = match self.display_number() {
+            Some(number) => format!("{} - {}", number, title),
+            None => title.clone(),
+        };
+
+        Some(sanitize_file_name(&name))
+    }
+}
+
+/// Replace the characters that aren't allowed in file names on all platforms
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(sanitize_file_name("What? Why: Because"), "What_ Why_ Because");
+/// ```
+pub fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim_end_matches(|c| c == ' ' || c == '.')
+        .trim_start()
+        .to_string()
+}
+
+/// Metadata file loading error
+pub enum SourceMetadataErr {
+    FailedToRead(PathBuf, io::Error),
+    InvalidContent(PathBuf, String),
+}
+
+impl fmt::Display for SourceMetadataErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FailedToRead(path, err) => write!(
+                f,
+                "failed to read metadata file '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+
+            Self::InvalidContent(path, err) => write!(
+                f,
+                "invalid metadata file '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::ops::Range;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Size of the blocks of a TAR archive
+const BLOCK_SIZE: usize = 512;
+
+/// Largest size of an entry in a TAR archive, as it is written with 11 octal digits
+const MAX_ENTRY_SIZE: u64 = 0o77777777777;
+
+/// Write the entries of a ZIP archive to an uncompressed TAR archive (in the USTAR format), keeping their order
+/// Entries are decompressed one by one, so the archive doesn't need to fit in memory
+pub fn zip_to_tar(zip_path: &Path, tar_path: &Path) -> Result<(), TarErr> {
+    let mut archive = ZipArchive::new(File::open(zip_path).map_err(TarErr::IOError)?)
+        .map_err(TarErr::InvalidArchive)?;
+
+    let mut tar = BufWriter::new(File::create(tar_path).map_err(TarErr::IOError)?);
+
+    // All entries get the same modification date
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    let mut buffer = vec![];
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(TarErr::InvalidArchive)?;
+        let name = entry.name().to_owned();
+
+        if entry.is_dir() {
+            tar.write_all(&header(&name, 0, mtime, true)?)
+                .map_err(TarErr::IOError)?;
+
+            continue;
+        }
+
+        entry.read_to_end(&mut buffer).map_err(TarErr::IOError)?;
+
+        if buffer.len() as u64 > MAX_ENTRY_SIZE {
+            return Err(TarErr::EntryTooLarge(name));
+        }
+
+        tar.write_all(&header(&name, buffer.len() as u64, mtime, false)?)
+            .and_then(|()| tar.write_all(&buffer))
+            .and_then(|()| tar.write_all(&vec![0; padding(buffer.len())]))
+            .map_err(TarErr::IOError)?;
+
+        buffer.clear();
+    }
+
+    // The archive ends with two empty blocks
+    tar.write_all(&[0; BLOCK_SIZE * 2])
+        .and_then(|()| tar.flush())
+        .map_err(TarErr::IOError)
+}
+
+/// Read the files of a TAR archive in their order in the archive
+/// `each` is given the name and the content of each file, and what it doesn't read of it is skipped
+/// Headers are checked against their checksum, but the content of the entries has none
+pub fn for_each_file(path: &Path, mut each: impl FnMut(&str, &mut dyn Read)) -> Result<(), TarErr> {
+    let mut tar = BufReader::new(File::open(path).map_err(TarErr::IOError)?);
+    let mut header = [0; BLOCK_SIZE];
+
+    loop {
+        tar.read_exact(&mut header).map_err(TarErr::IOError)?;
+
+        // The archive ends with empty blocks
+        if header.iter().all(|byte| *byte == 0) {
+            return Ok(());
+        }
+
+        let (name, size, is_file) = parse_header(&header)?;
+
+        let mut content = (&mut tar).take(size);
+
+        if is_file {
+            each(&name, &mut content);
+        }
+
+        // Skip what wasn't read, up to the end of the entry's last block
+        io::copy(&mut content, &mut io::sink()).map_err(TarErr::IOError)?;
+
+        io::copy(
+            &mut (&mut tar).take(padding(size as usize) as u64),
+            &mut io::sink(),
+        )
+        .map_err(TarErr::IOError)?;
+    }
+}
+
+/// Parse the header block of an entry
+/// Returns its name, the size of its content and if it's a regular file
+fn parse_header(header: &[u8; BLOCK_SIZE]) -> Result<(String, u64, bool), TarErr> {
+    // The checksum is computed with its own field filled with spaces
+    let checksum: u32 = header
+        .iter()
+        .enumerate()
+        .map(|(i, byte)| {
+            if (148..156).contains(&i) {
+                u32::from(b' ')
+            } else {
+                u32::from(*byte)
+            }
+        })
+        .sum();
+
+    if parse_octal(&header[148..156]) != Some(u64::from(checksum)) {
+        return Err(TarErr::InvalidHeader);
+    }
+
+    let size = parse_octal(&header[124..136]).ok_or(TarErr::InvalidHeader)?;
+
+    let field = |range: Range<usize>| {
+        let bytes = &header[range];
+        let len = bytes
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..len]).into_owned()
+    };
+
+    let (prefix, name) = (field(345..500), field(0..100));
+
+    let name = if prefix.is_empty() {
+        name
+    } else {
+        format!("{}/{}", prefix, name)
+    };
+
+    Ok((name, size, matches!(header[156], b'0' | 0)))
+}
+
+/// Parse a numeric field of a header, written in octal digits followed by spaces or null bytes
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let digits = String::from_utf8_lossy(field);
+    let digits = digits.trim_matches(|c: char| c == ' ' || c == '\0');
+
+    u64::from_str_radix(digits, 8).ok()
+}
+
+/// Build the header block of an entry
+/// Names longer than 100 bytes are split between the name and prefix fields
+fn header(name: &str, size: u64, mtime: u64, is_dir: bool) -> Result<[u8; BLOCK_SIZE], TarErr> {
+    let mut header = [0; BLOCK_SIZE];
+
+    let (prefix, name_field) =
+        split_name(name).ok_or_else(|| TarErr::NameTooLong(name.to_owned()))?;
+
+    header[..name_field.len()].copy_from_slice(name_field.as_bytes());
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    let mode: &[u8] = if is_dir { b"0000755\0" } else { b"0000644\0" };
+    header[100..108].copy_from_slice(mode);
+
+    // Owner and group
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(format!("{:011o}\0", mtime).as_bytes());
+
+    header[156] = if is_dir { b'5' } else { b'0' };
+
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+
+    let checksum: u32 = header.iter().map(|byte| u32::from(*byte)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    Ok(header)
+}
+
+/// Split an entry's name into the prefix and name fields of its header
+/// Returns `None` if the name is too long to be stored
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(split_name("Vol_1/Pic_01.jpg"), Some(("", "Vol_1/Pic_01.jpg")));
+/// ```
+fn split_name(name: &str) -> Option<(&str, &str)> {
+    if name.len() <= 100 {
+        return Some(("", name));
+    }
+
+    // The name must be split at a slash, which is not stored
+    name.match_indices('/')
+        .map(|(index, _)| (&name[..index], &name[index + 1..]))
+        .find(|(prefix, name)| prefix.len() <= 155 && name.len() <= 100 && !name.is_empty())
+}
+
+/// Get the number of bytes to add after an entry's content to fill its last block
+fn padding(size: usize) -> usize {
+    (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE
+}
+
+/// TAR archive reading or writing error
+pub enum TarErr {
+    IOError(io::Error),
+    InvalidArchive(ZipError),
+    NameTooLong(String),
+    EntryTooLarge(String),
+    InvalidHeader,
+}
+
+impl fmt::Display for TarErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IOError(err) => write!(f, "{}", err),
+            Self::InvalidArchive(err) => write!(f, "invalid ZIP archive: {}", err),
+            Self::NameTooLong(name) => write!(f, "entry name '{}' is too long", name),
+            Self::EntryTooLarge(name) => write!(f, "entry '{}' is too large", name),
+            Self::InvalidHeader => write!(f, "invalid entry header"),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::lib::comic_info;
+use regex::Regex;
+
+/// Name of the table of contents written at the root of the volumes
+pub const TOC_FILE_NAME: &str = "Contents.html";
+
+/// Default pattern to parse chapter titles from the chapters' names (e.g. 'Ch 012 - The Long Night')
+pub const DEFAULT_CHAPTER_TITLE_PATTERN: &str =
+    r"^(?i:ch(?:apter|ap)?\.?)?\s*(?P<number>\d+(?:\.\d+)?)\s*(?:[-_:.]\s*(?P<title>.*\S))?\s*$";
+
+/// A chapter listed in a table of contents
+pub struct TocEntry {
+    /// Name of the chapter
+    pub title: String,
+
+    /// Number of the chapter's first page in the volume, starting at 1
+    pub first_page: usize,
+}
+
+/// Parse the title of a chapter from its name, using a pattern with a `title` and an optional `number` named group
+/// Returns `None` if the name doesn't match the pattern
+///
+/// # Examples
+///
+/// ```
+/// let pattern = Regex::new(DEFAULT_CHAPTER_TITLE_PATTERN).unwrap();
+/// assert_eq!(chapter_title("Ch 012 - The Long Night", &pattern), Some("Chapter 12: The Long Night".to_owned()));
+/// assert_eq!(chapter_title("Chapter 3", &pattern), Some("Chapter 3".to_owned()));
+/// assert_eq!(chapter_title("Extras", &pattern), None);
+/// ```
+pub fn chapter_title(name: &str, pattern: &Regex) -> Option<String> {
+    let captures = pattern.captures(name)?;
+
+    let title = captures
+        .name("title")
+        .map(|title| title.as_str().trim())
+        .filter(|title| !title.is_empty());
+
+    // Leading zeros are only there to sort the chapters' directories
+    let number = captures.name("number").map(|number| {
+        let number = number.as_str().trim_start_matches('0');
+
+        if number.is_empty() || number.starts_with('.') {
+            format!("0{}", number)
+        } else {
+            number.to_owned()
+        }
+    });
+
+    match (number, title) {
+        (Some(number), Some(title)) => Some(format!("Chapter {}: {}", number, title)),
+        (Some(number), None) => Some(format!("Chapter {}", number)),
+        (None, Some(title)) => Some(title.to_owned()),
+        (None, None) => None,
+    }
+}
+
+/// Render the table of contents of a volume as a standalone HTML page
+pub fn render_toc_html(title: &str, entries: &[TocEntry]) -> String {
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            format!(
+                "      <tr><td>{}</td><td class=\"page\">{}</td></tr>\n",
+                comic_info::escape(&entry.title),
+                entry.first_page
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n  <head>\n    <meta charset=\"utf-8\">\n    <title>{title}</title>\n    <style>body {{ font-family: sans-serif; }} td {{ padding: 0.2em 1em; }} .page {{ text-align: right; }}</style>\n  </head>\n  <body>\n    <h1>{title}</h1>\n    <table>\n      <tr><th>Chapter</th><th>Page</th></tr>\n{rows}    </table>\n  </body>\n</html>\n",
+        title = comic_info::escape(title),
+        rows = rows
+    )
+}
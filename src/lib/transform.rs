@@ -0,0 +1,905 @@
+use crate::lib::image_meta;
+use color_quant::NeuQuant;
+use image::codecs::png::PngDecoder;
+use image::error::{
+    DecodingError, EncodingError, ImageFormatHint, LimitError, LimitErrorKind, UnsupportedError,
+    UnsupportedErrorKind,
+};
+use image::imageops::colorops::ColorMap;
+use image::imageops::{self, FilterType};
+use image::io::{Limits, Reader as ImageReader};
+use image::{
+    DynamicImage, GrayAlphaImage, GrayImage, ImageDecoder, ImageError, ImageFormat,
+    ImageOutputFormat, RgbImage, RgbaImage,
+};
+use jpeg_decoder::{Decoder as JpegDecoder, PixelFormat};
+use jxl_oxide::JxlImage;
+use qcms::{DataType, Intent, Profile, Transform};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io::Cursor;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+/// Quality of the JPEG pictures re-encoded after being transformed
+const JPEG_QUALITY: u8 = 90;
+
+/// Sampling factor of the palette computation when quantizing pictures, from 1 (slowest, best) to 30 (fastest)
+const NEUQUANT_SAMPLE_FACTOR: i32 = 10;
+
+/// Maximum difference between the channels of a pixel for it to be considered gray
+const GRAYSCALE_TOLERANCE: u8 = 12;
+
+/// Maximum proportion of colored pixels in a picture considered to have no color, to tolerate noise and small stains
+const MAX_COLORED_PIXELS_RATIO: f64 = 0.001;
+
+/// Default maximum difference between the margins' pixels and their color, from 0 to 255
+pub const DEFAULT_TRIM_THRESHOLD: u8 = 16;
+
+/// Maximum proportion of pixels of a margin's row or column which may differ from its color, to tolerate dust
+const MAX_MARGIN_NOISE_RATIO: f64 = 0.005;
+
+/// Minimum proportion of a picture's width and height kept when trimming its margins, to keep (almost) blank pages as they are
+const MIN_TRIMMED_RATIO: f64 = 0.2;
+
+/// Quality of the pages converted to lossy WebP, from 0 to 100
+const WEBP_QUALITY: f32 = 80.0;
+
+/// Width and height of the grayscale thumbnail perceptual hashes are computed from, each of its rows providing 8 bits
+const PERCEPTUAL_HASH_SIZE: u32 = 8;
+
+/// Minimum difference between the darkest and lightest pixels of a thumbnail for its picture to be hashed,
+///  as (almost) uniform pages such as blank ones all have the same hash
+const MIN_PERCEPTUAL_HASH_CONTRAST: u8 = 8;
+
+/// Default maximum number of differing bits between the perceptual hashes of near-duplicate pages, out of 64
+pub const DEFAULT_NEAR_DUPLICATE_DISTANCE: u32 = 4;
+
+/// Largest height of a JPEG picture (in pixels), used as the default maximum height of the slices of tall pages
+pub const MAX_JPEG_DIMENSION: u32 = 65500;
+
+/// Default maximum width and height of the decoded pages, in pixels, which lets very tall webtoon strips through
+pub const DEFAULT_MAX_PAGE_DIMENSION: u32 = 65535;
+
+/// Default maximum memory a decoded page may use, in megabytes
+pub const DEFAULT_MAX_PAGE_MEMORY_MB: u64 = 1024;
+
+/// Maximum width and height of the decoded pages, shared by all actions
+static MAX_PAGE_DIMENSION: AtomicU32 = AtomicU32::new(DEFAULT_MAX_PAGE_DIMENSION);
+
+/// Maximum memory a decoded page may use, in megabytes, shared by all actions
+static MAX_PAGE_MEMORY_MB: AtomicU64 = AtomicU64::new(DEFAULT_MAX_PAGE_MEMORY_MB);
+
+/// Signature of a bare JPEG XL codestream
+const JXL_CODESTREAM_SIGNATURE: &[u8] = &[0xFF, 0x0A];
+
+/// Signature of a JPEG XL picture in its container format
+const JXL_CONTAINER_SIGNATURE: &[u8] = b"\0\0\0\x0CJXL \r\n\x87\n";
+
+/// Format pages can be converted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageFormat {
+    Jpeg,
+    Png,
+    Webp,
+    WebpLossless,
+}
+
+impl PageFormat {
+    /// Get the extension of the pictures in this format
+    pub fn ext(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Webp | Self::WebpLossless => "webp",
+        }
+    }
+
+    /// Check if a picture's extension is one of this format
+    pub fn has_ext(self, ext: &str) -> bool {
+        match self {
+            Self::Jpeg => is_jpeg_ext(ext),
+            Self::Png => ext.eq_ignore_ascii_case("png"),
+            Self::Webp | Self::WebpLossless => ext.eq_ignore_ascii_case("webp"),
+        }
+    }
+
+    /// Get the name of this format, as provided on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Webp => "webp",
+            Self::WebpLossless => "webp-lossless",
+        }
+    }
+}
+
+impl fmt::Display for PageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Jpeg => "JPEG",
+                Self::Png => "PNG",
+                Self::Webp => "WebP",
+                Self::WebpLossless => "lossless WebP",
+            }
+        )
+    }
+}
+
+impl FromStr for PageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "jpg" | "jpeg" => Ok(Self::Jpeg),
+            "png" => Ok(Self::Png),
+            "webp" => Ok(Self::Webp),
+            "webp-lossless" => Ok(Self::WebpLossless),
+            _ => Err(format!("Unknown page format '{}'", s)),
+        }
+    }
+}
+
+/// Order pages are read in, used to put the halves of double-page spreads in the right order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReadingDirection {
+    Ltr,
+    Rtl,
+}
+
+impl ReadingDirection {
+    /// Get the name of this reading direction, as provided on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ltr => "ltr",
+            Self::Rtl => "rtl",
+        }
+    }
+}
+
+impl FromStr for ReadingDirection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ltr" => Ok(Self::Ltr),
+            "rtl" => Ok(Self::Rtl),
+            _ => Err(format!("Unknown reading direction '{}'", s)),
+        }
+    }
+}
+
+/// Color space pages can be converted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+}
+
+impl ColorSpace {
+    /// Get the name of this color space, as provided on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Srgb => "srgb",
+        }
+    }
+}
+
+impl FromStr for ColorSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "srgb" => Ok(Self::Srgb),
+            _ => Err(format!("Unknown color space '{}' (expected 'srgb')", s)),
+        }
+    }
+}
+
+/// Set the limits pages are decoded with from now on, so a malicious or corrupted page cannot exhaust the memory
+pub fn set_decoder_limits(max_dimension: u32, max_memory_mb: u64) {
+    MAX_PAGE_DIMENSION.store(max_dimension, Ordering::SeqCst);
+    MAX_PAGE_MEMORY_MB.store(max_memory_mb, Ordering::SeqCst);
+}
+
+/// Get the limits pages are decoded with
+fn decoder_limits() -> Limits {
+    let max_dimension = MAX_PAGE_DIMENSION.load(Ordering::SeqCst);
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(max_dimension);
+    limits.max_image_height = Some(max_dimension);
+    limits.max_alloc = Some(MAX_PAGE_MEMORY_MB.load(Ordering::SeqCst) * 1024 * 1024);
+    limits
+}
+
+/// Decode a picture in the provided format (or in the one guessed from its content), within the decoder limits
+fn decode(content: &[u8], format: Option<ImageFormat>) -> Result<DynamicImage, ImageError> {
+    decode_within(content, format, decoder_limits())
+}
+
+/// Decode a picture in the provided format (or in the one guessed from its content), within the provided limits
+fn decode_within(
+    content: &[u8],
+    format: Option<ImageFormat>,
+    limits: Limits,
+) -> Result<DynamicImage, ImageError> {
+    let mut reader = ImageReader::new(Cursor::new(content));
+
+    match format {
+        Some(format) => reader.set_format(format),
+        None => reader = reader.with_guessed_format()?,
+    }
+
+    reader.limits(limits);
+    reader.decode()
+}
+
+/// Check if a picture's extension is the one of a JPEG picture
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(is_jpeg_ext("JPG"), true);
+/// assert_eq!(is_jpeg_ext("png"), false);
+/// ```
+pub fn is_jpeg_ext(ext: &str) -> bool {
+    matches!(ext.to_lowercase().as_str(), "jpg" | "jpeg")
+}
+
+/// Downscale a picture so neither its width nor its height exceeds the provided number of pixels, keeping its aspect ratio
+/// The picture is re-encoded in its original format
+pub fn downscale(content: &[u8], max_dimension: u32) -> Result<Vec<u8>, ImageError> {
+    let format = image::guess_format(content)?;
+    let picture = decode(content, Some(format))?;
+
+    let downscaled = picture.resize(max_dimension, max_dimension, FilterType::Lanczos3);
+
+    encode_as(&downscaled, format)
+}
+
+/// Rotate and flip a picture as its EXIF orientation (from 1 to 8) asks readers to
+/// The picture is re-encoded in its original format, without its EXIF metadata
+pub fn apply_orientation(content: &[u8], orientation: u16) -> Result<Vec<u8>, ImageError> {
+    let format = image::guess_format(content)?;
+    let picture = decode(content, Some(format))?;
+
+    let oriented = match orientation {
+        2 => picture.fliph(),
+        3 => picture.rotate180(),
+        4 => picture.flipv(),
+        5 => picture.rotate90().fliph(),
+        6 => picture.rotate90(),
+        7 => picture.rotate270().fliph(),
+        8 => picture.rotate270(),
+        _ => picture,
+    };
+
+    encode_as(&oriented, format)
+}
+
+/// Crop the uniform margins around a picture, which are the rows and columns of the color of its top-left pixel
+///  (within the provided threshold), re-encoded in its original format
+/// Returns `None` if the picture has no margin, is (almost) blank or its format cannot be encoded (e.g. AVIF)
+pub fn trim_margins(content: &[u8], threshold: u8) -> Result<Option<Vec<u8>>, ImageError> {
+    let format = match image::guess_format(content) {
+        Ok(format) if is_encodable(format) => format,
+        _ => return Ok(None),
+    };
+
+    let picture = decode(content, Some(format))?;
+    let luma = picture.to_luma8();
+    let (width, height) = luma.dimensions();
+
+    if width == 0 || height == 0 {
+        return Ok(None);
+    }
+
+    let margin_color = i16::from(luma.get_pixel(0, 0).0[0]);
+
+    // Check if a line of pixels is part of the margins
+    let is_margin = |pixels: &mut dyn Iterator<Item = u8>, len: u32| {
+        let max_noise = (f64::from(len) * MAX_MARGIN_NOISE_RATIO) as usize;
+
+        pixels
+            .filter(|pixel| (i16::from(*pixel) - margin_color).abs() > i16::from(threshold))
+            .nth(max_noise)
+            .is_none()
+    };
+
+    let is_margin_row =
+        |y: u32| is_margin(&mut (0..width).map(|x| luma.get_pixel(x, y).0[0]), width);
+    let is_margin_col =
+        |x: u32| is_margin(&mut (0..height).map(|y| luma.get_pixel(x, y).0[0]), height);
+
+    let top = (0..height).find(|y| !is_margin_row(*y)).unwrap_or(height);
+    let bottom = (top..height)
+        .rev()
+        .find(|y| !is_margin_row(*y))
+        .map_or(top, |y| y + 1);
+    let left = (0..width).find(|x| !is_margin_col(*x)).unwrap_or(width);
+    let right = (left..width)
+        .rev()
+        .find(|x| !is_margin_col(*x))
+        .map_or(left, |x| x + 1);
+
+    let (trimmed_width, trimmed_height) = (right.saturating_sub(left), bottom.saturating_sub(top));
+
+    if (trimmed_width, trimmed_height) == (width, height)
+        || f64::from(trimmed_width) < f64::from(width) * MIN_TRIMMED_RATIO
+        || f64::from(trimmed_height) < f64::from(height) * MIN_TRIMMED_RATIO
+    {
+        return Ok(None);
+    }
+
+    let trimmed = picture.crop_imm(left, top, trimmed_width, trimmed_height);
+
+    encode_as(&trimmed, format).map(Some)
+}
+
+/// Convert a picture to grayscale, re-encoded in its original format
+/// If `only_if_colorless` is set, pictures with visible colors are left untouched
+/// Returns `None` if the picture is left untouched, is already in grayscale or its format cannot be encoded (e.g. AVIF)
+pub fn grayscale(content: &[u8], only_if_colorless: bool) -> Result<Option<Vec<u8>>, ImageError> {
+    let format = match image::guess_format(content) {
+        Ok(format) if is_encodable(format) => format,
+        _ => return Ok(None),
+    };
+
+    let picture = decode(content, Some(format))?;
+
+    if !picture.color().has_color() || (only_if_colorless && has_visible_colors(&picture)) {
+        return Ok(None);
+    }
+
+    let gray = if picture.color().has_alpha() {
+        DynamicImage::ImageLumaA8(picture.to_luma_alpha8())
+    } else {
+        DynamicImage::ImageLuma8(picture.to_luma8())
+    };
+
+    encode_as(&gray, format).map(Some)
+}
+
+/// Check if a picture has visible colors, which is the case if enough of its pixels are not gray
+fn has_visible_colors(picture: &DynamicImage) -> bool {
+    let rgb = picture.to_rgb8();
+    let max_colored =
+        (f64::from(rgb.width()) * f64::from(rgb.height()) * MAX_COLORED_PIXELS_RATIO) as usize;
+
+    rgb.pixels()
+        .filter(|pixel| {
+            let [r, g, b] = pixel.0;
+            r.max(g).max(b) - r.min(g).min(b) > GRAYSCALE_TOLERANCE
+        })
+        .nth(max_colored)
+        .is_some()
+}
+
+/// Reduce a picture to the provided number of colors (from 2 to 256), encoded as PNG which compresses such pictures best
+/// Pictures with (almost) no color are reduced to evenly spaced levels of gray (e.g. 16 levels for line art),
+///  the other ones to a palette of the colors which represent them best
+pub fn quantize(content: &[u8], colors: u16) -> Result<Vec<u8>, ImageError> {
+    let colors = colors.max(2).min(256);
+    let picture = load_picture(content)?;
+
+    let quantized = if !picture.color().has_color() || !has_visible_colors(&picture) {
+        let step = 255.0 / f64::from(colors - 1);
+
+        let mut gray = picture.to_luma_alpha8();
+
+        for pixel in gray.pixels_mut() {
+            pixel.0[0] = ((f64::from(pixel.0[0]) / step).round() * step).round() as u8;
+        }
+
+        if picture.color().has_alpha() {
+            DynamicImage::ImageLumaA8(gray)
+        } else {
+            DynamicImage::ImageLuma8(DynamicImage::ImageLumaA8(gray).to_luma8())
+        }
+    } else {
+        let mut rgba = picture.to_rgba8();
+        let palette = NeuQuant::new(NEUQUANT_SAMPLE_FACTOR, usize::from(colors), &rgba);
+
+        for pixel in rgba.pixels_mut() {
+            palette.map_color(pixel);
+        }
+
+        if picture.color().has_alpha() {
+            DynamicImage::ImageRgba8(rgba)
+        } else {
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(rgba).to_rgb8())
+        }
+    };
+
+    let mut output = Cursor::new(vec![]);
+    quantized.write_to(&mut output, ImageOutputFormat::Png)?;
+
+    Ok(output.into_inner())
+}
+
+/// Split a landscape picture (wider than tall) in two halves, returned in reading order
+/// Returns `None` if the picture isn't a landscape one. The halves are re-encoded in the picture's original format
+pub fn split_spread(
+    content: &[u8],
+    direction: ReadingDirection,
+) -> Result<Option<(Vec<u8>, Vec<u8>)>, ImageError> {
+    // Read the dimensions first to avoid decoding portrait pictures
+    let (width, height) = ImageReader::new(Cursor::new(content))
+        .with_guessed_format()?
+        .into_dimensions()?;
+
+    if width <= height {
+        return Ok(None);
+    }
+
+    let format = image::guess_format(content)?;
+    let picture = decode(content, Some(format))?;
+
+    let half = width / 2;
+    let left = encode_as(&picture.crop_imm(0, 0, half, height), format)?;
+    let right = encode_as(&picture.crop_imm(half, 0, width - half, height), format)?;
+
+    Ok(Some(match direction {
+        ReadingDirection::Ltr => (left, right),
+        ReadingDirection::Rtl => (right, left),
+    }))
+}
+
+/// Slice a tall picture (e.g. a webtoon strip) into pictures of the same height, no taller than the provided one, from top to bottom
+/// Its height isn't limited by the decoder limits as it is sliced anyway, but its width and the memory it takes still are
+/// Returns `None` if the picture isn't taller than the provided height
+/// Fails with an `Unsupported` error if the picture has to be sliced but its format cannot be both decoded and encoded (e.g. JPEG XL or AVIF)
+pub fn split_tall(content: &[u8], max_height: u32) -> Result<Option<Vec<Vec<u8>>>, ImageError> {
+    let max_height = max_height.max(1);
+
+    // Read the dimensions first to avoid decoding the pictures which are not sliced
+    let (width, height) = dimensions(content)?;
+
+    if height <= max_height {
+        return Ok(None);
+    }
+
+    let format = image::guess_format(content)?;
+
+    if !is_encodable(format) {
+        return Err(ImageError::Unsupported(
+            UnsupportedError::from_format_and_kind(
+                ImageFormatHint::Exact(format),
+                UnsupportedErrorKind::Format(ImageFormatHint::Exact(format)),
+            ),
+        ));
+    }
+
+    let mut limits = decoder_limits();
+    limits.max_image_height = None;
+
+    let picture = decode_within(content, Some(format), limits)?;
+
+    let slices = (height + max_height - 1) / max_height;
+    let slice_height = (height + slices - 1) / slices;
+
+    (0..slices)
+        .map(|i| i * slice_height)
+        .take_while(|top| *top < height)
+        .map(|top| {
+            encode_as(
+                &picture.crop_imm(0, top, width, slice_height.min(height - top)),
+                format,
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map(Some)
+}
+
+/// Join two pictures side-by-side in a single one, in reading order, encoded in the format of the first one
+/// The second picture is resized to the height of the first one if they don't have the same height
+pub fn join_pages(
+    first: &[u8],
+    second: &[u8],
+    direction: ReadingDirection,
+) -> Result<Vec<u8>, ImageError> {
+    let format = image::guess_format(first)?;
+    let first = decode(first, Some(format))?;
+    let second = load_picture(second)?;
+
+    let second = if second.height() == first.height() {
+        second
+    } else {
+        let width = second.width() * first.height() / second.height().max(1);
+        second.resize_exact(width.max(1), first.height(), FilterType::Lanczos3)
+    };
+
+    let (left, right) = match direction {
+        ReadingDirection::Ltr => (first, second),
+        ReadingDirection::Rtl => (second, first),
+    };
+
+    let mut joined = RgbaImage::new(left.width() + right.width(), left.height());
+    imageops::replace(&mut joined, &left.to_rgba8(), 0, 0);
+    imageops::replace(&mut joined, &right.to_rgba8(), left.width() as i64, 0);
+
+    // JPEG doesn't support transparency
+    let joined = if format == ImageFormat::Jpeg {
+        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(joined).to_rgb8())
+    } else {
+        DynamicImage::ImageRgba8(joined)
+    };
+
+    encode_as(&joined, format)
+}
+
+/// Convert a picture to sRGB if it's a CMYK JPEG picture or has a color profile other than sRGB, which many readers
+///  display with the wrong colors, re-encoded in its original format without a profile as readers assume sRGB
+/// Grayscale pictures are left untouched, as their profile rarely changes how they are displayed
+/// Returns `None` if the picture is left untouched, its profile cannot be used or its format cannot be encoded (e.g. AVIF)
+pub fn to_srgb(content: &[u8]) -> Result<Option<Vec<u8>>, ImageError> {
+    let format = match image::guess_format(content) {
+        Ok(format) if is_encodable(format) => format,
+        _ => return Ok(None),
+    };
+
+    if format == ImageFormat::Jpeg {
+        if let Some(picture) = decode_cmyk_jpeg(content)? {
+            return encode_as(&picture, format).map(Some);
+        }
+    }
+
+    let profile = match icc_profile(content, format)
+        .filter(|profile| {
+            !image_meta::icc_description(profile).map_or(false, |desc| desc.contains("sRGB"))
+        })
+        .and_then(|profile| Profile::new_from_slice(&profile, false))
+    {
+        Some(profile) => profile,
+        None => return Ok(None),
+    };
+
+    let picture = decode(content, Some(format))?;
+
+    if !picture.color().has_color() {
+        return Ok(None);
+    }
+
+    let (mut converted, data_type) = if picture.color().has_alpha() {
+        (
+            DynamicImage::ImageRgba8(picture.to_rgba8()),
+            DataType::RGBA8,
+        )
+    } else {
+        (DynamicImage::ImageRgb8(picture.to_rgb8()), DataType::RGB8)
+    };
+
+    // Profiles which don't describe RGB colors cannot be used for RGB pictures
+    let transform = match Transform::new(&profile, &srgb_profile(), data_type, Intent::Perceptual) {
+        Some(transform) => transform,
+        None => return Ok(None),
+    };
+
+    match &mut converted {
+        DynamicImage::ImageRgba8(buffer) => transform.apply(buffer),
+        DynamicImage::ImageRgb8(buffer) => transform.apply(buffer),
+        _ => unreachable!(),
+    }
+
+    encode_as(&converted, format).map(Some)
+}
+
+/// Get the sRGB profile, ready to convert pictures to it
+fn srgb_profile() -> Box<Profile> {
+    let mut srgb = Profile::new_sRGB();
+    srgb.precache_output_transform();
+    srgb
+}
+
+/// Get the ICC color profile embedded in a JPEG or PNG picture, if any
+fn icc_profile(content: &[u8], format: ImageFormat) -> Option<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => {
+            let mut decoder = JpegDecoder::new(content);
+            decoder.read_info().ok()?;
+            decoder.icc_profile()
+        }
+
+        ImageFormat::Png => PngDecoder::new(Cursor::new(content)).ok()?.icc_profile(),
+
+        _ => None,
+    }
+}
+
+/// Decode a CMYK JPEG picture to RGB, through its color profile if it has one
+/// Returns `None` if the picture isn't a CMYK one
+fn decode_cmyk_jpeg(content: &[u8]) -> Result<Option<DynamicImage>, ImageError> {
+    let jpeg_err = |err: jpeg_decoder::Error| {
+        ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Exact(ImageFormat::Jpeg),
+            err.to_string(),
+        ))
+    };
+
+    let mut decoder = JpegDecoder::new(content);
+    decoder.read_info().map_err(jpeg_err)?;
+
+    let (width, height) = match decoder.info() {
+        Some(info) if info.pixel_format == PixelFormat::CMYK32 => {
+            (u32::from(info.width), u32::from(info.height))
+        }
+        _ => return Ok(None),
+    };
+
+    // This decoder doesn't support the limits, so they are checked from the header
+    let max_dimension = MAX_PAGE_DIMENSION.load(Ordering::SeqCst);
+    let max_memory = MAX_PAGE_MEMORY_MB.load(Ordering::SeqCst) * 1024 * 1024;
+
+    // CMYK samples are decoded before being converted to RGB ones
+    if width > max_dimension
+        || height > max_dimension
+        || u64::from(width) * u64::from(height) * 7 > max_memory
+    {
+        return Err(ImageError::Limits(LimitError::from_kind(
+            LimitErrorKind::DimensionError,
+        )));
+    }
+
+    let mut cmyk = decoder.decode().map_err(jpeg_err)?;
+
+    // Most CMYK JPEG pictures are written by Adobe software, which stores inverted samples
+    cmyk.iter_mut().for_each(|sample| *sample = 255 - *sample);
+
+    let mut rgb = vec![0; width as usize * height as usize * 3];
+
+    let transform = decoder
+        .icc_profile()
+        .and_then(|profile| Profile::new_from_slice(&profile, false))
+        .and_then(|profile| {
+            Transform::new_to(
+                &profile,
+                &srgb_profile(),
+                DataType::CMYK,
+                DataType::RGB8,
+                Intent::Perceptual,
+            )
+        });
+
+    match transform {
+        Some(transform) => transform.convert(&cmyk, &mut rgb),
+
+        // Without a profile, colors can only be approximated
+        None => {
+            for (cmyk, rgb) in cmyk.chunks_exact(4).zip(rgb.chunks_exact_mut(3)) {
+                let black = 255 - u16::from(cmyk[3]);
+
+                for (color, ink) in rgb.iter_mut().zip(&cmyk[..3]) {
+                    *color = ((255 - u16::from(*ink)) * black / 255) as u8;
+                }
+            }
+        }
+    }
+
+    RgbImage::from_raw(width, height, rgb)
+        .map(|picture| Some(DynamicImage::ImageRgb8(picture)))
+        .ok_or_else(|| {
+            ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Exact(ImageFormat::Jpeg),
+                "unexpected number of samples",
+            ))
+        })
+}
+
+/// Check if pictures can be encoded in the provided format
+fn is_encodable(format: ImageFormat) -> bool {
+    format == ImageFormat::WebP
+        || !matches!(
+            ImageOutputFormat::from(format),
+            ImageOutputFormat::Unsupported(_)
+        )
+}
+
+/// Encode a transformed picture in the format of the original one
+fn encode_as(picture: &DynamicImage, format: ImageFormat) -> Result<Vec<u8>, ImageError> {
+    let output_format = match format {
+        ImageFormat::Jpeg => ImageOutputFormat::Jpeg(JPEG_QUALITY),
+        ImageFormat::WebP => return encode_webp(picture, PageFormat::Webp),
+        format => ImageOutputFormat::from(format),
+    };
+
+    let mut output = Cursor::new(vec![]);
+    picture.write_to(&mut output, output_format)?;
+
+    Ok(output.into_inner())
+}
+
+/// Re-encode a picture as JPEG with the provided quality (from 1 to 100)
+/// Grayscale pictures are kept grayscale, and transparency is dropped as JPEG doesn't support it
+pub fn recompress_jpeg(content: &[u8], quality: u8) -> Result<Vec<u8>, ImageError> {
+    let picture = load_picture(content)?;
+
+    let picture = if picture.color().has_color() {
+        DynamicImage::ImageRgb8(picture.to_rgb8())
+    } else {
+        DynamicImage::ImageLuma8(picture.to_luma8())
+    };
+
+    let mut output = Cursor::new(vec![]);
+    picture.write_to(
+        &mut output,
+        ImageOutputFormat::Jpeg(quality.max(1).min(100)),
+    )?;
+
+    Ok(output.into_inner())
+}
+
+/// Convert a picture to the provided format
+/// Transparency is kept (except in JPEG), but animated pictures only keep their first frame
+pub fn convert(content: &[u8], format: PageFormat) -> Result<Vec<u8>, ImageError> {
+    let picture = load_picture(content)?;
+
+    let output_format = match format {
+        PageFormat::Jpeg => ImageOutputFormat::Jpeg(JPEG_QUALITY),
+        PageFormat::Png => ImageOutputFormat::Png,
+        PageFormat::Webp | PageFormat::WebpLossless => return encode_webp(&picture, format),
+    };
+
+    // JPEG doesn't support transparency
+    let picture = match format {
+        PageFormat::Jpeg if picture.color().has_alpha() => {
+            DynamicImage::ImageRgb8(picture.to_rgb8())
+        }
+        _ => picture,
+    };
+
+    let mut output = Cursor::new(vec![]);
+    picture.write_to(&mut output, output_format)?;
+
+    Ok(output.into_inner())
+}
+
+/// Encode a picture as WebP, lossless or not depending on the provided format
+fn encode_webp(picture: &DynamicImage, format: PageFormat) -> Result<Vec<u8>, ImageError> {
+    // The WebP encoder only accepts RGB and RGBA pictures
+    let picture = if picture.color().has_alpha() {
+        DynamicImage::ImageRgba8(picture.to_rgba8())
+    } else {
+        DynamicImage::ImageRgb8(picture.to_rgb8())
+    };
+
+    let encoder = webp::Encoder::from_image(&picture).map_err(|err| {
+        ImageError::Encoding(EncodingError::new(
+            ImageFormatHint::Exact(ImageFormat::WebP),
+            err.to_owned(),
+        ))
+    })?;
+
+    let encoded = match format {
+        PageFormat::WebpLossless => encoder.encode_lossless(),
+        _ => encoder.encode(WEBP_QUALITY),
+    };
+
+    Ok(encoded.to_vec())
+}
+
+/// Compute the perceptual hash of a picture (difference hash), which barely changes when it is re-encoded, resized or slightly altered
+/// Each bit indicates if a pixel of a thumbnail of the picture is darker than the one at its right
+/// Returns `None` for the (almost) uniform pictures, whose hash is meaningless
+pub fn perceptual_hash(content: &[u8]) -> Result<Option<u64>, ImageError> {
+    let thumbnail = imageops::resize(
+        &load_picture(content)?.to_luma8(),
+        PERCEPTUAL_HASH_SIZE + 1,
+        PERCEPTUAL_HASH_SIZE,
+        FilterType::Triangle,
+    );
+
+    let (darkest, lightest) = thumbnail
+        .pixels()
+        .fold((u8::MAX, u8::MIN), |(darkest, lightest), pixel| {
+            (darkest.min(pixel.0[0]), lightest.max(pixel.0[0]))
+        });
+
+    if lightest - darkest < MIN_PERCEPTUAL_HASH_CONTRAST {
+        return Ok(None);
+    }
+
+    let mut hash = 0;
+
+    for y in 0..PERCEPTUAL_HASH_SIZE {
+        for x in 0..PERCEPTUAL_HASH_SIZE {
+            let darker = thumbnail.get_pixel(x, y).0[0] < thumbnail.get_pixel(x + 1, y).0[0];
+            hash = (hash << 1) | u64::from(darker);
+        }
+    }
+
+    Ok(Some(hash))
+}
+
+/// Get the number of differing bits between two perceptual hashes, from 0 (identical pictures) to 64
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Decode a picture in any supported format, including JPEG XL which the image library doesn't support, within the decoder limits
+pub fn load_picture(content: &[u8]) -> Result<DynamicImage, ImageError> {
+    if content.starts_with(JXL_CODESTREAM_SIGNATURE) || content.starts_with(JXL_CONTAINER_SIGNATURE)
+    {
+        decode_jxl(content)
+    } else {
+        decode(content, None)
+    }
+}
+
+/// Read the dimensions of a picture in any supported format, including JPEG XL, without decoding it
+fn dimensions(content: &[u8]) -> Result<(u32, u32), ImageError> {
+    if content.starts_with(JXL_CODESTREAM_SIGNATURE) || content.starts_with(JXL_CONTAINER_SIGNATURE)
+    {
+        let picture = JxlImage::builder().read(content).map_err(|err| {
+            ImageError::Decoding(DecodingError::new(
+                ImageFormatHint::Name("JPEG XL".to_owned()),
+                err.to_string(),
+            ))
+        })?;
+
+        return Ok((picture.width(), picture.height()));
+    }
+
+    ImageReader::new(Cursor::new(content))
+        .with_guessed_format()?
+        .into_dimensions()
+}
+
+/// Decode a JPEG XL picture, keeping its first frame
+fn decode_jxl(content: &[u8]) -> Result<DynamicImage, ImageError> {
+    let jxl_err = |err: jxl_oxide::Error| {
+        ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Name("JPEG XL".to_owned()),
+            err.to_string(),
+        ))
+    };
+
+    let picture = JxlImage::builder().read(content).map_err(jxl_err)?;
+
+    // The JPEG XL decoder doesn't support the limits, so they are checked from the header
+    let max_dimension = MAX_PAGE_DIMENSION.load(Ordering::SeqCst);
+    let max_memory = MAX_PAGE_MEMORY_MB.load(Ordering::SeqCst) * 1024 * 1024;
+
+    // Samples are decoded as 4-byte floating-point values, for up to 4 channels
+    if picture.width() > max_dimension
+        || picture.height() > max_dimension
+        || u64::from(picture.width()) * u64::from(picture.height()) * 16 > max_memory
+    {
+        return Err(ImageError::Limits(LimitError::from_kind(
+            LimitErrorKind::DimensionError,
+        )));
+    }
+
+    let frame = picture
+        .render_frame(0)
+        .map_err(jxl_err)?
+        .image_all_channels();
+
+    let (width, height) = (frame.width() as u32, frame.height() as u32);
+
+    // Samples are floating-point values between 0 and 1
+    let samples = frame
+        .buf()
+        .iter()
+        .map(|sample| (sample.max(0.0).min(1.0) * 255.0).round() as u8)
+        .collect::<Vec<_>>();
+
+    let picture = match frame.channels() {
+        1 => GrayImage::from_raw(width, height, samples).map(DynamicImage::ImageLuma8),
+        2 => GrayAlphaImage::from_raw(width, height, samples).map(DynamicImage::ImageLumaA8),
+        3 => RgbImage::from_raw(width, height, samples).map(DynamicImage::ImageRgb8),
+        4 => RgbaImage::from_raw(width, height, samples).map(DynamicImage::ImageRgba8),
+        _ => None,
+    };
+
+    picture.ok_or_else(|| {
+        ImageError::Decoding(DecodingError::new(
+            ImageFormatHint::Name("JPEG XL".to_owned()),
+            "unsupported channels layout",
+        ))
+    })
+}
@@ -0,0 +1,196 @@
+use crate::lib::hooks;
+use crate::lib::page_store::PageStore;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Placeholder replaced by the path of the original picture in upscaling commands
+pub const INPUT_PLACEHOLDER: &str = "{in}";
+
+/// Placeholder replaced by the path the upscaled picture must be written to in upscaling commands
+pub const OUTPUT_PLACEHOLDER: &str = "{out}";
+
+/// Number of upscaling directories created by this process, used to give each of them a unique name
+static UPSCALE_DIRS: AtomicUsize = AtomicUsize::new(0);
+
+/// What to do with a picture the upscaling command failed to upscale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpscaleFailurePolicy {
+    /// Stop building the volume
+    Fail,
+
+    /// Keep the original picture, with a warning
+    Keep,
+}
+
+impl FromStr for UpscaleFailurePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fail" => Ok(Self::Fail),
+            "keep" => Ok(Self::Keep),
+            _ => Err(format!(
+                "Unknown upscaling failure policy '{}' (expected 'fail' or 'keep')",
+                s
+            )),
+        }
+    }
+}
+
+/// Upscale pictures through the provided command, running it for up to `jobs` pictures at the same time
+/// Pictures are provided along with their extension, which upscalers usually rely on to detect their format
+/// If a page store is provided, pictures upscaled by the same command before are taken from it
+/// Returns the result of each picture, in order
+pub fn upscale_all(
+    command: &str,
+    pictures: &[(Vec<u8>, String)],
+    jobs: usize,
+    page_store: Option<&PageStore>,
+) -> Vec<Result<Vec<u8>, UpscaleErr>> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(pictures.iter().map(|_| None).collect::<Vec<_>>());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.max(1).min(pictures.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::SeqCst);
+
+                let (content, ext) = match pictures.get(i) {
+                    Some(picture) => picture,
+                    None => break,
+                };
+
+                let result = upscale_stored(command, content, ext, page_store);
+
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.unwrap_or(Err(UpscaleErr::NotUpscaled)))
+        .collect()
+}
+
+/// Upscale a picture, or take it from the page store if it was upscaled by the same command before
+fn upscale_stored(
+    command: &str,
+    content: &[u8],
+    ext: &str,
+    page_store: Option<&PageStore>,
+) -> Result<Vec<u8>, UpscaleErr> {
+    let store_key = page_store.map(|_| PageStore::key(content, &format!("upscale={}", command)));
+
+    if let (Some(page_store), Some(store_key)) = (page_store, &store_key) {
+        if let Some(stored) = page_store.get(store_key) {
+            return Ok(stored);
+        }
+    }
+
+    let upscaled = upscale(command, content, ext)?;
+
+    if let (Some(page_store), Some(store_key)) = (page_store, &store_key) {
+        if let Err(err) = page_store.put(store_key, &upscaled) {
+            warn!(
+                "Warning: failed to add an upscaled picture to the page store: {}",
+                err
+            );
+        }
+    }
+
+    Ok(upscaled)
+}
+
+/// Upscale a picture through the provided command, in a temporary directory removed afterwards
+pub fn upscale(command: &str, content: &[u8], ext: &str) -> Result<Vec<u8>, UpscaleErr> {
+    let dir = env::temp_dir().join(format!(
+        "comic-enc-upscale-{}-{}",
+        process::id(),
+        UPSCALE_DIRS.fetch_add(1, Ordering::SeqCst)
+    ));
+
+    fs::create_dir_all(&dir).map_err(UpscaleErr::IO)?;
+
+    let result = upscale_in(command, content, ext, &dir);
+
+    if let Err(err) = fs::remove_dir_all(&dir) {
+        warn!(
+            "Warning: failed to remove upscaling directory '{}': {}",
+            dir.to_string_lossy(),
+            err
+        );
+    }
+
+    result
+}
+
+/// Upscale a picture through the provided command, writing the files it needs to the provided directory
+fn upscale_in(command: &str, content: &[u8], ext: &str, dir: &Path) -> Result<Vec<u8>, UpscaleErr> {
+    let input = dir.join(format!("in.{}", ext));
+    let output = dir.join(format!("out.{}", ext));
+
+    fs::write(&input, content).map_err(UpscaleErr::IO)?;
+
+    let command = command
+        .replace(INPUT_PLACEHOLDER, &hooks::quote(&input.to_string_lossy()))
+        .replace(OUTPUT_PLACEHOLDER, &hooks::quote(&output.to_string_lossy()));
+
+    trace!("Running upscaling command: {}", command);
+
+    let result = hooks::shell(&command)
+        .output()
+        .map_err(UpscaleErr::FailedToStart)?;
+
+    if !result.status.success() {
+        return Err(UpscaleErr::Failed(
+            result.status.code(),
+            String::from_utf8_lossy(&result.stderr).trim().to_owned(),
+        ));
+    }
+
+    match fs::read(&output) {
+        Ok(upscaled) if !upscaled.is_empty() => Ok(upscaled),
+        _ => Err(UpscaleErr::NoOutput),
+    }
+}
+
+/// Upscaling error
+pub enum UpscaleErr {
+    IO(io::Error),
+    FailedToStart(io::Error),
+    Failed(Option<i32>, String),
+    NoOutput,
+    NotUpscaled,
+}
+
+impl fmt::Display for UpscaleErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::IO(err) => write!(f, "failed to write the pictures to upscale: {}", err),
+            Self::FailedToStart(err) => write!(f, "failed to start the shell: {}", err),
+            Self::Failed(Some(code), stderr) => {
+                write!(f, "command exited with code {}: {}", code, stderr)
+            }
+            Self::Failed(None, stderr) => {
+                write!(f, "command was terminated by a signal: {}", stderr)
+            }
+            Self::NoOutput => write!(
+                f,
+                "command didn't write the upscaled picture to '{}'",
+                OUTPUT_PLACEHOLDER
+            ),
+            Self::NotUpscaled => write!(f, "picture was not upscaled"),
+        }
+    }
+}
@@ -1,8 +1,33 @@
 use fern::colors::{Color, ColoredLevelConfig};
 use log::{Level, LevelFilter};
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
 use std::time::Instant;
 
+/// Width of the progress bar, in characters
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// State of the progress bar displayed below the log messages
+struct Progress {
+    /// Is the progress bar displayed at all
+    enabled: bool,
+
+    /// Current content of the progress bar, if it's displayed
+    line: Option<String>,
+}
+
+/// Progress bar shared by all log messages, so they are printed above it
+static PROGRESS: Mutex<Progress> = Mutex::new(Progress {
+    enabled: false,
+    line: None,
+});
+
+/// Warnings emitted since the logger started, so they can be reported at the end of the run
+static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
 /// Start the logger, hiding every message whose level is under the provided one
+/// Warnings are recorded even if they are hidden
 pub fn start(level: LevelFilter) {
     // Create color scheme
     let colors_line = ColoredLevelConfig::new()
@@ -16,7 +41,7 @@ pub fn start(level: LevelFilter) {
     let started = Instant::now();
 
     // Build the logger
-    fern::Dispatch::new()
+    let display = fern::Dispatch::new()
         .format(move |out, message, record| {
             let elapsed = started.elapsed();
             let secs = elapsed.as_secs();
@@ -41,7 +66,91 @@ pub fn start(level: LevelFilter) {
             ))
         })
         .level(level)
-        .chain(std::io::stdout())
+        .chain(fern::Output::call(|record| print_message(record.args())));
+
+    // Record the warnings as they are, without formatting them
+    let warnings = fern::Dispatch::new()
+        .filter(|metadata| metadata.level() == Level::Warn)
+        .chain(fern::Output::call(|record| {
+            WARNINGS
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .push(record.args().to_string())
+        }));
+
+    fern::Dispatch::new()
+        .level(level.max(LevelFilter::Warn))
+        .chain(display)
+        .chain(warnings)
         .apply()
         .unwrap()
 }
+
+/// Get the warnings emitted since the logger started
+pub fn take_warnings() -> Vec<String> {
+    std::mem::take(&mut *WARNINGS.lock().unwrap_or_else(|err| err.into_inner()))
+}
+
+/// Print a log message, above the progress bar if it's displayed
+fn print_message(message: &fmt::Arguments) {
+    let progress = PROGRESS.lock().unwrap_or_else(|err| err.into_inner());
+    let mut stdout = io::stdout();
+
+    // Failing to write to STDOUT cannot be reported anywhere
+    let _ = match &progress.line {
+        Some(line) => write!(stdout, "\r\x1B[2K{}\n{}", message, line),
+        None => writeln!(stdout, "{}", message),
+    };
+
+    let _ = stdout.flush();
+}
+
+/// Display a progress bar below the log messages from now on
+pub fn enable_progress() {
+    PROGRESS
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .enabled = true;
+}
+
+/// Update the progress bar with the number of items done out of the total, and a description of the current step
+/// Does nothing if the progress bar is not enabled
+pub fn set_progress(done: usize, total: usize, step: &str) {
+    let mut progress = PROGRESS.lock().unwrap_or_else(|err| err.into_inner());
+
+    if !progress.enabled {
+        return;
+    }
+
+    let filled = if total == 0 {
+        PROGRESS_BAR_WIDTH
+    } else {
+        done.min(total) * PROGRESS_BAR_WIDTH / total
+    };
+
+    let line = format!(
+        "[{}{}] {}/{} {}",
+        "=".repeat(filled),
+        " ".repeat(PROGRESS_BAR_WIDTH - filled),
+        done,
+        total,
+        step
+    );
+
+    let mut stdout = io::stdout();
+    let _ = write!(stdout, "\r\x1B[2K{}", line);
+    let _ = stdout.flush();
+
+    progress.line = Some(line);
+}
+
+/// Remove the progress bar, until it's updated again
+pub fn clear_progress() {
+    let mut progress = PROGRESS.lock().unwrap_or_else(|err| err.into_inner());
+
+    if progress.line.take().is_some() {
+        let mut stdout = io::stdout();
+        let _ = write!(stdout, "\r\x1B[2K");
+        let _ = stdout.flush();
+    }
+}
@@ -12,11 +12,18 @@ mod logger;
 
 use clap::Clap;
 use cli::opts::{Action, EncodingMethod, Opts};
+use lib::history::{HistoryEntry, RunHistory};
+use lib::prompt::{self, PromptMode};
+use lib::report::Report;
+use lib::safety;
+use lib::transform;
 use log::LevelFilter;
-use std::time::Instant;
+use std::env;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 fn main() {
     let started = Instant::now();
+    let started_at = SystemTime::now();
 
     let opts: Opts = Opts::parse();
 
@@ -26,10 +33,33 @@ fn main() {
         LevelFilter::Debug
     } else if opts.debug {
         LevelFilter::Trace
+    } else if opts.quiet_progress {
+        LevelFilter::Warn
     } else {
         LevelFilter::Info
     });
 
+    if opts.quiet_progress {
+        logger::enable_progress();
+    }
+
+    if opts.yes {
+        prompt::set_mode(PromptMode::AssumeYes);
+    } else if opts.no_input {
+        prompt::set_mode(PromptMode::NoInput);
+    }
+
+    if opts.force_dangerous_paths {
+        safety::force_dangerous_paths();
+    }
+
+    transform::set_decoder_limits(
+        opts.max_page_dimension
+            .unwrap_or(transform::DEFAULT_MAX_PAGE_DIMENSION),
+        opts.max_page_memory
+            .unwrap_or(transform::DEFAULT_MAX_PAGE_MEMORY_MB),
+    );
+
     trace!("Command-line arguments were parsed successfully.");
 
     let result = match &opts.action {
@@ -38,16 +68,80 @@ fn main() {
                 actions::compile(compile_opts, &opts.options).map_err(|err| format!("{}", err))
             }
 
-            EncodingMethod::Single(one_opts) => actions::encode_one(one_opts, &opts.options)
-                .map(|path| vec![path])
-                .map_err(|err| format!("{}", err)),
+            EncodingMethod::Single(one_opts) => {
+                actions::encode_one(one_opts, &opts.options).map_err(|err| format!("{}", err))
+            }
+
+            EncodingMethod::Append(append_opts) => {
+                actions::append(append_opts, &opts.options).map_err(|err| format!("{}", err))
+            }
         },
 
         Action::Decode(decode) => actions::decode(decode).map_err(|err| format!("{}", err)),
+
+        Action::Verify(verify) => actions::verify(verify).map_err(|err| format!("{}", err)),
+
+        Action::Edit(edit) => actions::edit(edit).map_err(|err| format!("{}", err)),
+
+        Action::Repack(repack) => actions::repack(repack).map_err(|err| format!("{}", err)),
+
+        Action::Convert(convert) => actions::convert(convert).map_err(|err| format!("{}", err)),
+
+        Action::Merge(merge) => actions::merge(merge).map_err(|err| format!("{}", err)),
+
+        Action::Split(split) => actions::split(split).map_err(|err| format!("{}", err)),
+
+        Action::ExtractCover(extract_cover) => {
+            actions::extract_cover(extract_cover).map_err(|err| format!("{}", err))
+        }
+
+        Action::Thumbnail(thumbnail) => {
+            actions::thumbnail(thumbnail).map_err(|err| format!("{}", err))
+        }
+
+        Action::Sort(sort) => actions::sort(sort).map_err(|err| format!("{}", err)),
+
+        Action::NumberPages(number_pages) => {
+            actions::number_pages(number_pages).map_err(|err| format!("{}", err))
+        }
+
+        Action::History(history) => actions::history(history).map_err(|err| format!("{}", err)),
+
+        Action::Doctor(doctor) => actions::doctor(doctor).map_err(|err| format!("{}", err)),
+
+        Action::Init(init) => actions::init(init).map_err(|err| format!("{}", err)),
     };
 
+    // Don't leave the progress bar below the final messages
+    logger::clear_progress();
+
+    // Record the run in the history, except when inspecting it
+    if !opts.no_history && !matches!(opts.action, Action::History(_)) {
+        record_history(started_at, started.elapsed(), &result);
+    }
+
+    // Write the run's report before the result is consumed
+    if let Some(report_path) = &opts.report {
+        match lib::report::write_run_report(
+            report_path,
+            &result,
+            started.elapsed(),
+            logger::take_warnings(),
+        ) {
+            Ok(()) => debug!("Written run report to '{}'.", report_path.to_string_lossy()),
+            Err(err) => {
+                error!(
+                    "Failed to write run report at '{}': {}",
+                    report_path.to_string_lossy(),
+                    err
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+
     match result {
-        Ok(_) => {
+        Ok(report) => {
             let elapsed = started.elapsed();
             let secs = elapsed.as_secs();
             info!(
@@ -56,6 +150,47 @@ fn main() {
                 secs % 60,
                 elapsed.subsec_millis()
             );
+
+            if !report.phases.is_empty() {
+                info!(
+                    "Breakdown: {}.",
+                    report
+                        .phases
+                        .iter()
+                        .map(|phase| format!(
+                            "{} in {}",
+                            phase.name,
+                            lib::report::format_millis(phase.elapsed_ms)
+                        ))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+
+            if !report.counts.is_empty() {
+                info!(
+                    "Counts: {}.",
+                    report
+                        .counts
+                        .iter()
+                        .map(|count| format!("{} {}", count.value, count.name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+
+            if let Some(stats_file) = &opts.stats_file {
+                if let Err(err) = report.write_stats_file(stats_file, elapsed) {
+                    error!(
+                        "Failed to write statistics file at '{}': {}",
+                        stats_file.to_string_lossy(),
+                        err
+                    );
+                    std::process::exit(1);
+                }
+
+                debug!("Written statistics to '{}'.", stats_file.to_string_lossy());
+            }
         }
 
         Err(err) => {
@@ -64,3 +199,29 @@ fn main() {
         }
     }
 }
+
+/// Record a run in the history of recent runs
+/// Failing to do so is not fatal as the run itself is over
+fn record_history(started_at: SystemTime, elapsed: Duration, result: &Result<Report, String>) {
+    let entry = HistoryEntry {
+        started_at: started_at
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0),
+        args: env::args_os()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().to_string())
+            .collect(),
+        cwd: env::current_dir().unwrap_or_default(),
+        outputs: match result {
+            Ok(report) => report.outputs.clone(),
+            Err(_) => vec![],
+        },
+        duration_ms: elapsed.as_millis() as u64,
+        error: result.as_ref().err().cloned(),
+    };
+
+    if let Err(err) = RunHistory::record(entry) {
+        warn!("Warning: failed to record run in history: {}", err);
+    }
+}